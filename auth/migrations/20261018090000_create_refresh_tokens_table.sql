@@ -0,0 +1,11 @@
+CREATE TABLE refresh_tokens(
+    refresh_token_hash TEXT NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES account (user_id),
+    family_id UUID NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE,
+    revoked BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (refresh_token_hash)
+);
+
+CREATE INDEX refresh_tokens_family_id_idx ON refresh_tokens (family_id);
@@ -0,0 +1,16 @@
+-- every login gets its own row in auth_tokens, which is identified by a session id
+ALTER TABLE auth_tokens
+ADD COLUMN session_id UUID NOT NULL DEFAULT gen_random_uuid(),
+ADD COLUMN device VARCHAR(255) NOT NULL DEFAULT '',
+ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+ADD COLUMN last_used_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+ALTER TABLE auth_tokens DROP CONSTRAINT auth_tokens_pkey;
+ALTER TABLE auth_tokens ADD PRIMARY KEY (session_id);
+ALTER TABLE auth_tokens ALTER COLUMN session_id DROP DEFAULT;
+
+CREATE INDEX auth_tokens_user_id_idx ON auth_tokens (user_id);
+
+-- a refresh token family is the chain of refresh tokens of one session
+ALTER TABLE refresh_tokens RENAME COLUMN family_id TO session_id;
+ALTER INDEX refresh_tokens_family_id_idx RENAME TO refresh_tokens_session_id_idx;
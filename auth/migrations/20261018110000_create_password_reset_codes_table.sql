@@ -0,0 +1,11 @@
+-- only the hash of a reset code is stored, the code itself is only ever sent to the user
+CREATE TABLE password_reset_codes(
+    code_hash TEXT NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES account (user_id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (code_hash)
+);
+
+CREATE INDEX password_reset_codes_user_id_idx ON password_reset_codes (user_id);
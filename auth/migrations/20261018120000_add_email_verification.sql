@@ -0,0 +1,16 @@
+-- accounts that existed before email verification was introduced are treated as verified
+ALTER TABLE account ADD COLUMN verified BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE account ALTER COLUMN verified SET DEFAULT FALSE;
+
+-- the email is stored with the token so a token only verifies the address it was sent to
+CREATE TABLE email_verification_tokens(
+    token_hash TEXT NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES account (user_id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (token_hash)
+);
+
+CREATE INDEX email_verification_tokens_user_id_idx ON email_verification_tokens (user_id);
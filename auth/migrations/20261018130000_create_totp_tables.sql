@@ -0,0 +1,21 @@
+-- the secret is encrypted with the totp_encryption_key secret, it only becomes the second factor of the user once it
+-- has been confirmed with a code from the authenticator app
+CREATE TABLE totp_secrets(
+    user_id INTEGER NOT NULL REFERENCES account (user_id) ON DELETE CASCADE,
+    encrypted_secret BYTEA NOT NULL,
+    nonce BYTEA NOT NULL,
+    confirmed BOOLEAN NOT NULL DEFAULT FALSE,
+    -- the time step of the last accepted code, so a code can't be used twice
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id)
+);
+
+-- like the other one-time codes only the hash of a backup code is stored
+CREATE TABLE totp_backup_codes(
+    user_id INTEGER NOT NULL REFERENCES account (user_id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, code_hash)
+);
@@ -0,0 +1,4 @@
+-- both are optional, NULL means that the user hasn't set them
+ALTER TABLE account
+ADD COLUMN display_name VARCHAR(255),
+ADD COLUMN status_text VARCHAR(255);
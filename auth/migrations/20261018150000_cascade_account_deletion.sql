@@ -0,0 +1,8 @@
+-- deleting an account removes its sessions and refresh tokens with it, like the other tables that belong to an account
+ALTER TABLE auth_tokens
+DROP CONSTRAINT auth_tokens_user_id_fkey,
+ADD CONSTRAINT auth_tokens_user_id_fkey FOREIGN KEY (user_id) REFERENCES account (user_id) ON DELETE CASCADE;
+
+ALTER TABLE refresh_tokens
+DROP CONSTRAINT refresh_tokens_user_id_fkey,
+ADD CONSTRAINT refresh_tokens_user_id_fkey FOREIGN KEY (user_id) REFERENCES account (user_id) ON DELETE CASCADE;
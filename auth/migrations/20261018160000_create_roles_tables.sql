@@ -0,0 +1,18 @@
+-- the roles that can be granted, their names are what ends up in the auth tokens
+CREATE TABLE roles(
+    role_id SERIAL PRIMARY KEY,
+    name VARCHAR(32) NOT NULL UNIQUE
+);
+
+INSERT INTO roles (name) VALUES ('user'), ('moderator'), ('admin');
+
+CREATE TABLE account_roles(
+    user_id INTEGER NOT NULL REFERENCES account (user_id) ON DELETE CASCADE,
+    role_id INTEGER NOT NULL REFERENCES roles (role_id) ON DELETE CASCADE,
+    granted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, role_id)
+);
+
+-- every account that already exists is a regular user
+INSERT INTO account_roles (user_id, role_id)
+SELECT account.user_id, roles.role_id FROM account, roles WHERE roles.name = 'user';
@@ -0,0 +1,15 @@
+-- security relevant events like logins and password changes, the events of a deleted account are kept without the
+-- user_id
+CREATE TABLE auth_events(
+    event_id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER REFERENCES account (user_id) ON DELETE SET NULL,
+    event_type VARCHAR(32) NOT NULL,
+    peer_address TEXT,
+    user_agent TEXT,
+    -- what else is known about the event, like why a login failed
+    detail TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX auth_events_user_id_idx ON auth_events (user_id);
+CREATE INDEX auth_events_created_at_idx ON auth_events (created_at);
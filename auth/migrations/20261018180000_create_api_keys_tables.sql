@@ -0,0 +1,17 @@
+-- accounts for bots and other services, they can't log in and only authenticate with their API keys
+CREATE TABLE service_accounts(
+    service_account_id SERIAL PRIMARY KEY,
+    name VARCHAR(64) NOT NULL UNIQUE,
+    created_by INTEGER REFERENCES account (user_id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- only the hash of an API key is stored, the key itself is handed out once when it's created
+CREATE TABLE api_keys(
+    key_id UUID PRIMARY KEY,
+    service_account_id INTEGER NOT NULL REFERENCES service_accounts (service_account_id) ON DELETE CASCADE,
+    key_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,617 @@
+-- usernames are stored in NFKC with their case folded and emails with their domain in lowercase, like Username::parse
+-- and Email::parse return them. the case mappings below are generated from the ones that the rust code uses, so
+-- both normalize the same way no matter how postgres was built, the auth tests compare them character by character
+
+-- full case folding of every character that has one
+CREATE FUNCTION fold_case(s TEXT) RETURNS TEXT
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT coalesce(string_agg(coalesce(case_folding ->> c, c), '' ORDER BY i), '')
+    FROM unnest(string_to_array(s, NULL)) WITH ORDINALITY AS chars (c, i),
+    (SELECT '{
+        "\u0041":"\u0061", "\u0042":"\u0062", "\u0043":"\u0063", "\u0044":"\u0064", "\u0045":"\u0065", "\u0046":"\u0066",
+        "\u0047":"\u0067", "\u0048":"\u0068", "\u0049":"\u0069", "\u004a":"\u006a", "\u004b":"\u006b", "\u004c":"\u006c",
+        "\u004d":"\u006d", "\u004e":"\u006e", "\u004f":"\u006f", "\u0050":"\u0070", "\u0051":"\u0071", "\u0052":"\u0072",
+        "\u0053":"\u0073", "\u0054":"\u0074", "\u0055":"\u0075", "\u0056":"\u0076", "\u0057":"\u0077", "\u0058":"\u0078",
+        "\u0059":"\u0079", "\u005a":"\u007a", "\u00b5":"\u03bc", "\u00c0":"\u00e0", "\u00c1":"\u00e1", "\u00c2":"\u00e2",
+        "\u00c3":"\u00e3", "\u00c4":"\u00e4", "\u00c5":"\u00e5", "\u00c6":"\u00e6", "\u00c7":"\u00e7", "\u00c8":"\u00e8",
+        "\u00c9":"\u00e9", "\u00ca":"\u00ea", "\u00cb":"\u00eb", "\u00cc":"\u00ec", "\u00cd":"\u00ed", "\u00ce":"\u00ee",
+        "\u00cf":"\u00ef", "\u00d0":"\u00f0", "\u00d1":"\u00f1", "\u00d2":"\u00f2", "\u00d3":"\u00f3", "\u00d4":"\u00f4",
+        "\u00d5":"\u00f5", "\u00d6":"\u00f6", "\u00d8":"\u00f8", "\u00d9":"\u00f9", "\u00da":"\u00fa", "\u00db":"\u00fb",
+        "\u00dc":"\u00fc", "\u00dd":"\u00fd", "\u00de":"\u00fe", "\u00df":"\u0073\u0073", "\u0100":"\u0101", "\u0102":"\u0103",
+        "\u0104":"\u0105", "\u0106":"\u0107", "\u0108":"\u0109", "\u010a":"\u010b", "\u010c":"\u010d", "\u010e":"\u010f",
+        "\u0110":"\u0111", "\u0112":"\u0113", "\u0114":"\u0115", "\u0116":"\u0117", "\u0118":"\u0119", "\u011a":"\u011b",
+        "\u011c":"\u011d", "\u011e":"\u011f", "\u0120":"\u0121", "\u0122":"\u0123", "\u0124":"\u0125", "\u0126":"\u0127",
+        "\u0128":"\u0129", "\u012a":"\u012b", "\u012c":"\u012d", "\u012e":"\u012f", "\u0130":"\u0069\u0307", "\u0132":"\u0133",
+        "\u0134":"\u0135", "\u0136":"\u0137", "\u0139":"\u013a", "\u013b":"\u013c", "\u013d":"\u013e", "\u013f":"\u0140",
+        "\u0141":"\u0142", "\u0143":"\u0144", "\u0145":"\u0146", "\u0147":"\u0148", "\u0149":"\u02bc\u006e", "\u014a":"\u014b",
+        "\u014c":"\u014d", "\u014e":"\u014f", "\u0150":"\u0151", "\u0152":"\u0153", "\u0154":"\u0155", "\u0156":"\u0157",
+        "\u0158":"\u0159", "\u015a":"\u015b", "\u015c":"\u015d", "\u015e":"\u015f", "\u0160":"\u0161", "\u0162":"\u0163",
+        "\u0164":"\u0165", "\u0166":"\u0167", "\u0168":"\u0169", "\u016a":"\u016b", "\u016c":"\u016d", "\u016e":"\u016f",
+        "\u0170":"\u0171", "\u0172":"\u0173", "\u0174":"\u0175", "\u0176":"\u0177", "\u0178":"\u00ff", "\u0179":"\u017a",
+        "\u017b":"\u017c", "\u017d":"\u017e", "\u017f":"\u0073", "\u0181":"\u0253", "\u0182":"\u0183", "\u0184":"\u0185",
+        "\u0186":"\u0254", "\u0187":"\u0188", "\u0189":"\u0256", "\u018a":"\u0257", "\u018b":"\u018c", "\u018e":"\u01dd",
+        "\u018f":"\u0259", "\u0190":"\u025b", "\u0191":"\u0192", "\u0193":"\u0260", "\u0194":"\u0263", "\u0196":"\u0269",
+        "\u0197":"\u0268", "\u0198":"\u0199", "\u019c":"\u026f", "\u019d":"\u0272", "\u019f":"\u0275", "\u01a0":"\u01a1",
+        "\u01a2":"\u01a3", "\u01a4":"\u01a5", "\u01a6":"\u0280", "\u01a7":"\u01a8", "\u01a9":"\u0283", "\u01ac":"\u01ad",
+        "\u01ae":"\u0288", "\u01af":"\u01b0", "\u01b1":"\u028a", "\u01b2":"\u028b", "\u01b3":"\u01b4", "\u01b5":"\u01b6",
+        "\u01b7":"\u0292", "\u01b8":"\u01b9", "\u01bc":"\u01bd", "\u01c4":"\u01c6", "\u01c5":"\u01c6", "\u01c7":"\u01c9",
+        "\u01c8":"\u01c9", "\u01ca":"\u01cc", "\u01cb":"\u01cc", "\u01cd":"\u01ce", "\u01cf":"\u01d0", "\u01d1":"\u01d2",
+        "\u01d3":"\u01d4", "\u01d5":"\u01d6", "\u01d7":"\u01d8", "\u01d9":"\u01da", "\u01db":"\u01dc", "\u01de":"\u01df",
+        "\u01e0":"\u01e1", "\u01e2":"\u01e3", "\u01e4":"\u01e5", "\u01e6":"\u01e7", "\u01e8":"\u01e9", "\u01ea":"\u01eb",
+        "\u01ec":"\u01ed", "\u01ee":"\u01ef", "\u01f0":"\u006a\u030c", "\u01f1":"\u01f3", "\u01f2":"\u01f3", "\u01f4":"\u01f5",
+        "\u01f6":"\u0195", "\u01f7":"\u01bf", "\u01f8":"\u01f9", "\u01fa":"\u01fb", "\u01fc":"\u01fd", "\u01fe":"\u01ff",
+        "\u0200":"\u0201", "\u0202":"\u0203", "\u0204":"\u0205", "\u0206":"\u0207", "\u0208":"\u0209", "\u020a":"\u020b",
+        "\u020c":"\u020d", "\u020e":"\u020f", "\u0210":"\u0211", "\u0212":"\u0213", "\u0214":"\u0215", "\u0216":"\u0217",
+        "\u0218":"\u0219", "\u021a":"\u021b", "\u021c":"\u021d", "\u021e":"\u021f", "\u0220":"\u019e", "\u0222":"\u0223",
+        "\u0224":"\u0225", "\u0226":"\u0227", "\u0228":"\u0229", "\u022a":"\u022b", "\u022c":"\u022d", "\u022e":"\u022f",
+        "\u0230":"\u0231", "\u0232":"\u0233", "\u023a":"\u2c65", "\u023b":"\u023c", "\u023d":"\u019a", "\u023e":"\u2c66",
+        "\u0241":"\u0242", "\u0243":"\u0180", "\u0244":"\u0289", "\u0245":"\u028c", "\u0246":"\u0247", "\u0248":"\u0249",
+        "\u024a":"\u024b", "\u024c":"\u024d", "\u024e":"\u024f", "\u0345":"\u03b9", "\u0370":"\u0371", "\u0372":"\u0373",
+        "\u0376":"\u0377", "\u037f":"\u03f3", "\u0386":"\u03ac", "\u0388":"\u03ad", "\u0389":"\u03ae", "\u038a":"\u03af",
+        "\u038c":"\u03cc", "\u038e":"\u03cd", "\u038f":"\u03ce", "\u0390":"\u03b9\u0308\u0301", "\u0391":"\u03b1", "\u0392":"\u03b2",
+        "\u0393":"\u03b3", "\u0394":"\u03b4", "\u0395":"\u03b5", "\u0396":"\u03b6", "\u0397":"\u03b7", "\u0398":"\u03b8",
+        "\u0399":"\u03b9", "\u039a":"\u03ba", "\u039b":"\u03bb", "\u039c":"\u03bc", "\u039d":"\u03bd", "\u039e":"\u03be",
+        "\u039f":"\u03bf", "\u03a0":"\u03c0", "\u03a1":"\u03c1", "\u03a3":"\u03c3", "\u03a4":"\u03c4", "\u03a5":"\u03c5",
+        "\u03a6":"\u03c6", "\u03a7":"\u03c7", "\u03a8":"\u03c8", "\u03a9":"\u03c9", "\u03aa":"\u03ca", "\u03ab":"\u03cb",
+        "\u03b0":"\u03c5\u0308\u0301", "\u03c2":"\u03c3", "\u03cf":"\u03d7", "\u03d0":"\u03b2", "\u03d1":"\u03b8", "\u03d5":"\u03c6",
+        "\u03d6":"\u03c0", "\u03d8":"\u03d9", "\u03da":"\u03db", "\u03dc":"\u03dd", "\u03de":"\u03df", "\u03e0":"\u03e1",
+        "\u03e2":"\u03e3", "\u03e4":"\u03e5", "\u03e6":"\u03e7", "\u03e8":"\u03e9", "\u03ea":"\u03eb", "\u03ec":"\u03ed",
+        "\u03ee":"\u03ef", "\u03f0":"\u03ba", "\u03f1":"\u03c1", "\u03f4":"\u03b8", "\u03f5":"\u03b5", "\u03f7":"\u03f8",
+        "\u03f9":"\u03f2", "\u03fa":"\u03fb", "\u03fd":"\u037b", "\u03fe":"\u037c", "\u03ff":"\u037d", "\u0400":"\u0450",
+        "\u0401":"\u0451", "\u0402":"\u0452", "\u0403":"\u0453", "\u0404":"\u0454", "\u0405":"\u0455", "\u0406":"\u0456",
+        "\u0407":"\u0457", "\u0408":"\u0458", "\u0409":"\u0459", "\u040a":"\u045a", "\u040b":"\u045b", "\u040c":"\u045c",
+        "\u040d":"\u045d", "\u040e":"\u045e", "\u040f":"\u045f", "\u0410":"\u0430", "\u0411":"\u0431", "\u0412":"\u0432",
+        "\u0413":"\u0433", "\u0414":"\u0434", "\u0415":"\u0435", "\u0416":"\u0436", "\u0417":"\u0437", "\u0418":"\u0438",
+        "\u0419":"\u0439", "\u041a":"\u043a", "\u041b":"\u043b", "\u041c":"\u043c", "\u041d":"\u043d", "\u041e":"\u043e",
+        "\u041f":"\u043f", "\u0420":"\u0440", "\u0421":"\u0441", "\u0422":"\u0442", "\u0423":"\u0443", "\u0424":"\u0444",
+        "\u0425":"\u0445", "\u0426":"\u0446", "\u0427":"\u0447", "\u0428":"\u0448", "\u0429":"\u0449", "\u042a":"\u044a",
+        "\u042b":"\u044b", "\u042c":"\u044c", "\u042d":"\u044d", "\u042e":"\u044e", "\u042f":"\u044f", "\u0460":"\u0461",
+        "\u0462":"\u0463", "\u0464":"\u0465", "\u0466":"\u0467", "\u0468":"\u0469", "\u046a":"\u046b", "\u046c":"\u046d",
+        "\u046e":"\u046f", "\u0470":"\u0471", "\u0472":"\u0473", "\u0474":"\u0475", "\u0476":"\u0477", "\u0478":"\u0479",
+        "\u047a":"\u047b", "\u047c":"\u047d", "\u047e":"\u047f", "\u0480":"\u0481", "\u048a":"\u048b", "\u048c":"\u048d",
+        "\u048e":"\u048f", "\u0490":"\u0491", "\u0492":"\u0493", "\u0494":"\u0495", "\u0496":"\u0497", "\u0498":"\u0499",
+        "\u049a":"\u049b", "\u049c":"\u049d", "\u049e":"\u049f", "\u04a0":"\u04a1", "\u04a2":"\u04a3", "\u04a4":"\u04a5",
+        "\u04a6":"\u04a7", "\u04a8":"\u04a9", "\u04aa":"\u04ab", "\u04ac":"\u04ad", "\u04ae":"\u04af", "\u04b0":"\u04b1",
+        "\u04b2":"\u04b3", "\u04b4":"\u04b5", "\u04b6":"\u04b7", "\u04b8":"\u04b9", "\u04ba":"\u04bb", "\u04bc":"\u04bd",
+        "\u04be":"\u04bf", "\u04c0":"\u04cf", "\u04c1":"\u04c2", "\u04c3":"\u04c4", "\u04c5":"\u04c6", "\u04c7":"\u04c8",
+        "\u04c9":"\u04ca", "\u04cb":"\u04cc", "\u04cd":"\u04ce", "\u04d0":"\u04d1", "\u04d2":"\u04d3", "\u04d4":"\u04d5",
+        "\u04d6":"\u04d7", "\u04d8":"\u04d9", "\u04da":"\u04db", "\u04dc":"\u04dd", "\u04de":"\u04df", "\u04e0":"\u04e1",
+        "\u04e2":"\u04e3", "\u04e4":"\u04e5", "\u04e6":"\u04e7", "\u04e8":"\u04e9", "\u04ea":"\u04eb", "\u04ec":"\u04ed",
+        "\u04ee":"\u04ef", "\u04f0":"\u04f1", "\u04f2":"\u04f3", "\u04f4":"\u04f5", "\u04f6":"\u04f7", "\u04f8":"\u04f9",
+        "\u04fa":"\u04fb", "\u04fc":"\u04fd", "\u04fe":"\u04ff", "\u0500":"\u0501", "\u0502":"\u0503", "\u0504":"\u0505",
+        "\u0506":"\u0507", "\u0508":"\u0509", "\u050a":"\u050b", "\u050c":"\u050d", "\u050e":"\u050f", "\u0510":"\u0511",
+        "\u0512":"\u0513", "\u0514":"\u0515", "\u0516":"\u0517", "\u0518":"\u0519", "\u051a":"\u051b", "\u051c":"\u051d",
+        "\u051e":"\u051f", "\u0520":"\u0521", "\u0522":"\u0523", "\u0524":"\u0525", "\u0526":"\u0527", "\u0528":"\u0529",
+        "\u052a":"\u052b", "\u052c":"\u052d", "\u052e":"\u052f", "\u0531":"\u0561", "\u0532":"\u0562", "\u0533":"\u0563",
+        "\u0534":"\u0564", "\u0535":"\u0565", "\u0536":"\u0566", "\u0537":"\u0567", "\u0538":"\u0568", "\u0539":"\u0569",
+        "\u053a":"\u056a", "\u053b":"\u056b", "\u053c":"\u056c", "\u053d":"\u056d", "\u053e":"\u056e", "\u053f":"\u056f",
+        "\u0540":"\u0570", "\u0541":"\u0571", "\u0542":"\u0572", "\u0543":"\u0573", "\u0544":"\u0574", "\u0545":"\u0575",
+        "\u0546":"\u0576", "\u0547":"\u0577", "\u0548":"\u0578", "\u0549":"\u0579", "\u054a":"\u057a", "\u054b":"\u057b",
+        "\u054c":"\u057c", "\u054d":"\u057d", "\u054e":"\u057e", "\u054f":"\u057f", "\u0550":"\u0580", "\u0551":"\u0581",
+        "\u0552":"\u0582", "\u0553":"\u0583", "\u0554":"\u0584", "\u0555":"\u0585", "\u0556":"\u0586", "\u0587":"\u0565\u0582",
+        "\u10a0":"\u2d00", "\u10a1":"\u2d01", "\u10a2":"\u2d02", "\u10a3":"\u2d03", "\u10a4":"\u2d04", "\u10a5":"\u2d05",
+        "\u10a6":"\u2d06", "\u10a7":"\u2d07", "\u10a8":"\u2d08", "\u10a9":"\u2d09", "\u10aa":"\u2d0a", "\u10ab":"\u2d0b",
+        "\u10ac":"\u2d0c", "\u10ad":"\u2d0d", "\u10ae":"\u2d0e", "\u10af":"\u2d0f", "\u10b0":"\u2d10", "\u10b1":"\u2d11",
+        "\u10b2":"\u2d12", "\u10b3":"\u2d13", "\u10b4":"\u2d14", "\u10b5":"\u2d15", "\u10b6":"\u2d16", "\u10b7":"\u2d17",
+        "\u10b8":"\u2d18", "\u10b9":"\u2d19", "\u10ba":"\u2d1a", "\u10bb":"\u2d1b", "\u10bc":"\u2d1c", "\u10bd":"\u2d1d",
+        "\u10be":"\u2d1e", "\u10bf":"\u2d1f", "\u10c0":"\u2d20", "\u10c1":"\u2d21", "\u10c2":"\u2d22", "\u10c3":"\u2d23",
+        "\u10c4":"\u2d24", "\u10c5":"\u2d25", "\u10c7":"\u2d27", "\u10cd":"\u2d2d", "\u13f8":"\u13f0", "\u13f9":"\u13f1",
+        "\u13fa":"\u13f2", "\u13fb":"\u13f3", "\u13fc":"\u13f4", "\u13fd":"\u13f5", "\u1c80":"\u0432", "\u1c81":"\u0434",
+        "\u1c82":"\u043e", "\u1c83":"\u0441", "\u1c84":"\u0442", "\u1c85":"\u0442", "\u1c86":"\u044a", "\u1c87":"\u0463",
+        "\u1c88":"\ua64b", "\u1c89":"\u1c8a", "\u1c90":"\u10d0", "\u1c91":"\u10d1", "\u1c92":"\u10d2", "\u1c93":"\u10d3",
+        "\u1c94":"\u10d4", "\u1c95":"\u10d5", "\u1c96":"\u10d6", "\u1c97":"\u10d7", "\u1c98":"\u10d8", "\u1c99":"\u10d9",
+        "\u1c9a":"\u10da", "\u1c9b":"\u10db", "\u1c9c":"\u10dc", "\u1c9d":"\u10dd", "\u1c9e":"\u10de", "\u1c9f":"\u10df",
+        "\u1ca0":"\u10e0", "\u1ca1":"\u10e1", "\u1ca2":"\u10e2", "\u1ca3":"\u10e3", "\u1ca4":"\u10e4", "\u1ca5":"\u10e5",
+        "\u1ca6":"\u10e6", "\u1ca7":"\u10e7", "\u1ca8":"\u10e8", "\u1ca9":"\u10e9", "\u1caa":"\u10ea", "\u1cab":"\u10eb",
+        "\u1cac":"\u10ec", "\u1cad":"\u10ed", "\u1cae":"\u10ee", "\u1caf":"\u10ef", "\u1cb0":"\u10f0", "\u1cb1":"\u10f1",
+        "\u1cb2":"\u10f2", "\u1cb3":"\u10f3", "\u1cb4":"\u10f4", "\u1cb5":"\u10f5", "\u1cb6":"\u10f6", "\u1cb7":"\u10f7",
+        "\u1cb8":"\u10f8", "\u1cb9":"\u10f9", "\u1cba":"\u10fa", "\u1cbd":"\u10fd", "\u1cbe":"\u10fe", "\u1cbf":"\u10ff",
+        "\u1e00":"\u1e01", "\u1e02":"\u1e03", "\u1e04":"\u1e05", "\u1e06":"\u1e07", "\u1e08":"\u1e09", "\u1e0a":"\u1e0b",
+        "\u1e0c":"\u1e0d", "\u1e0e":"\u1e0f", "\u1e10":"\u1e11", "\u1e12":"\u1e13", "\u1e14":"\u1e15", "\u1e16":"\u1e17",
+        "\u1e18":"\u1e19", "\u1e1a":"\u1e1b", "\u1e1c":"\u1e1d", "\u1e1e":"\u1e1f", "\u1e20":"\u1e21", "\u1e22":"\u1e23",
+        "\u1e24":"\u1e25", "\u1e26":"\u1e27", "\u1e28":"\u1e29", "\u1e2a":"\u1e2b", "\u1e2c":"\u1e2d", "\u1e2e":"\u1e2f",
+        "\u1e30":"\u1e31", "\u1e32":"\u1e33", "\u1e34":"\u1e35", "\u1e36":"\u1e37", "\u1e38":"\u1e39", "\u1e3a":"\u1e3b",
+        "\u1e3c":"\u1e3d", "\u1e3e":"\u1e3f", "\u1e40":"\u1e41", "\u1e42":"\u1e43", "\u1e44":"\u1e45", "\u1e46":"\u1e47",
+        "\u1e48":"\u1e49", "\u1e4a":"\u1e4b", "\u1e4c":"\u1e4d", "\u1e4e":"\u1e4f", "\u1e50":"\u1e51", "\u1e52":"\u1e53",
+        "\u1e54":"\u1e55", "\u1e56":"\u1e57", "\u1e58":"\u1e59", "\u1e5a":"\u1e5b", "\u1e5c":"\u1e5d", "\u1e5e":"\u1e5f",
+        "\u1e60":"\u1e61", "\u1e62":"\u1e63", "\u1e64":"\u1e65", "\u1e66":"\u1e67", "\u1e68":"\u1e69", "\u1e6a":"\u1e6b",
+        "\u1e6c":"\u1e6d", "\u1e6e":"\u1e6f", "\u1e70":"\u1e71", "\u1e72":"\u1e73", "\u1e74":"\u1e75", "\u1e76":"\u1e77",
+        "\u1e78":"\u1e79", "\u1e7a":"\u1e7b", "\u1e7c":"\u1e7d", "\u1e7e":"\u1e7f", "\u1e80":"\u1e81", "\u1e82":"\u1e83",
+        "\u1e84":"\u1e85", "\u1e86":"\u1e87", "\u1e88":"\u1e89", "\u1e8a":"\u1e8b", "\u1e8c":"\u1e8d", "\u1e8e":"\u1e8f",
+        "\u1e90":"\u1e91", "\u1e92":"\u1e93", "\u1e94":"\u1e95", "\u1e96":"\u0068\u0331", "\u1e97":"\u0074\u0308", "\u1e98":"\u0077\u030a",
+        "\u1e99":"\u0079\u030a", "\u1e9a":"\u0061\u02be", "\u1e9b":"\u1e61", "\u1e9e":"\u0073\u0073", "\u1ea0":"\u1ea1", "\u1ea2":"\u1ea3",
+        "\u1ea4":"\u1ea5", "\u1ea6":"\u1ea7", "\u1ea8":"\u1ea9", "\u1eaa":"\u1eab", "\u1eac":"\u1ead", "\u1eae":"\u1eaf",
+        "\u1eb0":"\u1eb1", "\u1eb2":"\u1eb3", "\u1eb4":"\u1eb5", "\u1eb6":"\u1eb7", "\u1eb8":"\u1eb9", "\u1eba":"\u1ebb",
+        "\u1ebc":"\u1ebd", "\u1ebe":"\u1ebf", "\u1ec0":"\u1ec1", "\u1ec2":"\u1ec3", "\u1ec4":"\u1ec5", "\u1ec6":"\u1ec7",
+        "\u1ec8":"\u1ec9", "\u1eca":"\u1ecb", "\u1ecc":"\u1ecd", "\u1ece":"\u1ecf", "\u1ed0":"\u1ed1", "\u1ed2":"\u1ed3",
+        "\u1ed4":"\u1ed5", "\u1ed6":"\u1ed7", "\u1ed8":"\u1ed9", "\u1eda":"\u1edb", "\u1edc":"\u1edd", "\u1ede":"\u1edf",
+        "\u1ee0":"\u1ee1", "\u1ee2":"\u1ee3", "\u1ee4":"\u1ee5", "\u1ee6":"\u1ee7", "\u1ee8":"\u1ee9", "\u1eea":"\u1eeb",
+        "\u1eec":"\u1eed", "\u1eee":"\u1eef", "\u1ef0":"\u1ef1", "\u1ef2":"\u1ef3", "\u1ef4":"\u1ef5", "\u1ef6":"\u1ef7",
+        "\u1ef8":"\u1ef9", "\u1efa":"\u1efb", "\u1efc":"\u1efd", "\u1efe":"\u1eff", "\u1f08":"\u1f00", "\u1f09":"\u1f01",
+        "\u1f0a":"\u1f02", "\u1f0b":"\u1f03", "\u1f0c":"\u1f04", "\u1f0d":"\u1f05", "\u1f0e":"\u1f06", "\u1f0f":"\u1f07",
+        "\u1f18":"\u1f10", "\u1f19":"\u1f11", "\u1f1a":"\u1f12", "\u1f1b":"\u1f13", "\u1f1c":"\u1f14", "\u1f1d":"\u1f15",
+        "\u1f28":"\u1f20", "\u1f29":"\u1f21", "\u1f2a":"\u1f22", "\u1f2b":"\u1f23", "\u1f2c":"\u1f24", "\u1f2d":"\u1f25",
+        "\u1f2e":"\u1f26", "\u1f2f":"\u1f27", "\u1f38":"\u1f30", "\u1f39":"\u1f31", "\u1f3a":"\u1f32", "\u1f3b":"\u1f33",
+        "\u1f3c":"\u1f34", "\u1f3d":"\u1f35", "\u1f3e":"\u1f36", "\u1f3f":"\u1f37", "\u1f48":"\u1f40", "\u1f49":"\u1f41",
+        "\u1f4a":"\u1f42", "\u1f4b":"\u1f43", "\u1f4c":"\u1f44", "\u1f4d":"\u1f45", "\u1f50":"\u03c5\u0313", "\u1f52":"\u03c5\u0313\u0300",
+        "\u1f54":"\u03c5\u0313\u0301", "\u1f56":"\u03c5\u0313\u0342", "\u1f59":"\u1f51", "\u1f5b":"\u1f53", "\u1f5d":"\u1f55", "\u1f5f":"\u1f57",
+        "\u1f68":"\u1f60", "\u1f69":"\u1f61", "\u1f6a":"\u1f62", "\u1f6b":"\u1f63", "\u1f6c":"\u1f64", "\u1f6d":"\u1f65",
+        "\u1f6e":"\u1f66", "\u1f6f":"\u1f67", "\u1f80":"\u1f00\u03b9", "\u1f81":"\u1f01\u03b9", "\u1f82":"\u1f02\u03b9", "\u1f83":"\u1f03\u03b9",
+        "\u1f84":"\u1f04\u03b9", "\u1f85":"\u1f05\u03b9", "\u1f86":"\u1f06\u03b9", "\u1f87":"\u1f07\u03b9", "\u1f88":"\u1f00\u03b9", "\u1f89":"\u1f01\u03b9",
+        "\u1f8a":"\u1f02\u03b9", "\u1f8b":"\u1f03\u03b9", "\u1f8c":"\u1f04\u03b9", "\u1f8d":"\u1f05\u03b9", "\u1f8e":"\u1f06\u03b9", "\u1f8f":"\u1f07\u03b9",
+        "\u1f90":"\u1f20\u03b9", "\u1f91":"\u1f21\u03b9", "\u1f92":"\u1f22\u03b9", "\u1f93":"\u1f23\u03b9", "\u1f94":"\u1f24\u03b9", "\u1f95":"\u1f25\u03b9",
+        "\u1f96":"\u1f26\u03b9", "\u1f97":"\u1f27\u03b9", "\u1f98":"\u1f20\u03b9", "\u1f99":"\u1f21\u03b9", "\u1f9a":"\u1f22\u03b9", "\u1f9b":"\u1f23\u03b9",
+        "\u1f9c":"\u1f24\u03b9", "\u1f9d":"\u1f25\u03b9", "\u1f9e":"\u1f26\u03b9", "\u1f9f":"\u1f27\u03b9", "\u1fa0":"\u1f60\u03b9", "\u1fa1":"\u1f61\u03b9",
+        "\u1fa2":"\u1f62\u03b9", "\u1fa3":"\u1f63\u03b9", "\u1fa4":"\u1f64\u03b9", "\u1fa5":"\u1f65\u03b9", "\u1fa6":"\u1f66\u03b9", "\u1fa7":"\u1f67\u03b9",
+        "\u1fa8":"\u1f60\u03b9", "\u1fa9":"\u1f61\u03b9", "\u1faa":"\u1f62\u03b9", "\u1fab":"\u1f63\u03b9", "\u1fac":"\u1f64\u03b9", "\u1fad":"\u1f65\u03b9",
+        "\u1fae":"\u1f66\u03b9", "\u1faf":"\u1f67\u03b9", "\u1fb2":"\u1f70\u03b9", "\u1fb3":"\u03b1\u03b9", "\u1fb4":"\u03ac\u03b9", "\u1fb6":"\u03b1\u0342",
+        "\u1fb7":"\u03b1\u0342\u03b9", "\u1fb8":"\u1fb0", "\u1fb9":"\u1fb1", "\u1fba":"\u1f70", "\u1fbb":"\u1f71", "\u1fbc":"\u03b1\u03b9",
+        "\u1fbe":"\u03b9", "\u1fc2":"\u1f74\u03b9", "\u1fc3":"\u03b7\u03b9", "\u1fc4":"\u03ae\u03b9", "\u1fc6":"\u03b7\u0342", "\u1fc7":"\u03b7\u0342\u03b9",
+        "\u1fc8":"\u1f72", "\u1fc9":"\u1f73", "\u1fca":"\u1f74", "\u1fcb":"\u1f75", "\u1fcc":"\u03b7\u03b9", "\u1fd2":"\u03b9\u0308\u0300",
+        "\u1fd3":"\u03b9\u0308\u0301", "\u1fd6":"\u03b9\u0342", "\u1fd7":"\u03b9\u0308\u0342", "\u1fd8":"\u1fd0", "\u1fd9":"\u1fd1", "\u1fda":"\u1f76",
+        "\u1fdb":"\u1f77", "\u1fe2":"\u03c5\u0308\u0300", "\u1fe3":"\u03c5\u0308\u0301", "\u1fe4":"\u03c1\u0313", "\u1fe6":"\u03c5\u0342", "\u1fe7":"\u03c5\u0308\u0342",
+        "\u1fe8":"\u1fe0", "\u1fe9":"\u1fe1", "\u1fea":"\u1f7a", "\u1feb":"\u1f7b", "\u1fec":"\u1fe5", "\u1ff2":"\u1f7c\u03b9",
+        "\u1ff3":"\u03c9\u03b9", "\u1ff4":"\u03ce\u03b9", "\u1ff6":"\u03c9\u0342", "\u1ff7":"\u03c9\u0342\u03b9", "\u1ff8":"\u1f78", "\u1ff9":"\u1f79",
+        "\u1ffa":"\u1f7c", "\u1ffb":"\u1f7d", "\u1ffc":"\u03c9\u03b9", "\u2126":"\u03c9", "\u212a":"\u006b", "\u212b":"\u00e5",
+        "\u2132":"\u214e", "\u2160":"\u2170", "\u2161":"\u2171", "\u2162":"\u2172", "\u2163":"\u2173", "\u2164":"\u2174",
+        "\u2165":"\u2175", "\u2166":"\u2176", "\u2167":"\u2177", "\u2168":"\u2178", "\u2169":"\u2179", "\u216a":"\u217a",
+        "\u216b":"\u217b", "\u216c":"\u217c", "\u216d":"\u217d", "\u216e":"\u217e", "\u216f":"\u217f", "\u2183":"\u2184",
+        "\u24b6":"\u24d0", "\u24b7":"\u24d1", "\u24b8":"\u24d2", "\u24b9":"\u24d3", "\u24ba":"\u24d4", "\u24bb":"\u24d5",
+        "\u24bc":"\u24d6", "\u24bd":"\u24d7", "\u24be":"\u24d8", "\u24bf":"\u24d9", "\u24c0":"\u24da", "\u24c1":"\u24db",
+        "\u24c2":"\u24dc", "\u24c3":"\u24dd", "\u24c4":"\u24de", "\u24c5":"\u24df", "\u24c6":"\u24e0", "\u24c7":"\u24e1",
+        "\u24c8":"\u24e2", "\u24c9":"\u24e3", "\u24ca":"\u24e4", "\u24cb":"\u24e5", "\u24cc":"\u24e6", "\u24cd":"\u24e7",
+        "\u24ce":"\u24e8", "\u24cf":"\u24e9", "\u2c00":"\u2c30", "\u2c01":"\u2c31", "\u2c02":"\u2c32", "\u2c03":"\u2c33",
+        "\u2c04":"\u2c34", "\u2c05":"\u2c35", "\u2c06":"\u2c36", "\u2c07":"\u2c37", "\u2c08":"\u2c38", "\u2c09":"\u2c39",
+        "\u2c0a":"\u2c3a", "\u2c0b":"\u2c3b", "\u2c0c":"\u2c3c", "\u2c0d":"\u2c3d", "\u2c0e":"\u2c3e", "\u2c0f":"\u2c3f",
+        "\u2c10":"\u2c40", "\u2c11":"\u2c41", "\u2c12":"\u2c42", "\u2c13":"\u2c43", "\u2c14":"\u2c44", "\u2c15":"\u2c45",
+        "\u2c16":"\u2c46", "\u2c17":"\u2c47", "\u2c18":"\u2c48", "\u2c19":"\u2c49", "\u2c1a":"\u2c4a", "\u2c1b":"\u2c4b",
+        "\u2c1c":"\u2c4c", "\u2c1d":"\u2c4d", "\u2c1e":"\u2c4e", "\u2c1f":"\u2c4f", "\u2c20":"\u2c50", "\u2c21":"\u2c51",
+        "\u2c22":"\u2c52", "\u2c23":"\u2c53", "\u2c24":"\u2c54", "\u2c25":"\u2c55", "\u2c26":"\u2c56", "\u2c27":"\u2c57",
+        "\u2c28":"\u2c58", "\u2c29":"\u2c59", "\u2c2a":"\u2c5a", "\u2c2b":"\u2c5b", "\u2c2c":"\u2c5c", "\u2c2d":"\u2c5d",
+        "\u2c2e":"\u2c5e", "\u2c2f":"\u2c5f", "\u2c60":"\u2c61", "\u2c62":"\u026b", "\u2c63":"\u1d7d", "\u2c64":"\u027d",
+        "\u2c67":"\u2c68", "\u2c69":"\u2c6a", "\u2c6b":"\u2c6c", "\u2c6d":"\u0251", "\u2c6e":"\u0271", "\u2c6f":"\u0250",
+        "\u2c70":"\u0252", "\u2c72":"\u2c73", "\u2c75":"\u2c76", "\u2c7e":"\u023f", "\u2c7f":"\u0240", "\u2c80":"\u2c81",
+        "\u2c82":"\u2c83", "\u2c84":"\u2c85", "\u2c86":"\u2c87", "\u2c88":"\u2c89", "\u2c8a":"\u2c8b", "\u2c8c":"\u2c8d",
+        "\u2c8e":"\u2c8f", "\u2c90":"\u2c91", "\u2c92":"\u2c93", "\u2c94":"\u2c95", "\u2c96":"\u2c97", "\u2c98":"\u2c99",
+        "\u2c9a":"\u2c9b", "\u2c9c":"\u2c9d", "\u2c9e":"\u2c9f", "\u2ca0":"\u2ca1", "\u2ca2":"\u2ca3", "\u2ca4":"\u2ca5",
+        "\u2ca6":"\u2ca7", "\u2ca8":"\u2ca9", "\u2caa":"\u2cab", "\u2cac":"\u2cad", "\u2cae":"\u2caf", "\u2cb0":"\u2cb1",
+        "\u2cb2":"\u2cb3", "\u2cb4":"\u2cb5", "\u2cb6":"\u2cb7", "\u2cb8":"\u2cb9", "\u2cba":"\u2cbb", "\u2cbc":"\u2cbd",
+        "\u2cbe":"\u2cbf", "\u2cc0":"\u2cc1", "\u2cc2":"\u2cc3", "\u2cc4":"\u2cc5", "\u2cc6":"\u2cc7", "\u2cc8":"\u2cc9",
+        "\u2cca":"\u2ccb", "\u2ccc":"\u2ccd", "\u2cce":"\u2ccf", "\u2cd0":"\u2cd1", "\u2cd2":"\u2cd3", "\u2cd4":"\u2cd5",
+        "\u2cd6":"\u2cd7", "\u2cd8":"\u2cd9", "\u2cda":"\u2cdb", "\u2cdc":"\u2cdd", "\u2cde":"\u2cdf", "\u2ce0":"\u2ce1",
+        "\u2ce2":"\u2ce3", "\u2ceb":"\u2cec", "\u2ced":"\u2cee", "\u2cf2":"\u2cf3", "\ua640":"\ua641", "\ua642":"\ua643",
+        "\ua644":"\ua645", "\ua646":"\ua647", "\ua648":"\ua649", "\ua64a":"\ua64b", "\ua64c":"\ua64d", "\ua64e":"\ua64f",
+        "\ua650":"\ua651", "\ua652":"\ua653", "\ua654":"\ua655", "\ua656":"\ua657", "\ua658":"\ua659", "\ua65a":"\ua65b",
+        "\ua65c":"\ua65d", "\ua65e":"\ua65f", "\ua660":"\ua661", "\ua662":"\ua663", "\ua664":"\ua665", "\ua666":"\ua667",
+        "\ua668":"\ua669", "\ua66a":"\ua66b", "\ua66c":"\ua66d", "\ua680":"\ua681", "\ua682":"\ua683", "\ua684":"\ua685",
+        "\ua686":"\ua687", "\ua688":"\ua689", "\ua68a":"\ua68b", "\ua68c":"\ua68d", "\ua68e":"\ua68f", "\ua690":"\ua691",
+        "\ua692":"\ua693", "\ua694":"\ua695", "\ua696":"\ua697", "\ua698":"\ua699", "\ua69a":"\ua69b", "\ua722":"\ua723",
+        "\ua724":"\ua725", "\ua726":"\ua727", "\ua728":"\ua729", "\ua72a":"\ua72b", "\ua72c":"\ua72d", "\ua72e":"\ua72f",
+        "\ua732":"\ua733", "\ua734":"\ua735", "\ua736":"\ua737", "\ua738":"\ua739", "\ua73a":"\ua73b", "\ua73c":"\ua73d",
+        "\ua73e":"\ua73f", "\ua740":"\ua741", "\ua742":"\ua743", "\ua744":"\ua745", "\ua746":"\ua747", "\ua748":"\ua749",
+        "\ua74a":"\ua74b", "\ua74c":"\ua74d", "\ua74e":"\ua74f", "\ua750":"\ua751", "\ua752":"\ua753", "\ua754":"\ua755",
+        "\ua756":"\ua757", "\ua758":"\ua759", "\ua75a":"\ua75b", "\ua75c":"\ua75d", "\ua75e":"\ua75f", "\ua760":"\ua761",
+        "\ua762":"\ua763", "\ua764":"\ua765", "\ua766":"\ua767", "\ua768":"\ua769", "\ua76a":"\ua76b", "\ua76c":"\ua76d",
+        "\ua76e":"\ua76f", "\ua779":"\ua77a", "\ua77b":"\ua77c", "\ua77d":"\u1d79", "\ua77e":"\ua77f", "\ua780":"\ua781",
+        "\ua782":"\ua783", "\ua784":"\ua785", "\ua786":"\ua787", "\ua78b":"\ua78c", "\ua78d":"\u0265", "\ua790":"\ua791",
+        "\ua792":"\ua793", "\ua796":"\ua797", "\ua798":"\ua799", "\ua79a":"\ua79b", "\ua79c":"\ua79d", "\ua79e":"\ua79f",
+        "\ua7a0":"\ua7a1", "\ua7a2":"\ua7a3", "\ua7a4":"\ua7a5", "\ua7a6":"\ua7a7", "\ua7a8":"\ua7a9", "\ua7aa":"\u0266",
+        "\ua7ab":"\u025c", "\ua7ac":"\u0261", "\ua7ad":"\u026c", "\ua7ae":"\u026a", "\ua7b0":"\u029e", "\ua7b1":"\u0287",
+        "\ua7b2":"\u029d", "\ua7b3":"\uab53", "\ua7b4":"\ua7b5", "\ua7b6":"\ua7b7", "\ua7b8":"\ua7b9", "\ua7ba":"\ua7bb",
+        "\ua7bc":"\ua7bd", "\ua7be":"\ua7bf", "\ua7c0":"\ua7c1", "\ua7c2":"\ua7c3", "\ua7c4":"\ua794", "\ua7c5":"\u0282",
+        "\ua7c6":"\u1d8e", "\ua7c7":"\ua7c8", "\ua7c9":"\ua7ca", "\ua7cb":"\u0264", "\ua7cc":"\ua7cd", "\ua7d0":"\ua7d1",
+        "\ua7d6":"\ua7d7", "\ua7d8":"\ua7d9", "\ua7da":"\ua7db", "\ua7dc":"\u019b", "\ua7f5":"\ua7f6", "\uab70":"\u13a0",
+        "\uab71":"\u13a1", "\uab72":"\u13a2", "\uab73":"\u13a3", "\uab74":"\u13a4", "\uab75":"\u13a5", "\uab76":"\u13a6",
+        "\uab77":"\u13a7", "\uab78":"\u13a8", "\uab79":"\u13a9", "\uab7a":"\u13aa", "\uab7b":"\u13ab", "\uab7c":"\u13ac",
+        "\uab7d":"\u13ad", "\uab7e":"\u13ae", "\uab7f":"\u13af", "\uab80":"\u13b0", "\uab81":"\u13b1", "\uab82":"\u13b2",
+        "\uab83":"\u13b3", "\uab84":"\u13b4", "\uab85":"\u13b5", "\uab86":"\u13b6", "\uab87":"\u13b7", "\uab88":"\u13b8",
+        "\uab89":"\u13b9", "\uab8a":"\u13ba", "\uab8b":"\u13bb", "\uab8c":"\u13bc", "\uab8d":"\u13bd", "\uab8e":"\u13be",
+        "\uab8f":"\u13bf", "\uab90":"\u13c0", "\uab91":"\u13c1", "\uab92":"\u13c2", "\uab93":"\u13c3", "\uab94":"\u13c4",
+        "\uab95":"\u13c5", "\uab96":"\u13c6", "\uab97":"\u13c7", "\uab98":"\u13c8", "\uab99":"\u13c9", "\uab9a":"\u13ca",
+        "\uab9b":"\u13cb", "\uab9c":"\u13cc", "\uab9d":"\u13cd", "\uab9e":"\u13ce", "\uab9f":"\u13cf", "\uaba0":"\u13d0",
+        "\uaba1":"\u13d1", "\uaba2":"\u13d2", "\uaba3":"\u13d3", "\uaba4":"\u13d4", "\uaba5":"\u13d5", "\uaba6":"\u13d6",
+        "\uaba7":"\u13d7", "\uaba8":"\u13d8", "\uaba9":"\u13d9", "\uabaa":"\u13da", "\uabab":"\u13db", "\uabac":"\u13dc",
+        "\uabad":"\u13dd", "\uabae":"\u13de", "\uabaf":"\u13df", "\uabb0":"\u13e0", "\uabb1":"\u13e1", "\uabb2":"\u13e2",
+        "\uabb3":"\u13e3", "\uabb4":"\u13e4", "\uabb5":"\u13e5", "\uabb6":"\u13e6", "\uabb7":"\u13e7", "\uabb8":"\u13e8",
+        "\uabb9":"\u13e9", "\uabba":"\u13ea", "\uabbb":"\u13eb", "\uabbc":"\u13ec", "\uabbd":"\u13ed", "\uabbe":"\u13ee",
+        "\uabbf":"\u13ef", "\ufb00":"\u0066\u0066", "\ufb01":"\u0066\u0069", "\ufb02":"\u0066\u006c", "\ufb03":"\u0066\u0066\u0069", "\ufb04":"\u0066\u0066\u006c",
+        "\ufb05":"\u0073\u0074", "\ufb06":"\u0073\u0074", "\ufb13":"\u0574\u0576", "\ufb14":"\u0574\u0565", "\ufb15":"\u0574\u056b", "\ufb16":"\u057e\u0576",
+        "\ufb17":"\u0574\u056d", "\uff21":"\uff41", "\uff22":"\uff42", "\uff23":"\uff43", "\uff24":"\uff44", "\uff25":"\uff45",
+        "\uff26":"\uff46", "\uff27":"\uff47", "\uff28":"\uff48", "\uff29":"\uff49", "\uff2a":"\uff4a", "\uff2b":"\uff4b",
+        "\uff2c":"\uff4c", "\uff2d":"\uff4d", "\uff2e":"\uff4e", "\uff2f":"\uff4f", "\uff30":"\uff50", "\uff31":"\uff51",
+        "\uff32":"\uff52", "\uff33":"\uff53", "\uff34":"\uff54", "\uff35":"\uff55", "\uff36":"\uff56", "\uff37":"\uff57",
+        "\uff38":"\uff58", "\uff39":"\uff59", "\uff3a":"\uff5a", "\ud801\udc00":"\ud801\udc28", "\ud801\udc01":"\ud801\udc29", "\ud801\udc02":"\ud801\udc2a",
+        "\ud801\udc03":"\ud801\udc2b", "\ud801\udc04":"\ud801\udc2c", "\ud801\udc05":"\ud801\udc2d", "\ud801\udc06":"\ud801\udc2e", "\ud801\udc07":"\ud801\udc2f", "\ud801\udc08":"\ud801\udc30",
+        "\ud801\udc09":"\ud801\udc31", "\ud801\udc0a":"\ud801\udc32", "\ud801\udc0b":"\ud801\udc33", "\ud801\udc0c":"\ud801\udc34", "\ud801\udc0d":"\ud801\udc35", "\ud801\udc0e":"\ud801\udc36",
+        "\ud801\udc0f":"\ud801\udc37", "\ud801\udc10":"\ud801\udc38", "\ud801\udc11":"\ud801\udc39", "\ud801\udc12":"\ud801\udc3a", "\ud801\udc13":"\ud801\udc3b", "\ud801\udc14":"\ud801\udc3c",
+        "\ud801\udc15":"\ud801\udc3d", "\ud801\udc16":"\ud801\udc3e", "\ud801\udc17":"\ud801\udc3f", "\ud801\udc18":"\ud801\udc40", "\ud801\udc19":"\ud801\udc41", "\ud801\udc1a":"\ud801\udc42",
+        "\ud801\udc1b":"\ud801\udc43", "\ud801\udc1c":"\ud801\udc44", "\ud801\udc1d":"\ud801\udc45", "\ud801\udc1e":"\ud801\udc46", "\ud801\udc1f":"\ud801\udc47", "\ud801\udc20":"\ud801\udc48",
+        "\ud801\udc21":"\ud801\udc49", "\ud801\udc22":"\ud801\udc4a", "\ud801\udc23":"\ud801\udc4b", "\ud801\udc24":"\ud801\udc4c", "\ud801\udc25":"\ud801\udc4d", "\ud801\udc26":"\ud801\udc4e",
+        "\ud801\udc27":"\ud801\udc4f", "\ud801\udcb0":"\ud801\udcd8", "\ud801\udcb1":"\ud801\udcd9", "\ud801\udcb2":"\ud801\udcda", "\ud801\udcb3":"\ud801\udcdb", "\ud801\udcb4":"\ud801\udcdc",
+        "\ud801\udcb5":"\ud801\udcdd", "\ud801\udcb6":"\ud801\udcde", "\ud801\udcb7":"\ud801\udcdf", "\ud801\udcb8":"\ud801\udce0", "\ud801\udcb9":"\ud801\udce1", "\ud801\udcba":"\ud801\udce2",
+        "\ud801\udcbb":"\ud801\udce3", "\ud801\udcbc":"\ud801\udce4", "\ud801\udcbd":"\ud801\udce5", "\ud801\udcbe":"\ud801\udce6", "\ud801\udcbf":"\ud801\udce7", "\ud801\udcc0":"\ud801\udce8",
+        "\ud801\udcc1":"\ud801\udce9", "\ud801\udcc2":"\ud801\udcea", "\ud801\udcc3":"\ud801\udceb", "\ud801\udcc4":"\ud801\udcec", "\ud801\udcc5":"\ud801\udced", "\ud801\udcc6":"\ud801\udcee",
+        "\ud801\udcc7":"\ud801\udcef", "\ud801\udcc8":"\ud801\udcf0", "\ud801\udcc9":"\ud801\udcf1", "\ud801\udcca":"\ud801\udcf2", "\ud801\udccb":"\ud801\udcf3", "\ud801\udccc":"\ud801\udcf4",
+        "\ud801\udccd":"\ud801\udcf5", "\ud801\udcce":"\ud801\udcf6", "\ud801\udccf":"\ud801\udcf7", "\ud801\udcd0":"\ud801\udcf8", "\ud801\udcd1":"\ud801\udcf9", "\ud801\udcd2":"\ud801\udcfa",
+        "\ud801\udcd3":"\ud801\udcfb", "\ud801\udd70":"\ud801\udd97", "\ud801\udd71":"\ud801\udd98", "\ud801\udd72":"\ud801\udd99", "\ud801\udd73":"\ud801\udd9a", "\ud801\udd74":"\ud801\udd9b",
+        "\ud801\udd75":"\ud801\udd9c", "\ud801\udd76":"\ud801\udd9d", "\ud801\udd77":"\ud801\udd9e", "\ud801\udd78":"\ud801\udd9f", "\ud801\udd79":"\ud801\udda0", "\ud801\udd7a":"\ud801\udda1",
+        "\ud801\udd7c":"\ud801\udda3", "\ud801\udd7d":"\ud801\udda4", "\ud801\udd7e":"\ud801\udda5", "\ud801\udd7f":"\ud801\udda6", "\ud801\udd80":"\ud801\udda7", "\ud801\udd81":"\ud801\udda8",
+        "\ud801\udd82":"\ud801\udda9", "\ud801\udd83":"\ud801\uddaa", "\ud801\udd84":"\ud801\uddab", "\ud801\udd85":"\ud801\uddac", "\ud801\udd86":"\ud801\uddad", "\ud801\udd87":"\ud801\uddae",
+        "\ud801\udd88":"\ud801\uddaf", "\ud801\udd89":"\ud801\uddb0", "\ud801\udd8a":"\ud801\uddb1", "\ud801\udd8c":"\ud801\uddb3", "\ud801\udd8d":"\ud801\uddb4", "\ud801\udd8e":"\ud801\uddb5",
+        "\ud801\udd8f":"\ud801\uddb6", "\ud801\udd90":"\ud801\uddb7", "\ud801\udd91":"\ud801\uddb8", "\ud801\udd92":"\ud801\uddb9", "\ud801\udd94":"\ud801\uddbb", "\ud801\udd95":"\ud801\uddbc",
+        "\ud803\udc80":"\ud803\udcc0", "\ud803\udc81":"\ud803\udcc1", "\ud803\udc82":"\ud803\udcc2", "\ud803\udc83":"\ud803\udcc3", "\ud803\udc84":"\ud803\udcc4", "\ud803\udc85":"\ud803\udcc5",
+        "\ud803\udc86":"\ud803\udcc6", "\ud803\udc87":"\ud803\udcc7", "\ud803\udc88":"\ud803\udcc8", "\ud803\udc89":"\ud803\udcc9", "\ud803\udc8a":"\ud803\udcca", "\ud803\udc8b":"\ud803\udccb",
+        "\ud803\udc8c":"\ud803\udccc", "\ud803\udc8d":"\ud803\udccd", "\ud803\udc8e":"\ud803\udcce", "\ud803\udc8f":"\ud803\udccf", "\ud803\udc90":"\ud803\udcd0", "\ud803\udc91":"\ud803\udcd1",
+        "\ud803\udc92":"\ud803\udcd2", "\ud803\udc93":"\ud803\udcd3", "\ud803\udc94":"\ud803\udcd4", "\ud803\udc95":"\ud803\udcd5", "\ud803\udc96":"\ud803\udcd6", "\ud803\udc97":"\ud803\udcd7",
+        "\ud803\udc98":"\ud803\udcd8", "\ud803\udc99":"\ud803\udcd9", "\ud803\udc9a":"\ud803\udcda", "\ud803\udc9b":"\ud803\udcdb", "\ud803\udc9c":"\ud803\udcdc", "\ud803\udc9d":"\ud803\udcdd",
+        "\ud803\udc9e":"\ud803\udcde", "\ud803\udc9f":"\ud803\udcdf", "\ud803\udca0":"\ud803\udce0", "\ud803\udca1":"\ud803\udce1", "\ud803\udca2":"\ud803\udce2", "\ud803\udca3":"\ud803\udce3",
+        "\ud803\udca4":"\ud803\udce4", "\ud803\udca5":"\ud803\udce5", "\ud803\udca6":"\ud803\udce6", "\ud803\udca7":"\ud803\udce7", "\ud803\udca8":"\ud803\udce8", "\ud803\udca9":"\ud803\udce9",
+        "\ud803\udcaa":"\ud803\udcea", "\ud803\udcab":"\ud803\udceb", "\ud803\udcac":"\ud803\udcec", "\ud803\udcad":"\ud803\udced", "\ud803\udcae":"\ud803\udcee", "\ud803\udcaf":"\ud803\udcef",
+        "\ud803\udcb0":"\ud803\udcf0", "\ud803\udcb1":"\ud803\udcf1", "\ud803\udcb2":"\ud803\udcf2", "\ud803\udd50":"\ud803\udd70", "\ud803\udd51":"\ud803\udd71", "\ud803\udd52":"\ud803\udd72",
+        "\ud803\udd53":"\ud803\udd73", "\ud803\udd54":"\ud803\udd74", "\ud803\udd55":"\ud803\udd75", "\ud803\udd56":"\ud803\udd76", "\ud803\udd57":"\ud803\udd77", "\ud803\udd58":"\ud803\udd78",
+        "\ud803\udd59":"\ud803\udd79", "\ud803\udd5a":"\ud803\udd7a", "\ud803\udd5b":"\ud803\udd7b", "\ud803\udd5c":"\ud803\udd7c", "\ud803\udd5d":"\ud803\udd7d", "\ud803\udd5e":"\ud803\udd7e",
+        "\ud803\udd5f":"\ud803\udd7f", "\ud803\udd60":"\ud803\udd80", "\ud803\udd61":"\ud803\udd81", "\ud803\udd62":"\ud803\udd82", "\ud803\udd63":"\ud803\udd83", "\ud803\udd64":"\ud803\udd84",
+        "\ud803\udd65":"\ud803\udd85", "\ud806\udca0":"\ud806\udcc0", "\ud806\udca1":"\ud806\udcc1", "\ud806\udca2":"\ud806\udcc2", "\ud806\udca3":"\ud806\udcc3", "\ud806\udca4":"\ud806\udcc4",
+        "\ud806\udca5":"\ud806\udcc5", "\ud806\udca6":"\ud806\udcc6", "\ud806\udca7":"\ud806\udcc7", "\ud806\udca8":"\ud806\udcc8", "\ud806\udca9":"\ud806\udcc9", "\ud806\udcaa":"\ud806\udcca",
+        "\ud806\udcab":"\ud806\udccb", "\ud806\udcac":"\ud806\udccc", "\ud806\udcad":"\ud806\udccd", "\ud806\udcae":"\ud806\udcce", "\ud806\udcaf":"\ud806\udccf", "\ud806\udcb0":"\ud806\udcd0",
+        "\ud806\udcb1":"\ud806\udcd1", "\ud806\udcb2":"\ud806\udcd2", "\ud806\udcb3":"\ud806\udcd3", "\ud806\udcb4":"\ud806\udcd4", "\ud806\udcb5":"\ud806\udcd5", "\ud806\udcb6":"\ud806\udcd6",
+        "\ud806\udcb7":"\ud806\udcd7", "\ud806\udcb8":"\ud806\udcd8", "\ud806\udcb9":"\ud806\udcd9", "\ud806\udcba":"\ud806\udcda", "\ud806\udcbb":"\ud806\udcdb", "\ud806\udcbc":"\ud806\udcdc",
+        "\ud806\udcbd":"\ud806\udcdd", "\ud806\udcbe":"\ud806\udcde", "\ud806\udcbf":"\ud806\udcdf", "\ud81b\ude40":"\ud81b\ude60", "\ud81b\ude41":"\ud81b\ude61", "\ud81b\ude42":"\ud81b\ude62",
+        "\ud81b\ude43":"\ud81b\ude63", "\ud81b\ude44":"\ud81b\ude64", "\ud81b\ude45":"\ud81b\ude65", "\ud81b\ude46":"\ud81b\ude66", "\ud81b\ude47":"\ud81b\ude67", "\ud81b\ude48":"\ud81b\ude68",
+        "\ud81b\ude49":"\ud81b\ude69", "\ud81b\ude4a":"\ud81b\ude6a", "\ud81b\ude4b":"\ud81b\ude6b", "\ud81b\ude4c":"\ud81b\ude6c", "\ud81b\ude4d":"\ud81b\ude6d", "\ud81b\ude4e":"\ud81b\ude6e",
+        "\ud81b\ude4f":"\ud81b\ude6f", "\ud81b\ude50":"\ud81b\ude70", "\ud81b\ude51":"\ud81b\ude71", "\ud81b\ude52":"\ud81b\ude72", "\ud81b\ude53":"\ud81b\ude73", "\ud81b\ude54":"\ud81b\ude74",
+        "\ud81b\ude55":"\ud81b\ude75", "\ud81b\ude56":"\ud81b\ude76", "\ud81b\ude57":"\ud81b\ude77", "\ud81b\ude58":"\ud81b\ude78", "\ud81b\ude59":"\ud81b\ude79", "\ud81b\ude5a":"\ud81b\ude7a",
+        "\ud81b\ude5b":"\ud81b\ude7b", "\ud81b\ude5c":"\ud81b\ude7c", "\ud81b\ude5d":"\ud81b\ude7d", "\ud81b\ude5e":"\ud81b\ude7e", "\ud81b\ude5f":"\ud81b\ude7f", "\ud83a\udd00":"\ud83a\udd22",
+        "\ud83a\udd01":"\ud83a\udd23", "\ud83a\udd02":"\ud83a\udd24", "\ud83a\udd03":"\ud83a\udd25", "\ud83a\udd04":"\ud83a\udd26", "\ud83a\udd05":"\ud83a\udd27", "\ud83a\udd06":"\ud83a\udd28",
+        "\ud83a\udd07":"\ud83a\udd29", "\ud83a\udd08":"\ud83a\udd2a", "\ud83a\udd09":"\ud83a\udd2b", "\ud83a\udd0a":"\ud83a\udd2c", "\ud83a\udd0b":"\ud83a\udd2d", "\ud83a\udd0c":"\ud83a\udd2e",
+        "\ud83a\udd0d":"\ud83a\udd2f", "\ud83a\udd0e":"\ud83a\udd30", "\ud83a\udd0f":"\ud83a\udd31", "\ud83a\udd10":"\ud83a\udd32", "\ud83a\udd11":"\ud83a\udd33", "\ud83a\udd12":"\ud83a\udd34",
+        "\ud83a\udd13":"\ud83a\udd35", "\ud83a\udd14":"\ud83a\udd36", "\ud83a\udd15":"\ud83a\udd37", "\ud83a\udd16":"\ud83a\udd38", "\ud83a\udd17":"\ud83a\udd39", "\ud83a\udd18":"\ud83a\udd3a",
+        "\ud83a\udd19":"\ud83a\udd3b", "\ud83a\udd1a":"\ud83a\udd3c", "\ud83a\udd1b":"\ud83a\udd3d", "\ud83a\udd1c":"\ud83a\udd3e", "\ud83a\udd1d":"\ud83a\udd3f", "\ud83a\udd1e":"\ud83a\udd40",
+        "\ud83a\udd1f":"\ud83a\udd41", "\ud83a\udd20":"\ud83a\udd42", "\ud83a\udd21":"\ud83a\udd43"
+    }'::JSONB AS case_folding) AS mappings
+$$;
+
+-- the lowercase of every character that has one
+CREATE FUNCTION lowercase(s TEXT) RETURNS TEXT
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT coalesce(string_agg(coalesce(lowercase_mapping ->> c, c), '' ORDER BY i), '')
+    FROM unnest(string_to_array(s, NULL)) WITH ORDINALITY AS chars (c, i),
+    (SELECT '{
+        "\u0041":"\u0061", "\u0042":"\u0062", "\u0043":"\u0063", "\u0044":"\u0064", "\u0045":"\u0065", "\u0046":"\u0066",
+        "\u0047":"\u0067", "\u0048":"\u0068", "\u0049":"\u0069", "\u004a":"\u006a", "\u004b":"\u006b", "\u004c":"\u006c",
+        "\u004d":"\u006d", "\u004e":"\u006e", "\u004f":"\u006f", "\u0050":"\u0070", "\u0051":"\u0071", "\u0052":"\u0072",
+        "\u0053":"\u0073", "\u0054":"\u0074", "\u0055":"\u0075", "\u0056":"\u0076", "\u0057":"\u0077", "\u0058":"\u0078",
+        "\u0059":"\u0079", "\u005a":"\u007a", "\u00c0":"\u00e0", "\u00c1":"\u00e1", "\u00c2":"\u00e2", "\u00c3":"\u00e3",
+        "\u00c4":"\u00e4", "\u00c5":"\u00e5", "\u00c6":"\u00e6", "\u00c7":"\u00e7", "\u00c8":"\u00e8", "\u00c9":"\u00e9",
+        "\u00ca":"\u00ea", "\u00cb":"\u00eb", "\u00cc":"\u00ec", "\u00cd":"\u00ed", "\u00ce":"\u00ee", "\u00cf":"\u00ef",
+        "\u00d0":"\u00f0", "\u00d1":"\u00f1", "\u00d2":"\u00f2", "\u00d3":"\u00f3", "\u00d4":"\u00f4", "\u00d5":"\u00f5",
+        "\u00d6":"\u00f6", "\u00d8":"\u00f8", "\u00d9":"\u00f9", "\u00da":"\u00fa", "\u00db":"\u00fb", "\u00dc":"\u00fc",
+        "\u00dd":"\u00fd", "\u00de":"\u00fe", "\u0100":"\u0101", "\u0102":"\u0103", "\u0104":"\u0105", "\u0106":"\u0107",
+        "\u0108":"\u0109", "\u010a":"\u010b", "\u010c":"\u010d", "\u010e":"\u010f", "\u0110":"\u0111", "\u0112":"\u0113",
+        "\u0114":"\u0115", "\u0116":"\u0117", "\u0118":"\u0119", "\u011a":"\u011b", "\u011c":"\u011d", "\u011e":"\u011f",
+        "\u0120":"\u0121", "\u0122":"\u0123", "\u0124":"\u0125", "\u0126":"\u0127", "\u0128":"\u0129", "\u012a":"\u012b",
+        "\u012c":"\u012d", "\u012e":"\u012f", "\u0130":"\u0069\u0307", "\u0132":"\u0133", "\u0134":"\u0135", "\u0136":"\u0137",
+        "\u0139":"\u013a", "\u013b":"\u013c", "\u013d":"\u013e", "\u013f":"\u0140", "\u0141":"\u0142", "\u0143":"\u0144",
+        "\u0145":"\u0146", "\u0147":"\u0148", "\u014a":"\u014b", "\u014c":"\u014d", "\u014e":"\u014f", "\u0150":"\u0151",
+        "\u0152":"\u0153", "\u0154":"\u0155", "\u0156":"\u0157", "\u0158":"\u0159", "\u015a":"\u015b", "\u015c":"\u015d",
+        "\u015e":"\u015f", "\u0160":"\u0161", "\u0162":"\u0163", "\u0164":"\u0165", "\u0166":"\u0167", "\u0168":"\u0169",
+        "\u016a":"\u016b", "\u016c":"\u016d", "\u016e":"\u016f", "\u0170":"\u0171", "\u0172":"\u0173", "\u0174":"\u0175",
+        "\u0176":"\u0177", "\u0178":"\u00ff", "\u0179":"\u017a", "\u017b":"\u017c", "\u017d":"\u017e", "\u0181":"\u0253",
+        "\u0182":"\u0183", "\u0184":"\u0185", "\u0186":"\u0254", "\u0187":"\u0188", "\u0189":"\u0256", "\u018a":"\u0257",
+        "\u018b":"\u018c", "\u018e":"\u01dd", "\u018f":"\u0259", "\u0190":"\u025b", "\u0191":"\u0192", "\u0193":"\u0260",
+        "\u0194":"\u0263", "\u0196":"\u0269", "\u0197":"\u0268", "\u0198":"\u0199", "\u019c":"\u026f", "\u019d":"\u0272",
+        "\u019f":"\u0275", "\u01a0":"\u01a1", "\u01a2":"\u01a3", "\u01a4":"\u01a5", "\u01a6":"\u0280", "\u01a7":"\u01a8",
+        "\u01a9":"\u0283", "\u01ac":"\u01ad", "\u01ae":"\u0288", "\u01af":"\u01b0", "\u01b1":"\u028a", "\u01b2":"\u028b",
+        "\u01b3":"\u01b4", "\u01b5":"\u01b6", "\u01b7":"\u0292", "\u01b8":"\u01b9", "\u01bc":"\u01bd", "\u01c4":"\u01c6",
+        "\u01c5":"\u01c6", "\u01c7":"\u01c9", "\u01c8":"\u01c9", "\u01ca":"\u01cc", "\u01cb":"\u01cc", "\u01cd":"\u01ce",
+        "\u01cf":"\u01d0", "\u01d1":"\u01d2", "\u01d3":"\u01d4", "\u01d5":"\u01d6", "\u01d7":"\u01d8", "\u01d9":"\u01da",
+        "\u01db":"\u01dc", "\u01de":"\u01df", "\u01e0":"\u01e1", "\u01e2":"\u01e3", "\u01e4":"\u01e5", "\u01e6":"\u01e7",
+        "\u01e8":"\u01e9", "\u01ea":"\u01eb", "\u01ec":"\u01ed", "\u01ee":"\u01ef", "\u01f1":"\u01f3", "\u01f2":"\u01f3",
+        "\u01f4":"\u01f5", "\u01f6":"\u0195", "\u01f7":"\u01bf", "\u01f8":"\u01f9", "\u01fa":"\u01fb", "\u01fc":"\u01fd",
+        "\u01fe":"\u01ff", "\u0200":"\u0201", "\u0202":"\u0203", "\u0204":"\u0205", "\u0206":"\u0207", "\u0208":"\u0209",
+        "\u020a":"\u020b", "\u020c":"\u020d", "\u020e":"\u020f", "\u0210":"\u0211", "\u0212":"\u0213", "\u0214":"\u0215",
+        "\u0216":"\u0217", "\u0218":"\u0219", "\u021a":"\u021b", "\u021c":"\u021d", "\u021e":"\u021f", "\u0220":"\u019e",
+        "\u0222":"\u0223", "\u0224":"\u0225", "\u0226":"\u0227", "\u0228":"\u0229", "\u022a":"\u022b", "\u022c":"\u022d",
+        "\u022e":"\u022f", "\u0230":"\u0231", "\u0232":"\u0233", "\u023a":"\u2c65", "\u023b":"\u023c", "\u023d":"\u019a",
+        "\u023e":"\u2c66", "\u0241":"\u0242", "\u0243":"\u0180", "\u0244":"\u0289", "\u0245":"\u028c", "\u0246":"\u0247",
+        "\u0248":"\u0249", "\u024a":"\u024b", "\u024c":"\u024d", "\u024e":"\u024f", "\u0370":"\u0371", "\u0372":"\u0373",
+        "\u0376":"\u0377", "\u037f":"\u03f3", "\u0386":"\u03ac", "\u0388":"\u03ad", "\u0389":"\u03ae", "\u038a":"\u03af",
+        "\u038c":"\u03cc", "\u038e":"\u03cd", "\u038f":"\u03ce", "\u0391":"\u03b1", "\u0392":"\u03b2", "\u0393":"\u03b3",
+        "\u0394":"\u03b4", "\u0395":"\u03b5", "\u0396":"\u03b6", "\u0397":"\u03b7", "\u0398":"\u03b8", "\u0399":"\u03b9",
+        "\u039a":"\u03ba", "\u039b":"\u03bb", "\u039c":"\u03bc", "\u039d":"\u03bd", "\u039e":"\u03be", "\u039f":"\u03bf",
+        "\u03a0":"\u03c0", "\u03a1":"\u03c1", "\u03a3":"\u03c3", "\u03a4":"\u03c4", "\u03a5":"\u03c5", "\u03a6":"\u03c6",
+        "\u03a7":"\u03c7", "\u03a8":"\u03c8", "\u03a9":"\u03c9", "\u03aa":"\u03ca", "\u03ab":"\u03cb", "\u03cf":"\u03d7",
+        "\u03d8":"\u03d9", "\u03da":"\u03db", "\u03dc":"\u03dd", "\u03de":"\u03df", "\u03e0":"\u03e1", "\u03e2":"\u03e3",
+        "\u03e4":"\u03e5", "\u03e6":"\u03e7", "\u03e8":"\u03e9", "\u03ea":"\u03eb", "\u03ec":"\u03ed", "\u03ee":"\u03ef",
+        "\u03f4":"\u03b8", "\u03f7":"\u03f8", "\u03f9":"\u03f2", "\u03fa":"\u03fb", "\u03fd":"\u037b", "\u03fe":"\u037c",
+        "\u03ff":"\u037d", "\u0400":"\u0450", "\u0401":"\u0451", "\u0402":"\u0452", "\u0403":"\u0453", "\u0404":"\u0454",
+        "\u0405":"\u0455", "\u0406":"\u0456", "\u0407":"\u0457", "\u0408":"\u0458", "\u0409":"\u0459", "\u040a":"\u045a",
+        "\u040b":"\u045b", "\u040c":"\u045c", "\u040d":"\u045d", "\u040e":"\u045e", "\u040f":"\u045f", "\u0410":"\u0430",
+        "\u0411":"\u0431", "\u0412":"\u0432", "\u0413":"\u0433", "\u0414":"\u0434", "\u0415":"\u0435", "\u0416":"\u0436",
+        "\u0417":"\u0437", "\u0418":"\u0438", "\u0419":"\u0439", "\u041a":"\u043a", "\u041b":"\u043b", "\u041c":"\u043c",
+        "\u041d":"\u043d", "\u041e":"\u043e", "\u041f":"\u043f", "\u0420":"\u0440", "\u0421":"\u0441", "\u0422":"\u0442",
+        "\u0423":"\u0443", "\u0424":"\u0444", "\u0425":"\u0445", "\u0426":"\u0446", "\u0427":"\u0447", "\u0428":"\u0448",
+        "\u0429":"\u0449", "\u042a":"\u044a", "\u042b":"\u044b", "\u042c":"\u044c", "\u042d":"\u044d", "\u042e":"\u044e",
+        "\u042f":"\u044f", "\u0460":"\u0461", "\u0462":"\u0463", "\u0464":"\u0465", "\u0466":"\u0467", "\u0468":"\u0469",
+        "\u046a":"\u046b", "\u046c":"\u046d", "\u046e":"\u046f", "\u0470":"\u0471", "\u0472":"\u0473", "\u0474":"\u0475",
+        "\u0476":"\u0477", "\u0478":"\u0479", "\u047a":"\u047b", "\u047c":"\u047d", "\u047e":"\u047f", "\u0480":"\u0481",
+        "\u048a":"\u048b", "\u048c":"\u048d", "\u048e":"\u048f", "\u0490":"\u0491", "\u0492":"\u0493", "\u0494":"\u0495",
+        "\u0496":"\u0497", "\u0498":"\u0499", "\u049a":"\u049b", "\u049c":"\u049d", "\u049e":"\u049f", "\u04a0":"\u04a1",
+        "\u04a2":"\u04a3", "\u04a4":"\u04a5", "\u04a6":"\u04a7", "\u04a8":"\u04a9", "\u04aa":"\u04ab", "\u04ac":"\u04ad",
+        "\u04ae":"\u04af", "\u04b0":"\u04b1", "\u04b2":"\u04b3", "\u04b4":"\u04b5", "\u04b6":"\u04b7", "\u04b8":"\u04b9",
+        "\u04ba":"\u04bb", "\u04bc":"\u04bd", "\u04be":"\u04bf", "\u04c0":"\u04cf", "\u04c1":"\u04c2", "\u04c3":"\u04c4",
+        "\u04c5":"\u04c6", "\u04c7":"\u04c8", "\u04c9":"\u04ca", "\u04cb":"\u04cc", "\u04cd":"\u04ce", "\u04d0":"\u04d1",
+        "\u04d2":"\u04d3", "\u04d4":"\u04d5", "\u04d6":"\u04d7", "\u04d8":"\u04d9", "\u04da":"\u04db", "\u04dc":"\u04dd",
+        "\u04de":"\u04df", "\u04e0":"\u04e1", "\u04e2":"\u04e3", "\u04e4":"\u04e5", "\u04e6":"\u04e7", "\u04e8":"\u04e9",
+        "\u04ea":"\u04eb", "\u04ec":"\u04ed", "\u04ee":"\u04ef", "\u04f0":"\u04f1", "\u04f2":"\u04f3", "\u04f4":"\u04f5",
+        "\u04f6":"\u04f7", "\u04f8":"\u04f9", "\u04fa":"\u04fb", "\u04fc":"\u04fd", "\u04fe":"\u04ff", "\u0500":"\u0501",
+        "\u0502":"\u0503", "\u0504":"\u0505", "\u0506":"\u0507", "\u0508":"\u0509", "\u050a":"\u050b", "\u050c":"\u050d",
+        "\u050e":"\u050f", "\u0510":"\u0511", "\u0512":"\u0513", "\u0514":"\u0515", "\u0516":"\u0517", "\u0518":"\u0519",
+        "\u051a":"\u051b", "\u051c":"\u051d", "\u051e":"\u051f", "\u0520":"\u0521", "\u0522":"\u0523", "\u0524":"\u0525",
+        "\u0526":"\u0527", "\u0528":"\u0529", "\u052a":"\u052b", "\u052c":"\u052d", "\u052e":"\u052f", "\u0531":"\u0561",
+        "\u0532":"\u0562", "\u0533":"\u0563", "\u0534":"\u0564", "\u0535":"\u0565", "\u0536":"\u0566", "\u0537":"\u0567",
+        "\u0538":"\u0568", "\u0539":"\u0569", "\u053a":"\u056a", "\u053b":"\u056b", "\u053c":"\u056c", "\u053d":"\u056d",
+        "\u053e":"\u056e", "\u053f":"\u056f", "\u0540":"\u0570", "\u0541":"\u0571", "\u0542":"\u0572", "\u0543":"\u0573",
+        "\u0544":"\u0574", "\u0545":"\u0575", "\u0546":"\u0576", "\u0547":"\u0577", "\u0548":"\u0578", "\u0549":"\u0579",
+        "\u054a":"\u057a", "\u054b":"\u057b", "\u054c":"\u057c", "\u054d":"\u057d", "\u054e":"\u057e", "\u054f":"\u057f",
+        "\u0550":"\u0580", "\u0551":"\u0581", "\u0552":"\u0582", "\u0553":"\u0583", "\u0554":"\u0584", "\u0555":"\u0585",
+        "\u0556":"\u0586", "\u10a0":"\u2d00", "\u10a1":"\u2d01", "\u10a2":"\u2d02", "\u10a3":"\u2d03", "\u10a4":"\u2d04",
+        "\u10a5":"\u2d05", "\u10a6":"\u2d06", "\u10a7":"\u2d07", "\u10a8":"\u2d08", "\u10a9":"\u2d09", "\u10aa":"\u2d0a",
+        "\u10ab":"\u2d0b", "\u10ac":"\u2d0c", "\u10ad":"\u2d0d", "\u10ae":"\u2d0e", "\u10af":"\u2d0f", "\u10b0":"\u2d10",
+        "\u10b1":"\u2d11", "\u10b2":"\u2d12", "\u10b3":"\u2d13", "\u10b4":"\u2d14", "\u10b5":"\u2d15", "\u10b6":"\u2d16",
+        "\u10b7":"\u2d17", "\u10b8":"\u2d18", "\u10b9":"\u2d19", "\u10ba":"\u2d1a", "\u10bb":"\u2d1b", "\u10bc":"\u2d1c",
+        "\u10bd":"\u2d1d", "\u10be":"\u2d1e", "\u10bf":"\u2d1f", "\u10c0":"\u2d20", "\u10c1":"\u2d21", "\u10c2":"\u2d22",
+        "\u10c3":"\u2d23", "\u10c4":"\u2d24", "\u10c5":"\u2d25", "\u10c7":"\u2d27", "\u10cd":"\u2d2d", "\u13a0":"\uab70",
+        "\u13a1":"\uab71", "\u13a2":"\uab72", "\u13a3":"\uab73", "\u13a4":"\uab74", "\u13a5":"\uab75", "\u13a6":"\uab76",
+        "\u13a7":"\uab77", "\u13a8":"\uab78", "\u13a9":"\uab79", "\u13aa":"\uab7a", "\u13ab":"\uab7b", "\u13ac":"\uab7c",
+        "\u13ad":"\uab7d", "\u13ae":"\uab7e", "\u13af":"\uab7f", "\u13b0":"\uab80", "\u13b1":"\uab81", "\u13b2":"\uab82",
+        "\u13b3":"\uab83", "\u13b4":"\uab84", "\u13b5":"\uab85", "\u13b6":"\uab86", "\u13b7":"\uab87", "\u13b8":"\uab88",
+        "\u13b9":"\uab89", "\u13ba":"\uab8a", "\u13bb":"\uab8b", "\u13bc":"\uab8c", "\u13bd":"\uab8d", "\u13be":"\uab8e",
+        "\u13bf":"\uab8f", "\u13c0":"\uab90", "\u13c1":"\uab91", "\u13c2":"\uab92", "\u13c3":"\uab93", "\u13c4":"\uab94",
+        "\u13c5":"\uab95", "\u13c6":"\uab96", "\u13c7":"\uab97", "\u13c8":"\uab98", "\u13c9":"\uab99", "\u13ca":"\uab9a",
+        "\u13cb":"\uab9b", "\u13cc":"\uab9c", "\u13cd":"\uab9d", "\u13ce":"\uab9e", "\u13cf":"\uab9f", "\u13d0":"\uaba0",
+        "\u13d1":"\uaba1", "\u13d2":"\uaba2", "\u13d3":"\uaba3", "\u13d4":"\uaba4", "\u13d5":"\uaba5", "\u13d6":"\uaba6",
+        "\u13d7":"\uaba7", "\u13d8":"\uaba8", "\u13d9":"\uaba9", "\u13da":"\uabaa", "\u13db":"\uabab", "\u13dc":"\uabac",
+        "\u13dd":"\uabad", "\u13de":"\uabae", "\u13df":"\uabaf", "\u13e0":"\uabb0", "\u13e1":"\uabb1", "\u13e2":"\uabb2",
+        "\u13e3":"\uabb3", "\u13e4":"\uabb4", "\u13e5":"\uabb5", "\u13e6":"\uabb6", "\u13e7":"\uabb7", "\u13e8":"\uabb8",
+        "\u13e9":"\uabb9", "\u13ea":"\uabba", "\u13eb":"\uabbb", "\u13ec":"\uabbc", "\u13ed":"\uabbd", "\u13ee":"\uabbe",
+        "\u13ef":"\uabbf", "\u13f0":"\u13f8", "\u13f1":"\u13f9", "\u13f2":"\u13fa", "\u13f3":"\u13fb", "\u13f4":"\u13fc",
+        "\u13f5":"\u13fd", "\u1c89":"\u1c8a", "\u1c90":"\u10d0", "\u1c91":"\u10d1", "\u1c92":"\u10d2", "\u1c93":"\u10d3",
+        "\u1c94":"\u10d4", "\u1c95":"\u10d5", "\u1c96":"\u10d6", "\u1c97":"\u10d7", "\u1c98":"\u10d8", "\u1c99":"\u10d9",
+        "\u1c9a":"\u10da", "\u1c9b":"\u10db", "\u1c9c":"\u10dc", "\u1c9d":"\u10dd", "\u1c9e":"\u10de", "\u1c9f":"\u10df",
+        "\u1ca0":"\u10e0", "\u1ca1":"\u10e1", "\u1ca2":"\u10e2", "\u1ca3":"\u10e3", "\u1ca4":"\u10e4", "\u1ca5":"\u10e5",
+        "\u1ca6":"\u10e6", "\u1ca7":"\u10e7", "\u1ca8":"\u10e8", "\u1ca9":"\u10e9", "\u1caa":"\u10ea", "\u1cab":"\u10eb",
+        "\u1cac":"\u10ec", "\u1cad":"\u10ed", "\u1cae":"\u10ee", "\u1caf":"\u10ef", "\u1cb0":"\u10f0", "\u1cb1":"\u10f1",
+        "\u1cb2":"\u10f2", "\u1cb3":"\u10f3", "\u1cb4":"\u10f4", "\u1cb5":"\u10f5", "\u1cb6":"\u10f6", "\u1cb7":"\u10f7",
+        "\u1cb8":"\u10f8", "\u1cb9":"\u10f9", "\u1cba":"\u10fa", "\u1cbd":"\u10fd", "\u1cbe":"\u10fe", "\u1cbf":"\u10ff",
+        "\u1e00":"\u1e01", "\u1e02":"\u1e03", "\u1e04":"\u1e05", "\u1e06":"\u1e07", "\u1e08":"\u1e09", "\u1e0a":"\u1e0b",
+        "\u1e0c":"\u1e0d", "\u1e0e":"\u1e0f", "\u1e10":"\u1e11", "\u1e12":"\u1e13", "\u1e14":"\u1e15", "\u1e16":"\u1e17",
+        "\u1e18":"\u1e19", "\u1e1a":"\u1e1b", "\u1e1c":"\u1e1d", "\u1e1e":"\u1e1f", "\u1e20":"\u1e21", "\u1e22":"\u1e23",
+        "\u1e24":"\u1e25", "\u1e26":"\u1e27", "\u1e28":"\u1e29", "\u1e2a":"\u1e2b", "\u1e2c":"\u1e2d", "\u1e2e":"\u1e2f",
+        "\u1e30":"\u1e31", "\u1e32":"\u1e33", "\u1e34":"\u1e35", "\u1e36":"\u1e37", "\u1e38":"\u1e39", "\u1e3a":"\u1e3b",
+        "\u1e3c":"\u1e3d", "\u1e3e":"\u1e3f", "\u1e40":"\u1e41", "\u1e42":"\u1e43", "\u1e44":"\u1e45", "\u1e46":"\u1e47",
+        "\u1e48":"\u1e49", "\u1e4a":"\u1e4b", "\u1e4c":"\u1e4d", "\u1e4e":"\u1e4f", "\u1e50":"\u1e51", "\u1e52":"\u1e53",
+        "\u1e54":"\u1e55", "\u1e56":"\u1e57", "\u1e58":"\u1e59", "\u1e5a":"\u1e5b", "\u1e5c":"\u1e5d", "\u1e5e":"\u1e5f",
+        "\u1e60":"\u1e61", "\u1e62":"\u1e63", "\u1e64":"\u1e65", "\u1e66":"\u1e67", "\u1e68":"\u1e69", "\u1e6a":"\u1e6b",
+        "\u1e6c":"\u1e6d", "\u1e6e":"\u1e6f", "\u1e70":"\u1e71", "\u1e72":"\u1e73", "\u1e74":"\u1e75", "\u1e76":"\u1e77",
+        "\u1e78":"\u1e79", "\u1e7a":"\u1e7b", "\u1e7c":"\u1e7d", "\u1e7e":"\u1e7f", "\u1e80":"\u1e81", "\u1e82":"\u1e83",
+        "\u1e84":"\u1e85", "\u1e86":"\u1e87", "\u1e88":"\u1e89", "\u1e8a":"\u1e8b", "\u1e8c":"\u1e8d", "\u1e8e":"\u1e8f",
+        "\u1e90":"\u1e91", "\u1e92":"\u1e93", "\u1e94":"\u1e95", "\u1e9e":"\u00df", "\u1ea0":"\u1ea1", "\u1ea2":"\u1ea3",
+        "\u1ea4":"\u1ea5", "\u1ea6":"\u1ea7", "\u1ea8":"\u1ea9", "\u1eaa":"\u1eab", "\u1eac":"\u1ead", "\u1eae":"\u1eaf",
+        "\u1eb0":"\u1eb1", "\u1eb2":"\u1eb3", "\u1eb4":"\u1eb5", "\u1eb6":"\u1eb7", "\u1eb8":"\u1eb9", "\u1eba":"\u1ebb",
+        "\u1ebc":"\u1ebd", "\u1ebe":"\u1ebf", "\u1ec0":"\u1ec1", "\u1ec2":"\u1ec3", "\u1ec4":"\u1ec5", "\u1ec6":"\u1ec7",
+        "\u1ec8":"\u1ec9", "\u1eca":"\u1ecb", "\u1ecc":"\u1ecd", "\u1ece":"\u1ecf", "\u1ed0":"\u1ed1", "\u1ed2":"\u1ed3",
+        "\u1ed4":"\u1ed5", "\u1ed6":"\u1ed7", "\u1ed8":"\u1ed9", "\u1eda":"\u1edb", "\u1edc":"\u1edd", "\u1ede":"\u1edf",
+        "\u1ee0":"\u1ee1", "\u1ee2":"\u1ee3", "\u1ee4":"\u1ee5", "\u1ee6":"\u1ee7", "\u1ee8":"\u1ee9", "\u1eea":"\u1eeb",
+        "\u1eec":"\u1eed", "\u1eee":"\u1eef", "\u1ef0":"\u1ef1", "\u1ef2":"\u1ef3", "\u1ef4":"\u1ef5", "\u1ef6":"\u1ef7",
+        "\u1ef8":"\u1ef9", "\u1efa":"\u1efb", "\u1efc":"\u1efd", "\u1efe":"\u1eff", "\u1f08":"\u1f00", "\u1f09":"\u1f01",
+        "\u1f0a":"\u1f02", "\u1f0b":"\u1f03", "\u1f0c":"\u1f04", "\u1f0d":"\u1f05", "\u1f0e":"\u1f06", "\u1f0f":"\u1f07",
+        "\u1f18":"\u1f10", "\u1f19":"\u1f11", "\u1f1a":"\u1f12", "\u1f1b":"\u1f13", "\u1f1c":"\u1f14", "\u1f1d":"\u1f15",
+        "\u1f28":"\u1f20", "\u1f29":"\u1f21", "\u1f2a":"\u1f22", "\u1f2b":"\u1f23", "\u1f2c":"\u1f24", "\u1f2d":"\u1f25",
+        "\u1f2e":"\u1f26", "\u1f2f":"\u1f27", "\u1f38":"\u1f30", "\u1f39":"\u1f31", "\u1f3a":"\u1f32", "\u1f3b":"\u1f33",
+        "\u1f3c":"\u1f34", "\u1f3d":"\u1f35", "\u1f3e":"\u1f36", "\u1f3f":"\u1f37", "\u1f48":"\u1f40", "\u1f49":"\u1f41",
+        "\u1f4a":"\u1f42", "\u1f4b":"\u1f43", "\u1f4c":"\u1f44", "\u1f4d":"\u1f45", "\u1f59":"\u1f51", "\u1f5b":"\u1f53",
+        "\u1f5d":"\u1f55", "\u1f5f":"\u1f57", "\u1f68":"\u1f60", "\u1f69":"\u1f61", "\u1f6a":"\u1f62", "\u1f6b":"\u1f63",
+        "\u1f6c":"\u1f64", "\u1f6d":"\u1f65", "\u1f6e":"\u1f66", "\u1f6f":"\u1f67", "\u1f88":"\u1f80", "\u1f89":"\u1f81",
+        "\u1f8a":"\u1f82", "\u1f8b":"\u1f83", "\u1f8c":"\u1f84", "\u1f8d":"\u1f85", "\u1f8e":"\u1f86", "\u1f8f":"\u1f87",
+        "\u1f98":"\u1f90", "\u1f99":"\u1f91", "\u1f9a":"\u1f92", "\u1f9b":"\u1f93", "\u1f9c":"\u1f94", "\u1f9d":"\u1f95",
+        "\u1f9e":"\u1f96", "\u1f9f":"\u1f97", "\u1fa8":"\u1fa0", "\u1fa9":"\u1fa1", "\u1faa":"\u1fa2", "\u1fab":"\u1fa3",
+        "\u1fac":"\u1fa4", "\u1fad":"\u1fa5", "\u1fae":"\u1fa6", "\u1faf":"\u1fa7", "\u1fb8":"\u1fb0", "\u1fb9":"\u1fb1",
+        "\u1fba":"\u1f70", "\u1fbb":"\u1f71", "\u1fbc":"\u1fb3", "\u1fc8":"\u1f72", "\u1fc9":"\u1f73", "\u1fca":"\u1f74",
+        "\u1fcb":"\u1f75", "\u1fcc":"\u1fc3", "\u1fd8":"\u1fd0", "\u1fd9":"\u1fd1", "\u1fda":"\u1f76", "\u1fdb":"\u1f77",
+        "\u1fe8":"\u1fe0", "\u1fe9":"\u1fe1", "\u1fea":"\u1f7a", "\u1feb":"\u1f7b", "\u1fec":"\u1fe5", "\u1ff8":"\u1f78",
+        "\u1ff9":"\u1f79", "\u1ffa":"\u1f7c", "\u1ffb":"\u1f7d", "\u1ffc":"\u1ff3", "\u2126":"\u03c9", "\u212a":"\u006b",
+        "\u212b":"\u00e5", "\u2132":"\u214e", "\u2160":"\u2170", "\u2161":"\u2171", "\u2162":"\u2172", "\u2163":"\u2173",
+        "\u2164":"\u2174", "\u2165":"\u2175", "\u2166":"\u2176", "\u2167":"\u2177", "\u2168":"\u2178", "\u2169":"\u2179",
+        "\u216a":"\u217a", "\u216b":"\u217b", "\u216c":"\u217c", "\u216d":"\u217d", "\u216e":"\u217e", "\u216f":"\u217f",
+        "\u2183":"\u2184", "\u24b6":"\u24d0", "\u24b7":"\u24d1", "\u24b8":"\u24d2", "\u24b9":"\u24d3", "\u24ba":"\u24d4",
+        "\u24bb":"\u24d5", "\u24bc":"\u24d6", "\u24bd":"\u24d7", "\u24be":"\u24d8", "\u24bf":"\u24d9", "\u24c0":"\u24da",
+        "\u24c1":"\u24db", "\u24c2":"\u24dc", "\u24c3":"\u24dd", "\u24c4":"\u24de", "\u24c5":"\u24df", "\u24c6":"\u24e0",
+        "\u24c7":"\u24e1", "\u24c8":"\u24e2", "\u24c9":"\u24e3", "\u24ca":"\u24e4", "\u24cb":"\u24e5", "\u24cc":"\u24e6",
+        "\u24cd":"\u24e7", "\u24ce":"\u24e8", "\u24cf":"\u24e9", "\u2c00":"\u2c30", "\u2c01":"\u2c31", "\u2c02":"\u2c32",
+        "\u2c03":"\u2c33", "\u2c04":"\u2c34", "\u2c05":"\u2c35", "\u2c06":"\u2c36", "\u2c07":"\u2c37", "\u2c08":"\u2c38",
+        "\u2c09":"\u2c39", "\u2c0a":"\u2c3a", "\u2c0b":"\u2c3b", "\u2c0c":"\u2c3c", "\u2c0d":"\u2c3d", "\u2c0e":"\u2c3e",
+        "\u2c0f":"\u2c3f", "\u2c10":"\u2c40", "\u2c11":"\u2c41", "\u2c12":"\u2c42", "\u2c13":"\u2c43", "\u2c14":"\u2c44",
+        "\u2c15":"\u2c45", "\u2c16":"\u2c46", "\u2c17":"\u2c47", "\u2c18":"\u2c48", "\u2c19":"\u2c49", "\u2c1a":"\u2c4a",
+        "\u2c1b":"\u2c4b", "\u2c1c":"\u2c4c", "\u2c1d":"\u2c4d", "\u2c1e":"\u2c4e", "\u2c1f":"\u2c4f", "\u2c20":"\u2c50",
+        "\u2c21":"\u2c51", "\u2c22":"\u2c52", "\u2c23":"\u2c53", "\u2c24":"\u2c54", "\u2c25":"\u2c55", "\u2c26":"\u2c56",
+        "\u2c27":"\u2c57", "\u2c28":"\u2c58", "\u2c29":"\u2c59", "\u2c2a":"\u2c5a", "\u2c2b":"\u2c5b", "\u2c2c":"\u2c5c",
+        "\u2c2d":"\u2c5d", "\u2c2e":"\u2c5e", "\u2c2f":"\u2c5f", "\u2c60":"\u2c61", "\u2c62":"\u026b", "\u2c63":"\u1d7d",
+        "\u2c64":"\u027d", "\u2c67":"\u2c68", "\u2c69":"\u2c6a", "\u2c6b":"\u2c6c", "\u2c6d":"\u0251", "\u2c6e":"\u0271",
+        "\u2c6f":"\u0250", "\u2c70":"\u0252", "\u2c72":"\u2c73", "\u2c75":"\u2c76", "\u2c7e":"\u023f", "\u2c7f":"\u0240",
+        "\u2c80":"\u2c81", "\u2c82":"\u2c83", "\u2c84":"\u2c85", "\u2c86":"\u2c87", "\u2c88":"\u2c89", "\u2c8a":"\u2c8b",
+        "\u2c8c":"\u2c8d", "\u2c8e":"\u2c8f", "\u2c90":"\u2c91", "\u2c92":"\u2c93", "\u2c94":"\u2c95", "\u2c96":"\u2c97",
+        "\u2c98":"\u2c99", "\u2c9a":"\u2c9b", "\u2c9c":"\u2c9d", "\u2c9e":"\u2c9f", "\u2ca0":"\u2ca1", "\u2ca2":"\u2ca3",
+        "\u2ca4":"\u2ca5", "\u2ca6":"\u2ca7", "\u2ca8":"\u2ca9", "\u2caa":"\u2cab", "\u2cac":"\u2cad", "\u2cae":"\u2caf",
+        "\u2cb0":"\u2cb1", "\u2cb2":"\u2cb3", "\u2cb4":"\u2cb5", "\u2cb6":"\u2cb7", "\u2cb8":"\u2cb9", "\u2cba":"\u2cbb",
+        "\u2cbc":"\u2cbd", "\u2cbe":"\u2cbf", "\u2cc0":"\u2cc1", "\u2cc2":"\u2cc3", "\u2cc4":"\u2cc5", "\u2cc6":"\u2cc7",
+        "\u2cc8":"\u2cc9", "\u2cca":"\u2ccb", "\u2ccc":"\u2ccd", "\u2cce":"\u2ccf", "\u2cd0":"\u2cd1", "\u2cd2":"\u2cd3",
+        "\u2cd4":"\u2cd5", "\u2cd6":"\u2cd7", "\u2cd8":"\u2cd9", "\u2cda":"\u2cdb", "\u2cdc":"\u2cdd", "\u2cde":"\u2cdf",
+        "\u2ce0":"\u2ce1", "\u2ce2":"\u2ce3", "\u2ceb":"\u2cec", "\u2ced":"\u2cee", "\u2cf2":"\u2cf3", "\ua640":"\ua641",
+        "\ua642":"\ua643", "\ua644":"\ua645", "\ua646":"\ua647", "\ua648":"\ua649", "\ua64a":"\ua64b", "\ua64c":"\ua64d",
+        "\ua64e":"\ua64f", "\ua650":"\ua651", "\ua652":"\ua653", "\ua654":"\ua655", "\ua656":"\ua657", "\ua658":"\ua659",
+        "\ua65a":"\ua65b", "\ua65c":"\ua65d", "\ua65e":"\ua65f", "\ua660":"\ua661", "\ua662":"\ua663", "\ua664":"\ua665",
+        "\ua666":"\ua667", "\ua668":"\ua669", "\ua66a":"\ua66b", "\ua66c":"\ua66d", "\ua680":"\ua681", "\ua682":"\ua683",
+        "\ua684":"\ua685", "\ua686":"\ua687", "\ua688":"\ua689", "\ua68a":"\ua68b", "\ua68c":"\ua68d", "\ua68e":"\ua68f",
+        "\ua690":"\ua691", "\ua692":"\ua693", "\ua694":"\ua695", "\ua696":"\ua697", "\ua698":"\ua699", "\ua69a":"\ua69b",
+        "\ua722":"\ua723", "\ua724":"\ua725", "\ua726":"\ua727", "\ua728":"\ua729", "\ua72a":"\ua72b", "\ua72c":"\ua72d",
+        "\ua72e":"\ua72f", "\ua732":"\ua733", "\ua734":"\ua735", "\ua736":"\ua737", "\ua738":"\ua739", "\ua73a":"\ua73b",
+        "\ua73c":"\ua73d", "\ua73e":"\ua73f", "\ua740":"\ua741", "\ua742":"\ua743", "\ua744":"\ua745", "\ua746":"\ua747",
+        "\ua748":"\ua749", "\ua74a":"\ua74b", "\ua74c":"\ua74d", "\ua74e":"\ua74f", "\ua750":"\ua751", "\ua752":"\ua753",
+        "\ua754":"\ua755", "\ua756":"\ua757", "\ua758":"\ua759", "\ua75a":"\ua75b", "\ua75c":"\ua75d", "\ua75e":"\ua75f",
+        "\ua760":"\ua761", "\ua762":"\ua763", "\ua764":"\ua765", "\ua766":"\ua767", "\ua768":"\ua769", "\ua76a":"\ua76b",
+        "\ua76c":"\ua76d", "\ua76e":"\ua76f", "\ua779":"\ua77a", "\ua77b":"\ua77c", "\ua77d":"\u1d79", "\ua77e":"\ua77f",
+        "\ua780":"\ua781", "\ua782":"\ua783", "\ua784":"\ua785", "\ua786":"\ua787", "\ua78b":"\ua78c", "\ua78d":"\u0265",
+        "\ua790":"\ua791", "\ua792":"\ua793", "\ua796":"\ua797", "\ua798":"\ua799", "\ua79a":"\ua79b", "\ua79c":"\ua79d",
+        "\ua79e":"\ua79f", "\ua7a0":"\ua7a1", "\ua7a2":"\ua7a3", "\ua7a4":"\ua7a5", "\ua7a6":"\ua7a7", "\ua7a8":"\ua7a9",
+        "\ua7aa":"\u0266", "\ua7ab":"\u025c", "\ua7ac":"\u0261", "\ua7ad":"\u026c", "\ua7ae":"\u026a", "\ua7b0":"\u029e",
+        "\ua7b1":"\u0287", "\ua7b2":"\u029d", "\ua7b3":"\uab53", "\ua7b4":"\ua7b5", "\ua7b6":"\ua7b7", "\ua7b8":"\ua7b9",
+        "\ua7ba":"\ua7bb", "\ua7bc":"\ua7bd", "\ua7be":"\ua7bf", "\ua7c0":"\ua7c1", "\ua7c2":"\ua7c3", "\ua7c4":"\ua794",
+        "\ua7c5":"\u0282", "\ua7c6":"\u1d8e", "\ua7c7":"\ua7c8", "\ua7c9":"\ua7ca", "\ua7cb":"\u0264", "\ua7cc":"\ua7cd",
+        "\ua7ce":"\ua7cf", "\ua7d0":"\ua7d1", "\ua7d2":"\ua7d3", "\ua7d4":"\ua7d5", "\ua7d6":"\ua7d7", "\ua7d8":"\ua7d9",
+        "\ua7da":"\ua7db", "\ua7dc":"\u019b", "\ua7f5":"\ua7f6", "\uff21":"\uff41", "\uff22":"\uff42", "\uff23":"\uff43",
+        "\uff24":"\uff44", "\uff25":"\uff45", "\uff26":"\uff46", "\uff27":"\uff47", "\uff28":"\uff48", "\uff29":"\uff49",
+        "\uff2a":"\uff4a", "\uff2b":"\uff4b", "\uff2c":"\uff4c", "\uff2d":"\uff4d", "\uff2e":"\uff4e", "\uff2f":"\uff4f",
+        "\uff30":"\uff50", "\uff31":"\uff51", "\uff32":"\uff52", "\uff33":"\uff53", "\uff34":"\uff54", "\uff35":"\uff55",
+        "\uff36":"\uff56", "\uff37":"\uff57", "\uff38":"\uff58", "\uff39":"\uff59", "\uff3a":"\uff5a", "\ud801\udc00":"\ud801\udc28",
+        "\ud801\udc01":"\ud801\udc29", "\ud801\udc02":"\ud801\udc2a", "\ud801\udc03":"\ud801\udc2b", "\ud801\udc04":"\ud801\udc2c", "\ud801\udc05":"\ud801\udc2d", "\ud801\udc06":"\ud801\udc2e",
+        "\ud801\udc07":"\ud801\udc2f", "\ud801\udc08":"\ud801\udc30", "\ud801\udc09":"\ud801\udc31", "\ud801\udc0a":"\ud801\udc32", "\ud801\udc0b":"\ud801\udc33", "\ud801\udc0c":"\ud801\udc34",
+        "\ud801\udc0d":"\ud801\udc35", "\ud801\udc0e":"\ud801\udc36", "\ud801\udc0f":"\ud801\udc37", "\ud801\udc10":"\ud801\udc38", "\ud801\udc11":"\ud801\udc39", "\ud801\udc12":"\ud801\udc3a",
+        "\ud801\udc13":"\ud801\udc3b", "\ud801\udc14":"\ud801\udc3c", "\ud801\udc15":"\ud801\udc3d", "\ud801\udc16":"\ud801\udc3e", "\ud801\udc17":"\ud801\udc3f", "\ud801\udc18":"\ud801\udc40",
+        "\ud801\udc19":"\ud801\udc41", "\ud801\udc1a":"\ud801\udc42", "\ud801\udc1b":"\ud801\udc43", "\ud801\udc1c":"\ud801\udc44", "\ud801\udc1d":"\ud801\udc45", "\ud801\udc1e":"\ud801\udc46",
+        "\ud801\udc1f":"\ud801\udc47", "\ud801\udc20":"\ud801\udc48", "\ud801\udc21":"\ud801\udc49", "\ud801\udc22":"\ud801\udc4a", "\ud801\udc23":"\ud801\udc4b", "\ud801\udc24":"\ud801\udc4c",
+        "\ud801\udc25":"\ud801\udc4d", "\ud801\udc26":"\ud801\udc4e", "\ud801\udc27":"\ud801\udc4f", "\ud801\udcb0":"\ud801\udcd8", "\ud801\udcb1":"\ud801\udcd9", "\ud801\udcb2":"\ud801\udcda",
+        "\ud801\udcb3":"\ud801\udcdb", "\ud801\udcb4":"\ud801\udcdc", "\ud801\udcb5":"\ud801\udcdd", "\ud801\udcb6":"\ud801\udcde", "\ud801\udcb7":"\ud801\udcdf", "\ud801\udcb8":"\ud801\udce0",
+        "\ud801\udcb9":"\ud801\udce1", "\ud801\udcba":"\ud801\udce2", "\ud801\udcbb":"\ud801\udce3", "\ud801\udcbc":"\ud801\udce4", "\ud801\udcbd":"\ud801\udce5", "\ud801\udcbe":"\ud801\udce6",
+        "\ud801\udcbf":"\ud801\udce7", "\ud801\udcc0":"\ud801\udce8", "\ud801\udcc1":"\ud801\udce9", "\ud801\udcc2":"\ud801\udcea", "\ud801\udcc3":"\ud801\udceb", "\ud801\udcc4":"\ud801\udcec",
+        "\ud801\udcc5":"\ud801\udced", "\ud801\udcc6":"\ud801\udcee", "\ud801\udcc7":"\ud801\udcef", "\ud801\udcc8":"\ud801\udcf0", "\ud801\udcc9":"\ud801\udcf1", "\ud801\udcca":"\ud801\udcf2",
+        "\ud801\udccb":"\ud801\udcf3", "\ud801\udccc":"\ud801\udcf4", "\ud801\udccd":"\ud801\udcf5", "\ud801\udcce":"\ud801\udcf6", "\ud801\udccf":"\ud801\udcf7", "\ud801\udcd0":"\ud801\udcf8",
+        "\ud801\udcd1":"\ud801\udcf9", "\ud801\udcd2":"\ud801\udcfa", "\ud801\udcd3":"\ud801\udcfb", "\ud801\udd70":"\ud801\udd97", "\ud801\udd71":"\ud801\udd98", "\ud801\udd72":"\ud801\udd99",
+        "\ud801\udd73":"\ud801\udd9a", "\ud801\udd74":"\ud801\udd9b", "\ud801\udd75":"\ud801\udd9c", "\ud801\udd76":"\ud801\udd9d", "\ud801\udd77":"\ud801\udd9e", "\ud801\udd78":"\ud801\udd9f",
+        "\ud801\udd79":"\ud801\udda0", "\ud801\udd7a":"\ud801\udda1", "\ud801\udd7c":"\ud801\udda3", "\ud801\udd7d":"\ud801\udda4", "\ud801\udd7e":"\ud801\udda5", "\ud801\udd7f":"\ud801\udda6",
+        "\ud801\udd80":"\ud801\udda7", "\ud801\udd81":"\ud801\udda8", "\ud801\udd82":"\ud801\udda9", "\ud801\udd83":"\ud801\uddaa", "\ud801\udd84":"\ud801\uddab", "\ud801\udd85":"\ud801\uddac",
+        "\ud801\udd86":"\ud801\uddad", "\ud801\udd87":"\ud801\uddae", "\ud801\udd88":"\ud801\uddaf", "\ud801\udd89":"\ud801\uddb0", "\ud801\udd8a":"\ud801\uddb1", "\ud801\udd8c":"\ud801\uddb3",
+        "\ud801\udd8d":"\ud801\uddb4", "\ud801\udd8e":"\ud801\uddb5", "\ud801\udd8f":"\ud801\uddb6", "\ud801\udd90":"\ud801\uddb7", "\ud801\udd91":"\ud801\uddb8", "\ud801\udd92":"\ud801\uddb9",
+        "\ud801\udd94":"\ud801\uddbb", "\ud801\udd95":"\ud801\uddbc", "\ud803\udc80":"\ud803\udcc0", "\ud803\udc81":"\ud803\udcc1", "\ud803\udc82":"\ud803\udcc2", "\ud803\udc83":"\ud803\udcc3",
+        "\ud803\udc84":"\ud803\udcc4", "\ud803\udc85":"\ud803\udcc5", "\ud803\udc86":"\ud803\udcc6", "\ud803\udc87":"\ud803\udcc7", "\ud803\udc88":"\ud803\udcc8", "\ud803\udc89":"\ud803\udcc9",
+        "\ud803\udc8a":"\ud803\udcca", "\ud803\udc8b":"\ud803\udccb", "\ud803\udc8c":"\ud803\udccc", "\ud803\udc8d":"\ud803\udccd", "\ud803\udc8e":"\ud803\udcce", "\ud803\udc8f":"\ud803\udccf",
+        "\ud803\udc90":"\ud803\udcd0", "\ud803\udc91":"\ud803\udcd1", "\ud803\udc92":"\ud803\udcd2", "\ud803\udc93":"\ud803\udcd3", "\ud803\udc94":"\ud803\udcd4", "\ud803\udc95":"\ud803\udcd5",
+        "\ud803\udc96":"\ud803\udcd6", "\ud803\udc97":"\ud803\udcd7", "\ud803\udc98":"\ud803\udcd8", "\ud803\udc99":"\ud803\udcd9", "\ud803\udc9a":"\ud803\udcda", "\ud803\udc9b":"\ud803\udcdb",
+        "\ud803\udc9c":"\ud803\udcdc", "\ud803\udc9d":"\ud803\udcdd", "\ud803\udc9e":"\ud803\udcde", "\ud803\udc9f":"\ud803\udcdf", "\ud803\udca0":"\ud803\udce0", "\ud803\udca1":"\ud803\udce1",
+        "\ud803\udca2":"\ud803\udce2", "\ud803\udca3":"\ud803\udce3", "\ud803\udca4":"\ud803\udce4", "\ud803\udca5":"\ud803\udce5", "\ud803\udca6":"\ud803\udce6", "\ud803\udca7":"\ud803\udce7",
+        "\ud803\udca8":"\ud803\udce8", "\ud803\udca9":"\ud803\udce9", "\ud803\udcaa":"\ud803\udcea", "\ud803\udcab":"\ud803\udceb", "\ud803\udcac":"\ud803\udcec", "\ud803\udcad":"\ud803\udced",
+        "\ud803\udcae":"\ud803\udcee", "\ud803\udcaf":"\ud803\udcef", "\ud803\udcb0":"\ud803\udcf0", "\ud803\udcb1":"\ud803\udcf1", "\ud803\udcb2":"\ud803\udcf2", "\ud803\udd50":"\ud803\udd70",
+        "\ud803\udd51":"\ud803\udd71", "\ud803\udd52":"\ud803\udd72", "\ud803\udd53":"\ud803\udd73", "\ud803\udd54":"\ud803\udd74", "\ud803\udd55":"\ud803\udd75", "\ud803\udd56":"\ud803\udd76",
+        "\ud803\udd57":"\ud803\udd77", "\ud803\udd58":"\ud803\udd78", "\ud803\udd59":"\ud803\udd79", "\ud803\udd5a":"\ud803\udd7a", "\ud803\udd5b":"\ud803\udd7b", "\ud803\udd5c":"\ud803\udd7c",
+        "\ud803\udd5d":"\ud803\udd7d", "\ud803\udd5e":"\ud803\udd7e", "\ud803\udd5f":"\ud803\udd7f", "\ud803\udd60":"\ud803\udd80", "\ud803\udd61":"\ud803\udd81", "\ud803\udd62":"\ud803\udd82",
+        "\ud803\udd63":"\ud803\udd83", "\ud803\udd64":"\ud803\udd84", "\ud803\udd65":"\ud803\udd85", "\ud806\udca0":"\ud806\udcc0", "\ud806\udca1":"\ud806\udcc1", "\ud806\udca2":"\ud806\udcc2",
+        "\ud806\udca3":"\ud806\udcc3", "\ud806\udca4":"\ud806\udcc4", "\ud806\udca5":"\ud806\udcc5", "\ud806\udca6":"\ud806\udcc6", "\ud806\udca7":"\ud806\udcc7", "\ud806\udca8":"\ud806\udcc8",
+        "\ud806\udca9":"\ud806\udcc9", "\ud806\udcaa":"\ud806\udcca", "\ud806\udcab":"\ud806\udccb", "\ud806\udcac":"\ud806\udccc", "\ud806\udcad":"\ud806\udccd", "\ud806\udcae":"\ud806\udcce",
+        "\ud806\udcaf":"\ud806\udccf", "\ud806\udcb0":"\ud806\udcd0", "\ud806\udcb1":"\ud806\udcd1", "\ud806\udcb2":"\ud806\udcd2", "\ud806\udcb3":"\ud806\udcd3", "\ud806\udcb4":"\ud806\udcd4",
+        "\ud806\udcb5":"\ud806\udcd5", "\ud806\udcb6":"\ud806\udcd6", "\ud806\udcb7":"\ud806\udcd7", "\ud806\udcb8":"\ud806\udcd8", "\ud806\udcb9":"\ud806\udcd9", "\ud806\udcba":"\ud806\udcda",
+        "\ud806\udcbb":"\ud806\udcdb", "\ud806\udcbc":"\ud806\udcdc", "\ud806\udcbd":"\ud806\udcdd", "\ud806\udcbe":"\ud806\udcde", "\ud806\udcbf":"\ud806\udcdf", "\ud81b\ude40":"\ud81b\ude60",
+        "\ud81b\ude41":"\ud81b\ude61", "\ud81b\ude42":"\ud81b\ude62", "\ud81b\ude43":"\ud81b\ude63", "\ud81b\ude44":"\ud81b\ude64", "\ud81b\ude45":"\ud81b\ude65", "\ud81b\ude46":"\ud81b\ude66",
+        "\ud81b\ude47":"\ud81b\ude67", "\ud81b\ude48":"\ud81b\ude68", "\ud81b\ude49":"\ud81b\ude69", "\ud81b\ude4a":"\ud81b\ude6a", "\ud81b\ude4b":"\ud81b\ude6b", "\ud81b\ude4c":"\ud81b\ude6c",
+        "\ud81b\ude4d":"\ud81b\ude6d", "\ud81b\ude4e":"\ud81b\ude6e", "\ud81b\ude4f":"\ud81b\ude6f", "\ud81b\ude50":"\ud81b\ude70", "\ud81b\ude51":"\ud81b\ude71", "\ud81b\ude52":"\ud81b\ude72",
+        "\ud81b\ude53":"\ud81b\ude73", "\ud81b\ude54":"\ud81b\ude74", "\ud81b\ude55":"\ud81b\ude75", "\ud81b\ude56":"\ud81b\ude76", "\ud81b\ude57":"\ud81b\ude77", "\ud81b\ude58":"\ud81b\ude78",
+        "\ud81b\ude59":"\ud81b\ude79", "\ud81b\ude5a":"\ud81b\ude7a", "\ud81b\ude5b":"\ud81b\ude7b", "\ud81b\ude5c":"\ud81b\ude7c", "\ud81b\ude5d":"\ud81b\ude7d", "\ud81b\ude5e":"\ud81b\ude7e",
+        "\ud81b\ude5f":"\ud81b\ude7f", "\ud81b\udea0":"\ud81b\udebb", "\ud81b\udea1":"\ud81b\udebc", "\ud81b\udea2":"\ud81b\udebd", "\ud81b\udea3":"\ud81b\udebe", "\ud81b\udea4":"\ud81b\udebf",
+        "\ud81b\udea5":"\ud81b\udec0", "\ud81b\udea6":"\ud81b\udec1", "\ud81b\udea7":"\ud81b\udec2", "\ud81b\udea8":"\ud81b\udec3", "\ud81b\udea9":"\ud81b\udec4", "\ud81b\udeaa":"\ud81b\udec5",
+        "\ud81b\udeab":"\ud81b\udec6", "\ud81b\udeac":"\ud81b\udec7", "\ud81b\udead":"\ud81b\udec8", "\ud81b\udeae":"\ud81b\udec9", "\ud81b\udeaf":"\ud81b\udeca", "\ud81b\udeb0":"\ud81b\udecb",
+        "\ud81b\udeb1":"\ud81b\udecc", "\ud81b\udeb2":"\ud81b\udecd", "\ud81b\udeb3":"\ud81b\udece", "\ud81b\udeb4":"\ud81b\udecf", "\ud81b\udeb5":"\ud81b\uded0", "\ud81b\udeb6":"\ud81b\uded1",
+        "\ud81b\udeb7":"\ud81b\uded2", "\ud81b\udeb8":"\ud81b\uded3", "\ud83a\udd00":"\ud83a\udd22", "\ud83a\udd01":"\ud83a\udd23", "\ud83a\udd02":"\ud83a\udd24", "\ud83a\udd03":"\ud83a\udd25",
+        "\ud83a\udd04":"\ud83a\udd26", "\ud83a\udd05":"\ud83a\udd27", "\ud83a\udd06":"\ud83a\udd28", "\ud83a\udd07":"\ud83a\udd29", "\ud83a\udd08":"\ud83a\udd2a", "\ud83a\udd09":"\ud83a\udd2b",
+        "\ud83a\udd0a":"\ud83a\udd2c", "\ud83a\udd0b":"\ud83a\udd2d", "\ud83a\udd0c":"\ud83a\udd2e", "\ud83a\udd0d":"\ud83a\udd2f", "\ud83a\udd0e":"\ud83a\udd30", "\ud83a\udd0f":"\ud83a\udd31",
+        "\ud83a\udd10":"\ud83a\udd32", "\ud83a\udd11":"\ud83a\udd33", "\ud83a\udd12":"\ud83a\udd34", "\ud83a\udd13":"\ud83a\udd35", "\ud83a\udd14":"\ud83a\udd36", "\ud83a\udd15":"\ud83a\udd37",
+        "\ud83a\udd16":"\ud83a\udd38", "\ud83a\udd17":"\ud83a\udd39", "\ud83a\udd18":"\ud83a\udd3a", "\ud83a\udd19":"\ud83a\udd3b", "\ud83a\udd1a":"\ud83a\udd3c", "\ud83a\udd1b":"\ud83a\udd3d",
+        "\ud83a\udd1c":"\ud83a\udd3e", "\ud83a\udd1d":"\ud83a\udd3f", "\ud83a\udd1e":"\ud83a\udd40", "\ud83a\udd1f":"\ud83a\udd41", "\ud83a\udd20":"\ud83a\udd42", "\ud83a\udd21":"\ud83a\udd43"
+    }'::JSONB AS lowercase_mapping) AS mappings
+$$;
+
+-- postgres normalizes with the unicode version that it was built with, which can be older than the one of the rust
+-- code. the characters that got a compatibility form after unicode 14, which postgres 15 normalizes with, are mapped
+-- to it first. postgres before 16 also doesn't know the combining class of the marks that were added since, so it
+-- leaves a few of them in the order they were written in
+CREATE FUNCTION map_newer_compatibility_characters(s TEXT) RETURNS TEXT
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT coalesce(string_agg(coalesce(compatibility_mapping ->> c, c), '' ORDER BY i), '')
+    FROM unnest(string_to_array(s, NULL)) WITH ORDINALITY AS chars (c, i),
+    (SELECT '{
+        "\ud838\udc30":"\u0430", "\ud838\udc31":"\u0431", "\ud838\udc32":"\u0432", "\ud838\udc33":"\u0433", "\ud838\udc34":"\u0434", "\ud838\udc35":"\u0435",
+        "\ud838\udc36":"\u0436", "\ud838\udc37":"\u0437", "\ud838\udc38":"\u0438", "\ud838\udc39":"\u043a", "\ud838\udc3a":"\u043b", "\ud838\udc3b":"\u043c",
+        "\ud838\udc3c":"\u043e", "\ud838\udc3d":"\u043f", "\ud838\udc3e":"\u0440", "\ud838\udc3f":"\u0441", "\ud838\udc40":"\u0442", "\ud838\udc41":"\u0443",
+        "\ud838\udc42":"\u0444", "\ud838\udc43":"\u0445", "\ud838\udc44":"\u0446", "\ud838\udc45":"\u0447", "\ud838\udc46":"\u0448", "\ud838\udc47":"\u044b",
+        "\ud838\udc48":"\u044d", "\ud838\udc49":"\u044e", "\ud838\udc4a":"\ua689", "\ud838\udc4b":"\u04d9", "\ud838\udc4c":"\u0456", "\ud838\udc4d":"\u0458",
+        "\ud838\udc4e":"\u04e9", "\ud838\udc4f":"\u04af", "\ud838\udc50":"\u04cf", "\ud838\udc51":"\u0430", "\ud838\udc52":"\u0431", "\ud838\udc53":"\u0432",
+        "\ud838\udc54":"\u0433", "\ud838\udc55":"\u0434", "\ud838\udc56":"\u0435", "\ud838\udc57":"\u0436", "\ud838\udc58":"\u0437", "\ud838\udc59":"\u0438",
+        "\ud838\udc5a":"\u043a", "\ud838\udc5b":"\u043b", "\ud838\udc5c":"\u043e", "\ud838\udc5d":"\u043f", "\ud838\udc5e":"\u0441", "\ud838\udc5f":"\u0443",
+        "\ud838\udc60":"\u0444", "\ud838\udc61":"\u0445", "\ud838\udc62":"\u0446", "\ud838\udc63":"\u0447", "\ud838\udc64":"\u0448", "\ud838\udc65":"\u044a",
+        "\ud838\udc66":"\u044b", "\ud838\udc67":"\u0491", "\ud838\udc68":"\u0456", "\ud838\udc69":"\u0455", "\ud838\udc6a":"\u045f", "\ud838\udc6b":"\u04ab",
+        "\ud838\udc6c":"\ua651", "\ud838\udc6d":"\u04b1"
+    }'::JSONB AS compatibility_mapping) AS mappings
+$$;
+
+CREATE FUNCTION normalize_username(username TEXT) RETURNS TEXT
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT normalize(fold_case(normalize(map_newer_compatibility_characters(username), NFKC)), NFKC)
+$$;
+
+-- only the domain is lowercased, the part in front of the last @ is left alone
+CREATE FUNCTION normalize_email(email TEXT) RETURNS TEXT
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT CASE
+        WHEN position('@' IN email) = 0 THEN email
+        ELSE substring(email FROM '^(.*)@') || '@' || lowercase(substring(email FROM '@([^@]*)$'))
+    END
+$$;
+
+-- accounts that end up with the same username or email can't be merged here, so this stops and lists them. once they
+-- have been renamed it can be run again
+CREATE PROCEDURE normalize_accounts()
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    collisions TEXT;
+BEGIN
+    -- row locks don't keep new accounts out, so nothing can register until the accounts have been normalized
+    LOCK TABLE account IN SHARE ROW EXCLUSIVE MODE;
+
+    SELECT string_agg(format('%s %L is used by the accounts with user_id %s', field, normalized, user_ids), E'\n')
+    INTO collisions
+    FROM (
+        SELECT 'username' AS field, normalize_username(username) AS normalized,
+            string_agg(user_id::TEXT, ', ' ORDER BY user_id) AS user_ids
+        FROM account
+        GROUP BY 2
+        HAVING count(*) > 1
+        UNION ALL
+        SELECT 'email', normalize_email(email), string_agg(user_id::TEXT, ', ' ORDER BY user_id)
+        FROM account
+        GROUP BY 2
+        HAVING count(*) > 1
+    ) AS colliding;
+
+    IF collisions IS NOT NULL THEN
+        RAISE EXCEPTION E'accounts collide once their usernames and emails are normalized:\n%', collisions
+            USING ERRCODE = 'unique_violation',
+                HINT = 'rename all but one of the accounts and run the migration again';
+    END IF;
+
+    UPDATE account
+    SET username = normalize_username(username),
+        email = normalize_email(email)
+    WHERE username <> normalize_username(username) OR email <> normalize_email(email);
+END
+$$;
+
+CALL normalize_accounts();
+
+-- the unique constraints already hold the normalized values, these make sure that nothing which wasn't normalized
+-- by the parsers can get around them, like two registrations that only differ in case
+CREATE UNIQUE INDEX account_username_normalized_key ON account (normalize_username(username));
+CREATE UNIQUE INDEX account_email_normalized_key ON account (normalize_email(email));
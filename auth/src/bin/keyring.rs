@@ -0,0 +1,183 @@
+// manages the jwt_keyring in the secrets file, the auth and chat services pick up the changes without a restart
+//
+// rotating the key that signs auth tokens is done in steps so no auth token is rejected along the way:
+//   auth-keyring add <kid>      adds a new key, every service accepts it once it has reloaded the keyring
+//   auth-keyring promote <kid>  signs new auth tokens with the key, the previous key is still accepted
+//   auth-keyring remove <kid>   removes a retired key once the auth tokens signed with it have expired
+//   auth-keyring list           shows the keys and which one is active
+
+use anyhow::anyhow;
+use auth::configuration::{get_configuration, JwtAlgorithm};
+use auth::secrets::{get_secrets, Secrets, SECRETS_PATH};
+use auth::server::JwtKeys;
+use rand::{thread_rng, RngCore};
+use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+use rsa::RsaPrivateKey;
+use secrecy::ExposeSecret;
+use serde_yaml::{Mapping, Value};
+
+const RSA_KEY_BITS: usize = 2048;
+const HMAC_SECRET_LENGTH: usize = 64;
+
+#[derive(serde::Serialize)]
+struct Keyring {
+    active_kid: String,
+    keys: Vec<Key>,
+}
+
+#[derive(serde::Serialize)]
+struct Key {
+    kid: String,
+    key: String,
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let configuration = get_configuration().expect("Failed to read config file");
+    let algorithm = configuration.auth_token.signing_algorithm;
+    let secrets = get_secrets().expect("Failed to read secrets file");
+
+    let mut keyring = read_keyring(&secrets, algorithm)?;
+
+    match args.as_slice() {
+        ["list"] => {
+            for key in &keyring.keys {
+                let marker = if key.kid == keyring.active_kid {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{} {}", marker, key.kid);
+            }
+
+            return Ok(());
+        }
+        ["add", kid] => {
+            if keyring.keys.iter().any(|key| key.kid == *kid) {
+                return Err(anyhow!(
+                    "the keyring already has a key with the kid {}",
+                    kid
+                ));
+            }
+
+            keyring.keys.push(Key {
+                kid: kid.to_string(),
+                key: generate_key(algorithm)?,
+            });
+
+            println!(
+                "Added {}, promote it once every service has picked up the keyring",
+                kid
+            );
+        }
+        ["promote", kid] => {
+            if !keyring.keys.iter().any(|key| key.kid == *kid) {
+                return Err(anyhow!("the keyring has no key with the kid {}", kid));
+            }
+
+            println!(
+                "Promoted {}, {} is still accepted until it's removed",
+                kid, keyring.active_kid
+            );
+            keyring.active_kid = kid.to_string();
+        }
+        ["remove", kid] => {
+            if keyring.active_kid == *kid {
+                return Err(anyhow!(
+                    "{} signs auth tokens, promote another key before removing it",
+                    kid
+                ));
+            }
+
+            let keys = keyring.keys.len();
+            keyring.keys.retain(|key| key.kid != *kid);
+            if keyring.keys.len() == keys {
+                return Err(anyhow!("the keyring has no key with the kid {}", kid));
+            }
+
+            println!(
+                "Removed {}, auth tokens signed with it are no longer accepted",
+                kid
+            );
+        }
+        _ => {
+            return Err(anyhow!(
+                "usage: auth-keyring list | add <kid> | promote <kid> | remove <kid>"
+            ))
+        }
+    }
+
+    write_keyring(&keyring, algorithm)
+}
+
+// a secrets file without a keyring gets one that holds the jwt_secret or jwt_private_key, so the auth tokens that
+// have been signed with it stay valid
+fn read_keyring(secrets: &Secrets, algorithm: JwtAlgorithm) -> Result<Keyring, anyhow::Error> {
+    if let Some(keyring) = &secrets.jwt_keyring {
+        return Ok(Keyring {
+            active_kid: keyring.active_kid.clone(),
+            keys: keyring
+                .keys
+                .iter()
+                .map(|key| Key {
+                    kid: key.kid.clone(),
+                    key: key.key.expose_secret().clone(),
+                })
+                .collect(),
+        });
+    }
+
+    let jwt_keys = JwtKeys::from_secrets(secrets, algorithm)?;
+    let key = match algorithm {
+        JwtAlgorithm::Hs512 => secrets.jwt_secret.as_ref(),
+        JwtAlgorithm::Rs256 => secrets.jwt_private_key.as_ref(),
+    }
+    .map(|key| key.expose_secret().clone())
+    .unwrap_or_default();
+
+    let kid = jwt_keys.active().0.to_string();
+
+    Ok(Keyring {
+        active_kid: kid.clone(),
+        keys: vec![Key { kid, key }],
+    })
+}
+
+// only the keyring is rewritten, the other secrets are kept as they are
+fn write_keyring(keyring: &Keyring, algorithm: JwtAlgorithm) -> Result<(), anyhow::Error> {
+    let mut secrets: Mapping = serde_yaml::from_str(&std::fs::read_to_string(SECRETS_PATH)?)?;
+
+    let keyring_value = serde_yaml::to_value(keyring)?;
+
+    // the keyring is checked before it's written, so the services never read a keyring they can't use
+    JwtKeys::from_keyring(&serde_yaml::from_value(keyring_value.clone())?, algorithm)?;
+
+    // the key that has been moved into the keyring
+    secrets.remove(match algorithm {
+        JwtAlgorithm::Hs512 => "jwt_secret",
+        JwtAlgorithm::Rs256 => "jwt_private_key",
+    });
+    secrets.insert(Value::from("jwt_keyring"), keyring_value);
+
+    std::fs::write(SECRETS_PATH, serde_yaml::to_string(&secrets)?)?;
+
+    Ok(())
+}
+
+fn generate_key(algorithm: JwtAlgorithm) -> Result<String, anyhow::Error> {
+    match algorithm {
+        JwtAlgorithm::Hs512 => {
+            let mut secret = [0u8; HMAC_SECRET_LENGTH];
+            thread_rng().fill_bytes(&mut secret);
+
+            Ok(secret.iter().map(|byte| format!("{:02x}", byte)).collect())
+        }
+        JwtAlgorithm::Rs256 => {
+            let private_key = RsaPrivateKey::new(&mut thread_rng(), RSA_KEY_BITS)?;
+
+            Ok(private_key.to_pkcs8_pem(LineEnding::LF)?.to_string())
+        }
+    }
+}
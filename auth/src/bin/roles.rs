@@ -0,0 +1,72 @@
+// grants and revokes roles straight in the database, this is how the first admin is made. after that admins can use the
+// GrantRole and RevokeRole RPCs as well
+//
+//   auth-roles list <username>            shows the roles of the user
+//   auth-roles grant <username> <role>    grants one of user, moderator or admin
+//   auth-roles revoke <username> <role>   revokes the role again
+//
+// the auth tokens of the user carry the change once they are refreshed
+
+use anyhow::anyhow;
+use auth::configuration::get_configuration;
+use auth::server::{
+    get_roles_db, get_user_id_by_username_db, grant_role_db, normalize_username, revoke_role_db,
+    Role,
+};
+use secrecy::ExposeSecret;
+use sqlx::postgres::PgPool;
+
+const USAGE: &str =
+    "usage: auth-roles list <username> | grant <username> <role> | revoke <username> <role>";
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let username = match args.as_slice() {
+        ["list", username] | ["grant", username, _] | ["revoke", username, _] => *username,
+        _ => return Err(anyhow!(USAGE)),
+    };
+
+    let configuration = get_configuration().expect("Failed to read config file");
+
+    let connection_pool =
+        PgPool::connect(configuration.database.connection_string().expose_secret())
+            .await
+            .expect("failed to connect to postgres");
+
+    let user_id =
+        match get_user_id_by_username_db(&connection_pool, &normalize_username(username)).await? {
+            Some(user_id) => user_id,
+            None => return Err(anyhow!("there is no user with the username {}", username)),
+        };
+
+    match args.as_slice() {
+        ["grant", _, role] => {
+            let role: Role = role.parse().map_err(|e: String| anyhow!(e))?;
+
+            if grant_role_db(&connection_pool, &user_id, role).await? {
+                println!("Granted {} to {}", role, username);
+            } else {
+                println!("{} already has {}", username, role);
+            }
+        }
+        ["revoke", _, role] => {
+            let role: Role = role.parse().map_err(|e: String| anyhow!(e))?;
+
+            if revoke_role_db(&connection_pool, &user_id, role).await? {
+                println!("Revoked {} of {}", role, username);
+            } else {
+                println!("{} doesn't have {}", username, role);
+            }
+        }
+        _ => (),
+    }
+
+    let roles = get_roles_db(&connection_pool, &user_id).await?;
+    let roles: Vec<String> = roles.iter().map(Role::to_string).collect();
+    println!("{}: {}", username, roles.join(", "));
+
+    Ok(())
+}
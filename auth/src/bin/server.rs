@@ -1,4 +1,4 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use auth::configuration::get_configuration;
 use auth::logging::{get_subscriber, init_subscriber};
 use auth::secrets::get_secrets;
@@ -6,7 +6,12 @@ use secrecy::ExposeSecret;
 use sqlx::postgres::PgPool;
 use std::net::SocketAddr;
 
-use auth::server::build_server;
+use auth::server::{
+    build_server, close_redis, reload_jwt_keyring, restore_api_keys_redis, serve_until_shutdown,
+    shutdown_signal, JwtKeyring, JwtKeys,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
@@ -36,26 +41,64 @@ async fn main() -> Result<(), anyhow::Error> {
     tracing::info!("Creating redis client");
 
     let redis_client = redis::Client::open(configuration.redis_uri.expose_secret().to_owned())?;
-    let mut redis_con = match redis_client.get_multiplexed_async_connection().await {
+    let redis_con = match redis_client.get_multiplexed_async_connection().await {
         Ok(con) => con,
         Err(_) => return Err(anyhow!("couldn't get a redis connection")),
     };
 
     tracing::info!("Successfully created redis client");
 
+    tracing::info!("Restoring API keys into redis");
+
+    let restored_api_keys =
+        restore_api_keys_redis(&connection_pool, Arc::new(Mutex::new(redis_con.clone()))).await?;
+
+    tracing::info!("Successfully restored {} API keys", restored_api_keys);
+
     //println!("Address {:?}", address);
 
+    let signing_algorithm = configuration.auth_token.signing_algorithm;
+    // switching to rs256 without adding the jwt_private_key would otherwise only show once auth tokens are signed
+    let jwt_keys = JwtKeys::from_secrets(&secrets, signing_algorithm)
+        .context("Failed to read the keys that auth tokens are signed with")?;
+    let jwt_keyring = JwtKeyring::new(jwt_keys);
+
+    tokio::spawn(reload_jwt_keyring(
+        jwt_keyring.clone(),
+        signing_algorithm,
+        configuration.auth_token.keyring_reload_interval(),
+    ));
+
     tracing::info!("Building gRPC Server");
 
-    let server = build_server(connection_pool, redis_con, secrets);
+    let server = build_server(
+        connection_pool.clone(),
+        redis_con.clone(),
+        secrets,
+        jwt_keyring,
+        &configuration,
+    );
 
     tracing::info!("Succesfully built gRPC Server");
 
     let address: SocketAddr = format!("[::1]:{}", configuration.application_port).parse()?;
 
-    server.serve(address).await?;
+    serve_until_shutdown(
+        server,
+        address,
+        shutdown_signal(),
+        configuration.shutdown.drain_deadline(),
+    )
+    .await?;
 
     tracing::info!("Successfully served Server");
 
+    tracing::info!("Closing PostgreSQL and redis connections");
+
+    connection_pool.close().await;
+    close_redis(redis_con).await;
+
+    tracing::info!("Successfully closed connections");
+
     Ok(())
 }
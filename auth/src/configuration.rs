@@ -1,4 +1,6 @@
+use chrono::Duration;
 use secrecy::{ExposeSecret, Secret};
+use std::path::{Path, PathBuf};
 
 #[derive(serde::Deserialize)]
 pub struct Settings {
@@ -6,6 +8,16 @@ pub struct Settings {
     pub application_port: u16,
     pub argon: ArgonSettings,
     pub redis_uri: Secret<String>,
+    pub auth_token: AuthTokenSettings,
+    pub mailer: MailerSettings,
+    pub password_reset: PasswordResetSettings,
+    pub email_verification: EmailVerificationSettings,
+    pub login_throttle: LoginThrottleSettings,
+    pub totp: TotpSettings,
+    pub introspection: IntrospectionSettings,
+    pub password_policy: PasswordPolicySettings,
+    pub health_check: HealthCheckSettings,
+    pub shutdown: ShutdownSettings,
 }
 
 #[derive(serde::Deserialize)]
@@ -25,6 +37,154 @@ pub struct ArgonSettings {
     pub memory: u32,
 }
 
+#[derive(serde::Deserialize, Clone)]
+pub struct AuthTokenSettings {
+    pub access_token_lifetime_minutes: i64,
+    pub refresh_token_lifetime_days: i64,
+    // configurations from before auth tokens could be signed with RSA keep using HMAC
+    #[serde(default)]
+    pub signing_algorithm: JwtAlgorithm,
+    pub keyring_reload_seconds: u64,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum JwtAlgorithm {
+    // HMAC with the jwt_secret, every service that verifies auth tokens needs the secret
+    #[default]
+    Hs512,
+    // RSA with the jwt_private_key, other services get the public key from the auth service
+    Rs256,
+}
+
+impl AuthTokenSettings {
+    pub fn access_token_lifetime(&self) -> Duration {
+        Duration::minutes(self.access_token_lifetime_minutes)
+    }
+
+    pub fn refresh_token_lifetime(&self) -> Duration {
+        Duration::days(self.refresh_token_lifetime_days)
+    }
+
+    pub fn keyring_reload_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.keyring_reload_seconds)
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct MailerSettings {
+    pub sender: String,
+    pub outbox_directory: String,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct PasswordResetSettings {
+    pub code_lifetime_minutes: i64,
+}
+
+impl PasswordResetSettings {
+    pub fn code_lifetime(&self) -> Duration {
+        Duration::minutes(self.code_lifetime_minutes)
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct EmailVerificationSettings {
+    pub token_lifetime_hours: i64,
+    pub unverified_login: UnverifiedLoginPolicy,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum UnverifiedLoginPolicy {
+    // users can't login until they have verified their email
+    Reject,
+    // users can login but their auth tokens say that their email isn't verified
+    Mark,
+}
+
+impl EmailVerificationSettings {
+    pub fn token_lifetime(&self) -> Duration {
+        Duration::hours(self.token_lifetime_hours)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct LoginThrottleSettings {
+    pub max_failed_attempts_per_username: u32,
+    pub max_failed_attempts_per_ip: u32,
+    pub base_lockout_seconds: u64,
+    pub max_lockout_seconds: u64,
+    pub failed_attempts_window_seconds: u64,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct TotpSettings {
+    pub issuer: String,
+    pub login_challenge_lifetime_minutes: i64,
+    pub max_code_attempts: u32,
+    pub backup_codes: usize,
+}
+
+impl TotpSettings {
+    pub fn login_challenge_lifetime(&self) -> Duration {
+        Duration::minutes(self.login_challenge_lifetime_minutes)
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct IntrospectionSettings {
+    pub cache_lifetime_seconds: i64,
+}
+
+impl IntrospectionSettings {
+    pub fn cache_lifetime(&self) -> Duration {
+        Duration::seconds(self.cache_lifetime_seconds)
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct PasswordPolicySettings {
+    pub min_score: u8,
+    pub denylist_path: Option<String>,
+}
+
+impl PasswordPolicySettings {
+    // relative paths are relative to the auth directory, like the configuration file
+    pub fn denylist_path(&self) -> Option<PathBuf> {
+        self.denylist_path
+            .as_ref()
+            .map(|path| Path::new(env!("CARGO_MANIFEST_DIR")).join(path))
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct HealthCheckSettings {
+    pub interval_seconds: u64,
+    pub timeout_seconds: u64,
+}
+
+impl HealthCheckSettings {
+    pub fn interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.interval_seconds)
+    }
+
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_seconds)
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct ShutdownSettings {
+    pub drain_deadline_seconds: u64,
+}
+
+impl ShutdownSettings {
+    pub fn drain_deadline(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.drain_deadline_seconds)
+    }
+}
+
 impl DatabaseSettings {
     pub fn connection_string(&self) -> Secret<String> {
         Secret::new(format!(
@@ -57,3 +217,20 @@ pub fn get_configuration() -> Result<Settings, config::ConfigError> {
 
     settings.try_deserialize::<Settings>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthTokenSettings, JwtAlgorithm};
+
+    #[test]
+    fn auth_tokens_are_signed_with_hmac_when_no_signing_algorithm_is_configured() {
+        let settings: AuthTokenSettings = serde_yaml::from_str(
+            "access_token_lifetime_minutes: 15\n\
+            refresh_token_lifetime_days: 30\n\
+            keyring_reload_seconds: 30\n",
+        )
+        .expect("failed to read auth token settings");
+
+        assert_eq!(settings.signing_algorithm, JwtAlgorithm::Hs512);
+    }
+}
@@ -1,6 +1,7 @@
 pub mod authentication;
 pub mod configuration;
 pub mod logging;
+pub mod mailer;
 pub mod proto;
 pub mod secrets;
 pub mod server;
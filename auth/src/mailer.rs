@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::Utc;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// everything that sends mails to users goes through this, so the way mails are delivered can be swapped out
+#[tonic::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: MailMessage) -> Result<(), anyhow::Error>;
+}
+
+// writes every mail as a file into a directory instead of delivering it, this makes it possible to use the flows
+// that send mails without having a SMTP server
+pub struct FileOutbox {
+    sender: String,
+    directory: PathBuf,
+}
+
+impl FileOutbox {
+    pub fn new(sender: String, directory: impl Into<PathBuf>) -> FileOutbox {
+        Self {
+            sender,
+            directory: directory.into(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Mailer for FileOutbox {
+    #[tracing::instrument(name = "Writing mail into outbox" skip(self, message))]
+    async fn send(&self, message: MailMessage) -> Result<(), anyhow::Error> {
+        tokio::fs::create_dir_all(&self.directory)
+            .await
+            .context("failed to create outbox directory")?;
+
+        // the timestamp keeps the mails in the order they were sent when listing the directory
+        let file_name = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S%6f"),
+            Uuid::new_v4()
+        );
+
+        let contents = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\n\r\n{}\r\n",
+            self.sender,
+            message.to,
+            message.subject,
+            Utc::now().to_rfc2822(),
+            message.body
+        );
+
+        tokio::fs::write(self.directory.join(file_name), contents)
+            .await
+            .context("failed to write mail into outbox")?;
+
+        Ok(())
+    }
+}
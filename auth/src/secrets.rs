@@ -2,14 +2,35 @@ use secrecy::Secret;
 
 #[derive(serde::Deserialize, Clone)]
 pub struct Secrets {
-    pub jwt_secret: Secret<String>,
+    // used to sign auth tokens with HS512
+    pub jwt_secret: Option<Secret<String>>,
+    // a PKCS#8 PEM of a RSA key, used to sign auth tokens with RS256
+    pub jwt_private_key: Option<Secret<String>>,
+    // several keys of which one signs auth tokens, takes the place of the jwt_secret or jwt_private_key
+    pub jwt_keyring: Option<JwtKeyringSecret>,
+    // used to encrypt the TOTP secrets of the users before they are stored
+    pub totp_encryption_key: Secret<String>,
 }
 
-pub fn get_secrets() -> Result<Secrets, config::ConfigError> {
-    let path = "../secrets.yaml";
+// the keys are HMAC secrets or PKCS#8 PEMs of RSA keys depending on the signing algorithm, every key is accepted when
+// verifying auth tokens but only the active one signs them
+#[derive(serde::Deserialize, Clone)]
+pub struct JwtKeyringSecret {
+    pub active_kid: String,
+    pub keys: Vec<JwtKeySecret>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct JwtKeySecret {
+    pub kid: String,
+    pub key: Secret<String>,
+}
 
+pub const SECRETS_PATH: &str = "../secrets.yaml";
+
+pub fn get_secrets() -> Result<Secrets, config::ConfigError> {
     let secrets = config::Config::builder()
-        .add_source(config::File::new(path, config::FileFormat::Yaml))
+        .add_source(config::File::new(SECRETS_PATH, config::FileFormat::Yaml))
         .build()?;
 
     secrets.try_deserialize::<Secrets>()
@@ -0,0 +1,30 @@
+mod postgres_operations;
+mod redis_operations;
+
+pub use postgres_operations::*;
+pub use redis_operations::*;
+
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+pub use token_claims::{hash_api_key, Scope, API_KEY_PREFIX};
+
+const API_KEY_LENGTH: usize = 48;
+
+pub struct GeneratedApiKey {
+    // this is what is handed out, once
+    pub key: String,
+    // this is what is stored
+    pub hash: String,
+}
+
+pub fn generate_api_key() -> GeneratedApiKey {
+    let random_part: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(API_KEY_LENGTH)
+        .map(char::from)
+        .collect();
+
+    let key = format!("{}{}", API_KEY_PREFIX, random_part);
+    let hash = hash_api_key(&key);
+
+    GeneratedApiKey { key, hash }
+}
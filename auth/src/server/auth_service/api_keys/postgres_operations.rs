@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Row, Transaction};
+use uuid::Uuid;
+
+pub struct StoredApiKey {
+    pub key_id: Uuid,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+// an API key that can be used, together with the service account that it belongs to
+pub struct ActiveApiKey {
+    pub key_id: Uuid,
+    pub key_hash: String,
+    pub service_account_id: i32,
+    pub name: String,
+    pub scopes: Vec<String>,
+}
+
+#[tracing::instrument(name = "Store service account into DB", skip(db_pool))]
+pub async fn store_service_account_db(
+    db_pool: &PgPool,
+    name: &str,
+    created_by: &i32,
+) -> Result<i32, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO service_accounts (name, created_by) VALUES ($1, $2)
+        RETURNING service_account_id
+        "#,
+        name,
+        created_by
+    )
+    .fetch_one(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.service_account_id)
+}
+
+#[tracing::instrument(name = "Get service account id of name", skip(db_pool))]
+pub async fn get_service_account_id_db(
+    db_pool: &PgPool,
+    name: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    let service_account_id = sqlx::query!(
+        r#"SELECT service_account_id FROM service_accounts WHERE name = $1"#,
+        name
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve service account: {:?}", e);
+        e
+    })?
+    .map(|row| row.service_account_id);
+
+    Ok(service_account_id)
+}
+
+#[tracing::instrument(name = "Store API key into DB", skip(transaction, key_hash))]
+pub async fn store_api_key_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    key_id: &Uuid,
+    service_account_id: &i32,
+    key_hash: &str,
+    scopes: &[String],
+) -> Result<DateTime<Utc>, sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO api_keys (key_id, service_account_id, key_hash, scopes)
+        VALUES ($1, $2, $3, $4)
+        RETURNING created_at
+        "#,
+        key_id,
+        service_account_id,
+        key_hash,
+        scopes
+    );
+
+    let row = transaction.fetch_one(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.get("created_at"))
+}
+
+// the revoked API keys are listed as well, newest first
+#[tracing::instrument(name = "Get API keys of service account", skip(db_pool))]
+pub async fn get_api_keys_db(
+    db_pool: &PgPool,
+    service_account_id: &i32,
+) -> Result<Vec<StoredApiKey>, sqlx::Error> {
+    let api_keys = sqlx::query_as!(
+        StoredApiKey,
+        r#"
+        SELECT key_id, scopes, created_at, revoked_at FROM api_keys
+        WHERE service_account_id = $1
+        ORDER BY created_at DESC
+        "#,
+        service_account_id
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve API keys: {:?}", e);
+        e
+    })?;
+
+    Ok(api_keys)
+}
+
+#[tracing::instrument(name = "Get every active API key", skip(db_pool))]
+pub async fn get_active_api_keys_db(db_pool: &PgPool) -> Result<Vec<ActiveApiKey>, sqlx::Error> {
+    let api_keys = sqlx::query_as!(
+        ActiveApiKey,
+        r#"
+        SELECT api_keys.key_id, api_keys.key_hash, api_keys.service_account_id, service_accounts.name,
+            api_keys.scopes
+        FROM api_keys
+        JOIN service_accounts ON service_accounts.service_account_id = api_keys.service_account_id
+        WHERE api_keys.revoked_at IS NULL
+        "#
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve API keys: {:?}", e);
+        e
+    })?;
+
+    Ok(api_keys)
+}
+
+pub struct RevokedApiKey {
+    pub key_hash: String,
+    // false when the API key had already been revoked before
+    pub newly_revoked: bool,
+}
+
+// revoking an API key that has already been revoked keeps when it was revoked, none when there is no such API key
+#[tracing::instrument(name = "Revoke API key in DB", skip(transaction))]
+pub async fn revoke_api_key_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    key_id: &Uuid,
+) -> Result<Option<RevokedApiKey>, sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        WITH previous AS (SELECT key_id, revoked_at FROM api_keys WHERE key_id = $1 FOR UPDATE)
+        UPDATE api_keys SET revoked_at = COALESCE(previous.revoked_at, now())
+        FROM previous
+        WHERE api_keys.key_id = previous.key_id
+        RETURNING api_keys.key_hash, previous.revoked_at IS NULL AS newly_revoked
+        "#,
+        key_id
+    );
+
+    let revoked_api_key = transaction
+        .fetch_optional(query)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to exectute query: {:?}", e);
+            e
+        })?
+        .map(|row| RevokedApiKey {
+            key_hash: row.get("key_hash"),
+            newly_revoked: row.get("newly_revoked"),
+        });
+
+    Ok(revoked_api_key)
+}
@@ -0,0 +1,91 @@
+use anyhow::anyhow;
+use redis::{AsyncCommands, RedisResult};
+use sqlx::PgPool;
+
+use super::{get_active_api_keys_db, ActiveApiKey};
+use crate::server::RedisCon;
+
+// the API keys that can be used are kept as "api_key:<hash>" so services can check them without asking the auth
+// service, the value is "<key id> <service account id> <service account name> <comma separated scopes>"
+pub const API_KEY_PREFIX_REDIS: &str = "api_key";
+// the hash of an API key is published here whenever it's added or removed
+pub const API_KEYS_CHANNEL: &str = "api_keys";
+
+fn api_key_redis_key(key_hash: &str) -> String {
+    format!("{}:{}", API_KEY_PREFIX_REDIS, key_hash)
+}
+
+#[tracing::instrument(
+    name = "Store API key into redis"
+    skip(redis_connection, api_key)
+)]
+pub async fn store_api_key_redis(
+    redis_connection: RedisCon,
+    api_key: &ActiveApiKey,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let value = format!(
+        "{} {} {} {}",
+        api_key.key_id,
+        api_key.service_account_id,
+        api_key.name,
+        api_key.scopes.join(",")
+    );
+
+    let res: RedisResult<()> = redis_con
+        .set(api_key_redis_key(&api_key.key_hash), value)
+        .await;
+
+    if res.is_err() {
+        return Err(anyhow!("couldn't save API key in redis"));
+    }
+
+    let publish_res: RedisResult<()> = redis_con.publish(API_KEYS_CHANNEL, &api_key.key_hash).await;
+
+    if publish_res.is_err() {
+        return Err(anyhow!("couldn't publish API key in redis"));
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Delete API key from redis"
+    skip(redis_connection, key_hash)
+)]
+pub async fn delete_api_key_redis(
+    redis_connection: RedisCon,
+    key_hash: &str,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let res: RedisResult<()> = redis_con.del(api_key_redis_key(key_hash)).await;
+
+    if res.is_err() {
+        return Err(anyhow!("couldn't delete API key from redis"));
+    }
+
+    let publish_res: RedisResult<()> = redis_con.publish(API_KEYS_CHANNEL, key_hash).await;
+
+    if publish_res.is_err() {
+        return Err(anyhow!("couldn't publish API key in redis"));
+    }
+
+    Ok(())
+}
+
+// redis only keeps what it is told, so the active API keys are written into it again whenever the auth service starts
+#[tracing::instrument(name = "Restore API keys into redis" skip(db_pool, redis_connection))]
+pub async fn restore_api_keys_redis(
+    db_pool: &PgPool,
+    redis_connection: RedisCon,
+) -> Result<usize, anyhow::Error> {
+    let api_keys = get_active_api_keys_db(db_pool).await?;
+
+    for api_key in api_keys.iter() {
+        store_api_key_redis(redis_connection.clone(), api_key).await?;
+    }
+
+    Ok(api_keys.len())
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tonic::Request;
+
+use crate::proto::auth::AuthEvent;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthEventKind {
+    LoginSucceeded,
+    LoginFailed,
+    Registered,
+    TokenRefreshed,
+    SessionRevoked,
+    PasswordChanged,
+    RoleGranted,
+    RoleRevoked,
+    ApiKeyCreated,
+    ApiKeyRevoked,
+    AccountDeleted,
+}
+
+impl AuthEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthEventKind::LoginSucceeded => "login_succeeded",
+            AuthEventKind::LoginFailed => "login_failed",
+            AuthEventKind::Registered => "registered",
+            AuthEventKind::TokenRefreshed => "token_refreshed",
+            AuthEventKind::SessionRevoked => "session_revoked",
+            AuthEventKind::PasswordChanged => "password_changed",
+            AuthEventKind::RoleGranted => "role_granted",
+            AuthEventKind::RoleRevoked => "role_revoked",
+            AuthEventKind::ApiKeyCreated => "api_key_created",
+            AuthEventKind::ApiKeyRevoked => "api_key_revoked",
+            AuthEventKind::AccountDeleted => "account_deleted",
+        }
+    }
+}
+
+impl fmt::Display for AuthEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuthEventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AuthEventKind, String> {
+        match s {
+            "login_succeeded" => Ok(AuthEventKind::LoginSucceeded),
+            "login_failed" => Ok(AuthEventKind::LoginFailed),
+            "registered" => Ok(AuthEventKind::Registered),
+            "token_refreshed" => Ok(AuthEventKind::TokenRefreshed),
+            "session_revoked" => Ok(AuthEventKind::SessionRevoked),
+            "password_changed" => Ok(AuthEventKind::PasswordChanged),
+            "role_granted" => Ok(AuthEventKind::RoleGranted),
+            "role_revoked" => Ok(AuthEventKind::RoleRevoked),
+            "api_key_created" => Ok(AuthEventKind::ApiKeyCreated),
+            "api_key_revoked" => Ok(AuthEventKind::ApiKeyRevoked),
+            "account_deleted" => Ok(AuthEventKind::AccountDeleted),
+            _ => Err(format!("{} is not an event type", s)),
+        }
+    }
+}
+
+// the client that a request came from, it has to be taken before the request is consumed
+#[derive(Clone, Debug, Default)]
+pub struct RequestOrigin {
+    pub peer_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl RequestOrigin {
+    pub fn of<T>(request: &Request<T>) -> RequestOrigin {
+        RequestOrigin {
+            peer_address: request
+                .remote_addr()
+                .map(|peer_addr| peer_addr.ip().to_string()),
+            user_agent: request
+                .metadata()
+                .get("user-agent")
+                .and_then(|user_agent| user_agent.to_str().ok())
+                .map(str::to_string),
+        }
+    }
+}
+
+pub struct StoredAuthEvent {
+    pub event_id: i64,
+    pub user_id: Option<i32>,
+    pub username: Option<String>,
+    pub event_type: String,
+    pub peer_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<StoredAuthEvent> for AuthEvent {
+    fn from(event: StoredAuthEvent) -> AuthEvent {
+        AuthEvent {
+            event_id: event.event_id,
+            user_id: event.user_id,
+            username: event.username,
+            event_type: event.event_type,
+            peer_address: event.peer_address.unwrap_or_default(),
+            user_agent: event.user_agent.unwrap_or_default(),
+            detail: event.detail.unwrap_or_default(),
+            created_at: Some(SystemTime::from(event.created_at).into()),
+        }
+    }
+}
+
+// every filter that is set has to match
+#[derive(Debug, Default)]
+pub struct AuthEventFilter {
+    pub user_id: Option<i32>,
+    pub kind: Option<AuthEventKind>,
+    pub peer_address: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    // only events that are older than this one, which is how the pages are walked through
+    pub before_event_id: Option<i64>,
+}
+
+#[tracing::instrument(name = "Store auth event into DB", skip(db_pool, origin))]
+pub async fn store_auth_event_db(
+    db_pool: &PgPool,
+    kind: AuthEventKind,
+    user_id: Option<i32>,
+    origin: &RequestOrigin,
+    detail: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO auth_events (user_id, event_type, peer_address, user_agent, detail)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        user_id,
+        kind.as_str(),
+        origin.peer_address,
+        origin.user_agent,
+        detail
+    )
+    .execute(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+// the newest events come first
+#[tracing::instrument(name = "Get auth events from DB", skip(db_pool))]
+pub async fn get_auth_events_db(
+    db_pool: &PgPool,
+    filter: &AuthEventFilter,
+    limit: i64,
+) -> Result<Vec<StoredAuthEvent>, sqlx::Error> {
+    let events = sqlx::query_as!(
+        StoredAuthEvent,
+        r#"
+        SELECT auth_events.event_id, auth_events.user_id, account.username AS "username?",
+            auth_events.event_type, auth_events.peer_address, auth_events.user_agent, auth_events.detail,
+            auth_events.created_at
+        FROM auth_events
+        LEFT JOIN account ON account.user_id = auth_events.user_id
+        WHERE ($1::INTEGER IS NULL OR auth_events.user_id = $1)
+            AND ($2::TEXT IS NULL OR auth_events.event_type = $2)
+            AND ($3::TEXT IS NULL OR auth_events.peer_address = $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR auth_events.created_at >= $4)
+            AND ($5::TIMESTAMPTZ IS NULL OR auth_events.created_at < $5)
+            AND ($6::BIGINT IS NULL OR auth_events.event_id < $6)
+        ORDER BY auth_events.event_id DESC
+        LIMIT $7
+        "#,
+        filter.user_id,
+        filter.kind.map(|kind| kind.as_str()),
+        filter.peer_address,
+        filter.since,
+        filter.until,
+        filter.before_event_id,
+        limit
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve auth events: {:?}", e);
+        e
+    })?;
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AuthEventKind;
+
+    #[test]
+    fn event_kinds_are_parsed_from_their_name() {
+        let kinds = [
+            AuthEventKind::LoginSucceeded,
+            AuthEventKind::LoginFailed,
+            AuthEventKind::Registered,
+            AuthEventKind::TokenRefreshed,
+            AuthEventKind::SessionRevoked,
+            AuthEventKind::PasswordChanged,
+            AuthEventKind::RoleGranted,
+            AuthEventKind::RoleRevoked,
+            AuthEventKind::ApiKeyCreated,
+            AuthEventKind::ApiKeyRevoked,
+            AuthEventKind::AccountDeleted,
+        ];
+
+        for kind in kinds {
+            assert_eq!(kind.as_str().parse::<AuthEventKind>(), Ok(kind));
+        }
+
+        assert!("logged_in".parse::<AuthEventKind>().is_err());
+    }
+}
@@ -1,33 +1,74 @@
-use chrono::{Duration, Local};
+use chrono::Duration;
 
-use hmac::{Hmac, Mac};
-use jwt::SignWithKey;
-use secrecy::{ExposeSecret, Secret};
-use sha2::Sha512;
-use std::collections::BTreeMap;
+use jwt::{Header, SignWithKey, Token};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+use token_claims::Role;
+use uuid::Uuid;
+
+use super::{Claims, JwtKey, JwtKeys};
+
+const REFRESH_TOKEN_LENGTH: usize = 64;
 
 pub fn generate_auth_token(
-    secret_key: Secret<String>,
-    user_id: &str,
+    keys: &JwtKeys,
+    user_id: i32,
+    username: String,
+    roles: Vec<Role>,
+    session_id: Uuid,
+    email_verified: bool,
+    lifetime: Duration,
 ) -> Result<String, anyhow::Error> {
-    let key: Hmac<Sha512> = Hmac::new_from_slice(secret_key.expose_secret().as_bytes())?;
-    let mut claims = BTreeMap::new();
+    let claims = Claims::new(
+        user_id,
+        username,
+        &roles,
+        session_id,
+        email_verified,
+        lifetime,
+    );
+
+    // the kid tells the verifying services which key to use
+    let (kid, key) = keys.active();
+    let header = Header {
+        algorithm: key.algorithm(),
+        key_id: Some(kid.to_string()),
+        ..Default::default()
+    };
+
+    let token = match key {
+        JwtKey::Hmac(key) => Token::new(header, claims).sign_with_key(key)?,
+        JwtKey::Rs256 { signing_key, .. } => {
+            Token::new(header, claims).sign_with_key(signing_key)?
+        }
+    };
 
-    // generic claims
-    claims.insert("iss", "Chat-gRPC");
-    claims.insert("sub", "auth token");
+    Ok(token.as_str().to_string())
+}
+
+pub struct RefreshToken {
+    // this is what is handed out to the user
+    pub token: String,
+    // this is what is stored in the DB, so a leaked DB doesn't leak usable refresh tokens
+    pub hash: String,
+}
 
-    let now_timestamp = Local::now().timestamp().to_string();
-    // issued at
-    claims.insert("iat", now_timestamp.as_str());
+pub fn generate_refresh_token() -> RefreshToken {
+    let token: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(REFRESH_TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
 
-    let one_week_from_now_timestamp = (Local::now() + Duration::weeks(1)).timestamp().to_string();
-    // expiration time
-    claims.insert("exp", one_week_from_now_timestamp.as_str());
+    let hash = hash_refresh_token(&token);
 
-    // application claims
-    claims.insert("user_id", user_id);
-    let token = claims.sign_with_key(&key)?;
+    RefreshToken { token, hash }
+}
 
-    Ok(token)
+// refresh tokens are long random strings so a fast hash is enough here, unlike passwords
+pub fn hash_refresh_token(refresh_token: &str) -> String {
+    Sha256::digest(refresh_token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
 }
@@ -0,0 +1,63 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::configuration::JwtAlgorithm;
+use crate::secrets::get_secrets;
+
+use super::JwtKeys;
+
+// the keys that the service currently uses, they are swapped out whenever the keyring in the secrets file changes so
+// a key can be promoted without restarting the service
+#[derive(Clone)]
+pub struct JwtKeyring {
+    keys: Arc<RwLock<Arc<JwtKeys>>>,
+}
+
+impl JwtKeyring {
+    pub fn new(keys: JwtKeys) -> JwtKeyring {
+        JwtKeyring {
+            keys: Arc::new(RwLock::new(Arc::new(keys))),
+        }
+    }
+
+    pub fn current(&self) -> Arc<JwtKeys> {
+        self.keys
+            .read()
+            .expect("jwt keyring lock is poisoned")
+            .clone()
+    }
+
+    pub fn replace(&self, keys: JwtKeys) {
+        *self.keys.write().expect("jwt keyring lock is poisoned") = Arc::new(keys);
+    }
+}
+
+// reads the keyring from the secrets file every interval for as long as the server is running, a keyring that can't
+// be read is skipped so the service keeps the keys it has
+pub async fn reload_jwt_keyring(keyring: JwtKeyring, algorithm: JwtAlgorithm, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let keys = match get_secrets()
+            .map_err(anyhow::Error::from)
+            .and_then(|secrets| JwtKeys::from_secrets(&secrets, algorithm))
+        {
+            Ok(keys) => keys,
+            Err(e) => {
+                tracing::error!("Failed to reload the jwt keyring: {:?}", e);
+                continue;
+            }
+        };
+
+        let current = keyring.current();
+        if keys.active().0 != current.active().0 || keys.kids() != current.kids() {
+            tracing::info!(
+                "Reloaded the jwt keyring, auth tokens are signed with {} and verified with {:?}",
+                keys.active().0,
+                keys.kids()
+            );
+        }
+
+        keyring.replace(keys);
+    }
+}
@@ -1,9 +1,13 @@
 mod generation;
+mod keyring;
 mod postgres_operations;
 mod redis_operations;
+mod signing;
 mod validation;
 
 pub use generation::*;
+pub use keyring::*;
 pub use postgres_operations::*;
 pub use redis_operations::*;
+pub use signing::*;
 pub use validation::*;
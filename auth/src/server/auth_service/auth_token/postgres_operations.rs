@@ -1,5 +1,8 @@
+use chrono::{DateTime, Utc};
 use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
 
+// every login creates a new session which holds the current auth token of that session
 #[tracing::instrument(
     name = "Store auth_token into DB"
     skip(transaction, auth_token)
@@ -7,15 +10,19 @@ use sqlx::{Executor, PgPool, Postgres, Transaction};
 pub async fn store_token_db(
     transaction: &mut Transaction<'_, Postgres>,
     user_id: &i32,
+    session_id: &Uuid,
+    device: &str,
     auth_token: &str,
 ) -> Result<(), sqlx::Error> {
     let query = sqlx::query!(
         r#"
-        INSERT INTO auth_tokens (auth_token, user_id)
-        VALUES ($1, $2)
+        INSERT INTO auth_tokens (auth_token, user_id, session_id, device)
+        VALUES ($1, $2, $3, $4)
         "#,
         auth_token,
-        user_id
+        user_id,
+        session_id,
+        device
     );
 
     transaction.execute(query).await.map_err(|e| {
@@ -28,22 +35,22 @@ pub async fn store_token_db(
 
 #[tracing::instrument(
     name = "update auth_token in DB"
-    skip(pg_pool, auth_token)
+    skip(transaction, auth_token)
 )]
 pub async fn update_token_db(
-    pg_pool: &PgPool,
-    user_id: &i32,
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: &Uuid,
     auth_token: &str,
 ) -> Result<(), sqlx::Error> {
     let query = sqlx::query!(
         r#"
-        UPDATE auth_tokens SET auth_token = $1 WHERE user_id = $2
+        UPDATE auth_tokens SET auth_token = $1, last_used_at = now() WHERE session_id = $2
         "#,
         auth_token,
-        user_id
+        session_id
     );
 
-    pg_pool.execute(query).await.map_err(|e| {
+    transaction.execute(query).await.map_err(|e| {
         tracing::error!("Failed to exectute query: {:?}", e);
         e
     })?;
@@ -51,14 +58,95 @@ pub async fn update_token_db(
     Ok(())
 }
 
+// returns false when the user doesn't have a session with the given id
+#[tracing::instrument(
+    name = "delete auth_token from DB"
+    skip(transaction)
+)]
+pub async fn delete_token_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    session_id: &Uuid,
+) -> Result<bool, sqlx::Error> {
+    let query = sqlx::query!(
+        r#"DELETE FROM auth_tokens WHERE user_id = $1 AND session_id = $2"#,
+        user_id,
+        session_id
+    );
+
+    let result = transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// removes every session of the user except the given one, returns the ids of the removed sessions
+#[tracing::instrument(
+    name = "delete other auth_tokens of user from DB"
+    skip(transaction)
+)]
+pub async fn delete_other_tokens_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    session_id: &Uuid,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let session_ids = sqlx::query!(
+        r#"DELETE FROM auth_tokens WHERE user_id = $1 AND session_id <> $2 RETURNING session_id"#,
+        user_id,
+        session_id
+    )
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?
+    .into_iter()
+    .map(|row| row.session_id)
+    .collect();
+
+    Ok(session_ids)
+}
+
+// removes every session of the user, returns the ids of the removed sessions
+#[tracing::instrument(
+    name = "delete all auth_tokens of user from DB"
+    skip(transaction)
+)]
+pub async fn delete_all_tokens_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let session_ids = sqlx::query!(
+        r#"DELETE FROM auth_tokens WHERE user_id = $1 RETURNING session_id"#,
+        user_id
+    )
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?
+    .into_iter()
+    .map(|row| row.session_id)
+    .collect();
+
+    Ok(session_ids)
+}
+
 #[tracing::instrument(
     name = "get auth_token from db"
     skip(db_pool, )
 )]
-pub async fn get_token_db(db_pool: &PgPool, user_id: &i32) -> Result<Option<String>, sqlx::Error> {
+pub async fn get_token_db(
+    db_pool: &PgPool,
+    session_id: &Uuid,
+) -> Result<Option<String>, sqlx::Error> {
     let auth_token = sqlx::query!(
-        r#"SELECT auth_token from auth_tokens WHERE user_id = $1"#,
-        user_id
+        r#"SELECT auth_token from auth_tokens WHERE session_id = $1"#,
+        session_id
     )
     .fetch_optional(db_pool)
     .await
@@ -70,3 +158,146 @@ pub async fn get_token_db(db_pool: &PgPool, user_id: &i32) -> Result<Option<Stri
 
     Ok(auth_token)
 }
+
+pub struct StoredSession {
+    pub session_id: Uuid,
+    pub device: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+}
+
+#[tracing::instrument(
+    name = "get sessions of user from db"
+    skip(db_pool)
+)]
+pub async fn get_sessions_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+) -> Result<Vec<StoredSession>, sqlx::Error> {
+    let sessions = sqlx::query_as!(
+        StoredSession,
+        r#"
+        SELECT session_id, device, created_at, last_used_at FROM auth_tokens
+        WHERE user_id = $1
+        ORDER BY last_used_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve sessions: {:?}", e);
+        e
+    })?;
+
+    Ok(sessions)
+}
+
+#[tracing::instrument(
+    name = "Store refresh token into DB"
+    skip(transaction, refresh_token_hash)
+)]
+pub async fn store_refresh_token_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    session_id: &Uuid,
+    refresh_token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO refresh_tokens (refresh_token_hash, user_id, session_id, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        refresh_token_hash,
+        user_id,
+        session_id,
+        expires_at
+    );
+
+    transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+pub struct StoredRefreshToken {
+    pub user_id: i32,
+    pub session_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub used: bool,
+    pub revoked: bool,
+}
+
+#[tracing::instrument(
+    name = "get refresh token from DB"
+    skip(transaction, refresh_token_hash)
+)]
+pub async fn get_refresh_token_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    refresh_token_hash: &str,
+) -> Result<Option<StoredRefreshToken>, sqlx::Error> {
+    // the row is locked so two concurrent refreshes with the same token can't both succeed
+    let refresh_token = sqlx::query_as!(
+        StoredRefreshToken,
+        r#"
+        SELECT user_id, session_id, expires_at, used, revoked FROM refresh_tokens
+        WHERE refresh_token_hash = $1
+        FOR UPDATE
+        "#,
+        refresh_token_hash
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve refresh token: {:?}", e);
+        e
+    })?;
+
+    Ok(refresh_token)
+}
+
+#[tracing::instrument(
+    name = "mark refresh token as used in DB"
+    skip(transaction, refresh_token_hash)
+)]
+pub async fn mark_refresh_token_used_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    refresh_token_hash: &str,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"UPDATE refresh_tokens SET used = TRUE WHERE refresh_token_hash = $1"#,
+        refresh_token_hash
+    );
+
+    transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+// revokes the whole chain of refresh tokens that belongs to the session
+#[tracing::instrument(
+    name = "revoke refresh tokens of session in DB"
+    skip(transaction)
+)]
+pub async fn revoke_refresh_tokens_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: &Uuid,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"UPDATE refresh_tokens SET revoked = TRUE WHERE session_id = $1"#,
+        session_id
+    );
+
+    transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
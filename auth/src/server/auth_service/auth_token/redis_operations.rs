@@ -1,30 +1,45 @@
 use anyhow::anyhow;
 use chrono::{Duration, Local};
 use redis::{AsyncCommands, RedisResult};
+use uuid::Uuid;
 
 use crate::server::RedisCon;
 
+// the auth token of every session is cached as "auth_token:<session id>"
+pub const AUTH_TOKEN_PREFIX: &str = "auth_token";
+// revocations are stored as "revoked_token:<id>" until the affected tokens would have expired anyway, where the id
+// is either the jti of a single token or a session id which covers every token of that session
+pub const REVOKED_TOKEN_PREFIX: &str = "revoked_token";
+// revocations are published here so services holding open streams can act on them right away
+pub const REVOKED_TOKENS_CHANNEL: &str = "revoked_tokens";
+
+pub fn auth_token_key(session_id: &Uuid) -> String {
+    format!("{}:{}", AUTH_TOKEN_PREFIX, session_id)
+}
+
 #[tracing::instrument(
     name = "Store auth_token into redis"
     skip(redis_connection, auth_token)
 )]
 pub async fn store_token_redis(
     redis_connection: RedisCon,
-    user_id: &i32,
+    session_id: &Uuid,
     auth_token: &str,
+    lifetime: Duration,
 ) -> Result<(), anyhow::Error> {
     let mut redis_con = redis_connection.lock().await;
 
-    let res: RedisResult<()> = redis_con.set(user_id, auth_token).await;
+    let key = auth_token_key(session_id);
+
+    let res: RedisResult<()> = redis_con.set(&key, auth_token).await;
 
     if res.is_err() {
         return Err(anyhow!("couldn't save auth token in redis"));
     }
 
-    let one_week_from_now_timestamp = (Local::now() + Duration::weeks(1)).timestamp();
-    let expire_res: RedisResult<()> = redis_con
-        .expire_at(user_id, one_week_from_now_timestamp)
-        .await;
+    // the cached token is useless once it has expired
+    let expiration_timestamp = (Local::now() + lifetime).timestamp();
+    let expire_res: RedisResult<()> = redis_con.expire_at(&key, expiration_timestamp).await;
 
     if expire_res.is_err() {
         return Err(anyhow!("couldn't set expire time for auth token in redis"));
@@ -35,18 +50,105 @@ pub async fn store_token_redis(
 
 #[tracing::instrument(
     name = "get auth_token into redis"
-    skip(redis_connection, user_id)
+    skip(redis_connection, session_id)
 )]
 pub async fn get_token_redis(
     redis_connection: RedisCon,
-    user_id: &i32,
+    session_id: &Uuid,
 ) -> Result<String, anyhow::Error> {
     let mut redis_con = redis_connection.lock().await;
 
-    let token = match redis_con.get(user_id).await {
+    let token = match redis_con.get(auth_token_key(session_id)).await {
         Ok(token) => token,
         Err(_) => return Err(anyhow!("couldn't get auth token in redis")),
     };
 
     Ok(token)
 }
+
+#[tracing::instrument(
+    name = "delete auth_token from redis"
+    skip(redis_connection)
+)]
+pub async fn delete_token_redis(
+    redis_connection: RedisCon,
+    session_id: &Uuid,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let res: RedisResult<()> = redis_con.del(auth_token_key(session_id)).await;
+
+    if res.is_err() {
+        return Err(anyhow!("couldn't delete auth token in redis"));
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "revoke session in redis"
+    skip(redis_connection)
+)]
+pub async fn revoke_session_redis(
+    redis_connection: RedisCon,
+    session_id: &Uuid,
+    expiration_timestamp: i64,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let key = format!("{}:{}", REVOKED_TOKEN_PREFIX, session_id);
+
+    // the value is the expiration time so subscribers know for how long the revocation matters
+    let res: RedisResult<()> = redis_con.set(&key, expiration_timestamp).await;
+
+    if res.is_err() {
+        return Err(anyhow!("couldn't save revoked session in redis"));
+    }
+
+    let expire_res: RedisResult<()> = redis_con.expire_at(&key, expiration_timestamp).await;
+
+    if expire_res.is_err() {
+        return Err(anyhow!(
+            "couldn't set expire time for revoked session in redis"
+        ));
+    }
+
+    let publish_res: RedisResult<()> = redis_con
+        .publish(
+            REVOKED_TOKENS_CHANNEL,
+            format!("{} {}", session_id, expiration_timestamp),
+        )
+        .await;
+
+    if publish_res.is_err() {
+        return Err(anyhow!("couldn't publish revoked session in redis"));
+    }
+
+    Ok(())
+}
+
+// an auth token is revoked when either the token itself or the session it belongs to has been revoked
+#[tracing::instrument(
+    name = "check if auth_token is revoked in redis"
+    skip(redis_connection)
+)]
+pub async fn is_token_revoked_redis(
+    redis_connection: RedisCon,
+    jti: &str,
+    session_id: &str,
+) -> Result<bool, anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let revoked_count: u32 = match redis_con
+        .exists(&[
+            format!("{}:{}", REVOKED_TOKEN_PREFIX, jti),
+            format!("{}:{}", REVOKED_TOKEN_PREFIX, session_id),
+        ])
+        .await
+    {
+        Ok(revoked_count) => revoked_count,
+        Err(_) => return Err(anyhow!("couldn't check if auth token is revoked in redis")),
+    };
+
+    Ok(revoked_count > 0)
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use data_encoding::BASE64URL_NOPAD;
+use hmac::{Hmac, Mac};
+use jwt::{AlgorithmType, SigningAlgorithm};
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256, Sha512};
+use token_claims::{Rs256VerifyingKey, TokenVerifier, VerifyingKey};
+
+use crate::configuration::JwtAlgorithm;
+use crate::secrets::{JwtKeyringSecret, Secrets};
+
+// RS256 of RFC 7518, which is RSASSA-PKCS1-v1_5 with SHA-256
+#[derive(Clone)]
+pub struct Rs256SigningKey(SigningKey<Sha256>);
+
+impl SigningAlgorithm for Rs256SigningKey {
+    fn algorithm_type(&self) -> AlgorithmType {
+        AlgorithmType::Rs256
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, jwt::Error> {
+        let signature = self
+            .0
+            .try_sign(format!("{}.{}", header, claims).as_bytes())
+            .map_err(|_| jwt::Error::InvalidSignature)?;
+
+        Ok(BASE64URL_NOPAD.encode(&signature.to_bytes()))
+    }
+}
+
+// the kid of the jwt_secret or jwt_private_key when the secrets file doesn't have a keyring, the jwt_private_key
+// uses its JWK thumbprint instead
+pub const JWT_SECRET_KID: &str = "jwt_secret";
+
+// a single key of the keyring
+#[derive(Clone)]
+pub enum JwtKey {
+    // the same secret signs and verifies auth tokens, so every service that can verify them can create them too
+    Hmac(Hmac<Sha512>),
+    // only the auth service has the private key, other services verify auth tokens with the public key
+    Rs256 {
+        signing_key: Rs256SigningKey,
+        verifying_key: Rs256VerifyingKey,
+    },
+}
+
+impl JwtKey {
+    pub fn hmac(secret: &Secret<String>) -> Result<JwtKey, anyhow::Error> {
+        Ok(JwtKey::Hmac(Hmac::new_from_slice(
+            secret.expose_secret().as_bytes(),
+        )?))
+    }
+
+    // the private key is a PKCS#8 PEM
+    pub fn rs256(private_key_pem: &Secret<String>) -> Result<JwtKey, anyhow::Error> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem.expose_secret())?;
+
+        Ok(JwtKey::Rs256 {
+            verifying_key: Rs256VerifyingKey::new(private_key.to_public_key()),
+            signing_key: Rs256SigningKey(SigningKey::new(private_key)),
+        })
+    }
+
+    pub fn algorithm(&self) -> AlgorithmType {
+        match self {
+            JwtKey::Hmac(_) => AlgorithmType::Hs512,
+            JwtKey::Rs256 { .. } => AlgorithmType::Rs256,
+        }
+    }
+
+    pub fn public_key(&self) -> Option<&RsaPublicKey> {
+        match self {
+            JwtKey::Hmac(_) => None,
+            JwtKey::Rs256 { verifying_key, .. } => Some(verifying_key.public_key()),
+        }
+    }
+
+    // the JWK of the public key, a HMAC secret has none
+    pub fn public_jwk(&self, kid: &str) -> Option<PublicJwk> {
+        match self {
+            JwtKey::Hmac(_) => None,
+            JwtKey::Rs256 { verifying_key, .. } => {
+                let (n, e) = jwk_components(verifying_key.public_key());
+
+                Some(PublicJwk {
+                    kid: kid.to_string(),
+                    kty: "RSA",
+                    alg: "RS256",
+                    n,
+                    e,
+                })
+            }
+        }
+    }
+
+    // the key that auth tokens signed with this key are verified with
+    pub fn verifying_key(&self) -> VerifyingKey {
+        match self {
+            JwtKey::Hmac(key) => VerifyingKey::Hmac(Box::new(key.clone())),
+            JwtKey::Rs256 { verifying_key, .. } => VerifyingKey::Rs256(verifying_key.clone()),
+        }
+    }
+
+    fn new(secret: &Secret<String>, algorithm: JwtAlgorithm) -> Result<JwtKey, anyhow::Error> {
+        match algorithm {
+            JwtAlgorithm::Hs512 => JwtKey::hmac(secret),
+            JwtAlgorithm::Rs256 => JwtKey::rs256(secret),
+        }
+    }
+}
+
+// a public key in the fields of a JWK
+#[derive(Debug)]
+pub struct PublicJwk {
+    pub kid: String,
+    pub kty: &'static str,
+    pub alg: &'static str,
+    pub n: String,
+    pub e: String,
+}
+
+// the keys that auth tokens are signed and verified with, only the active key signs new auth tokens while the other
+// keys are still accepted so rotating the active key doesn't invalidate the auth tokens that have been handed out
+#[derive(Clone)]
+pub struct JwtKeys {
+    active_kid: String,
+    // kid -> key
+    keys: BTreeMap<String, JwtKey>,
+    verifier: TokenVerifier,
+}
+
+impl JwtKeys {
+    pub fn from_secrets(
+        secrets: &Secrets,
+        algorithm: JwtAlgorithm,
+    ) -> Result<JwtKeys, anyhow::Error> {
+        if let Some(keyring) = &secrets.jwt_keyring {
+            return JwtKeys::from_keyring(keyring, algorithm);
+        }
+
+        match algorithm {
+            JwtAlgorithm::Hs512 => match &secrets.jwt_secret {
+                Some(jwt_secret) => JwtKeys::hmac(jwt_secret),
+                None => Err(anyhow!(
+                    "jwt_secret or jwt_keyring is needed to sign auth tokens with HS512"
+                )),
+            },
+            JwtAlgorithm::Rs256 => match &secrets.jwt_private_key {
+                Some(jwt_private_key) => JwtKeys::rs256(jwt_private_key),
+                None => Err(anyhow!(
+                    "jwt_private_key or jwt_keyring is needed to sign auth tokens with RS256"
+                )),
+            },
+        }
+    }
+
+    pub fn from_keyring(
+        keyring: &JwtKeyringSecret,
+        algorithm: JwtAlgorithm,
+    ) -> Result<JwtKeys, anyhow::Error> {
+        let mut keys = BTreeMap::new();
+        for key in &keyring.keys {
+            let jwt_key = JwtKey::new(&key.key, algorithm)
+                .map_err(|e| anyhow!("key {} of the jwt_keyring is invalid: {}", key.kid, e))?;
+
+            if keys.insert(key.kid.clone(), jwt_key).is_some() {
+                return Err(anyhow!("kid {} is used twice in the jwt_keyring", key.kid));
+            }
+        }
+
+        if !keys.contains_key(&keyring.active_kid) {
+            return Err(anyhow!(
+                "the active kid {} is not part of the jwt_keyring",
+                keyring.active_kid
+            ));
+        }
+
+        Ok(JwtKeys::new(keyring.active_kid.clone(), keys))
+    }
+
+    pub fn hmac(secret: &Secret<String>) -> Result<JwtKeys, anyhow::Error> {
+        Ok(JwtKeys::single(
+            JWT_SECRET_KID.to_string(),
+            JwtKey::hmac(secret)?,
+        ))
+    }
+
+    pub fn rs256(private_key_pem: &Secret<String>) -> Result<JwtKeys, anyhow::Error> {
+        let key = JwtKey::rs256(private_key_pem)?;
+        let kid = jwk_thumbprint(key.public_key().expect("RS256 keys have a public key"));
+
+        Ok(JwtKeys::single(kid, key))
+    }
+
+    fn single(kid: String, key: JwtKey) -> JwtKeys {
+        JwtKeys::new(kid.clone(), BTreeMap::from([(kid, key)]))
+    }
+
+    fn new(active_kid: String, keys: BTreeMap<String, JwtKey>) -> JwtKeys {
+        let mut verifier = TokenVerifier::new();
+        for (kid, key) in &keys {
+            verifier.add_key(kid.clone(), key.verifying_key());
+        }
+
+        JwtKeys {
+            active_kid,
+            keys,
+            verifier,
+        }
+    }
+
+    // the key that new auth tokens are signed with
+    pub fn active(&self) -> (&str, &JwtKey) {
+        (&self.active_kid, &self.keys[&self.active_kid])
+    }
+
+    // every key of the keyring is accepted, not only the active one
+    pub fn verifier(&self) -> &TokenVerifier {
+        &self.verifier
+    }
+
+    pub fn kids(&self) -> Vec<&str> {
+        self.keys.keys().map(String::as_str).collect()
+    }
+
+    // the keys that other services need to verify auth tokens, a HMAC secret is never handed out
+    pub fn public_keys(&self) -> Vec<PublicJwk> {
+        self.keys
+            .iter()
+            .filter_map(|(kid, key)| key.public_jwk(kid))
+            .collect()
+    }
+}
+
+// the base64url encoded modulus and exponent of the public key, as they are written in a JWK
+pub fn jwk_components(public_key: &RsaPublicKey) -> (String, String) {
+    (
+        BASE64URL_NOPAD.encode(&public_key.n().to_bytes_be()),
+        BASE64URL_NOPAD.encode(&public_key.e().to_bytes_be()),
+    )
+}
+
+// the JWK thumbprint of RFC 7638 is used as the kid, so the same key always gets the same kid
+pub fn jwk_thumbprint(public_key: &RsaPublicKey) -> String {
+    let (n, e) = jwk_components(public_key);
+    let jwk = format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, e, n);
+
+    BASE64URL_NOPAD.encode(&Sha256::digest(jwk.as_bytes()))
+}
@@ -1,21 +1,8 @@
-use std::collections::BTreeMap;
+pub use token_claims::{get_bearer_token, Claims, TokenError};
 
-use anyhow::Error;
-use chrono::Local;
-use hmac::{Hmac, Mac};
-use jwt::VerifyWithKey;
-use secrecy::{ExposeSecret, Secret};
-use sha2::Sha512;
+use super::JwtKeys;
 
-pub fn auth_token_expired(secret_key: Secret<String>, auth_token: &str) -> Result<bool, Error> {
-    let key: Hmac<Sha512> = Hmac::new_from_slice(secret_key.expose_secret().as_bytes())?;
-
-    let claims: BTreeMap<String, String> = auth_token.verify_with_key(&key).unwrap();
-
-    let now_timestamp = Local::now().timestamp().to_string();
-    if now_timestamp > claims["exp"] {
-        return Ok(true);
-    }
-
-    Ok(false)
+// verifies the signature, the generic claims and the expiration time of the auth token and returns the claims
+pub fn verify_auth_token(keys: &JwtKeys, auth_token: &str) -> Result<Claims, TokenError> {
+    keys.verifier().verify(auth_token)
 }
@@ -0,0 +1,54 @@
+use sqlx::PgPool;
+
+// the unique constraints of the account table, the one that a failed insert names tells which field is taken
+pub const USERNAME_UNIQUE_CONSTRAINT: &str = "account_username_key";
+pub const EMAIL_UNIQUE_CONSTRAINT: &str = "account_email_key";
+// unique indexes on the normalized form of the username and email
+pub const USERNAME_NORMALIZED_UNIQUE_INDEX: &str = "account_username_normalized_key";
+pub const EMAIL_NORMALIZED_UNIQUE_INDEX: &str = "account_email_normalized_key";
+
+#[tracing::instrument(name = "Check if username is taken", skip(db_pool))]
+pub async fn is_username_taken_db(db_pool: &PgPool, username: &str) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM account WHERE username = $1) AS "taken!""#,
+        username
+    )
+    .fetch_one(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.taken)
+}
+
+#[tracing::instrument(name = "Check if email is taken", skip(db_pool))]
+pub async fn is_email_taken_db(db_pool: &PgPool, email: &str) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM account WHERE email = $1) AS "taken!""#,
+        email
+    )
+    .fetch_one(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(row.taken)
+}
+
+// the field that a failed registration collided with, none when the error isn't about a taken username or email
+pub fn taken_field(error: &anyhow::Error) -> Option<&'static str> {
+    let database_error = error
+        .downcast_ref::<sqlx::Error>()?
+        .as_database_error()
+        .filter(|e| e.is_unique_violation())?;
+
+    match database_error.constraint()? {
+        USERNAME_UNIQUE_CONSTRAINT | USERNAME_NORMALIZED_UNIQUE_INDEX => Some("username"),
+        EMAIL_UNIQUE_CONSTRAINT | EMAIL_NORMALIZED_UNIQUE_INDEX => Some("email"),
+        _ => None,
+    }
+}
@@ -0,0 +1,45 @@
+use secrecy::{ExposeSecret, Secret};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+#[tracing::instrument(name = "Get stored password hash of user", skip(db_pool))]
+pub async fn get_password_hash_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+) -> Result<Option<Secret<String>>, sqlx::Error> {
+    let password_hash = sqlx::query!(
+        r#"SELECT password_hash from account WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve password hash: {:?}", e);
+        e
+    })?
+    .map(|row| Secret::new(row.password_hash));
+
+    Ok(password_hash)
+}
+
+#[tracing::instrument(
+    name = "Update password hash of user in DB",
+    skip(transaction, password_hash)
+)]
+pub async fn update_password_hash_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    password_hash: Secret<String>,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"UPDATE account SET password_hash = $1 WHERE user_id = $2"#,
+        password_hash.expose_secret(),
+        user_id
+    );
+
+    transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
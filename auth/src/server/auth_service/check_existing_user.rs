@@ -5,20 +5,49 @@ use tokio::task::spawn_blocking;
 
 use crate::proto::auth::LoginRequest;
 
-use super::verify_password_hash;
+use super::{
+    compute_password_hash, normalize_username, password_hash_needs_rehash, update_password_hash_db,
+    verify_password_hash, Email,
+};
 
 #[derive(Debug, Error)]
 pub enum CheckUserExistsError {
     #[error("Provided credintels does not belong to any registered user")]
     NonExistingUser,
+    // carries the user that the password was wrong for
     #[error("Provided password is wrong")]
-    WrongPassword,
+    WrongPassword(i32),
     #[error("Something went wrong in the DB: {0}")]
     DatabaseError(#[from] sqlx::Error),
     #[error(transparent)]
     UnexpectedError(#[from] tokio::task::JoinError),
 }
 
+// users log in with either their username or their email
+#[derive(Debug)]
+pub enum LoginIdentifier {
+    Username(String),
+    Email(Email),
+}
+
+impl LoginIdentifier {
+    pub fn parse(identifier: String) -> LoginIdentifier {
+        match Email::parse(identifier.clone()) {
+            Ok(email) => LoginIdentifier::Email(email),
+            Err(_) => LoginIdentifier::Username(normalize_username(&identifier)),
+        }
+    }
+}
+
+impl AsRef<str> for LoginIdentifier {
+    fn as_ref(&self) -> &str {
+        match self {
+            LoginIdentifier::Username(username) => username,
+            LoginIdentifier::Email(email) => email.as_ref(),
+        }
+    }
+}
+
 #[tracing::instrument(name = "Get stored password hash", skip(username, db_pool))]
 pub async fn get_stored_password_hash(
     username: &str,
@@ -41,6 +70,72 @@ pub async fn get_stored_password_hash(
     Ok(query)
 }
 
+#[tracing::instrument(name = "Get stored password hash by email", skip(email, db_pool))]
+pub async fn get_stored_password_hash_by_email(
+    email: &str,
+    db_pool: &PgPool,
+) -> Result<Option<(i32, Secret<String>)>, sqlx::Error> {
+    let query = sqlx::query!(
+        r#"SELECT user_id, password_hash from account WHERE email = $1"#,
+        email
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve password hash: {:?}", e);
+        e
+    })?
+    .map(|row| (row.user_id, Secret::new(row.password_hash)));
+
+    Ok(query)
+}
+
+async fn get_stored_password_hash_by_identifier(
+    identifier: &LoginIdentifier,
+    db_pool: &PgPool,
+) -> Result<Option<(i32, Secret<String>)>, sqlx::Error> {
+    match identifier {
+        LoginIdentifier::Username(username) => get_stored_password_hash(username, db_pool).await,
+        LoginIdentifier::Email(email) => {
+            match get_stored_password_hash_by_email(email.as_ref(), db_pool).await? {
+                Some(stored) => Ok(Some(stored)),
+                // usernames may contain an @, so a username can look like an email
+                None => {
+                    get_stored_password_hash(&normalize_username(email.as_ref()), db_pool).await
+                }
+            }
+        }
+    }
+}
+
+// an email is also tried as a username, like when the password hash is looked up
+#[tracing::instrument(name = "Get username by login identifier", skip(identifier, db_pool))]
+pub async fn get_username_by_identifier(
+    identifier: &LoginIdentifier,
+    db_pool: &PgPool,
+) -> Result<Option<String>, sqlx::Error> {
+    let (email, username) = match identifier {
+        LoginIdentifier::Username(username) => (None, username.clone()),
+        LoginIdentifier::Email(email) => (Some(email.as_ref()), normalize_username(email.as_ref())),
+    };
+
+    let query = sqlx::query!(
+        r#"SELECT username FROM account WHERE email = $1 OR username = $2
+        ORDER BY email = $1 DESC NULLS LAST LIMIT 1"#,
+        email,
+        username
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve username: {:?}", e);
+        e
+    })?
+    .map(|row| row.username);
+
+    Ok(query)
+}
+
 #[tracing::instrument(name = "checking if user exists", skip(db_pool, login_request))]
 pub async fn check_user_exists(
     login_request: LoginRequest,
@@ -55,27 +150,69 @@ pub async fn check_user_exists(
             .to_string(),
     );
 
+    let identifier = LoginIdentifier::parse(login_request.identifier);
+
     if let Some((stored_user_id, stored_password_hash)) =
-        get_stored_password_hash(login_request.username.as_ref(), db_pool).await?
+        get_stored_password_hash_by_identifier(&identifier, db_pool).await?
     {
         user_id = Some(stored_user_id);
         expected_password_hash = stored_password_hash;
     }
 
-    if user_id.is_none() {
-        return Err(CheckUserExistsError::NonExistingUser);
-    }
+    let stored_password_hash = expected_password_hash.clone();
+    let password = login_request.password.clone();
 
+    // the password is verified against the dummy hash when there is no such user, so the response time doesn't
+    // reveal whether an account exists
     let result_verifying_password = spawn_blocking(move || {
         verify_password_hash(expected_password_hash, login_request.password)
     })
     .await
     .map_err(CheckUserExistsError::UnexpectedError)?;
 
+    let user_id = match user_id {
+        Some(user_id) => user_id,
+        None => return Err(CheckUserExistsError::NonExistingUser),
+    };
+
     match result_verifying_password {
         Ok(_) => {}
-        Err(_) => return Err(CheckUserExistsError::WrongPassword),
+        Err(_) => return Err(CheckUserExistsError::WrongPassword(user_id)),
+    }
+
+    // the login doesn't depend on the rehash, so it's fine if it fails
+    if let Err(e) =
+        rehash_password_if_needed(user_id, stored_password_hash, password, db_pool).await
+    {
+        tracing::warn!("Failed to rehash password: {:?}", e);
+    }
+
+    Ok(user_id)
+}
+
+// the password is only known right after a successful login, so this is when a password hash that was made with
+// outdated argon params can be replaced
+#[tracing::instrument(
+    name = "Rehashing password if needed",
+    skip(stored_password_hash, password, db_pool)
+)]
+async fn rehash_password_if_needed(
+    user_id: i32,
+    stored_password_hash: Secret<String>,
+    password: String,
+    db_pool: &PgPool,
+) -> Result<(), anyhow::Error> {
+    if !password_hash_needs_rehash(&stored_password_hash)? {
+        return Ok(());
     }
 
-    Ok(user_id.unwrap())
+    tracing::info!("password hash has outdated params, rehashing it");
+
+    let password_hash = spawn_blocking(move || compute_password_hash(&password)).await??;
+
+    let mut transaction = db_pool.begin().await?;
+    update_password_hash_db(&mut transaction, &user_id, password_hash).await?;
+    transaction.commit().await?;
+
+    Ok(())
 }
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use super::{StoredAuthEvent, StoredProfile, StoredSession};
+
+// everything that is stored about a user, secrets like the password hash, the totp secret and hashes of one-time
+// codes are left out since they are of no use to the user and would only make the export dangerous to keep around
+#[derive(Serialize)]
+pub struct DataExport {
+    pub exported_at: DateTime<Utc>,
+    pub account: ExportedAccount,
+    pub roles: Vec<String>,
+    pub sessions: Vec<ExportedSession>,
+    pub two_factor: ExportedTwoFactor,
+    pub auth_events: Vec<ExportedAuthEvent>,
+}
+
+#[derive(Serialize)]
+pub struct ExportedAccount {
+    pub user_id: i32,
+    pub username: String,
+    pub firstname: String,
+    pub lastname: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub display_name: Option<String>,
+    pub status_text: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExportedSession {
+    pub session_id: String,
+    pub device: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct ExportedAuthEvent {
+    pub event_type: String,
+    pub peer_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct ExportedTwoFactor {
+    pub enabled: bool,
+    pub unused_backup_codes: i64,
+}
+
+impl ExportedAccount {
+    pub fn new(user_id: i32, profile: StoredProfile) -> ExportedAccount {
+        ExportedAccount {
+            user_id,
+            username: profile.username,
+            firstname: profile.firstname,
+            lastname: profile.lastname,
+            email: profile.email,
+            email_verified: profile.verified,
+            display_name: profile.display_name,
+            status_text: profile.status_text,
+        }
+    }
+}
+
+impl From<StoredAuthEvent> for ExportedAuthEvent {
+    fn from(event: StoredAuthEvent) -> ExportedAuthEvent {
+        ExportedAuthEvent {
+            event_type: event.event_type,
+            peer_address: event.peer_address,
+            user_agent: event.user_agent,
+            detail: event.detail,
+            created_at: event.created_at,
+        }
+    }
+}
+
+impl From<StoredSession> for ExportedSession {
+    fn from(session: StoredSession) -> ExportedSession {
+        ExportedSession {
+            session_id: session.session_id.to_string(),
+            device: session.device,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+        }
+    }
+}
+
+#[tracing::instrument(name = "Count unused backup codes of user", skip(db_pool))]
+pub async fn count_unused_backup_codes_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+) -> Result<i64, sqlx::Error> {
+    let count = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM totp_backup_codes
+        WHERE user_id = $1 AND used = FALSE
+        "#,
+        user_id
+    )
+    .fetch_one(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count backup codes: {:?}", e);
+        e
+    })?
+    .count;
+
+    Ok(count)
+}
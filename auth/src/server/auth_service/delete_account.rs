@@ -0,0 +1,18 @@
+use sqlx::{Executor, Postgres, Transaction};
+
+// the rows of every other table that belong to the account are removed with it by their foreign keys, returns false
+// when the account didn't exist
+#[tracing::instrument(name = "Delete account of user from DB", skip(transaction))]
+pub async fn delete_account_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+) -> Result<bool, sqlx::Error> {
+    let query = sqlx::query!(r#"DELETE FROM account WHERE user_id = $1"#, user_id);
+
+    let result = transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+pub struct EmailVerificationStatus {
+    pub user_id: i32,
+    pub email: String,
+    pub verified: bool,
+}
+
+#[tracing::instrument(name = "Get email verification status of user", skip(db_pool))]
+pub async fn get_verification_status_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+) -> Result<Option<EmailVerificationStatus>, sqlx::Error> {
+    let status = sqlx::query_as!(
+        EmailVerificationStatus,
+        r#"SELECT user_id, email, verified FROM account WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve email verification status: {:?}", e);
+        e
+    })?;
+
+    Ok(status)
+}
+
+#[tracing::instrument(name = "Get email verification status of email", skip(db_pool, email))]
+pub async fn get_verification_status_by_email_db(
+    db_pool: &PgPool,
+    email: &str,
+) -> Result<Option<EmailVerificationStatus>, sqlx::Error> {
+    let status = sqlx::query_as!(
+        EmailVerificationStatus,
+        r#"SELECT user_id, email, verified FROM account WHERE email = $1"#,
+        email
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve email verification status: {:?}", e);
+        e
+    })?;
+
+    Ok(status)
+}
+
+// only the latest verification token of a user can be used, so the earlier ones are marked as used
+#[tracing::instrument(
+    name = "Store email verification token into DB"
+    skip(transaction, email, token_hash)
+)]
+pub async fn store_verification_token_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    email: &str,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let invalidate_query = sqlx::query!(
+        r#"UPDATE email_verification_tokens SET used = TRUE WHERE user_id = $1 AND used = FALSE"#,
+        user_id
+    );
+
+    transaction.execute(invalidate_query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO email_verification_tokens (token_hash, user_id, email, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        token_hash,
+        user_id,
+        email,
+        expires_at
+    );
+
+    transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+// marks the verification token as used and marks the account as verified, as long as the account still has the
+// email the token was sent to. returns false when the token doesn't exist, has expired or has already been used
+#[tracing::instrument(
+    name = "Verify email with token in DB"
+    skip(transaction, token_hash)
+)]
+pub async fn verify_email_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    token_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let token = sqlx::query!(
+        r#"
+        UPDATE email_verification_tokens SET used = TRUE
+        WHERE token_hash = $1 AND used = FALSE AND expires_at > now()
+        RETURNING user_id, email
+        "#,
+        token_hash
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    let token = match token {
+        Some(token) => token,
+        None => return Ok(false),
+    };
+
+    let query = sqlx::query!(
+        r#"UPDATE account SET verified = TRUE WHERE user_id = $1 AND email = $2"#,
+        token.user_id,
+        token.email
+    );
+
+    let result = transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,5 @@
+mod postgres_operations;
+mod redis_operations;
+
+pub use postgres_operations::*;
+pub use redis_operations::*;
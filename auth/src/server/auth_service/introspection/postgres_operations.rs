@@ -0,0 +1,29 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// the username of the user that the session belongs to, none when the session doesn't exist anymore
+#[tracing::instrument(name = "Get username of session", skip(db_pool))]
+pub async fn get_session_username_db(
+    db_pool: &PgPool,
+    session_id: &Uuid,
+    user_id: &i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let username = sqlx::query!(
+        r#"
+        SELECT account.username FROM auth_tokens
+        JOIN account ON account.user_id = auth_tokens.user_id
+        WHERE auth_tokens.session_id = $1 AND auth_tokens.user_id = $2
+        "#,
+        session_id,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve username of session: {:?}", e);
+        e
+    })?
+    .map(|row| row.username);
+
+    Ok(username)
+}
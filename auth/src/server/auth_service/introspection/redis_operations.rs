@@ -0,0 +1,58 @@
+use anyhow::anyhow;
+use chrono::Duration;
+use redis::{AsyncCommands, RedisResult};
+
+use crate::server::RedisCon;
+
+// the session lookup of an introspected auth token is cached as "introspection:<jti>", the value is the username or
+// empty when the session doesn't exist anymore
+pub const INTROSPECTION_PREFIX: &str = "introspection";
+
+#[tracing::instrument(
+    name = "Store introspection into redis"
+    skip(redis_connection, username)
+)]
+pub async fn store_introspection_redis(
+    redis_connection: RedisCon,
+    jti: &str,
+    username: Option<&str>,
+    lifetime: Duration,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let res: RedisResult<()> = redis_con
+        .set_ex(
+            format!("{}:{}", INTROSPECTION_PREFIX, jti),
+            username.unwrap_or_default(),
+            lifetime.num_seconds() as u64,
+        )
+        .await;
+
+    if res.is_err() {
+        return Err(anyhow!("couldn't save introspection in redis"));
+    }
+
+    Ok(())
+}
+
+// none when nothing has been cached, otherwise the cached username of the session
+#[tracing::instrument(
+    name = "Get introspection from redis"
+    skip(redis_connection)
+)]
+pub async fn get_introspection_redis(
+    redis_connection: RedisCon,
+    jti: &str,
+) -> Result<Option<Option<String>>, anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let username: Option<String> = match redis_con
+        .get(format!("{}:{}", INTROSPECTION_PREFIX, jti))
+        .await
+    {
+        Ok(username) => username,
+        Err(_) => return Err(anyhow!("couldn't get introspection from redis")),
+    };
+
+    Ok(username.map(|username| Some(username).filter(|username| !username.is_empty())))
+}
@@ -0,0 +1,174 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use redis::{AsyncCommands, RedisResult};
+
+use crate::configuration::LoginThrottleSettings;
+use crate::server::RedisCon;
+
+// failed logins are counted as "login_failures:<subject>" and a locked out subject has a "login_lockout:<subject>"
+// key that expires when the lockout ends
+const LOGIN_FAILURES_PREFIX: &str = "login_failures";
+const LOGIN_LOCKOUT_PREFIX: &str = "login_lockout";
+
+// something that failed logins are counted for, either the account that was tried or the address the attempts
+// come from
+#[derive(Debug)]
+pub struct ThrottleSubject {
+    key: String,
+    max_failed_attempts: u32,
+}
+
+// failed logins are counted for the username of the account that was tried, so its username and its email share
+// the guesses. an identifier that doesn't belong to any account is counted as it is, otherwise only existing
+// accounts could be locked out and a lockout would give away which ones exist
+pub fn account_throttle_subject(
+    account: &str,
+    settings: &LoginThrottleSettings,
+) -> ThrottleSubject {
+    ThrottleSubject {
+        key: format!("account:{}", account),
+        max_failed_attempts: settings.max_failed_attempts_per_username,
+    }
+}
+
+pub fn login_throttle_subjects(
+    account: &str,
+    peer_ip: Option<IpAddr>,
+    settings: &LoginThrottleSettings,
+) -> Vec<ThrottleSubject> {
+    let mut subjects = vec![account_throttle_subject(account, settings)];
+
+    // the peer address isn't known when the server isn't served over a TCP socket
+    if let Some(peer_ip) = peer_ip {
+        subjects.push(ThrottleSubject {
+            key: format!("ip:{}", peer_ip),
+            max_failed_attempts: settings.max_failed_attempts_per_ip,
+        });
+    }
+
+    subjects
+}
+
+// returns how long the longest running lockout of the subjects has left
+#[tracing::instrument(name = "Check login lockout in redis" skip(redis_connection))]
+pub async fn get_login_lockout_redis(
+    redis_connection: RedisCon,
+    subjects: &[ThrottleSubject],
+) -> Result<Option<Duration>, anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let mut lockout = None;
+    for subject in subjects {
+        let remaining_millis: RedisResult<i64> = redis_con
+            .pttl(format!("{}:{}", LOGIN_LOCKOUT_PREFIX, subject.key))
+            .await;
+
+        let remaining_millis = match remaining_millis {
+            Ok(remaining_millis) => remaining_millis,
+            Err(_) => return Err(anyhow!("couldn't check login lockout in redis")),
+        };
+
+        // a negative ttl means that there is no lockout
+        if remaining_millis > 0 {
+            lockout = lockout.max(Some(Duration::from_millis(remaining_millis as u64)));
+        }
+    }
+
+    Ok(lockout)
+}
+
+// counts the failed login for every subject, a subject that has gone over its threshold is locked out for a
+// duration that doubles with every further failed login
+#[tracing::instrument(name = "Record failed login in redis" skip(redis_connection, settings))]
+pub async fn record_failed_login_redis(
+    redis_connection: RedisCon,
+    subjects: &[ThrottleSubject],
+    settings: &LoginThrottleSettings,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    for subject in subjects {
+        let failures_key = format!("{}:{}", LOGIN_FAILURES_PREFIX, subject.key);
+
+        let failed_attempts: u32 = match redis_con.incr(&failures_key, 1).await {
+            Ok(failed_attempts) => failed_attempts,
+            Err(_) => return Err(anyhow!("couldn't count failed login in redis")),
+        };
+
+        // failed attempts are forgotten when there hasn't been one for a while
+        let expire_res: RedisResult<()> = redis_con
+            .expire(
+                &failures_key,
+                settings.failed_attempts_window_seconds as i64,
+            )
+            .await;
+
+        if expire_res.is_err() {
+            return Err(anyhow!(
+                "couldn't set expire time for failed logins in redis"
+            ));
+        }
+
+        if failed_attempts < subject.max_failed_attempts {
+            continue;
+        }
+
+        let lockout_seconds = lockout_seconds(
+            failed_attempts - subject.max_failed_attempts,
+            settings.base_lockout_seconds,
+            settings.max_lockout_seconds,
+        );
+
+        tracing::warn!(
+            "too many failed logins for {}, locking it out for {} seconds",
+            subject.key,
+            lockout_seconds
+        );
+
+        let lockout_res: RedisResult<()> = redis_con
+            .set_ex(
+                format!("{}:{}", LOGIN_LOCKOUT_PREFIX, subject.key),
+                failed_attempts,
+                lockout_seconds,
+            )
+            .await;
+
+        if lockout_res.is_err() {
+            return Err(anyhow!("couldn't save login lockout in redis"));
+        }
+    }
+
+    Ok(())
+}
+
+// only meant for the account that has been logged into. the failed logins of an address are never reset, since
+// anyone could otherwise wipe them by logging into their own account every few guesses, they expire with the window
+#[tracing::instrument(name = "Reset failed logins in redis" skip(redis_connection))]
+pub async fn reset_failed_logins_redis(
+    redis_connection: RedisCon,
+    subject: &ThrottleSubject,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let keys = [
+        format!("{}:{}", LOGIN_FAILURES_PREFIX, subject.key),
+        format!("{}:{}", LOGIN_LOCKOUT_PREFIX, subject.key),
+    ];
+
+    let res: RedisResult<()> = redis_con.del(&keys).await;
+
+    if res.is_err() {
+        return Err(anyhow!("couldn't reset failed logins in redis"));
+    }
+
+    Ok(())
+}
+
+fn lockout_seconds(attempts_over_threshold: u32, base_seconds: u64, max_seconds: u64) -> u64 {
+    // the shift is capped so it can't overflow, the lockout is capped by the max long before that anyway
+    base_seconds
+        .saturating_mul(1 << attempts_over_threshold.min(32))
+        .min(max_seconds)
+}
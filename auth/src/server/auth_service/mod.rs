@@ -1,34 +1,99 @@
+mod api_keys;
+mod auth_events;
 mod auth_token;
+mod availability;
+mod change_password;
 mod check_existing_user;
+mod data_export;
+mod delete_account;
+mod email_verification;
+mod introspection;
+mod login_throttle;
+mod one_time_code;
 mod password;
+mod password_reset;
+mod profile;
 mod register;
+mod roles;
+mod two_factor;
 
+pub use api_keys::*;
+pub use auth_events::*;
 pub use auth_token::*;
+pub use availability::*;
+pub use change_password::*;
 pub use check_existing_user::*;
+pub use data_export::*;
+pub use delete_account::*;
+pub use email_verification::*;
+pub use introspection::*;
+pub use login_throttle::*;
+pub use one_time_code::*;
 pub use password::*;
+pub use password_reset::*;
+pub use profile::*;
 pub use register::*;
+pub use roles::*;
+pub use two_factor::*;
 
+use chrono::{DateTime, Utc};
 use redis::aio::MultiplexedConnection;
-use sqlx::postgres::PgPool;
+use secrecy::Secret;
+use sqlx::{postgres::PgPool, Postgres, Transaction};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::sync::Mutex;
 use tokio::task::spawn_blocking;
 use tonic::{Code, Request, Response, Status};
 use tonic_types::{ErrorDetails, StatusExt};
+use uuid::Uuid;
 
 use crate::proto::auth::auth_server::Auth;
 // bring in our messages
-use crate::proto::auth::{LoginRequest, RegisterRequest, Token};
+use crate::configuration::{
+    AuthTokenSettings, EmailVerificationSettings, IntrospectionSettings, LoginThrottleSettings,
+    PasswordResetSettings, TotpSettings, UnverifiedLoginPolicy,
+};
+use crate::mailer::{MailMessage, Mailer};
+use crate::proto::auth::{
+    ApiKey, AuthEvent, ChangePasswordRequest, ChangePasswordResponse, CheckAvailabilityRequest,
+    CheckAvailabilityResponse, CompleteLoginRequest, ConfirmTotpRequest, ConfirmTotpResponse,
+    CreateApiKeyRequest, CreateApiKeyResponse, CreateServiceAccountRequest, DeleteAccountRequest,
+    DeleteAccountResponse, EnrollTotpRequest, EnrollTotpResponse, ExportMyDataRequest,
+    ExportMyDataResponse, GetProfileRequest, GetSigningKeysRequest, GetSigningKeysResponse,
+    GrantRoleRequest, GrantRoleResponse, IntrospectRequest, IntrospectResponse, ListApiKeysRequest,
+    ListApiKeysResponse, ListAuthEventsRequest, ListAuthEventsResponse, ListSessionsRequest,
+    ListSessionsResponse, LoginRequest, LogoutRequest, LogoutResponse, Profile, RefreshRequest,
+    RegisterRequest, RequestPasswordResetRequest, RequestPasswordResetResponse,
+    ResendVerificationEmailRequest, ResendVerificationEmailResponse, ResetPasswordRequest,
+    ResetPasswordResponse, RevokeApiKeyRequest, RevokeApiKeyResponse, RevokeRoleRequest,
+    RevokeRoleResponse, RevokeSessionRequest, RevokeSessionResponse, ServiceAccount, Session,
+    SigningKey, Token, UpdateProfileRequest, VerifyEmailRequest, VerifyEmailResponse,
+};
 use crate::secrets::Secrets;
 
-pub use super::RegisterData;
+use super::{normalize_username, PasswordPolicy, RegisterDataError, ServiceAccountName, Username};
+pub use super::{Email, Password, ProfileUpdate, RegisterData};
 
 pub type RedisCon = Arc<Mutex<MultiplexedConnection>>;
 
+const DEFAULT_AUTH_EVENTS_PAGE_SIZE: u32 = 50;
+const MAX_AUTH_EVENTS_PAGE_SIZE: u32 = 100;
+
 pub struct AuthenticationService {
     pub db_pool: PgPool,
     pub redis_con: RedisCon,
     pub secrets: Secrets,
+    pub jwt_keyring: JwtKeyring,
+    pub auth_token_settings: AuthTokenSettings,
+    pub password_reset_settings: PasswordResetSettings,
+    pub email_verification_settings: EmailVerificationSettings,
+    pub login_throttle_settings: LoginThrottleSettings,
+    pub totp_settings: TotpSettings,
+    pub introspection_settings: IntrospectionSettings,
+    pub password_policy: PasswordPolicy,
+    pub mailer: Arc<dyn Mailer>,
 }
 
 #[tonic::async_trait]
@@ -38,16 +103,19 @@ impl Auth for AuthenticationService {
         name = "User Login"
         skip(self, request)
         fields(
-            username = %request.get_ref().username
+            identifier = %request.get_ref().identifier
         )
     )]
     async fn login(&self, request: Request<LoginRequest>) -> Result<Response<Token>, Status> {
-        let login_request = request.into_inner();
+        let device = session_device(&request, &request.get_ref().device);
+        let peer_ip = request.remote_addr().map(|peer_addr| peer_addr.ip());
+        let origin = RequestOrigin::of(&request);
+        let mut login_request = request.into_inner();
 
         let mut error_details = ErrorDetails::new();
 
-        if login_request.username.is_empty() {
-            error_details.add_bad_request_violation("username", "username field is empty");
+        if login_request.identifier.is_empty() {
+            error_details.add_bad_request_violation("identifier", "identifier field is empty");
         }
 
         if login_request.password.is_empty() {
@@ -64,75 +132,231 @@ impl Auth for AuthenticationService {
             return Err(status);
         }
 
-        let user_id = match check_user_exists(login_request, &self.db_pool).await {
-            Ok(e) => e,
-            Err(e) => match e {
-                CheckUserExistsError::NonExistingUser => {
-                    return Err(Status::unauthenticated(e.to_string()))
-                }
-                _ => return Err(Status::internal(e.to_string())),
-            },
+        // the same user can write their identifier in different ways, it's throttled and looked up the way it's stored
+        let parsed_identifier = LoginIdentifier::parse(login_request.identifier);
+        login_request.identifier = parsed_identifier.as_ref().to_string();
+
+        let account = match get_username_by_identifier(&parsed_identifier, &self.db_pool).await {
+            Ok(username) => username.unwrap_or_else(|| login_request.identifier.clone()),
+            Err(_) => return Err(Status::internal("Couldn't get username from DB")),
         };
 
-        let mut auth_token = match get_token_redis(self.redis_con.clone(), &user_id).await {
+        // failed logins are limited both for the account and for the client, so passwords can't be guessed as
+        // fast as requests can be sent
+        let throttle_subjects =
+            login_throttle_subjects(&account, peer_ip, &self.login_throttle_settings);
+
+        match get_login_lockout_redis(self.redis_con.clone(), &throttle_subjects).await {
+            Ok(None) => (),
+            Ok(Some(lockout)) => {
+                let detail = format!("locked out, identifier {}", login_request.identifier);
+                self.record_event(AuthEventKind::LoginFailed, None, &origin, Some(&detail))
+                    .await;
+
+                let status = Status::with_error_details(
+                    Code::ResourceExhausted,
+                    "Too many failed login attempts, try again later",
+                    ErrorDetails::with_retry_info(Some(lockout)),
+                );
+
+                return Err(status);
+            }
+            Err(_) => return Err(Status::internal("Couldn't check login lockout")),
+        }
+
+        let identifier = login_request.identifier.clone();
+
+        let user_id = match check_user_exists(login_request, &self.db_pool).await {
             Ok(e) => e,
-            Err(_) => match get_token_db(&self.db_pool, &user_id).await {
-                Ok(e) => match e {
-                    Some(token) => token,
-                    None => return Err(Status::internal("Couldn't get auth token from DB")),
-                },
-                Err(_) => return Err(Status::internal("Couldn't get auth token")),
-            },
-        };
+            Err(e) => {
+                let failed_login = match &e {
+                    CheckUserExistsError::NonExistingUser => {
+                        Some((None, format!("unknown identifier {}", identifier)))
+                    }
+                    CheckUserExistsError::WrongPassword(user_id) => {
+                        Some((Some(*user_id), "wrong password".to_string()))
+                    }
+                    _ => None,
+                };
+
+                if let Some((user_id, detail)) = failed_login {
+                    self.record_event(AuthEventKind::LoginFailed, user_id, &origin, Some(&detail))
+                        .await;
 
-        let secret_key = self.secrets.jwt_secret.clone();
-        auth_token = match auth_token_expired(secret_key.clone(), auth_token.as_str()) {
-            Ok(token_expired) => {
-                if token_expired {
-                    tracing::info!("auth token has expired");
-                    let new_auth_token = match spawn_blocking(move || {
-                        generate_auth_token(secret_key, user_id.to_string().as_str())
-                    })
+                    if let Err(e) = record_failed_login_redis(
+                        self.redis_con.clone(),
+                        &throttle_subjects,
+                        &self.login_throttle_settings,
+                    )
                     .await
                     {
-                        Ok(res) => match res {
-                            Ok(e) => e,
-                            Err(_) => {
-                                return Err(Status::internal("Failed to generate auth token"))
-                            }
-                        },
-                        Err(_) => return Err(Status::internal("Could not create a auth token")),
-                    };
-
-                    if update_token_db(&self.db_pool, &user_id, &new_auth_token)
-                        .await
-                        .is_err()
-                    {
-                        return Err(Status::internal(
-                            "auth token was invalid and could not update auth token in DB",
-                        ));
+                        tracing::error!("Failed to record failed login: {:?}", e);
                     }
+                }
 
-                    if store_token_redis(self.redis_con.clone(), &user_id, &new_auth_token)
-                        .await
-                        .is_err()
-                    {
-                        return Err(Status::internal("Could not store auth token into redis"));
+                match e {
+                    // a wrong password gets the same answer as an unknown user, so it doesn't give away which
+                    // accounts exist
+                    CheckUserExistsError::NonExistingUser
+                    | CheckUserExistsError::WrongPassword(_) => {
+                        return Err(Status::unauthenticated(
+                            CheckUserExistsError::NonExistingUser.to_string(),
+                        ))
                     }
-
-                    new_auth_token
-                } else {
-                    // returning original auth_token
-                    auth_token
+                    _ => return Err(Status::internal(e.to_string())),
                 }
             }
-            Err(_) => return Err(Status::internal("Couldn't check expire time of auth token")),
         };
 
-        let token = Token {
-            access_token: auth_token,
+        if let Err(e) = reset_failed_logins_redis(
+            self.redis_con.clone(),
+            &account_throttle_subject(&account, &self.login_throttle_settings),
+        )
+        .await
+        {
+            tracing::error!("Failed to reset failed logins: {:?}", e);
+        }
+
+        let email_verified = self.check_email_verified(&user_id).await?;
+
+        // users with two-factor authentication only get a session once they have completed the login with a code
+        match get_totp_db(&self.db_pool, &user_id).await {
+            Ok(Some(stored_totp)) if stored_totp.confirmed => {
+                return Err(self.require_second_factor(&user_id, &device).await)
+            }
+            Ok(_) => (),
+            Err(_) => return Err(Status::internal("Couldn't get totp secret from DB")),
+        }
+
+        let token = self.start_session(user_id, &device, email_verified).await?;
+
+        self.record_event(AuthEventKind::LoginSucceeded, Some(user_id), &origin, None)
+            .await;
+
+        Ok(Response::new(token))
+    }
+
+    #[tracing::instrument(name = "Completing user login" skip(self, request))]
+    async fn complete_login(
+        &self,
+        request: Request<CompleteLoginRequest>,
+    ) -> Result<Response<Token>, Status> {
+        let origin = RequestOrigin::of(&request);
+        let complete_login_request = request.into_inner();
+
+        let mut error_details = ErrorDetails::new();
+
+        if complete_login_request.challenge.is_empty() {
+            error_details.add_bad_request_violation("challenge", "challenge field is empty");
+        }
+
+        if complete_login_request.code.trim().is_empty() {
+            error_details.add_bad_request_violation("code", "code field is empty");
+        }
+
+        if error_details.has_bad_request_violations() {
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let challenge_hash = hash_refresh_token(&complete_login_request.challenge);
+
+        let pending_login =
+            match get_login_challenge_redis(self.redis_con.clone(), &challenge_hash).await {
+                Ok(Some(pending_login)) => pending_login,
+                Ok(None) => {
+                    return Err(Status::unauthenticated(
+                        "Login challenge is not valid or has expired",
+                    ))
+                }
+                Err(_) => return Err(Status::internal("Couldn't get login challenge from redis")),
+            };
+
+        // the codes are short, so only a few of them can be tried before the password has to be given again
+        let attempts = match count_login_challenge_attempt_redis(
+            self.redis_con.clone(),
+            &challenge_hash,
+            self.totp_settings.login_challenge_lifetime(),
+        )
+        .await
+        {
+            Ok(attempts) => attempts,
+            Err(_) => return Err(Status::internal("Couldn't count login challenge attempt")),
         };
 
+        if attempts > self.totp_settings.max_code_attempts {
+            if delete_login_challenge_redis(self.redis_con.clone(), &challenge_hash)
+                .await
+                .is_err()
+            {
+                return Err(Status::internal(
+                    "Couldn't delete login challenge from redis",
+                ));
+            }
+
+            return Err(Status::unauthenticated("Too many wrong codes, login again"));
+        }
+
+        if !self
+            .use_second_factor(&pending_login.user_id, &complete_login_request.code)
+            .await?
+        {
+            self.record_event(
+                AuthEventKind::LoginFailed,
+                Some(pending_login.user_id),
+                &origin,
+                Some("wrong second factor code"),
+            )
+            .await;
+
+            let error_details = ErrorDetails::with_bad_request_violation(
+                "code",
+                "code is not valid or has already been used",
+            );
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        match delete_login_challenge_redis(self.redis_con.clone(), &challenge_hash).await {
+            Ok(true) => (),
+            // the challenge has been completed in the meantime
+            Ok(false) => {
+                return Err(Status::unauthenticated(
+                    "Login challenge is not valid or has expired",
+                ))
+            }
+            Err(_) => {
+                return Err(Status::internal(
+                    "Couldn't delete login challenge from redis",
+                ))
+            }
+        }
+
+        let email_verified = self.check_email_verified(&pending_login.user_id).await?;
+
+        let token = self
+            .start_session(pending_login.user_id, &pending_login.device, email_verified)
+            .await?;
+
+        self.record_event(
+            AuthEventKind::LoginSucceeded,
+            Some(pending_login.user_id),
+            &origin,
+            Some("with second factor"),
+        )
+        .await;
+
         Ok(Response::new(token))
     }
 
@@ -145,6 +369,8 @@ impl Auth for AuthenticationService {
         )
     )]
     async fn register(&self, request: Request<RegisterRequest>) -> Result<Response<Token>, Status> {
+        let device = session_device(&request, &request.get_ref().device);
+        let origin = RequestOrigin::of(&request);
         // this is needed for type annotation
         let request_result: Result<RegisterData, _> = request.into_inner().try_into();
 
@@ -163,6 +389,24 @@ impl Auth for AuthenticationService {
             }
             Ok(s) => s,
         };
+
+        if let Some(reason) = self.password_policy.check(
+            &reqister_request.password,
+            &[
+                reqister_request.username.as_ref(),
+                reqister_request.email.as_ref(),
+            ],
+        ) {
+            let error_details = ErrorDetails::with_bad_request_violation("password", reason);
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "bad request, Invalid arguments",
+                error_details,
+            );
+
+            return Err(status);
+        }
         // let register_request_arc = Arc::new(reqister_request);
 
         let mut transaction = match self.db_pool.begin().await {
@@ -174,33 +418,77 @@ impl Auth for AuthenticationService {
             }
         };
 
+        let email = reqister_request.email.as_ref().to_string();
+        let username = reqister_request.username.as_ref().to_string();
+
         let user_id = match register_user_into_db(&mut transaction, reqister_request).await {
-            Err(_) => return Err(Status::internal("Could not retrieve user_id")),
-            Ok(user_id) => user_id,
-        };
+            Err(e) => match taken_field(&e) {
+                Some(field) => {
+                    let error_details = ErrorDetails::with_bad_request_violation(
+                        field,
+                        format!("{} is already in use", field),
+                    );
 
-        let secret_key = self.secrets.jwt_secret.clone();
-        let auth_token = match spawn_blocking(move || {
-            generate_auth_token(secret_key, user_id.to_string().as_str())
-        })
-        .await
-        {
-            Ok(res) => match res {
-                Ok(e) => e,
-                Err(_) => return Err(Status::internal("Failed to generate auth token")),
+                    let status = Status::with_error_details(
+                        Code::AlreadyExists,
+                        format!("The {} is already in use", field),
+                        error_details,
+                    );
+
+                    return Err(status);
+                }
+                None => return Err(Status::internal("Could not retrieve user_id")),
             },
-            Err(_) => return Err(Status::internal("Could not create a auth token")),
+            Ok(user_id) => user_id,
         };
 
-        if store_token_db(&mut transaction, &user_id, &auth_token)
+        // every new user starts out as a regular user
+        if grant_role_db(&mut *transaction, &user_id, Role::User)
             .await
             .is_err()
+        {
+            return Err(Status::internal("Could not grant role to user"));
+        }
+
+        let verification_mail = self
+            .create_verification_mail(&mut transaction, &user_id, &email)
+            .await?;
+
+        // the email of a new user has not been verified yet
+        let session_id = Uuid::new_v4();
+        let token = self
+            .generate_token_pair(
+                &mut transaction,
+                user_id,
+                username,
+                vec![Role::User],
+                session_id,
+                false,
+            )
+            .await?;
+
+        if store_token_db(
+            &mut transaction,
+            &user_id,
+            &session_id,
+            &device,
+            &token.access_token,
+        )
+        .await
+        .is_err()
         {
             return Err(Status::internal("Could not store auth token into DB"));
         }
 
         match transaction.commit().await {
-            Ok(_) => match store_token_redis(self.redis_con.clone(), &user_id, &auth_token).await {
+            Ok(_) => match store_token_redis(
+                self.redis_con.clone(),
+                &session_id,
+                &token.access_token,
+                self.auth_token_settings.access_token_lifetime(),
+            )
+            .await
+            {
                 Ok(_) => (),
                 Err(e) => {
                     tracing::error!("Failed to save auth token to redis {:?}", e);
@@ -214,10 +502,2303 @@ impl Auth for AuthenticationService {
             }
         };
 
-        let token = Token {
-            access_token: auth_token,
+        self.record_event(AuthEventKind::Registered, Some(user_id), &origin, None)
+            .await;
+
+        self.send_mail(verification_mail).await;
+
+        Ok(Response::new(token))
+    }
+
+    #[tracing::instrument(
+        name = "Checking availability of username and email"
+        skip(self, request)
+        fields(
+            username = %request.get_ref().username,
+            email = %request.get_ref().email
+        )
+    )]
+    async fn check_availability(
+        &self,
+        request: Request<CheckAvailabilityRequest>,
+    ) -> Result<Response<CheckAvailabilityResponse>, Status> {
+        let request = request.into_inner();
+        let mut error_details = ErrorDetails::new();
+
+        // only what could be registered is looked up
+        let username = match request.username.as_str() {
+            "" => None,
+            _ => match Username::parse(request.username) {
+                Ok(username) => Some(username),
+                Err(e) => {
+                    let e = RegisterDataError::from(e);
+                    error_details.add_bad_request_violation(e.field, e.message.to_string());
+                    None
+                }
+            },
+        };
+
+        let email = match request.email.as_str() {
+            "" => None,
+            _ => match Email::parse(request.email) {
+                Ok(email) => Some(email),
+                Err(e) => {
+                    let e = RegisterDataError::from(e);
+                    error_details.add_bad_request_violation(e.field, e.message.to_string());
+                    None
+                }
+            },
+        };
+
+        if error_details.has_bad_request_violations() {
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let username_available = match username {
+            Some(username) => match is_username_taken_db(&self.db_pool, username.as_ref()).await {
+                Ok(taken) => Some(!taken),
+                Err(_) => return Err(Status::internal("Couldn't check username in DB")),
+            },
+            None => None,
+        };
+
+        let email_available = match email {
+            Some(email) => match is_email_taken_db(&self.db_pool, email.as_ref()).await {
+                Ok(taken) => Some(!taken),
+                Err(_) => return Err(Status::internal("Couldn't check email in DB")),
+            },
+            None => None,
+        };
+
+        Ok(Response::new(CheckAvailabilityResponse {
+            username_available,
+            email_available,
+        }))
+    }
+
+    #[tracing::instrument(name = "Refreshing auth token" skip(self, request))]
+    async fn refresh(&self, request: Request<RefreshRequest>) -> Result<Response<Token>, Status> {
+        let origin = RequestOrigin::of(&request);
+        let refresh_request = request.into_inner();
+
+        if refresh_request.refresh_token.is_empty() {
+            let error_details = ErrorDetails::with_bad_request_violation(
+                "refresh_token",
+                "refresh_token field is empty",
+            );
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let refresh_token_hash = hash_refresh_token(&refresh_request.refresh_token);
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
         };
 
+        let stored_refresh_token =
+            match get_refresh_token_db(&mut transaction, &refresh_token_hash).await {
+                Ok(Some(stored_refresh_token)) => stored_refresh_token,
+                Ok(None) => return Err(Status::unauthenticated("Refresh token is not valid")),
+                Err(_) => return Err(Status::internal("Couldn't get refresh token from DB")),
+            };
+
+        // a refresh token can only be used once, seeing it again means that it has been stolen so the whole
+        // session that the refresh token belongs to is revoked
+        if stored_refresh_token.used || stored_refresh_token.revoked {
+            // the row lock on the refresh token is no longer needed
+            drop(transaction);
+
+            if stored_refresh_token.used {
+                tracing::warn!(
+                    "refresh token reuse detected, revoking session {}",
+                    stored_refresh_token.session_id
+                );
+
+                self.end_session(
+                    &stored_refresh_token.user_id,
+                    &stored_refresh_token.session_id,
+                )
+                .await?;
+            }
+
+            return Err(Status::unauthenticated(
+                "Refresh token has already been used",
+            ));
+        }
+
+        if stored_refresh_token.expires_at < Utc::now() {
+            return Err(Status::unauthenticated("Refresh token has expired"));
+        }
+
+        if mark_refresh_token_used_db(&mut transaction, &refresh_token_hash)
+            .await
+            .is_err()
+        {
+            return Err(Status::internal("Could not update refresh token in DB"));
+        }
+
+        let user_id = stored_refresh_token.user_id;
+        let session_id = stored_refresh_token.session_id;
+        // the email might have been verified since the last auth token was handed out
+        let email_verified = self.check_email_verified(&user_id).await?;
+        // roles that have been granted or revoked since then are picked up here as well
+        let username = self.get_username(&user_id).await?;
+        let roles = self.get_roles(&user_id).await?;
+        let token = self
+            .generate_token_pair(
+                &mut transaction,
+                user_id,
+                username,
+                roles,
+                session_id,
+                email_verified,
+            )
+            .await?;
+
+        if update_token_db(&mut transaction, &session_id, &token.access_token)
+            .await
+            .is_err()
+        {
+            return Err(Status::internal("Could not update auth token in DB"));
+        }
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal("Could not commit refresh transaction"));
+        }
+
+        if store_token_redis(
+            self.redis_con.clone(),
+            &session_id,
+            &token.access_token,
+            self.auth_token_settings.access_token_lifetime(),
+        )
+        .await
+        .is_err()
+        {
+            return Err(Status::internal("Could not store auth token into redis"));
+        }
+
+        let detail = format!("session {}", session_id);
+        self.record_event(
+            AuthEventKind::TokenRefreshed,
+            Some(user_id),
+            &origin,
+            Some(&detail),
+        )
+        .await;
+
         Ok(Response::new(token))
     }
+
+    #[tracing::instrument(name = "User Logout" skip(self, request))]
+    async fn logout(
+        &self,
+        request: Request<LogoutRequest>,
+    ) -> Result<Response<LogoutResponse>, Status> {
+        let session = self.authenticate(&request).await?;
+
+        self.end_session(&session.user_id, &session.session_id)
+            .await?;
+
+        let detail = format!("logout of session {}", session.session_id);
+        self.record_event(
+            AuthEventKind::SessionRevoked,
+            Some(session.user_id),
+            &RequestOrigin::of(&request),
+            Some(&detail),
+        )
+        .await;
+
+        Ok(Response::new(LogoutResponse {}))
+    }
+
+    #[tracing::instrument(name = "Listing sessions of user" skip(self, request))]
+    async fn list_sessions(
+        &self,
+        request: Request<ListSessionsRequest>,
+    ) -> Result<Response<ListSessionsResponse>, Status> {
+        let current_session = self.authenticate(&request).await?;
+
+        let stored_sessions = match get_sessions_db(&self.db_pool, &current_session.user_id).await {
+            Ok(stored_sessions) => stored_sessions,
+            Err(_) => return Err(Status::internal("Couldn't get sessions from DB")),
+        };
+
+        let sessions = stored_sessions
+            .into_iter()
+            .map(|session| Session {
+                session_id: session.session_id.to_string(),
+                device: session.device,
+                created_at: Some(SystemTime::from(session.created_at).into()),
+                last_used_at: Some(SystemTime::from(session.last_used_at).into()),
+                current: session.session_id == current_session.session_id,
+            })
+            .collect();
+
+        Ok(Response::new(ListSessionsResponse { sessions }))
+    }
+
+    #[tracing::instrument(
+        name = "Revoking session of user"
+        skip(self, request)
+        fields(
+            session_id = %request.get_ref().session_id
+        )
+    )]
+    async fn revoke_session(
+        &self,
+        request: Request<RevokeSessionRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        let current_session = self.authenticate(&request).await?;
+
+        let session_id = match Uuid::parse_str(&request.get_ref().session_id) {
+            Ok(session_id) => session_id,
+            Err(_) => {
+                let error_details = ErrorDetails::with_bad_request_violation(
+                    "session_id",
+                    "session_id is not a valid session id",
+                );
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+        };
+
+        // users can only revoke their own sessions
+        if !self
+            .end_session(&current_session.user_id, &session_id)
+            .await?
+        {
+            return Err(Status::not_found("Session does not exist"));
+        }
+
+        let detail = format!("session {}", session_id);
+        self.record_event(
+            AuthEventKind::SessionRevoked,
+            Some(current_session.user_id),
+            &RequestOrigin::of(&request),
+            Some(&detail),
+        )
+        .await;
+
+        Ok(Response::new(RevokeSessionResponse {}))
+    }
+
+    #[tracing::instrument(name = "Changing password of user" skip(self, request))]
+    async fn change_password(
+        &self,
+        request: Request<ChangePasswordRequest>,
+    ) -> Result<Response<ChangePasswordResponse>, Status> {
+        let current_session = self.authenticate(&request).await?;
+        let origin = RequestOrigin::of(&request);
+        let change_password_request = request.into_inner();
+
+        self.verify_current_password(
+            &current_session.user_id,
+            change_password_request.old_password,
+            "old_password",
+        )
+        .await?;
+
+        let new_password_hash = self
+            .hash_new_password(
+                &current_session.user_id,
+                change_password_request.new_password,
+            )
+            .await?;
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        if update_password_hash_db(
+            &mut transaction,
+            &current_session.user_id,
+            new_password_hash,
+        )
+        .await
+        .is_err()
+        {
+            return Err(Status::internal("Could not update password in DB"));
+        }
+
+        // anyone who knew the old password might still be signed in, so only the session that changed the
+        // password is kept
+        let other_session_ids = match delete_other_tokens_db(
+            &mut transaction,
+            &current_session.user_id,
+            &current_session.session_id,
+        )
+        .await
+        {
+            Ok(other_session_ids) => other_session_ids,
+            Err(_) => return Err(Status::internal("Could not delete auth tokens from DB")),
+        };
+
+        for session_id in &other_session_ids {
+            if revoke_refresh_tokens_db(&mut transaction, session_id)
+                .await
+                .is_err()
+            {
+                return Err(Status::internal("Could not revoke refresh tokens in DB"));
+            }
+        }
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal(
+                "Could not commit change password transaction",
+            ));
+        }
+
+        for session_id in &other_session_ids {
+            self.forget_session(session_id).await?;
+        }
+
+        self.record_event(
+            AuthEventKind::PasswordChanged,
+            Some(current_session.user_id),
+            &origin,
+            None,
+        )
+        .await;
+
+        Ok(Response::new(ChangePasswordResponse {}))
+    }
+
+    // the response is the same whether or not the email belongs to a user, so it can't be used to find out which
+    // emails are registered
+    #[tracing::instrument(name = "Requesting password reset" skip(self, request))]
+    async fn request_password_reset(
+        &self,
+        request: Request<RequestPasswordResetRequest>,
+    ) -> Result<Response<RequestPasswordResetResponse>, Status> {
+        let email = match Email::parse(request.into_inner().email) {
+            Ok(email) => email,
+            Err(e) => {
+                let error_details =
+                    ErrorDetails::with_bad_request_violation("email", e.to_string());
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+        };
+
+        // the code is created and mailed in the background, so the response takes just as long whether or not the
+        // email belongs to a user
+        tokio::spawn(send_password_reset_code(
+            self.db_pool.clone(),
+            self.mailer.clone(),
+            email,
+            self.password_reset_settings.clone(),
+        ));
+
+        Ok(Response::new(RequestPasswordResetResponse {}))
+    }
+
+    #[tracing::instrument(name = "Resetting password" skip(self, request))]
+    async fn reset_password(
+        &self,
+        request: Request<ResetPasswordRequest>,
+    ) -> Result<Response<ResetPasswordResponse>, Status> {
+        let origin = RequestOrigin::of(&request);
+        let reset_password_request = request.into_inner();
+
+        if reset_password_request.code.trim().is_empty() {
+            let error_details =
+                ErrorDetails::with_bad_request_violation("code", "code field is empty");
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        let user_id = match use_reset_code_db(
+            &mut transaction,
+            &hash_one_time_code(&reset_password_request.code),
+        )
+        .await
+        {
+            Ok(Some(user_id)) => user_id,
+            Ok(None) => {
+                let error_details = ErrorDetails::with_bad_request_violation(
+                    "code",
+                    "code is not valid or has expired",
+                );
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+            Err(_) => return Err(Status::internal("Could not use password reset code")),
+        };
+
+        // the code stays usable when the new password is refused, since the transaction isn't committed
+        let new_password_hash = self
+            .hash_new_password(&user_id, reset_password_request.new_password)
+            .await?;
+
+        if update_password_hash_db(&mut transaction, &user_id, new_password_hash)
+            .await
+            .is_err()
+        {
+            return Err(Status::internal("Could not update password in DB"));
+        }
+
+        // whoever knew the old password shouldn't stay signed in
+        let session_ids = match delete_all_tokens_db(&mut transaction, &user_id).await {
+            Ok(session_ids) => session_ids,
+            Err(_) => return Err(Status::internal("Could not delete auth tokens from DB")),
+        };
+
+        for session_id in &session_ids {
+            if revoke_refresh_tokens_db(&mut transaction, session_id)
+                .await
+                .is_err()
+            {
+                return Err(Status::internal("Could not revoke refresh tokens in DB"));
+            }
+        }
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal(
+                "Could not commit reset password transaction",
+            ));
+        }
+
+        for session_id in &session_ids {
+            self.forget_session(session_id).await?;
+        }
+
+        self.record_event(
+            AuthEventKind::PasswordChanged,
+            Some(user_id),
+            &origin,
+            Some("reset with a mailed code"),
+        )
+        .await;
+
+        Ok(Response::new(ResetPasswordResponse {}))
+    }
+
+    #[tracing::instrument(name = "Verifying email" skip(self, request))]
+    async fn verify_email(
+        &self,
+        request: Request<VerifyEmailRequest>,
+    ) -> Result<Response<VerifyEmailResponse>, Status> {
+        let verify_email_request = request.into_inner();
+
+        if verify_email_request.token.trim().is_empty() {
+            let error_details =
+                ErrorDetails::with_bad_request_violation("token", "token field is empty");
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        let verified = match verify_email_db(
+            &mut transaction,
+            &hash_one_time_code(&verify_email_request.token),
+        )
+        .await
+        {
+            Ok(verified) => verified,
+            Err(_) => return Err(Status::internal("Could not verify email in DB")),
+        };
+
+        if !verified {
+            let error_details = ErrorDetails::with_bad_request_violation(
+                "token",
+                "token is not valid or has expired",
+            );
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal(
+                "Could not commit verify email transaction",
+            ));
+        }
+
+        Ok(Response::new(VerifyEmailResponse {}))
+    }
+
+    // like requesting a password reset, the response doesn't reveal whether the email belongs to a user
+    #[tracing::instrument(name = "Resending verification email" skip(self, request))]
+    async fn resend_verification_email(
+        &self,
+        request: Request<ResendVerificationEmailRequest>,
+    ) -> Result<Response<ResendVerificationEmailResponse>, Status> {
+        let email = match Email::parse(request.into_inner().email) {
+            Ok(email) => email,
+            Err(e) => {
+                let error_details =
+                    ErrorDetails::with_bad_request_violation("email", e.to_string());
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+        };
+
+        let user_id = match get_verification_status_by_email_db(&self.db_pool, email.as_ref()).await
+        {
+            Ok(Some(status)) if !status.verified => status.user_id,
+            Ok(_) => {
+                tracing::info!("verification email requested for an email that doesn't need it");
+                return Ok(Response::new(ResendVerificationEmailResponse {}));
+            }
+            Err(_) => return Err(Status::internal("Couldn't get user from DB")),
+        };
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        let verification_mail = self
+            .create_verification_mail(&mut transaction, &user_id, email.as_ref())
+            .await?;
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal(
+                "Could not commit verification email transaction",
+            ));
+        }
+
+        self.send_mail(verification_mail).await;
+
+        Ok(Response::new(ResendVerificationEmailResponse {}))
+    }
+
+    #[tracing::instrument(name = "Enrolling totp of user" skip(self, request))]
+    async fn enroll_totp(
+        &self,
+        request: Request<EnrollTotpRequest>,
+    ) -> Result<Response<EnrollTotpResponse>, Status> {
+        let current_session = self.authenticate(&request).await?;
+
+        let username = self.get_username(&current_session.user_id).await?;
+
+        let totp_secret = generate_totp_secret();
+
+        let encrypted_secret = match encrypt_totp_secret(
+            &self.secrets.totp_encryption_key,
+            &current_session.user_id,
+            &totp_secret,
+        ) {
+            Ok(encrypted_secret) => encrypted_secret,
+            Err(_) => return Err(Status::internal("Could not encrypt totp secret")),
+        };
+
+        match store_pending_totp_db(&self.db_pool, &current_session.user_id, &encrypted_secret)
+            .await
+        {
+            Ok(true) => (),
+            Ok(false) => {
+                return Err(Status::failed_precondition(
+                    "Two-factor authentication is already enabled",
+                ))
+            }
+            Err(_) => return Err(Status::internal("Could not store totp secret into DB")),
+        }
+
+        let secret = encode_totp_secret(&totp_secret);
+        let otpauth_uri = totp_uri(&self.totp_settings.issuer, &username, &secret);
+
+        Ok(Response::new(EnrollTotpResponse {
+            secret,
+            otpauth_uri,
+        }))
+    }
+
+    #[tracing::instrument(name = "Confirming totp of user" skip(self, request))]
+    async fn confirm_totp(
+        &self,
+        request: Request<ConfirmTotpRequest>,
+    ) -> Result<Response<ConfirmTotpResponse>, Status> {
+        let current_session = self.authenticate(&request).await?;
+        let confirm_totp_request = request.into_inner();
+
+        if confirm_totp_request.code.trim().is_empty() {
+            let error_details =
+                ErrorDetails::with_bad_request_violation("code", "code field is empty");
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let stored_totp = match get_totp_db(&self.db_pool, &current_session.user_id).await {
+            Ok(Some(stored_totp)) if stored_totp.confirmed => {
+                return Err(Status::failed_precondition(
+                    "Two-factor authentication is already enabled",
+                ))
+            }
+            Ok(Some(stored_totp)) => stored_totp,
+            Ok(None) => {
+                return Err(Status::failed_precondition(
+                    "Two-factor authentication has not been enrolled",
+                ))
+            }
+            Err(_) => return Err(Status::internal("Couldn't get totp secret from DB")),
+        };
+
+        let totp_secret = match decrypt_totp_secret(
+            &self.secrets.totp_encryption_key,
+            &current_session.user_id,
+            &stored_totp.encrypted_secret,
+        ) {
+            Ok(totp_secret) => totp_secret,
+            Err(_) => return Err(Status::internal("Could not decrypt totp secret")),
+        };
+
+        // confirming shows that the authenticator app has been set up correctly, so the user can't lock themself out
+        let time_step = match verify_totp_code(
+            &totp_secret,
+            &confirm_totp_request.code,
+            Utc::now().timestamp(),
+        ) {
+            Some(time_step) => time_step,
+            None => {
+                let error_details =
+                    ErrorDetails::with_bad_request_violation("code", "code is not valid");
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+        };
+
+        let backup_codes: Vec<OneTimeCode> = (0..self.totp_settings.backup_codes)
+            .map(|_| generate_one_time_code())
+            .collect();
+        let backup_code_hashes: Vec<String> = backup_codes
+            .iter()
+            .map(|backup_code| backup_code.hash.clone())
+            .collect();
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        match confirm_totp_db(&mut transaction, &current_session.user_id, time_step).await {
+            Ok(true) => (),
+            // the enrollment has been confirmed or replaced in the meantime
+            Ok(false) => {
+                return Err(Status::failed_precondition(
+                    "Two-factor authentication has not been enrolled",
+                ))
+            }
+            Err(_) => return Err(Status::internal("Could not confirm totp secret in DB")),
+        }
+
+        if store_backup_codes_db(
+            &mut transaction,
+            &current_session.user_id,
+            &backup_code_hashes,
+        )
+        .await
+        .is_err()
+        {
+            return Err(Status::internal("Could not store backup codes into DB"));
+        }
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal(
+                "Could not commit confirm totp transaction",
+            ));
+        }
+
+        Ok(Response::new(ConfirmTotpResponse {
+            backup_codes: backup_codes
+                .into_iter()
+                .map(|backup_code| backup_code.code)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(name = "Getting signing keys" skip(self, _request))]
+    async fn get_signing_keys(
+        &self,
+        _request: Request<GetSigningKeysRequest>,
+    ) -> Result<Response<GetSigningKeysResponse>, Status> {
+        let keys: Vec<SigningKey> = self
+            .jwt_keyring
+            .current()
+            .public_keys()
+            .into_iter()
+            .map(|public_jwk| SigningKey {
+                kid: public_jwk.kid,
+                kty: public_jwk.kty.to_string(),
+                alg: public_jwk.alg.to_string(),
+                n: public_jwk.n,
+                e: public_jwk.e,
+            })
+            .collect();
+
+        // every key of the keyring has the same algorithm, so there are only none when they are HMAC secrets
+        if keys.is_empty() {
+            return Err(Status::failed_precondition(
+                "Auth tokens are signed with HMAC, there are no public keys",
+            ));
+        }
+
+        Ok(Response::new(GetSigningKeysResponse { keys }))
+    }
+
+    #[tracing::instrument(name = "Introspecting auth token" skip(self, request))]
+    async fn introspect(
+        &self,
+        request: Request<IntrospectRequest>,
+    ) -> Result<Response<IntrospectResponse>, Status> {
+        let auth_token = request.into_inner().token;
+
+        if auth_token.is_empty() {
+            let error_details =
+                ErrorDetails::with_bad_request_violation("token", "token field is empty");
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let inactive = Response::new(IntrospectResponse::default());
+
+        // a token that can't be verified is inactive rather than an error, that's the answer the caller asked for
+        let claims = match verify_auth_token(&self.jwt_keyring.current(), &auth_token) {
+            Ok(claims) => claims,
+            Err(_) => return Ok(inactive),
+        };
+
+        // revocations are checked every time, so the cached session can't keep a revoked auth token active
+        match is_token_revoked_redis(
+            self.redis_con.clone(),
+            &claims.jti.to_string(),
+            &claims.session_id.to_string(),
+        )
+        .await
+        {
+            Ok(false) => (),
+            Ok(true) => return Ok(inactive),
+            Err(_) => return Err(Status::internal("Couldn't check if auth token is revoked")),
+        }
+
+        let username = match self
+            .session_username(
+                &claims.jti.to_string(),
+                &claims.user_id,
+                &claims.session_id,
+                claims.exp,
+            )
+            .await?
+        {
+            Some(username) => username,
+            None => return Ok(inactive),
+        };
+
+        Ok(Response::new(IntrospectResponse {
+            active: true,
+            user_id: claims.user_id,
+            username,
+            issued_at: claims.iat,
+            expires_at: claims.exp,
+            session_id: claims.session_id.to_string(),
+        }))
+    }
+
+    #[tracing::instrument(name = "Getting profile of user" skip(self, request))]
+    async fn get_profile(
+        &self,
+        request: Request<GetProfileRequest>,
+    ) -> Result<Response<Profile>, Status> {
+        let current_session = self.authenticate(&request).await?;
+
+        let profile = self.get_profile(&current_session.user_id).await?;
+
+        Ok(Response::new(profile))
+    }
+
+    #[tracing::instrument(name = "Updating profile of user" skip(self, request))]
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<Profile>, Status> {
+        let current_session = self.authenticate(&request).await?;
+        let mut update_profile_request = request.into_inner();
+        let current_password = std::mem::take(&mut update_profile_request.current_password);
+
+        let profile_update: ProfileUpdate = match update_profile_request.try_into() {
+            Ok(profile_update) => profile_update,
+            Err(e) => {
+                let error_details =
+                    ErrorDetails::with_bad_request_violation(e.field, e.message.to_string());
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+        };
+
+        let profile = self.get_profile(&current_session.user_id).await?;
+
+        let new_email = profile_update
+            .email
+            .as_ref()
+            .map(|email| email.as_ref().to_string())
+            .filter(|email| *email != profile.email);
+
+        // whoever has a session of the user shouldn't be able to take over the account by changing the email that
+        // password reset codes are sent to
+        if new_email.is_some() {
+            if current_password.is_empty() {
+                let error_details = ErrorDetails::with_bad_request_violation(
+                    "current_password",
+                    "current password is needed to change the email",
+                );
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+
+            self.verify_current_password(
+                &current_session.user_id,
+                current_password,
+                "current_password",
+            )
+            .await?;
+        }
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        if let Err(e) =
+            update_profile_db(&mut transaction, &current_session.user_id, &profile_update).await
+        {
+            let email_taken = e
+                .as_database_error()
+                .is_some_and(|e| e.is_unique_violation());
+
+            if email_taken {
+                return Err(Status::already_exists("Email is already in use"));
+            }
+
+            return Err(Status::internal("Could not update profile in DB"));
+        }
+
+        let verification_mail = match &new_email {
+            Some(new_email) => Some(
+                self.create_verification_mail(
+                    &mut transaction,
+                    &current_session.user_id,
+                    new_email,
+                )
+                .await?,
+            ),
+            None => None,
+        };
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal(
+                "Could not commit update profile transaction",
+            ));
+        }
+
+        if let Some(verification_mail) = verification_mail {
+            // the previous email is told about the change, in case it wasn't made by the user
+            self.send_mail(MailMessage {
+                to: profile.email.clone(),
+                subject: "Your Chat-gRPC email has been changed".to_string(),
+                body: format!(
+                    "The email of your Chat-gRPC account has been changed to {}.\r\n\r\n\
+                    If you didn't do this, reset your password and contact us.",
+                    verification_mail.to
+                ),
+            })
+            .await;
+
+            self.send_mail(verification_mail).await;
+        }
+
+        let profile = self.get_profile(&current_session.user_id).await?;
+
+        Ok(Response::new(profile))
+    }
+
+    #[tracing::instrument(name = "Deleting account of user" skip(self, request))]
+    async fn delete_account(
+        &self,
+        request: Request<DeleteAccountRequest>,
+    ) -> Result<Response<DeleteAccountResponse>, Status> {
+        let current_session = self.authenticate(&request).await?;
+        let origin = RequestOrigin::of(&request);
+        let delete_account_request = request.into_inner();
+
+        if delete_account_request.password.is_empty() {
+            let error_details =
+                ErrorDetails::with_bad_request_violation("password", "password field is empty");
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        self.verify_current_password(
+            &current_session.user_id,
+            delete_account_request.password,
+            "password",
+        )
+        .await?;
+
+        // the events of the account lose their user once it's deleted, so this one names the username to still tell
+        // whose account it was
+        let username = self.get_username(&current_session.user_id).await?;
+        let detail = format!("username {}", username);
+        self.record_event(
+            AuthEventKind::AccountDeleted,
+            Some(current_session.user_id),
+            &origin,
+            Some(&detail),
+        )
+        .await;
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        // the sessions are removed together with the account, their ids are needed to sign them out everywhere
+        let session_ids =
+            match delete_all_tokens_db(&mut transaction, &current_session.user_id).await {
+                Ok(session_ids) => session_ids,
+                Err(_) => return Err(Status::internal("Could not delete auth tokens from DB")),
+            };
+
+        match delete_account_db(&mut transaction, &current_session.user_id).await {
+            Ok(true) => (),
+            Ok(false) => return Err(Status::not_found("User does not exist")),
+            Err(_) => return Err(Status::internal("Could not delete account from DB")),
+        }
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal(
+                "Could not commit delete account transaction",
+            ));
+        }
+
+        for session_id in &session_ids {
+            self.forget_session(session_id).await?;
+        }
+
+        Ok(Response::new(DeleteAccountResponse {}))
+    }
+
+    #[tracing::instrument(name = "Exporting data of user" skip(self, request))]
+    async fn export_my_data(
+        &self,
+        request: Request<ExportMyDataRequest>,
+    ) -> Result<Response<ExportMyDataResponse>, Status> {
+        let current_session = self.authenticate(&request).await?;
+        let user_id = current_session.user_id;
+
+        let profile = match get_profile_db(&self.db_pool, &user_id).await {
+            Ok(Some(profile)) => profile,
+            Ok(None) => return Err(Status::not_found("User does not exist")),
+            Err(_) => return Err(Status::internal("Couldn't get profile from DB")),
+        };
+
+        let sessions = match get_sessions_db(&self.db_pool, &user_id).await {
+            Ok(sessions) => sessions,
+            Err(_) => return Err(Status::internal("Couldn't get sessions from DB")),
+        };
+
+        let two_factor_enabled = match get_totp_db(&self.db_pool, &user_id).await {
+            Ok(stored_totp) => stored_totp.is_some_and(|stored_totp| stored_totp.confirmed),
+            Err(_) => return Err(Status::internal("Couldn't get totp secret from DB")),
+        };
+
+        let unused_backup_codes = match count_unused_backup_codes_db(&self.db_pool, &user_id).await
+        {
+            Ok(unused_backup_codes) => unused_backup_codes,
+            Err(_) => return Err(Status::internal("Couldn't count backup codes in DB")),
+        };
+
+        let roles = self.get_roles(&user_id).await?;
+
+        // every event of the user, not just a page of them
+        let auth_events_filter = AuthEventFilter {
+            user_id: Some(user_id),
+            ..Default::default()
+        };
+        let auth_events =
+            match get_auth_events_db(&self.db_pool, &auth_events_filter, i64::MAX).await {
+                Ok(auth_events) => auth_events,
+                Err(_) => return Err(Status::internal("Couldn't get auth events from DB")),
+            };
+
+        let data_export = DataExport {
+            exported_at: Utc::now(),
+            account: ExportedAccount::new(user_id, profile),
+            roles: roles.iter().map(Role::to_string).collect(),
+            sessions: sessions.into_iter().map(ExportedSession::from).collect(),
+            two_factor: ExportedTwoFactor {
+                enabled: two_factor_enabled,
+                unused_backup_codes,
+            },
+            auth_events: auth_events
+                .into_iter()
+                .map(ExportedAuthEvent::from)
+                .collect(),
+        };
+
+        let document = match serde_json::to_string_pretty(&data_export) {
+            Ok(document) => document,
+            Err(_) => return Err(Status::internal("Could not serialize data export")),
+        };
+
+        Ok(Response::new(ExportMyDataResponse { document }))
+    }
+
+    #[tracing::instrument(
+        name = "Granting role to user"
+        skip(self, request)
+        fields(
+            username = %request.get_ref().username,
+            role = %request.get_ref().role
+        )
+    )]
+    async fn grant_role(
+        &self,
+        request: Request<GrantRoleRequest>,
+    ) -> Result<Response<GrantRoleResponse>, Status> {
+        let admin = self.authorize(&request, Role::Admin).await?;
+        let origin = RequestOrigin::of(&request);
+
+        let request = request.into_inner();
+        let (user_id, role) = self.role_change(&request.username, &request.role).await?;
+
+        match grant_role_db(&self.db_pool, &user_id, role).await {
+            Ok(true) => {
+                let detail = format!("{} granted by user {}", role, admin.user_id);
+                self.record_event(
+                    AuthEventKind::RoleGranted,
+                    Some(user_id),
+                    &origin,
+                    Some(&detail),
+                )
+                .await;
+            }
+            Ok(false) => (),
+            Err(_) => return Err(Status::internal("Could not grant role to user")),
+        }
+
+        let roles = self.get_roles(&user_id).await?;
+
+        Ok(Response::new(GrantRoleResponse {
+            roles: roles.iter().map(Role::to_string).collect(),
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "Revoking role of user"
+        skip(self, request)
+        fields(
+            username = %request.get_ref().username,
+            role = %request.get_ref().role
+        )
+    )]
+    async fn revoke_role(
+        &self,
+        request: Request<RevokeRoleRequest>,
+    ) -> Result<Response<RevokeRoleResponse>, Status> {
+        let admin = self.authorize(&request, Role::Admin).await?;
+        let origin = RequestOrigin::of(&request);
+
+        let request = request.into_inner();
+        let (user_id, role) = self.role_change(&request.username, &request.role).await?;
+
+        match revoke_role_db(&self.db_pool, &user_id, role).await {
+            Ok(true) => {
+                let detail = format!("{} revoked by user {}", role, admin.user_id);
+                self.record_event(
+                    AuthEventKind::RoleRevoked,
+                    Some(user_id),
+                    &origin,
+                    Some(&detail),
+                )
+                .await;
+            }
+            Ok(false) => (),
+            Err(_) => return Err(Status::internal("Could not revoke role of user")),
+        }
+
+        let roles = self.get_roles(&user_id).await?;
+
+        Ok(Response::new(RevokeRoleResponse {
+            roles: roles.iter().map(Role::to_string).collect(),
+        }))
+    }
+
+    #[tracing::instrument(name = "Listing auth events" skip(self, request))]
+    async fn list_auth_events(
+        &self,
+        request: Request<ListAuthEventsRequest>,
+    ) -> Result<Response<ListAuthEventsResponse>, Status> {
+        self.authorize(&request, Role::Admin).await?;
+
+        let request = request.into_inner();
+        let mut error_details = ErrorDetails::new();
+
+        let kind = match request.event_type.as_str() {
+            "" => None,
+            event_type => match event_type.parse::<AuthEventKind>() {
+                Ok(kind) => Some(kind),
+                Err(e) => {
+                    error_details.add_bad_request_violation("event_type", e);
+                    None
+                }
+            },
+        };
+
+        let mut timestamp =
+            |field: &'static str, timestamp: Option<prost_types::Timestamp>| match timestamp
+                .map(SystemTime::try_from)
+            {
+                None => None,
+                Some(Ok(timestamp)) => Some(DateTime::<Utc>::from(timestamp)),
+                Some(Err(_)) => {
+                    error_details.add_bad_request_violation(field, "timestamp is out of range");
+                    None
+                }
+            };
+        let since = timestamp("since", request.since);
+        let until = timestamp("until", request.until);
+
+        let before_event_id = match request.page_token.as_str() {
+            "" => None,
+            page_token => match page_token.parse::<i64>() {
+                Ok(event_id) => Some(event_id),
+                Err(_) => {
+                    error_details
+                        .add_bad_request_violation("page_token", "page_token is not valid");
+                    None
+                }
+            },
+        };
+
+        if error_details.has_bad_request_violations() {
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let user_id = match request.username.as_str() {
+            "" => None,
+            username => {
+                match get_user_id_by_username_db(&self.db_pool, &normalize_username(username)).await
+                {
+                    Ok(Some(user_id)) => Some(user_id),
+                    Ok(None) => return Err(Status::not_found("User does not exist")),
+                    Err(_) => return Err(Status::internal("Couldn't get user from DB")),
+                }
+            }
+        };
+
+        let filter = AuthEventFilter {
+            user_id,
+            kind,
+            peer_address: Some(request.peer_address).filter(|address| !address.is_empty()),
+            since,
+            until,
+            before_event_id,
+        };
+
+        let page_size = match request.page_size {
+            0 => DEFAULT_AUTH_EVENTS_PAGE_SIZE,
+            page_size => page_size.min(MAX_AUTH_EVENTS_PAGE_SIZE),
+        } as usize;
+
+        // one more than fits on the page is asked for, so it's known whether there is another page
+        let mut events =
+            match get_auth_events_db(&self.db_pool, &filter, page_size as i64 + 1).await {
+                Ok(events) => events,
+                Err(_) => return Err(Status::internal("Couldn't get auth events from DB")),
+            };
+
+        let next_page_token = if events.len() > page_size {
+            events.truncate(page_size);
+            events
+                .last()
+                .map(|event| event.event_id.to_string())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        Ok(Response::new(ListAuthEventsResponse {
+            events: events.into_iter().map(AuthEvent::from).collect(),
+            next_page_token,
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "Creating service account"
+        skip(self, request)
+        fields(
+            name = %request.get_ref().name
+        )
+    )]
+    async fn create_service_account(
+        &self,
+        request: Request<CreateServiceAccountRequest>,
+    ) -> Result<Response<ServiceAccount>, Status> {
+        let admin = self.authorize(&request, Role::Admin).await?;
+
+        let name = match ServiceAccountName::parse(request.into_inner().name) {
+            Ok(name) => name,
+            Err(e) => {
+                let e = RegisterDataError::from(e);
+                let error_details =
+                    ErrorDetails::with_bad_request_violation(e.field, e.message.to_string());
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+        };
+
+        let service_account_id =
+            match store_service_account_db(&self.db_pool, name.as_ref(), &admin.user_id).await {
+                Ok(service_account_id) => service_account_id,
+                Err(e) => {
+                    let name_taken = e
+                        .as_database_error()
+                        .is_some_and(|e| e.is_unique_violation());
+
+                    if name_taken {
+                        return Err(Status::already_exists("Service account already exists"));
+                    }
+
+                    return Err(Status::internal("Could not store service account in DB"));
+                }
+            };
+
+        Ok(Response::new(ServiceAccount {
+            service_account_id,
+            name: name.as_ref().to_string(),
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "Creating API key"
+        skip(self, request)
+        fields(
+            service_account = %request.get_ref().service_account
+        )
+    )]
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status> {
+        let admin = self.authorize(&request, Role::Admin).await?;
+        let origin = RequestOrigin::of(&request);
+
+        let request = request.into_inner();
+        let mut error_details = ErrorDetails::new();
+
+        if request.service_account.is_empty() {
+            error_details
+                .add_bad_request_violation("service_account", "service_account field is empty");
+        }
+
+        if request.scopes.is_empty() {
+            error_details.add_bad_request_violation("scopes", "at least one scope is required");
+        }
+
+        let mut scopes: Vec<Scope> = Vec::new();
+        for scope in request.scopes.iter() {
+            match scope.parse::<Scope>() {
+                Ok(scope) if scopes.contains(&scope) => (),
+                Ok(scope) => scopes.push(scope),
+                Err(e) => {
+                    error_details.add_bad_request_violation("scopes", e);
+                }
+            }
+        }
+
+        if error_details.has_bad_request_violations() {
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let service_account_id = self.service_account_id(&request.service_account).await?;
+
+        let generated_key = generate_api_key();
+        let api_key = ActiveApiKey {
+            key_id: Uuid::new_v4(),
+            key_hash: generated_key.hash,
+            service_account_id,
+            name: request.service_account,
+            scopes: scopes.iter().map(Scope::to_string).collect(),
+        };
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        let created_at = match store_api_key_db(
+            &mut transaction,
+            &api_key.key_id,
+            &api_key.service_account_id,
+            &api_key.key_hash,
+            &api_key.scopes,
+        )
+        .await
+        {
+            Ok(created_at) => created_at,
+            Err(_) => return Err(Status::internal("Could not store API key in DB")),
+        };
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal("Failed to commit postgresql transaction"));
+        }
+
+        // services only accept the API key once it's in redis
+        if store_api_key_redis(self.redis_con.clone(), &api_key)
+            .await
+            .is_err()
+        {
+            return Err(Status::internal("Could not store API key in redis"));
+        }
+
+        let detail = format!("key {} of service account {}", api_key.key_id, api_key.name);
+        self.record_event(
+            AuthEventKind::ApiKeyCreated,
+            Some(admin.user_id),
+            &origin,
+            Some(&detail),
+        )
+        .await;
+
+        Ok(Response::new(CreateApiKeyResponse {
+            key_id: api_key.key_id.to_string(),
+            api_key: generated_key.key,
+            scopes: api_key.scopes,
+            created_at: Some(SystemTime::from(created_at).into()),
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "Listing API keys"
+        skip(self, request)
+        fields(
+            service_account = %request.get_ref().service_account
+        )
+    )]
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ListApiKeysResponse>, Status> {
+        self.authorize(&request, Role::Admin).await?;
+
+        let service_account_id = self
+            .service_account_id(&request.get_ref().service_account)
+            .await?;
+
+        let api_keys = match get_api_keys_db(&self.db_pool, &service_account_id).await {
+            Ok(api_keys) => api_keys,
+            Err(_) => return Err(Status::internal("Couldn't get API keys from DB")),
+        };
+
+        let api_keys = api_keys
+            .into_iter()
+            .map(|api_key| ApiKey {
+                key_id: api_key.key_id.to_string(),
+                scopes: api_key.scopes,
+                created_at: Some(SystemTime::from(api_key.created_at).into()),
+                revoked_at: api_key
+                    .revoked_at
+                    .map(|revoked_at| SystemTime::from(revoked_at).into()),
+            })
+            .collect();
+
+        Ok(Response::new(ListApiKeysResponse { api_keys }))
+    }
+
+    #[tracing::instrument(
+        name = "Revoking API key"
+        skip(self, request)
+        fields(
+            key_id = %request.get_ref().key_id
+        )
+    )]
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<RevokeApiKeyResponse>, Status> {
+        let admin = self.authorize(&request, Role::Admin).await?;
+
+        let key_id = match Uuid::parse_str(&request.get_ref().key_id) {
+            Ok(key_id) => key_id,
+            Err(_) => {
+                let error_details = ErrorDetails::with_bad_request_violation(
+                    "key_id",
+                    "key_id is not a valid key id",
+                );
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+        };
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        let revoked_api_key = match revoke_api_key_db(&mut transaction, &key_id).await {
+            Ok(Some(revoked_api_key)) => revoked_api_key,
+            Ok(None) => return Err(Status::not_found("API key does not exist")),
+            Err(_) => return Err(Status::internal("Could not revoke API key in DB")),
+        };
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal("Failed to commit postgresql transaction"));
+        }
+
+        // redis is only updated once the revocation is committed, so it never shows an API key as revoked that is
+        // still active in postgres. when updating redis fails the API key stays revoked in postgres, and revoking
+        // it again takes it out of redis
+        if delete_api_key_redis(self.redis_con.clone(), &revoked_api_key.key_hash)
+            .await
+            .is_err()
+        {
+            return Err(Status::unavailable(
+                "Could not delete API key from redis, revoke it again",
+            ));
+        }
+
+        // requests with an API key use the key id as their session, which closes the streams that are still open
+        let expiration_timestamp =
+            (Utc::now() + self.auth_token_settings.access_token_lifetime()).timestamp();
+        if revoke_session_redis(self.redis_con.clone(), &key_id, expiration_timestamp)
+            .await
+            .is_err()
+        {
+            return Err(Status::unavailable(
+                "Could not revoke API key in redis, revoke it again",
+            ));
+        }
+
+        if revoked_api_key.newly_revoked {
+            let detail = format!("key {}", key_id);
+            self.record_event(
+                AuthEventKind::ApiKeyRevoked,
+                Some(admin.user_id),
+                &RequestOrigin::of(&request),
+                Some(&detail),
+            )
+            .await;
+        }
+
+        Ok(Response::new(RevokeApiKeyResponse {}))
+    }
+}
+
+// the label of a session is taken from the request and otherwise from the user agent of the caller
+fn session_device<T>(request: &Request<T>, device: &str) -> String {
+    let device = match device.trim() {
+        "" => request
+            .metadata()
+            .get("user-agent")
+            .and_then(|user_agent| user_agent.to_str().ok())
+            .unwrap_or_default(),
+        device => device,
+    };
+
+    // the device column only fits 255 characters
+    device.chars().take(255).collect()
+}
+
+// the user and session that an authenticated request was made from
+pub struct AuthenticatedSession {
+    pub user_id: i32,
+    pub session_id: Uuid,
+    // the names of the roles that the auth token carries
+    pub roles: Vec<String>,
+}
+
+impl AuthenticationService {
+    // the username of the session that the auth token belongs to, the lookup is cached for a short while so callers
+    // that introspect a lot don't hit postgres every time
+    async fn session_username(
+        &self,
+        jti: &str,
+        user_id: &i32,
+        session_id: &Uuid,
+        expires_at: i64,
+    ) -> Result<Option<String>, Status> {
+        // the cache is only an optimization, so postgres is asked when redis can't answer
+        match get_introspection_redis(self.redis_con.clone(), jti).await {
+            Ok(Some(username)) => return Ok(username),
+            Ok(None) => (),
+            Err(e) => tracing::error!("Failed to get cached introspection: {:?}", e),
+        }
+
+        let username = match get_session_username_db(&self.db_pool, session_id, user_id).await {
+            Ok(username) => username,
+            Err(_) => return Err(Status::internal("Couldn't get session from DB")),
+        };
+
+        // there's no need to cache it for longer than the auth token is valid
+        let lifetime = self
+            .introspection_settings
+            .cache_lifetime()
+            .min(chrono::Duration::seconds(
+                expires_at - Utc::now().timestamp(),
+            ));
+
+        if lifetime.num_seconds() > 0 {
+            if let Err(e) = store_introspection_redis(
+                self.redis_con.clone(),
+                jti,
+                username.as_deref(),
+                lifetime,
+            )
+            .await
+            {
+                tracing::error!("Failed to cache introspection: {:?}", e);
+            }
+        }
+
+        Ok(username)
+    }
+
+    // validates the bearer auth token of the request and makes sure it hasn't been revoked, returns the session
+    // that the auth token belongs to
+    async fn authenticate<T>(&self, request: &Request<T>) -> Result<AuthenticatedSession, Status> {
+        let claims = get_bearer_token(request.metadata())
+            .and_then(|auth_token| verify_auth_token(&self.jwt_keyring.current(), auth_token))
+            .map_err(Status::from)?;
+
+        match is_token_revoked_redis(
+            self.redis_con.clone(),
+            &claims.jti.to_string(),
+            &claims.session_id.to_string(),
+        )
+        .await
+        {
+            Ok(false) => (),
+            Ok(true) => return Err(TokenError::Revoked.into()),
+            Err(_) => return Err(Status::internal("Couldn't check if auth token is revoked")),
+        }
+
+        Ok(AuthenticatedSession {
+            user_id: claims.user_id,
+            session_id: claims.session_id,
+            roles: claims.roles,
+        })
+    }
+
+    // like authenticate, but the auth token also has to carry the role
+    async fn authorize<T>(
+        &self,
+        request: &Request<T>,
+        role: Role,
+    ) -> Result<AuthenticatedSession, Status> {
+        let session = self.authenticate(request).await?;
+
+        if !session.roles.iter().any(|name| name == role.as_str()) {
+            return Err(Status::permission_denied(format!(
+                "The {} role is required",
+                role
+            )));
+        }
+
+        Ok(session)
+    }
+
+    // ends the session of the user by removing its auth token and revoking its refresh tokens, the access tokens
+    // that have been handed out stay valid until they expire so the whole session is put on the revocation list.
+    // returns false when the user doesn't have a session with the given id
+    async fn end_session(&self, user_id: &i32, session_id: &Uuid) -> Result<bool, Status> {
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        let session_existed = match delete_token_db(&mut transaction, user_id, session_id).await {
+            Ok(session_existed) => session_existed,
+            Err(_) => return Err(Status::internal("Could not delete auth token from DB")),
+        };
+
+        if !session_existed {
+            return Ok(false);
+        }
+
+        if revoke_refresh_tokens_db(&mut transaction, session_id)
+            .await
+            .is_err()
+        {
+            return Err(Status::internal("Could not revoke refresh tokens in DB"));
+        }
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal(
+                "Could not commit session revoke transaction",
+            ));
+        }
+
+        self.forget_session(session_id).await?;
+
+        Ok(true)
+    }
+
+    // checks the password of the user that sent the request, a wrong password is a bad request on the given field.
+    // wrong passwords count towards the login throttle of the username, so a stolen auth token can't be used to guess
+    // the password any faster than logging in
+    async fn verify_current_password(
+        &self,
+        user_id: &i32,
+        password: String,
+        field: &'static str,
+    ) -> Result<(), Status> {
+        let username = self.get_username(user_id).await?;
+        let throttle_subject = account_throttle_subject(&username, &self.login_throttle_settings);
+
+        match get_login_lockout_redis(
+            self.redis_con.clone(),
+            std::slice::from_ref(&throttle_subject),
+        )
+        .await
+        {
+            Ok(None) => (),
+            Ok(Some(lockout)) => {
+                let status = Status::with_error_details(
+                    Code::ResourceExhausted,
+                    "Too many failed login attempts, try again later",
+                    ErrorDetails::with_retry_info(Some(lockout)),
+                );
+
+                return Err(status);
+            }
+            Err(_) => return Err(Status::internal("Couldn't check login lockout")),
+        }
+
+        let stored_password_hash = match get_password_hash_db(&self.db_pool, user_id).await {
+            Ok(Some(stored_password_hash)) => stored_password_hash,
+            Ok(None) => return Err(Status::not_found("User does not exist")),
+            Err(_) => return Err(Status::internal("Couldn't get password hash from DB")),
+        };
+
+        let result_verifying_password = match spawn_blocking(move || {
+            verify_password_hash(stored_password_hash, password)
+        })
+        .await
+        {
+            Ok(result_verifying_password) => result_verifying_password,
+            Err(_) => return Err(Status::internal("Could not verify password")),
+        };
+
+        match result_verifying_password {
+            Ok(_) => {
+                if let Err(e) =
+                    reset_failed_logins_redis(self.redis_con.clone(), &throttle_subject).await
+                {
+                    tracing::error!("Failed to reset failed logins: {:?}", e);
+                }
+
+                Ok(())
+            }
+            Err(AuthError::InvalidCredentials(_)) => {
+                if let Err(e) = record_failed_login_redis(
+                    self.redis_con.clone(),
+                    std::slice::from_ref(&throttle_subject),
+                    &self.login_throttle_settings,
+                )
+                .await
+                {
+                    tracing::error!("Failed to record failed login: {:?}", e);
+                }
+
+                let error_details =
+                    ErrorDetails::with_bad_request_violation(field, "password is wrong");
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                Err(status)
+            }
+            Err(AuthError::UnexpectedError(_)) => {
+                Err(Status::internal("Could not verify password"))
+            }
+        }
+    }
+
+    async fn get_profile(&self, user_id: &i32) -> Result<Profile, Status> {
+        match get_profile_db(&self.db_pool, user_id).await {
+            Ok(Some(profile)) => Ok(profile.into()),
+            Ok(None) => Err(Status::not_found("User does not exist")),
+            Err(_) => Err(Status::internal("Couldn't get profile from DB")),
+        }
+    }
+
+    // the username is put into the auth tokens, so services know who the user is without asking the auth service
+    async fn get_username(&self, user_id: &i32) -> Result<String, Status> {
+        match get_username_db(&self.db_pool, user_id).await {
+            Ok(Some(username)) => Ok(username),
+            Ok(None) => Err(Status::not_found("User does not exist")),
+            Err(_) => Err(Status::internal("Couldn't get user from DB")),
+        }
+    }
+
+    // the user and role that a GrantRole or RevokeRole request is about
+    async fn role_change(&self, username: &str, role: &str) -> Result<(i32, Role), Status> {
+        let mut error_details = ErrorDetails::new();
+
+        if username.is_empty() {
+            error_details.add_bad_request_violation("username", "username field is empty");
+        }
+
+        let role = match role.parse::<Role>() {
+            Ok(role) => Some(role),
+            Err(e) => {
+                error_details.add_bad_request_violation("role", e);
+                None
+            }
+        };
+
+        if error_details.has_bad_request_violations() {
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        let user_id =
+            match get_user_id_by_username_db(&self.db_pool, &normalize_username(username)).await {
+                Ok(Some(user_id)) => user_id,
+                Ok(None) => return Err(Status::not_found("User does not exist")),
+                Err(_) => return Err(Status::internal("Couldn't get user from DB")),
+            };
+
+        Ok((user_id, role.expect("role is checked above")))
+    }
+
+    async fn service_account_id(&self, name: &str) -> Result<i32, Status> {
+        match get_service_account_id_db(&self.db_pool, name).await {
+            Ok(Some(service_account_id)) => Ok(service_account_id),
+            Ok(None) => Err(Status::not_found("Service account does not exist")),
+            Err(_) => Err(Status::internal("Couldn't get service account from DB")),
+        }
+    }
+
+    // the auth events are only a record, so a request doesn't fail when its event can't be stored
+    async fn record_event(
+        &self,
+        kind: AuthEventKind,
+        user_id: Option<i32>,
+        origin: &RequestOrigin,
+        detail: Option<&str>,
+    ) {
+        if store_auth_event_db(&self.db_pool, kind, user_id, origin, detail)
+            .await
+            .is_err()
+        {
+            tracing::warn!("{} event could not be recorded", kind);
+        }
+    }
+
+    // the roles are put into the auth tokens as well, so services can check them on their own
+    async fn get_roles(&self, user_id: &i32) -> Result<Vec<Role>, Status> {
+        match get_roles_db(&self.db_pool, user_id).await {
+            Ok(roles) => Ok(roles),
+            Err(_) => Err(Status::internal("Couldn't get roles from DB")),
+        }
+    }
+
+    // returns whether the user has verified their email, users that haven't are refused when the configuration says
+    // so
+    async fn check_email_verified(&self, user_id: &i32) -> Result<bool, Status> {
+        let verification_status = match get_verification_status_db(&self.db_pool, user_id).await {
+            Ok(Some(verification_status)) => verification_status,
+            Ok(None) => return Err(Status::unauthenticated("User does not exist")),
+            Err(_) => {
+                return Err(Status::internal(
+                    "Couldn't get email verification status from DB",
+                ))
+            }
+        };
+
+        if !verification_status.verified
+            && self.email_verification_settings.unverified_login == UnverifiedLoginPolicy::Reject
+        {
+            return Err(Status::failed_precondition(
+                "Email address has not been verified",
+            ));
+        }
+
+        Ok(verification_status.verified)
+    }
+
+    // stores a new verification token for the email as part of the provided transaction, the returned mail should
+    // only be sent once the transaction has been committed
+    async fn create_verification_mail(
+        &self,
+        transaction: &mut Transaction<'_, Postgres>,
+        user_id: &i32,
+        email: &str,
+    ) -> Result<MailMessage, Status> {
+        let verification_token = generate_one_time_code();
+        let expires_at = Utc::now() + self.email_verification_settings.token_lifetime();
+
+        if store_verification_token_db(
+            transaction,
+            user_id,
+            email,
+            &verification_token.hash,
+            expires_at,
+        )
+        .await
+        .is_err()
+        {
+            return Err(Status::internal(
+                "Could not store email verification token into DB",
+            ));
+        }
+
+        Ok(MailMessage {
+            to: email.to_string(),
+            subject: "Verify your Chat-gRPC email".to_string(),
+            body: format!(
+                "Welcome to Chat-gRPC!\r\n\r\n\
+                Your email verification code is: {}\r\n\r\n\
+                The code expires in {} hours.",
+                verification_token.code, self.email_verification_settings.token_lifetime_hours
+            ),
+        })
+    }
+
+    // failing to send a mail is only logged, the user can always ask for the mail again
+    async fn send_mail(&self, message: MailMessage) {
+        if let Err(e) = self.mailer.send(message).await {
+            tracing::error!("Failed to send mail: {:?}", e);
+        }
+    }
+
+    // validates a new password of the user and hashes it
+    async fn hash_new_password(
+        &self,
+        user_id: &i32,
+        new_password: String,
+    ) -> Result<Secret<String>, Status> {
+        let new_password = match Password::parse(new_password) {
+            Ok(new_password) => new_password,
+            Err(e) => {
+                let error_details =
+                    ErrorDetails::with_bad_request_violation("new_password", e.to_string());
+
+                let status = Status::with_error_details(
+                    Code::InvalidArgument,
+                    "Request has invalid argumetns",
+                    error_details,
+                );
+
+                return Err(status);
+            }
+        };
+
+        let profile = self.get_profile(user_id).await?;
+
+        if let Some(reason) = self
+            .password_policy
+            .check(&new_password, &[&profile.username, &profile.email])
+        {
+            let error_details = ErrorDetails::with_bad_request_violation("new_password", reason);
+
+            let status = Status::with_error_details(
+                Code::InvalidArgument,
+                "Request has invalid argumetns",
+                error_details,
+            );
+
+            return Err(status);
+        }
+
+        match spawn_blocking(move || compute_password_hash(new_password.as_ref())).await {
+            Ok(Ok(new_password_hash)) => Ok(new_password_hash),
+            _ => Err(Status::internal("Could not hash new password")),
+        }
+    }
+
+    // starts a new session for a user that has logged in, so logging in on another device doesn't affect the existing
+    // sessions
+    async fn start_session(
+        &self,
+        user_id: i32,
+        device: &str,
+        email_verified: bool,
+    ) -> Result<Token, Status> {
+        let username = self.get_username(&user_id).await?;
+        let roles = self.get_roles(&user_id).await?;
+
+        let mut transaction = match self.db_pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => {
+                return Err(Status::internal(
+                    "Could not create a transaction for postgresql",
+                ))
+            }
+        };
+
+        let session_id = Uuid::new_v4();
+        let token = self
+            .generate_token_pair(
+                &mut transaction,
+                user_id,
+                username,
+                roles,
+                session_id,
+                email_verified,
+            )
+            .await?;
+
+        if store_token_db(
+            &mut transaction,
+            &user_id,
+            &session_id,
+            device,
+            &token.access_token,
+        )
+        .await
+        .is_err()
+        {
+            return Err(Status::internal("Could not store auth token into DB"));
+        }
+
+        if transaction.commit().await.is_err() {
+            return Err(Status::internal("Could not commit user login transaction"));
+        }
+
+        if store_token_redis(
+            self.redis_con.clone(),
+            &session_id,
+            &token.access_token,
+            self.auth_token_settings.access_token_lifetime(),
+        )
+        .await
+        .is_err()
+        {
+            return Err(Status::internal("Could not store auth token into redis"));
+        }
+
+        Ok(token)
+    }
+
+    // hands out a challenge that can be exchanged for a session together with a code of the second factor, the
+    // returned status is what login responds with
+    async fn require_second_factor(&self, user_id: &i32, device: &str) -> Status {
+        let login_challenge = generate_login_challenge();
+        let lifetime = self.totp_settings.login_challenge_lifetime();
+
+        if store_login_challenge_redis(
+            self.redis_con.clone(),
+            &login_challenge.hash,
+            user_id,
+            device,
+            lifetime,
+        )
+        .await
+        .is_err()
+        {
+            return Status::internal("Could not store login challenge into redis");
+        }
+
+        let metadata = HashMap::from([
+            (
+                LOGIN_CHALLENGE_METADATA_KEY.to_string(),
+                login_challenge.challenge,
+            ),
+            ("expires_in".to_string(), lifetime.num_seconds().to_string()),
+        ]);
+
+        Status::with_error_details(
+            Code::Unauthenticated,
+            "Second factor required",
+            ErrorDetails::with_error_info(SECOND_FACTOR_REQUIRED, "auth", metadata),
+        )
+    }
+
+    // checks the code of a login challenge, which is either a code of the authenticator app or a backup code, and
+    // makes sure it can't be used again
+    async fn use_second_factor(&self, user_id: &i32, code: &str) -> Result<bool, Status> {
+        if !is_totp_code(code) {
+            return match use_backup_code_db(&self.db_pool, user_id, &hash_one_time_code(code)).await
+            {
+                Ok(used) => Ok(used),
+                Err(_) => Err(Status::internal("Could not use backup code")),
+            };
+        }
+
+        let stored_totp = match get_totp_db(&self.db_pool, user_id).await {
+            Ok(Some(stored_totp)) if stored_totp.confirmed => stored_totp,
+            Ok(_) => return Ok(false),
+            Err(_) => return Err(Status::internal("Couldn't get totp secret from DB")),
+        };
+
+        let totp_secret = match decrypt_totp_secret(
+            &self.secrets.totp_encryption_key,
+            user_id,
+            &stored_totp.encrypted_secret,
+        ) {
+            Ok(totp_secret) => totp_secret,
+            Err(_) => return Err(Status::internal("Could not decrypt totp secret")),
+        };
+
+        let time_step = match verify_totp_code(&totp_secret, code, Utc::now().timestamp()) {
+            Some(time_step) => time_step,
+            None => return Ok(false),
+        };
+
+        match use_totp_step_db(&self.db_pool, user_id, time_step).await {
+            Ok(used) => Ok(used),
+            Err(_) => Err(Status::internal("Could not use totp code")),
+        }
+    }
+
+    // drops the cached auth token of a session that has been removed from the DB and puts the session on the
+    // revocation list
+    async fn forget_session(&self, session_id: &Uuid) -> Result<(), Status> {
+        if delete_token_redis(self.redis_con.clone(), session_id)
+            .await
+            .is_err()
+        {
+            return Err(Status::internal("Could not delete auth token from redis"));
+        }
+
+        // no access token of the session can outlive this
+        let expiration_timestamp =
+            (Utc::now() + self.auth_token_settings.access_token_lifetime()).timestamp();
+        if revoke_session_redis(self.redis_con.clone(), session_id, expiration_timestamp)
+            .await
+            .is_err()
+        {
+            return Err(Status::internal("Could not revoke session in redis"));
+        }
+
+        Ok(())
+    }
+
+    // creates a short-lived access token and a refresh token belonging to the session, where the refresh token is
+    // stored as part of the provided transaction
+    async fn generate_token_pair(
+        &self,
+        transaction: &mut Transaction<'_, Postgres>,
+        user_id: i32,
+        username: String,
+        roles: Vec<Role>,
+        session_id: Uuid,
+        email_verified: bool,
+    ) -> Result<Token, Status> {
+        let jwt_keys = self.jwt_keyring.current();
+        let access_token_lifetime = self.auth_token_settings.access_token_lifetime();
+
+        let access_token = match spawn_blocking(move || {
+            generate_auth_token(
+                &jwt_keys,
+                user_id,
+                username,
+                roles,
+                session_id,
+                email_verified,
+                access_token_lifetime,
+            )
+        })
+        .await
+        {
+            Ok(res) => match res {
+                Ok(e) => e,
+                Err(_) => return Err(Status::internal("Failed to generate auth token")),
+            },
+            Err(_) => return Err(Status::internal("Could not create a auth token")),
+        };
+
+        let refresh_token = generate_refresh_token();
+        let refresh_token_expires_at =
+            Utc::now() + self.auth_token_settings.refresh_token_lifetime();
+
+        if store_refresh_token_db(
+            transaction,
+            &user_id,
+            &session_id,
+            &refresh_token.hash,
+            refresh_token_expires_at,
+        )
+        .await
+        .is_err()
+        {
+            return Err(Status::internal("Could not store refresh token into DB"));
+        }
+
+        Ok(Token {
+            access_token,
+            refresh_token: refresh_token.token,
+            expires_in: access_token_lifetime.num_seconds(),
+        })
+    }
 }
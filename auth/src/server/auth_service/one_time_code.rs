@@ -0,0 +1,32 @@
+use rand::{thread_rng, Rng};
+
+use super::hash_refresh_token;
+
+const ONE_TIME_CODE_LENGTH: usize = 10;
+// characters that are easily confused with each other (0/O, 1/I) are left out since the code is typed by hand
+const ONE_TIME_CODE_CHARSET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+// codes that the user types in by hand, like the mailed password reset codes or the backup codes of two-factor
+// authentication
+pub struct OneTimeCode {
+    // this is what is mailed to the user
+    pub code: String,
+    // this is what is stored in the DB
+    pub hash: String,
+}
+
+pub fn generate_one_time_code() -> OneTimeCode {
+    let mut rng = thread_rng();
+    let code: String = (0..ONE_TIME_CODE_LENGTH)
+        .map(|_| char::from(ONE_TIME_CODE_CHARSET[rng.gen_range(0..ONE_TIME_CODE_CHARSET.len())]))
+        .collect();
+
+    let hash = hash_one_time_code(&code);
+
+    OneTimeCode { code, hash }
+}
+
+// codes are compared case insensitive and without surrounding whitespace since they are typed by hand
+pub fn hash_one_time_code(code: &str) -> String {
+    hash_refresh_token(&code.trim().to_uppercase())
+}
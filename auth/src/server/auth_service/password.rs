@@ -35,6 +35,36 @@ pub fn verify_password_hash(
 pub fn compute_password_hash(password: &str) -> Result<Secret<String>, anyhow::Error> {
     let salt = SaltString::generate(&mut rand::thread_rng());
 
+    let (algorithm, params) = configured_argon_params()?;
+
+    let password_hash = Argon2::new(algorithm, Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string();
+
+    Ok(Secret::new(password_hash))
+}
+
+// hashes are verified with the params stored in them, so a hash made with different params than the ones that are
+// configured right now has to be replaced to make use of the new params
+pub fn password_hash_needs_rehash(password_hash: &Secret<String>) -> Result<bool, anyhow::Error> {
+    let password_hash = PasswordHash::new(password_hash.expose_secret())
+        .context("Failed to parse password hash in PHC format")?;
+
+    let (algorithm, params) = configured_argon_params()?;
+
+    let stored_params =
+        Params::try_from(&password_hash).context("Failed to read params of password hash")?;
+
+    let same_params = password_hash.algorithm == algorithm.ident()
+        && password_hash.version == Some(Version::V0x13.into())
+        && stored_params.m_cost() == params.m_cost()
+        && stored_params.t_cost() == params.t_cost()
+        && stored_params.p_cost() == params.p_cost();
+
+    Ok(!same_params)
+}
+
+fn configured_argon_params() -> Result<(Algorithm, Params), anyhow::Error> {
     let argon_configuration = get_configuration()
         .expect("Failed to get configuration")
         .argon;
@@ -46,19 +76,13 @@ pub fn compute_password_hash(password: &str) -> Result<Secret<String>, anyhow::E
         _ => return Err(anyhow!("wrong Argon2 variant")),
     };
 
-    let password_hash = Argon2::new(
-        algorithm,
-        Version::V0x13,
-        Params::new(
-            argon_configuration.memory,
-            argon_configuration.iterations,
-            argon_configuration.parallelism,
-            None,
-        )
-        .unwrap(),
+    let params = Params::new(
+        argon_configuration.memory,
+        argon_configuration.iterations,
+        argon_configuration.parallelism,
+        None,
     )
-    .hash_password(password.as_bytes(), &salt)?
-    .to_string();
+    .map_err(|e| anyhow!("invalid Argon2 params: {}", e))?;
 
-    Ok(Secret::new(password_hash))
+    Ok((algorithm, params))
 }
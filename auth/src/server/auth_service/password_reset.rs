@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+use crate::configuration::PasswordResetSettings;
+use crate::mailer::{MailMessage, Mailer};
+
+use super::{generate_one_time_code, Email};
+
+// mails a reset code to the user that the email belongs to. it's run in the background of RequestPasswordReset, so
+// everything that goes wrong is only logged
+#[tracing::instrument(name = "Send password reset code", skip_all)]
+pub async fn send_password_reset_code(
+    db_pool: PgPool,
+    mailer: Arc<dyn Mailer>,
+    email: Email,
+    settings: PasswordResetSettings,
+) {
+    let user_id = match get_user_id_by_email_db(&db_pool, email.as_ref()).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => {
+            tracing::info!("password reset requested for an email that isn't registered");
+            return;
+        }
+        Err(_) => return,
+    };
+
+    let reset_code = generate_one_time_code();
+    let expires_at = Utc::now() + settings.code_lifetime();
+
+    let mut transaction = match db_pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            tracing::error!("Could not create a transaction for postgresql: {:?}", e);
+            return;
+        }
+    };
+
+    if store_reset_code_db(&mut transaction, &user_id, &reset_code.hash, expires_at)
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Could not commit password reset transaction: {:?}", e);
+        return;
+    }
+
+    let message = MailMessage {
+        to: email.as_ref().to_string(),
+        subject: "Reset your Chat-gRPC password".to_string(),
+        body: format!(
+            "Someone asked to reset the password of your Chat-gRPC account.\r\n\r\n\
+            Your password reset code is: {}\r\n\r\n\
+            The code expires in {} minutes. If you didn't ask for this you can ignore this mail.",
+            reset_code.code, settings.code_lifetime_minutes
+        ),
+    };
+
+    if let Err(e) = mailer.send(message).await {
+        tracing::error!("Failed to send mail: {:?}", e);
+    }
+}
+
+#[tracing::instrument(name = "Get user_id of email", skip(db_pool, email))]
+pub async fn get_user_id_by_email_db(
+    db_pool: &PgPool,
+    email: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    let user_id = sqlx::query!(r#"SELECT user_id FROM account WHERE email = $1"#, email)
+        .fetch_optional(db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to retrieve user_id: {:?}", e);
+            e
+        })?
+        .map(|row| row.user_id);
+
+    Ok(user_id)
+}
+
+// only the latest reset code of a user can be used, so the earlier ones are marked as used
+#[tracing::instrument(
+    name = "Store password reset code into DB"
+    skip(transaction, code_hash)
+)]
+pub async fn store_reset_code_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    code_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let invalidate_query = sqlx::query!(
+        r#"UPDATE password_reset_codes SET used = TRUE WHERE user_id = $1 AND used = FALSE"#,
+        user_id
+    );
+
+    transaction.execute(invalidate_query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO password_reset_codes (code_hash, user_id, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+        code_hash,
+        user_id,
+        expires_at
+    );
+
+    transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+// marks the reset code as used and returns the user it belongs to, nothing is returned when the code doesn't exist,
+// has expired or has already been used
+#[tracing::instrument(
+    name = "Use password reset code in DB"
+    skip(transaction, code_hash)
+)]
+pub async fn use_reset_code_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    code_hash: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    let user_id = sqlx::query!(
+        r#"
+        UPDATE password_reset_codes SET used = TRUE
+        WHERE code_hash = $1 AND used = FALSE AND expires_at > now()
+        RETURNING user_id
+        "#,
+        code_hash
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?
+    .map(|row| row.user_id);
+
+    Ok(user_id)
+}
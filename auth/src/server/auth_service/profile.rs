@@ -0,0 +1,94 @@
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+use super::ProfileUpdate;
+use crate::proto::auth::Profile;
+
+pub struct StoredProfile {
+    pub username: String,
+    pub firstname: String,
+    pub lastname: String,
+    pub email: String,
+    pub verified: bool,
+    pub display_name: Option<String>,
+    pub status_text: Option<String>,
+}
+
+#[tracing::instrument(name = "Get profile of user", skip(db_pool))]
+pub async fn get_profile_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+) -> Result<Option<StoredProfile>, sqlx::Error> {
+    let profile = sqlx::query_as!(
+        StoredProfile,
+        r#"
+        SELECT username, firstname, lastname, email, verified, display_name, status_text
+        FROM account WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve profile: {:?}", e);
+        e
+    })?;
+
+    Ok(profile)
+}
+
+// an empty display name or status text is stored as NULL. a new email has to be verified again
+#[tracing::instrument(
+    name = "Update profile of user in DB",
+    skip(transaction, profile_update)
+)]
+pub async fn update_profile_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    profile_update: &ProfileUpdate,
+) -> Result<(), sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        UPDATE account SET
+            firstname = COALESCE($2, firstname),
+            lastname = COALESCE($3, lastname),
+            email = COALESCE($4, email),
+            verified = CASE WHEN $4::TEXT IS NULL OR $4 = email THEN verified ELSE FALSE END,
+            display_name = CASE WHEN $5::TEXT IS NULL THEN display_name ELSE NULLIF($5, '') END,
+            status_text = CASE WHEN $6::TEXT IS NULL THEN status_text ELSE NULLIF($6, '') END
+        WHERE user_id = $1
+        "#,
+        user_id,
+        profile_update.firstname.as_ref().map(AsRef::<str>::as_ref),
+        profile_update.lastname.as_ref().map(AsRef::<str>::as_ref),
+        profile_update.email.as_ref().map(AsRef::<str>::as_ref),
+        profile_update
+            .display_name
+            .as_ref()
+            .map(AsRef::<str>::as_ref),
+        profile_update
+            .status_text
+            .as_ref()
+            .map(AsRef::<str>::as_ref),
+    );
+
+    transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+impl From<StoredProfile> for Profile {
+    fn from(profile: StoredProfile) -> Profile {
+        Profile {
+            username: profile.username,
+            firstname: profile.firstname,
+            lastname: profile.lastname,
+            email: profile.email,
+            email_verified: profile.verified,
+            display_name: profile.display_name.unwrap_or_default(),
+            status_text: profile.status_text.unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use sqlx::{Executor, PgPool, Postgres};
+
+pub use token_claims::Role;
+
+#[tracing::instrument(name = "Get user_id of username", skip(db_pool))]
+pub async fn get_user_id_by_username_db(
+    db_pool: &PgPool,
+    username: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    let user_id = sqlx::query!(
+        r#"SELECT user_id FROM account WHERE username = $1"#,
+        username
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve user_id: {:?}", e);
+        e
+    })?
+    .map(|row| row.user_id);
+
+    Ok(user_id)
+}
+
+#[tracing::instrument(name = "Get roles of user", skip(db_pool))]
+pub async fn get_roles_db(db_pool: &PgPool, user_id: &i32) -> Result<Vec<Role>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT roles.name FROM account_roles
+        JOIN roles ON roles.role_id = account_roles.role_id
+        WHERE account_roles.user_id = $1
+        ORDER BY roles.role_id
+        "#,
+        user_id
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve roles: {:?}", e);
+        e
+    })?;
+
+    // a role that has been added to the table but not to the code can't be checked anywhere, so it's left out
+    let roles = rows
+        .into_iter()
+        .filter_map(|row| match row.name.parse() {
+            Ok(role) => Some(role),
+            Err(e) => {
+                tracing::warn!("{}", e);
+                None
+            }
+        })
+        .collect();
+
+    Ok(roles)
+}
+
+// returns false when the user already had the role
+#[tracing::instrument(name = "Grant role to user", skip(executor))]
+pub async fn grant_role_db<'c>(
+    executor: impl Executor<'c, Database = Postgres>,
+    user_id: &i32,
+    role: Role,
+) -> Result<bool, sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO account_roles (user_id, role_id)
+        SELECT $1, role_id FROM roles WHERE name = $2
+        ON CONFLICT DO NOTHING
+        "#,
+        user_id,
+        role.as_str()
+    );
+
+    let result = executor.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// returns false when the user didn't have the role
+#[tracing::instrument(name = "Revoke role of user", skip(executor))]
+pub async fn revoke_role_db<'c>(
+    executor: impl Executor<'c, Database = Postgres>,
+    user_id: &i32,
+    role: Role,
+) -> Result<bool, sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        DELETE FROM account_roles
+        WHERE user_id = $1 AND role_id = (SELECT role_id FROM roles WHERE name = $2)
+        "#,
+        user_id,
+        role.as_str()
+    );
+
+    let result = executor.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
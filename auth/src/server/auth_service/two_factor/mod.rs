@@ -0,0 +1,7 @@
+mod postgres_operations;
+mod redis_operations;
+mod totp;
+
+pub use postgres_operations::*;
+pub use redis_operations::*;
+pub use totp::*;
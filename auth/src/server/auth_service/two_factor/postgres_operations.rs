@@ -0,0 +1,196 @@
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+use super::EncryptedTotpSecret;
+
+pub struct StoredTotp {
+    pub encrypted_secret: EncryptedTotpSecret,
+    // the second factor is only required once the enrollment has been confirmed
+    pub confirmed: bool,
+}
+
+#[tracing::instrument(name = "Get username of user", skip(db_pool))]
+pub async fn get_username_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let username = sqlx::query!(
+        r#"SELECT username FROM account WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve username: {:?}", e);
+        e
+    })?
+    .map(|row| row.username);
+
+    Ok(username)
+}
+
+#[tracing::instrument(name = "Get totp secret of user", skip(db_pool))]
+pub async fn get_totp_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+) -> Result<Option<StoredTotp>, sqlx::Error> {
+    let stored_totp = sqlx::query!(
+        r#"SELECT encrypted_secret, nonce, confirmed FROM totp_secrets WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to retrieve totp secret: {:?}", e);
+        e
+    })?
+    .map(|row| StoredTotp {
+        encrypted_secret: EncryptedTotpSecret {
+            ciphertext: row.encrypted_secret,
+            nonce: row.nonce,
+        },
+        confirmed: row.confirmed,
+    });
+
+    Ok(stored_totp)
+}
+
+// replaces an enrollment that hasn't been confirmed yet, returns false when the user already has a confirmed secret
+#[tracing::instrument(
+    name = "Store pending totp secret into DB",
+    skip(db_pool, encrypted_secret)
+)]
+pub async fn store_pending_totp_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+    encrypted_secret: &EncryptedTotpSecret,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO totp_secrets (user_id, encrypted_secret, nonce)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id) DO UPDATE
+        SET encrypted_secret = EXCLUDED.encrypted_secret, nonce = EXCLUDED.nonce, last_used_step = NULL,
+            created_at = now()
+        WHERE totp_secrets.confirmed = FALSE
+        "#,
+        user_id,
+        encrypted_secret.ciphertext,
+        encrypted_secret.nonce
+    )
+    .execute(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// the code that confirmed the secret counts as used, returns false when there was no pending enrollment
+#[tracing::instrument(name = "Confirm totp secret in DB", skip(transaction))]
+pub async fn confirm_totp_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    time_step: i64,
+) -> Result<bool, sqlx::Error> {
+    let query = sqlx::query!(
+        r#"
+        UPDATE totp_secrets SET confirmed = TRUE, last_used_step = $2
+        WHERE user_id = $1 AND confirmed = FALSE
+        "#,
+        user_id,
+        time_step
+    );
+
+    let result = transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// a code can only be used once, so it's only accepted when it's from a later time step than the last accepted code
+#[tracing::instrument(name = "Use totp time step in DB", skip(db_pool))]
+pub async fn use_totp_step_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+    time_step: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE totp_secrets SET last_used_step = $2
+        WHERE user_id = $1 AND confirmed = TRUE AND (last_used_step IS NULL OR last_used_step < $2)
+        "#,
+        user_id,
+        time_step
+    )
+    .execute(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// the new backup codes replace the ones the user had before
+#[tracing::instrument(name = "Store backup codes into DB", skip(transaction, code_hashes))]
+pub async fn store_backup_codes_db(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: &i32,
+    code_hashes: &[String],
+) -> Result<(), sqlx::Error> {
+    let delete_query = sqlx::query!(
+        r#"DELETE FROM totp_backup_codes WHERE user_id = $1"#,
+        user_id
+    );
+
+    transaction.execute(delete_query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO totp_backup_codes (user_id, code_hash)
+        SELECT $1, code_hash FROM UNNEST($2::TEXT[]) AS code_hash
+        "#,
+        user_id,
+        code_hashes
+    );
+
+    transaction.execute(query).await.map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+// marks the backup code as used, returns false when the user doesn't have such a backup code or it has been used
+#[tracing::instrument(name = "Use backup code in DB", skip(db_pool, code_hash))]
+pub async fn use_backup_code_db(
+    db_pool: &PgPool,
+    user_id: &i32,
+    code_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE totp_backup_codes SET used = TRUE
+        WHERE user_id = $1 AND code_hash = $2 AND used = FALSE
+        "#,
+        user_id,
+        code_hash
+    )
+    .execute(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to exectute query: {:?}", e);
+        e
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,170 @@
+use anyhow::anyhow;
+use chrono::Duration;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use redis::{AsyncCommands, RedisResult};
+
+use crate::server::{hash_refresh_token, RedisCon};
+
+// a login that still needs the second factor is stored as "login_challenge:<challenge hash>" and the wrong codes
+// that have been tried for it are counted as "login_challenge_attempts:<challenge hash>"
+const LOGIN_CHALLENGE_PREFIX: &str = "login_challenge";
+const LOGIN_CHALLENGE_ATTEMPTS_PREFIX: &str = "login_challenge_attempts";
+const LOGIN_CHALLENGE_LENGTH: usize = 64;
+
+// the reason of the ErrorInfo that login responds with when the user has to complete the login with a second factor,
+// the challenge is found under the "challenge" key of its metadata
+pub const SECOND_FACTOR_REQUIRED: &str = "SECOND_FACTOR_REQUIRED";
+pub const LOGIN_CHALLENGE_METADATA_KEY: &str = "challenge";
+
+pub struct LoginChallenge {
+    // this is what is handed out to the user
+    pub challenge: String,
+    // this is what is stored in redis
+    pub hash: String,
+}
+
+pub fn generate_login_challenge() -> LoginChallenge {
+    let challenge: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(LOGIN_CHALLENGE_LENGTH)
+        .map(char::from)
+        .collect();
+
+    let hash = hash_refresh_token(&challenge);
+
+    LoginChallenge { challenge, hash }
+}
+
+// the user that has given the right password and the device the session will be created for
+pub struct PendingLogin {
+    pub user_id: i32,
+    pub device: String,
+}
+
+#[tracing::instrument(
+    name = "Store login challenge into redis"
+    skip(redis_connection, challenge_hash, device)
+)]
+pub async fn store_login_challenge_redis(
+    redis_connection: RedisCon,
+    challenge_hash: &str,
+    user_id: &i32,
+    device: &str,
+    lifetime: Duration,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let res: RedisResult<()> = redis_con
+        .set_ex(
+            format!("{}:{}", LOGIN_CHALLENGE_PREFIX, challenge_hash),
+            format!("{}:{}", user_id, device),
+            lifetime.num_seconds() as u64,
+        )
+        .await;
+
+    if res.is_err() {
+        return Err(anyhow!("couldn't save login challenge in redis"));
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Get login challenge from redis"
+    skip(redis_connection, challenge_hash)
+)]
+pub async fn get_login_challenge_redis(
+    redis_connection: RedisCon,
+    challenge_hash: &str,
+) -> Result<Option<PendingLogin>, anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let pending_login: Option<String> = match redis_con
+        .get(format!("{}:{}", LOGIN_CHALLENGE_PREFIX, challenge_hash))
+        .await
+    {
+        Ok(pending_login) => pending_login,
+        Err(_) => return Err(anyhow!("couldn't get login challenge from redis")),
+    };
+
+    let pending_login = match pending_login {
+        Some(pending_login) => pending_login,
+        None => return Ok(None),
+    };
+
+    // the device can contain ':' as well, but the user_id can't
+    let (user_id, device) = pending_login
+        .split_once(':')
+        .ok_or_else(|| anyhow!("login challenge in redis is malformed"))?;
+
+    Ok(Some(PendingLogin {
+        user_id: user_id.parse()?,
+        device: device.to_string(),
+    }))
+}
+
+// counts a try to complete the login of the challenge and returns how many tries there have been
+#[tracing::instrument(
+    name = "Count login challenge attempt in redis"
+    skip(redis_connection, challenge_hash)
+)]
+pub async fn count_login_challenge_attempt_redis(
+    redis_connection: RedisCon,
+    challenge_hash: &str,
+    lifetime: Duration,
+) -> Result<u32, anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let key = format!("{}:{}", LOGIN_CHALLENGE_ATTEMPTS_PREFIX, challenge_hash);
+
+    let attempts: u32 = match redis_con.incr(&key, 1).await {
+        Ok(attempts) => attempts,
+        Err(_) => return Err(anyhow!("couldn't count login challenge attempt in redis")),
+    };
+
+    // the count doesn't have to outlive the challenge
+    let expire_res: RedisResult<()> = redis_con.expire(&key, lifetime.num_seconds()).await;
+
+    if expire_res.is_err() {
+        return Err(anyhow!(
+            "couldn't set expire time for login challenge attempts in redis"
+        ));
+    }
+
+    Ok(attempts)
+}
+
+// returns whether the challenge still existed, so a challenge is only ever exchanged for a session once
+#[tracing::instrument(
+    name = "Delete login challenge from redis"
+    skip(redis_connection, challenge_hash)
+)]
+pub async fn delete_login_challenge_redis(
+    redis_connection: RedisCon,
+    challenge_hash: &str,
+) -> Result<bool, anyhow::Error> {
+    let mut redis_con = redis_connection.lock().await;
+
+    let deleted: u32 = match redis_con
+        .del(format!("{}:{}", LOGIN_CHALLENGE_PREFIX, challenge_hash))
+        .await
+    {
+        Ok(deleted) => deleted,
+        Err(_) => return Err(anyhow!("couldn't delete login challenge from redis")),
+    };
+
+    let res: RedisResult<()> = redis_con
+        .del(format!(
+            "{}:{}",
+            LOGIN_CHALLENGE_ATTEMPTS_PREFIX, challenge_hash
+        ))
+        .await;
+
+    if res.is_err() {
+        return Err(anyhow!(
+            "couldn't delete login challenge attempts from redis"
+        ));
+    }
+
+    Ok(deleted > 0)
+}
@@ -0,0 +1,201 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use anyhow::anyhow;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, RngCore};
+use secrecy::{ExposeSecret, Secret};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+// 160 bits, which is the secret length that RFC 4226 recommends
+const TOTP_SECRET_LENGTH: usize = 20;
+const TOTP_DIGITS: usize = 6;
+pub const TOTP_PERIOD_SECONDS: i64 = 30;
+// codes of the previous and the next period are accepted as well, so a clock that is a bit off doesn't matter
+const TOTP_ALLOWED_SKEW: i64 = 1;
+
+pub fn generate_totp_secret() -> Vec<u8> {
+    let mut secret = vec![0; TOTP_SECRET_LENGTH];
+    thread_rng().fill_bytes(&mut secret);
+
+    secret
+}
+
+// authenticator apps expect the secret to be base32 encoded
+pub fn encode_totp_secret(secret: &[u8]) -> String {
+    BASE32_NOPAD.encode(secret)
+}
+
+// the uri is usually shown as a QR code that authenticator apps can scan
+pub fn totp_uri(issuer: &str, username: &str, encoded_secret: &str) -> String {
+    let issuer = urlencoding::encode(issuer);
+
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer,
+        urlencoding::encode(username),
+        encoded_secret,
+        issuer,
+        TOTP_DIGITS,
+        TOTP_PERIOD_SECONDS
+    )
+}
+
+pub fn totp_time_step(timestamp: i64) -> i64 {
+    timestamp.div_euclid(TOTP_PERIOD_SECONDS)
+}
+
+// the code of a time step as defined by RFC 6238, which is the HOTP code of RFC 4226 where the counter is the time
+// step
+pub fn totp_code(secret: &[u8], time_step: i64) -> String {
+    let mut mac =
+        <Hmac<Sha1> as Mac>::new_from_slice(secret).expect("HMAC can take a key of any size");
+    mac.update(&time_step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS as u32),
+        width = TOTP_DIGITS
+    )
+}
+
+// codes of authenticator apps are only digits, anything else is taken to be a backup code
+pub fn is_totp_code(code: &str) -> bool {
+    let code = code.trim();
+    code.len() == TOTP_DIGITS && code.chars().all(|c| c.is_ascii_digit())
+}
+
+// returns the time step that the code belongs to, so the caller can make sure it isn't used again
+pub fn verify_totp_code(secret: &[u8], code: &str, timestamp: i64) -> Option<i64> {
+    if !is_totp_code(code) {
+        return None;
+    }
+
+    let current_time_step = totp_time_step(timestamp);
+
+    (-TOTP_ALLOWED_SKEW..=TOTP_ALLOWED_SKEW)
+        .map(|skew| current_time_step + skew)
+        .find(|time_step| totp_code(secret, *time_step) == code.trim())
+}
+
+pub struct EncryptedTotpSecret {
+    pub ciphertext: Vec<u8>,
+    pub nonce: Vec<u8>,
+}
+
+fn totp_cipher(encryption_key: &Secret<String>) -> Aes256Gcm {
+    // the configured key can be any string, hashing it gives a key of the right size
+    let key = Sha256::digest(encryption_key.expose_secret().as_bytes());
+
+    Aes256Gcm::new(&key)
+}
+
+// the user_id is authenticated together with the secret, so a secret can't be moved over to another user in the DB
+pub fn encrypt_totp_secret(
+    encryption_key: &Secret<String>,
+    user_id: &i32,
+    secret: &[u8],
+) -> Result<EncryptedTotpSecret, anyhow::Error> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let payload = Payload {
+        msg: secret,
+        aad: &user_id.to_be_bytes(),
+    };
+
+    let ciphertext = totp_cipher(encryption_key)
+        .encrypt(&nonce, payload)
+        .map_err(|_| anyhow!("couldn't encrypt totp secret"))?;
+
+    Ok(EncryptedTotpSecret {
+        ciphertext,
+        nonce: nonce.to_vec(),
+    })
+}
+
+pub fn decrypt_totp_secret(
+    encryption_key: &Secret<String>,
+    user_id: &i32,
+    encrypted_secret: &EncryptedTotpSecret,
+) -> Result<Vec<u8>, anyhow::Error> {
+    if encrypted_secret.nonce.len() != 12 {
+        return Err(anyhow!("totp secret has a nonce of the wrong size"));
+    }
+
+    let payload = Payload {
+        msg: &encrypted_secret.ciphertext,
+        aad: &user_id.to_be_bytes(),
+    };
+
+    totp_cipher(encryption_key)
+        .decrypt(Nonce::from_slice(&encrypted_secret.nonce), payload)
+        .map_err(|_| anyhow!("couldn't decrypt totp secret"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_none, assert_some_eq};
+
+    // the SHA-1 test vectors of RFC 6238, which have 8 digits so only the last 6 digits are compared
+    #[test]
+    fn totp_code_matches_rfc_6238_test_vectors() {
+        let secret = b"12345678901234567890";
+        let test_vectors = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+            (20000000000, "65353130"),
+        ];
+
+        for (timestamp, code) in test_vectors {
+            assert_eq!(totp_code(secret, totp_time_step(timestamp)), code[2..]);
+        }
+    }
+
+    #[test]
+    fn codes_of_neighbouring_time_steps_are_accepted() {
+        let secret = generate_totp_secret();
+        let timestamp = 1_700_000_000;
+        let time_step = totp_time_step(timestamp);
+
+        for skew in [-1, 0, 1] {
+            let code = totp_code(&secret, time_step + skew);
+            assert_some_eq!(
+                verify_totp_code(&secret, &code, timestamp),
+                time_step + skew
+            );
+        }
+
+        let code = totp_code(&secret, time_step + 2);
+        assert_none!(verify_totp_code(&secret, &code, timestamp));
+    }
+
+    #[test]
+    fn encrypted_secret_can_only_be_decrypted_for_its_user() {
+        let encryption_key = Secret::new("encryption key".to_string());
+        let secret = generate_totp_secret();
+
+        let encrypted_secret = encrypt_totp_secret(&encryption_key, &1, &secret).unwrap();
+
+        assert_eq!(
+            decrypt_totp_secret(&encryption_key, &1, &encrypted_secret).unwrap(),
+            secret
+        );
+        assert!(decrypt_totp_secret(&encryption_key, &2, &encrypted_secret).is_err());
+    }
+}
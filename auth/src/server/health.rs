@@ -0,0 +1,98 @@
+use std::{sync::Arc, time::Duration};
+
+use redis::aio::MultiplexedConnection;
+use sqlx::PgPool;
+use tokio::sync::Mutex;
+use tonic::server::NamedService;
+use tonic_health::{server::HealthReporter, ServingStatus};
+
+use crate::configuration::HealthCheckSettings;
+use crate::proto::auth::auth_server::AuthServer;
+
+use super::AuthenticationService;
+
+// keeps the health of the Auth service up to date for as long as the server is running, it can only answer requests
+// while both postgres and redis can be reached
+pub async fn report_health(
+    mut health_reporter: HealthReporter,
+    db_pool: PgPool,
+    redis_con: Arc<Mutex<MultiplexedConnection>>,
+    settings: HealthCheckSettings,
+) {
+    let mut reported_status = None;
+
+    loop {
+        let (postgres_reachable, redis_reachable) = tokio::join!(
+            ping_postgres(&db_pool, settings.timeout()),
+            ping_redis(&redis_con, settings.timeout())
+        );
+
+        let status = if postgres_reachable && redis_reachable {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+
+        // only changes are reported, so the log isn't flooded while a dependency is down
+        if reported_status != Some(status) {
+            match status {
+                ServingStatus::Serving => tracing::info!("Auth service is serving"),
+                _ => tracing::error!(
+                    "Auth service is not serving, postgres reachable: {}, redis reachable: {}",
+                    postgres_reachable,
+                    redis_reachable
+                ),
+            }
+
+            // the empty service name is the health of the server as a whole
+            health_reporter.set_service_status("", status).await;
+            health_reporter
+                .set_service_status(
+                    <AuthServer<AuthenticationService> as NamedService>::NAME,
+                    status,
+                )
+                .await;
+
+            reported_status = Some(status);
+        }
+
+        tokio::time::sleep(settings.interval()).await;
+    }
+}
+
+async fn ping_postgres(db_pool: &PgPool, timeout: Duration) -> bool {
+    let ping = sqlx::query("SELECT 1").execute(db_pool);
+
+    match tokio::time::timeout(timeout, ping).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to ping postgres: {:?}", e);
+            false
+        }
+        Err(_) => {
+            tracing::error!("Pinging postgres timed out");
+            false
+        }
+    }
+}
+
+async fn ping_redis(redis_con: &Arc<Mutex<MultiplexedConnection>>, timeout: Duration) -> bool {
+    let ping = async {
+        let mut redis_con = redis_con.lock().await;
+        redis::cmd("PING")
+            .query_async::<_, ()>(&mut *redis_con)
+            .await
+    };
+
+    match tokio::time::timeout(timeout, ping).await {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to ping redis: {:?}", e);
+            false
+        }
+        Err(_) => {
+            tracing::error!("Pinging redis timed out");
+            false
+        }
+    }
+}
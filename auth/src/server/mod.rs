@@ -1,7 +1,11 @@
 mod auth_service;
+mod health;
+mod shutdown;
 mod startup;
 mod validation;
 
 pub use auth_service::*;
+pub use health::*;
+pub use shutdown::*;
 pub use startup::*;
 pub use validation::*;
@@ -10,38 +10,68 @@ use sqlx::postgres::PgPool;
 use tonic::transport::{server::Router, Server};
 use tonic_health::server::HealthReporter;
 
+use crate::configuration::Settings;
+use crate::mailer::FileOutbox;
 use crate::proto::auth::auth_server::AuthServer;
 use crate::proto::auth::FILE_DESCRIPTOR_SET;
 use crate::secrets::Secrets;
-use crate::server::AuthenticationService;
+use crate::server::{
+    report_health, AuthenticationService, JwtKeyring, PasswordDenylist, PasswordPolicy,
+};
 
 pub fn build_server(
     connection_pool: PgPool,
     redis_con: MultiplexedConnection,
     secrets: Secrets,
+    jwt_keyring: JwtKeyring,
+    configuration: &Settings,
 ) -> Router {
+    // the denylist is read once, so checking a password doesn't touch the disk
+    let denylist = match configuration.password_policy.denylist_path() {
+        Some(path) => PasswordDenylist::load(&path).expect("Failed to read the password denylist"),
+        None => PasswordDenylist::new(),
+    };
+
+    let redis_con = Arc::new(Mutex::new(redis_con));
+
+    // the Auth service is reported as serving once the first check has found postgres and redis to be reachable
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    tokio::spawn(report_health(
+        health_reporter,
+        connection_pool.clone(),
+        redis_con.clone(),
+        configuration.health_check.clone(),
+    ));
+
     let auth = AuthenticationService {
         db_pool: connection_pool,
-        redis_con: Arc::new(Mutex::new(redis_con)),
+        redis_con,
         secrets,
+        jwt_keyring,
+        auth_token_settings: configuration.auth_token.clone(),
+        password_reset_settings: configuration.password_reset.clone(),
+        email_verification_settings: configuration.email_verification.clone(),
+        login_throttle_settings: configuration.login_throttle.clone(),
+        totp_settings: configuration.totp.clone(),
+        introspection_settings: configuration.introspection.clone(),
+        password_policy: PasswordPolicy {
+            min_score: configuration.password_policy.min_score,
+            denylist,
+        },
+        mailer: Arc::new(FileOutbox::new(
+            configuration.mailer.sender.clone(),
+            &configuration.mailer.outbox_directory,
+        )),
     };
 
-    // ! for some reason the health service is not working look into it later
-    //
-    // setting up health service
-    // this will create a HealthReporter and HealthServer pair
-    // let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
-    // health_reporter
-    //     .set_serving::<AuthServer<AuthenticationService>>()
-    //     .await;
-
     let reflection_service = tonic_reflection::server::Builder::configure()
         .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
         .build()
         .unwrap();
 
     Server::builder()
-        // .add_service(health_service)
+        .add_service(health_service)
         .add_service(AuthServer::new(auth))
         .add_service(reflection_service)
 }
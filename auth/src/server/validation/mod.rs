@@ -1,5 +1,9 @@
+mod password_strength;
 mod register_signup_data;
 mod types;
+mod update_profile_data;
 
+pub use password_strength::*;
 pub use register_signup_data::*;
 pub use types::*;
+pub use update_profile_data::*;
@@ -0,0 +1,342 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::Password;
+
+pub const MAX_PASSWORD_SCORE: u8 = 4;
+
+// the score is reached once the estimated entropy of a password is at least this many bits
+const SCORE_THRESHOLDS: [f64; 4] = [20.0, 35.0, 50.0, 65.0];
+const SCORE_LABELS: [&str; 5] = ["very weak", "weak", "fair", "strong", "very strong"];
+
+// characters that continue a pattern are easy to guess, so they only count for this many bits
+const PATTERN_CHARACTER_BITS: f64 = 1.0;
+// a pattern is only noticed once it's this long, otherwise every password would be full of them
+const MIN_PATTERN_LENGTH: usize = 3;
+// shorter parts of the username or email are too likely to show up by chance
+const MIN_USER_INPUT_LENGTH: usize = 3;
+
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+// whether a character belongs to a class like digits or uppercase letters
+type CharacterClass = fn(&char) -> bool;
+// whether the second character carries on a pattern from the first one
+type PatternLink = fn(char, char) -> bool;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PasswordStrength {
+    // from 0 to MAX_PASSWORD_SCORE
+    pub score: u8,
+    // what would make the password stronger
+    pub hints: Vec<String>,
+}
+
+impl PasswordStrength {
+    pub fn label(&self) -> &'static str {
+        SCORE_LABELS[self.score as usize]
+    }
+}
+
+// estimates how hard a password is to guess from the characters it uses, where repeats, sequences, keyboard walks and
+// the username or email of the user barely count. user_inputs are the other things the user has entered
+pub fn estimate_password_strength(password: &str, user_inputs: &[&str]) -> PasswordStrength {
+    let chars: Vec<char> = password.to_lowercase().chars().collect();
+    let mut hints = Vec::new();
+
+    let from_user_input = user_input_characters(&chars, user_inputs);
+    if from_user_input.contains(&true) {
+        hints.push("don't use your username or email in the password".to_string());
+    }
+
+    let patterns: [(&str, PatternLink); 3] = [
+        ("avoid repeated characters like \"aaa\"", |a, b| a == b),
+        ("avoid sequences like \"abc\" or \"123\"", is_sequence),
+        (
+            "avoid keyboard patterns like \"qwerty\"",
+            is_keyboard_neighbour,
+        ),
+    ];
+
+    let mut in_pattern = vec![false; chars.len()];
+    for (hint, linked) in patterns {
+        let marked = pattern_characters(&chars, linked);
+
+        if marked.contains(&true) {
+            hints.push(hint.to_string());
+        }
+
+        for (in_pattern, marked) in in_pattern.iter_mut().zip(marked) {
+            *in_pattern |= marked;
+        }
+    }
+
+    let character_bits = character_set_size(password).log2();
+    let bits: f64 = (0..chars.len())
+        .map(|i| match (from_user_input[i], in_pattern[i]) {
+            (true, _) => 0.0,
+            (false, true) => PATTERN_CHARACTER_BITS,
+            (false, false) => character_bits,
+        })
+        .sum();
+
+    let score = SCORE_THRESHOLDS
+        .iter()
+        .filter(|threshold| bits >= **threshold)
+        .count() as u8;
+
+    if score < 3 {
+        if character_classes(password) < 3 {
+            hints.push("mix in uppercase letters, digits or symbols".to_string());
+        }
+        hints.push("add more words or characters".to_string());
+    }
+
+    PasswordStrength { score, hints }
+}
+
+fn character_classes(password: &str) -> usize {
+    let classes: [CharacterClass; 5] = [
+        char::is_ascii_lowercase,
+        char::is_ascii_uppercase,
+        char::is_ascii_digit,
+        |c| c.is_ascii_punctuation() || *c == ' ',
+        |c| !c.is_ascii(),
+    ];
+
+    classes
+        .iter()
+        .filter(|class| password.chars().any(|c| class(&c)))
+        .count()
+}
+
+// how many characters a password like this one could have been made of
+fn character_set_size(password: &str) -> f64 {
+    let sizes: [(CharacterClass, f64); 5] = [
+        (char::is_ascii_lowercase, 26.0),
+        (char::is_ascii_uppercase, 26.0),
+        (char::is_ascii_digit, 10.0),
+        (|c| c.is_ascii_punctuation() || *c == ' ', 33.0),
+        (|c| !c.is_ascii(), 100.0),
+    ];
+
+    sizes
+        .iter()
+        .filter(|(class, _)| password.chars().any(|c| class(&c)))
+        .map(|(_, size)| size)
+        .sum::<f64>()
+        .max(2.0)
+}
+
+// marks the characters that are part of the username or email, or of the name in front of the @ of the email
+fn user_input_characters(chars: &[char], user_inputs: &[&str]) -> Vec<bool> {
+    let mut marked = vec![false; chars.len()];
+
+    let user_inputs = user_inputs
+        .iter()
+        .flat_map(|input| [Some(*input), input.split_once('@').map(|(local, _)| local)])
+        .flatten()
+        .map(|input| input.to_lowercase().chars().collect::<Vec<char>>())
+        .filter(|input| input.len() >= MIN_USER_INPUT_LENGTH);
+
+    for input in user_inputs {
+        for start in 0..chars.len().saturating_sub(input.len() - 1) {
+            if chars[start..start + input.len()] == input[..] {
+                marked[start..start + input.len()].fill(true);
+            }
+        }
+    }
+
+    marked
+}
+
+// marks every character that continues a run of linked characters, the first character of a run is left alone
+fn pattern_characters(chars: &[char], linked: PatternLink) -> Vec<bool> {
+    let mut marked = vec![false; chars.len()];
+    let mut run_start = 0;
+
+    for i in 1..=chars.len() {
+        if i < chars.len() && linked(chars[i - 1], chars[i]) {
+            continue;
+        }
+
+        if i - run_start >= MIN_PATTERN_LENGTH {
+            marked[run_start + 1..i].fill(true);
+        }
+        run_start = i;
+    }
+
+    marked
+}
+
+fn is_sequence(a: char, b: char) -> bool {
+    a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric() && (a as u32).abs_diff(b as u32) == 1
+}
+
+fn is_keyboard_neighbour(a: char, b: char) -> bool {
+    KEYBOARD_ROWS
+        .iter()
+        .any(|row| match (row.find(a), row.find(b)) {
+            (Some(a), Some(b)) => a.abs_diff(b) == 1,
+            _ => false,
+        })
+}
+
+// passwords that have shown up in data breaches, only a hash of each one is kept so even a long list stays small.
+// passwords are compared without regard to case
+#[derive(Clone, Debug, Default)]
+pub struct PasswordDenylist {
+    // sorted so they can be searched
+    hashes: Arc<Vec<u64>>,
+}
+
+impl PasswordDenylist {
+    pub fn new() -> PasswordDenylist {
+        Self::default()
+    }
+
+    pub fn from_passwords<'a>(passwords: impl IntoIterator<Item = &'a str>) -> PasswordDenylist {
+        let mut hashes: Vec<u64> = passwords
+            .into_iter()
+            .filter(|password| !password.is_empty())
+            .map(denylist_hash)
+            .collect();
+
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes.shrink_to_fit();
+
+        Self {
+            hashes: Arc::new(hashes),
+        }
+    }
+
+    // the file has one password on each line
+    pub fn load(path: &Path) -> Result<PasswordDenylist, std::io::Error> {
+        let passwords = std::fs::read_to_string(path)?;
+
+        Ok(Self::from_passwords(
+            passwords.lines().map(|line| line.trim_end_matches('\r')),
+        ))
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        self.hashes.binary_search(&denylist_hash(password)).is_ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+}
+
+fn denylist_hash(password: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    password.to_lowercase().hash(&mut hasher);
+    hasher.finish()
+}
+
+// what new passwords have to live up to, on top of what Password::parse checks
+#[derive(Clone, Debug)]
+pub struct PasswordPolicy {
+    pub min_score: u8,
+    pub denylist: PasswordDenylist,
+}
+
+impl PasswordPolicy {
+    // none when the password can be used, otherwise the reason it can't which includes the score and the hints
+    pub fn check(&self, password: &Password, user_inputs: &[&str]) -> Option<String> {
+        if self.denylist.contains(password.as_ref()) {
+            return Some("password has appeared in a data breach, choose another one".to_string());
+        }
+
+        let strength = estimate_password_strength(password.as_ref(), user_inputs);
+
+        if strength.score < self.min_score {
+            return Some(format!(
+                "password is too weak, score {} of {}: {}",
+                strength.score,
+                MAX_PASSWORD_SCORE,
+                strength.hints.join(", ")
+            ));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(password: &str) -> u8 {
+        estimate_password_strength(password, &[]).score
+    }
+
+    #[test]
+    fn long_passwords_with_mixed_characters_are_strong() {
+        assert_eq!(score("strong password"), MAX_PASSWORD_SCORE);
+        assert_eq!(score("Tr0ub4dor&3x"), MAX_PASSWORD_SCORE);
+    }
+
+    #[test]
+    fn patterns_barely_count() {
+        for password in ["aaaaaaaaaaaa", "abcdefghijkl", "123456789012", "qwertyuiop"] {
+            assert_eq!(score(password), 0, "{} should be very weak", password);
+        }
+    }
+
+    #[test]
+    fn patterns_are_named_in_the_hints() {
+        let strength = estimate_password_strength("xkcd1234asdf", &[]);
+
+        assert!(strength.hints.iter().any(|hint| hint.contains("\"123\"")));
+        assert!(strength
+            .hints
+            .iter()
+            .any(|hint| hint.contains("\"qwerty\"")));
+    }
+
+    #[test]
+    fn username_and_email_in_the_password_are_weak() {
+        let user_inputs = ["atheer2104", "atheer.abc@gmail.com"];
+
+        let strength = estimate_password_strength("Atheer2104!", &user_inputs);
+        assert_eq!(strength.score, 0);
+        assert!(strength.hints[0].contains("username"));
+
+        let strength = estimate_password_strength("atheer.abc99", &user_inputs);
+        assert!(strength.score < 2);
+    }
+
+    #[test]
+    fn denylist_ignores_case() {
+        let denylist = PasswordDenylist::from_passwords(["password1", "letmein", ""]);
+
+        assert_eq!(denylist.len(), 2);
+        assert!(denylist.contains("Password1"));
+        assert!(denylist.contains("letmein"));
+        assert!(!denylist.contains("strong password"));
+    }
+
+    #[test]
+    fn policy_rejects_denylisted_and_weak_passwords() {
+        let policy = PasswordPolicy {
+            min_score: 2,
+            denylist: PasswordDenylist::from_passwords(["correct horse battery staple"]),
+        };
+        let parse = |password: &str| Password::parse(password.to_string()).unwrap();
+
+        assert!(policy
+            .check(&parse("correct horse battery staple"), &[])
+            .is_some_and(|reason| reason.contains("breach")));
+        assert!(policy
+            .check(&parse("12345678"), &[])
+            .is_some_and(|reason| reason.contains("score 0 of 4")));
+        assert_eq!(policy.check(&parse("strong password"), &[]), None);
+    }
+}
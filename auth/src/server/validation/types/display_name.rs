@@ -0,0 +1,54 @@
+use super::{validate_profile_text, RegisterDataError};
+
+const MAX_DISPLAY_NAME_LENGTH: u8 = 64;
+
+// shown instead of the username, an empty display name means that the user doesn't have one
+#[derive(Debug)]
+pub struct DisplayName(String);
+
+impl DisplayName {
+    pub fn parse(s: String) -> Result<DisplayName, RegisterDataError> {
+        let s = s.trim().to_string();
+
+        match validate_profile_text(&s, MAX_DISPLAY_NAME_LENGTH) {
+            Ok(_) => Ok(Self(s)),
+            Err(e) => Err(RegisterDataError::new("display_name".into(), e.into())),
+        }
+    }
+}
+
+impl AsRef<str> for DisplayName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DisplayName;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_empty_display_name_is_parsed() {
+        let display_name = "".to_string();
+        assert_ok!(DisplayName::parse(display_name));
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_removed() {
+        let display_name = DisplayName::parse("  Atheer  ".to_string()).unwrap();
+        assert_eq!(display_name.as_ref(), "Atheer");
+    }
+
+    #[test]
+    fn display_name_longer_than_64_graphemes_is_rejected() {
+        let display_name = "a".repeat(65);
+        assert_err!(DisplayName::parse(display_name));
+    }
+
+    #[test]
+    fn display_name_with_a_newline_is_rejected() {
+        let display_name = "Atheer\nABC".to_string();
+        assert_err!(DisplayName::parse(display_name));
+    }
+}
@@ -17,13 +17,26 @@ impl From<ValidateEmailError> for RegisterDataError {
     }
 }
 
+// emails are stored the way this returns them. domains don't care about case, the part in front of the @ is left
+// alone since the mail server decides what it means. every character is lowercased on its own, like the
+// normalize_email of the database does
+pub fn normalize_email(s: &str) -> String {
+    match s.rsplit_once('@') {
+        Some((local, domain)) => {
+            let domain: String = domain.chars().flat_map(char::to_lowercase).collect();
+            format!("{}@{}", local, domain)
+        }
+        None => s.to_string(),
+    }
+}
+
 impl Email {
     pub fn parse(s: String) -> Result<Email, ValidateEmailError> {
-        if validate_email(&s) {
-            Ok(Self(s))
-        } else {
-            Err(ValidateEmailError::NotValidEmail)
+        if !validate_email(&s) {
+            return Err(ValidateEmailError::NotValidEmail);
         }
+
+        Ok(Self(normalize_email(&s)))
     }
 }
 
@@ -59,6 +72,12 @@ mod tests {
         assert_err!(Email::parse(email));
     }
 
+    #[test]
+    fn only_the_domain_is_lowercased() {
+        let email = Email::parse("Atheer.ABC@GMail.COM".to_string()).unwrap();
+        assert_eq!(email.as_ref(), "Atheer.ABC@gmail.com");
+    }
+
     #[derive(Debug, Clone)]
     struct ValidEmail(pub String);
 
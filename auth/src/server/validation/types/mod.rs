@@ -1,13 +1,19 @@
+mod display_name;
 mod email;
 mod firstname;
 mod lastname;
 mod password;
+mod service_account_name;
+mod status_text;
 mod username;
 
+pub use display_name::*;
 pub use email::*;
 pub use firstname::*;
 pub use lastname::*;
 pub use password::*;
+pub use service_account_name::*;
+pub use status_text::*;
 pub use username::*;
 
 use thiserror::Error;
@@ -27,7 +33,7 @@ pub enum ValidateNameError {
 
 const MAX_NAME_LENGTH: u8 = 255;
 
-pub fn validate_name(s: &String) -> Result<(), ValidateNameError> {
+pub fn validate_name(s: &str) -> Result<(), ValidateNameError> {
     // is_empty_or_whitespace
     if s.trim().is_empty() {
         return Err(ValidateNameError::EmptyOrWhitepace);
@@ -45,3 +51,27 @@ pub fn validate_name(s: &String) -> Result<(), ValidateNameError> {
 
     Ok(())
 }
+
+#[derive(Debug, Error)]
+pub enum ValidateProfileTextError {
+    #[error("text is longer than {0} characters")]
+    TooLong(u8),
+    #[error("text contains control characters")]
+    ContainControlCharacters,
+}
+
+// the display name and status text are free text, only the length and characters that would mess up how they are
+// shown are checked
+pub fn validate_profile_text(s: &str, max_length: u8) -> Result<(), ValidateProfileTextError> {
+    // is_too_long
+    if s.graphemes(true).count() > max_length.into() {
+        return Err(ValidateProfileTextError::TooLong(max_length));
+    }
+
+    // is_contain_control_characters
+    if s.chars().any(|c| c.is_control()) {
+        return Err(ValidateProfileTextError::ContainControlCharacters);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use super::RegisterDataError;
+use thiserror::Error;
+
+const MAX_SERVICE_ACCOUNT_NAME_LENGTH: usize = 64;
+
+// the name that the messages of a service account are sent under, it's kept simple so it can't be mistaken for
+// another name
+#[derive(Debug)]
+pub struct ServiceAccountName(String);
+
+#[derive(Debug, Error)]
+pub enum ValidateServiceAccountNameError {
+    #[error("name is empty")]
+    Empty,
+    #[error("name is longer than {0} characters")]
+    TooLong(usize),
+    #[error("name contains '{0}', only lowercase letters, digits, '-' and '_' are allowed")]
+    ForbiddenCharacter(char),
+}
+
+impl From<ValidateServiceAccountNameError> for RegisterDataError {
+    fn from(value: ValidateServiceAccountNameError) -> Self {
+        RegisterDataError::new("name".into(), value.into())
+    }
+}
+
+impl ServiceAccountName {
+    pub fn parse(s: String) -> Result<ServiceAccountName, ValidateServiceAccountNameError> {
+        if s.is_empty() {
+            return Err(ValidateServiceAccountNameError::Empty);
+        }
+
+        if s.len() > MAX_SERVICE_ACCOUNT_NAME_LENGTH {
+            return Err(ValidateServiceAccountNameError::TooLong(
+                MAX_SERVICE_ACCOUNT_NAME_LENGTH,
+            ));
+        }
+
+        let forbidden_char = s
+            .chars()
+            .find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '-' || *c == '_'));
+
+        if let Some(forbidden_char) = forbidden_char {
+            return Err(ValidateServiceAccountNameError::ForbiddenCharacter(
+                forbidden_char,
+            ));
+        }
+
+        Ok(Self(s))
+    }
+}
+
+impl AsRef<str> for ServiceAccountName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServiceAccountName;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_valid_name_is_parsed() {
+        assert_ok!(ServiceAccountName::parse("deploy-bot_2".to_string()));
+    }
+
+    #[test]
+    fn empty_name_is_rejected() {
+        assert_err!(ServiceAccountName::parse("".to_string()));
+    }
+
+    #[test]
+    fn name_longer_than_64_characters_is_rejected() {
+        assert_err!(ServiceAccountName::parse("a".repeat(65)));
+    }
+
+    #[test]
+    fn names_with_other_characters_are_rejected() {
+        for name in ["Deploy", "deploy bot", "deploy(bot)", "dëploy"] {
+            assert_err!(ServiceAccountName::parse(name.to_string()));
+        }
+    }
+}
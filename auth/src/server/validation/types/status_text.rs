@@ -0,0 +1,48 @@
+use super::{validate_profile_text, RegisterDataError};
+
+const MAX_STATUS_TEXT_LENGTH: u8 = 255;
+
+// an empty status text means that the user doesn't have one
+#[derive(Debug)]
+pub struct StatusText(String);
+
+impl StatusText {
+    pub fn parse(s: String) -> Result<StatusText, RegisterDataError> {
+        let s = s.trim().to_string();
+
+        match validate_profile_text(&s, MAX_STATUS_TEXT_LENGTH) {
+            Ok(_) => Ok(Self(s)),
+            Err(e) => Err(RegisterDataError::new("status_text".into(), e.into())),
+        }
+    }
+}
+
+impl AsRef<str> for StatusText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusText;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_255_grapheme_status_text_is_parsed() {
+        let status_text = "᪬".repeat(255);
+        assert_ok!(StatusText::parse(status_text));
+    }
+
+    #[test]
+    fn status_text_longer_than_255_graphemes_is_rejected() {
+        let status_text = "ᾥ".repeat(256);
+        assert_err!(StatusText::parse(status_text));
+    }
+
+    #[test]
+    fn status_text_with_control_characters_is_rejected() {
+        let status_text = "out for lunch\u{7}".to_string();
+        assert_err!(StatusText::parse(status_text));
+    }
+}
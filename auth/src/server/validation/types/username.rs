@@ -1,5 +1,8 @@
 use super::{RegisterDataError, UnicodeSegmentation};
+use caseless::default_case_fold_str;
 use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::MixedScript;
 
 #[derive(Debug)]
 pub struct Username(String);
@@ -12,6 +15,10 @@ pub enum ValidateUsernameError {
     TooLong(u8),
     #[error("username contains '{0}' which is a forbidden character")]
     ContainForbiddenCharacater(char),
+    #[error(
+        "username mixes characters from different scripts, which can look like other usernames"
+    )]
+    MixedScripts,
 }
 
 impl From<ValidateUsernameError> for RegisterDataError {
@@ -23,8 +30,17 @@ impl From<ValidateUsernameError> for RegisterDataError {
 const FORBIDDEN_CHARACTERS: [char; 9] = ['/', '(', ')', '"', '<', '>', '\\', '{', '}'];
 const MAX_USERNAME_LENGTH: u8 = 255;
 
+// usernames are stored the way this returns them, so "Atheer" and "atheer" or two encodings of "é" are the same
+// username. this is what unicode calls NFKC_Casefold
+pub fn normalize_username(s: &str) -> String {
+    let folded = default_case_fold_str(&s.nfkc().collect::<String>());
+    folded.nfkc().collect()
+}
+
 impl Username {
     pub fn parse(s: String) -> Result<Username, ValidateUsernameError> {
+        let s = normalize_username(&s);
+
         // is_empty_or_whitespace
         if s.trim().is_empty() {
             return Err(ValidateUsernameError::EmptyOrWhitespace);
@@ -44,6 +60,11 @@ impl Username {
             ));
         }
 
+        // is_mixed_script, digits and punctuation belong to every script so they can still be used
+        if !s.as_str().is_single_script() {
+            return Err(ValidateUsernameError::MixedScripts);
+        }
+
         Ok(Self(s))
     }
 }
@@ -91,6 +112,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn usernames_are_case_folded_and_normalized() {
+        let username = Username::parse("Atheer2104".to_string()).unwrap();
+        assert_eq!(username.as_ref(), "atheer2104");
+
+        // fullwidth letters and a decomposed "é" are the same as the plain ones
+        let username = Username::parse("ＡＴＨＥＥＲ_e\u{301}".to_string()).unwrap();
+        assert_eq!(username.as_ref(), "atheer_é");
+
+        let username = Username::parse("STRASSE".to_string()).unwrap();
+        assert_eq!(
+            username.as_ref(),
+            Username::parse("straße".to_string()).unwrap().as_ref()
+        );
+    }
+
+    #[test]
+    fn forbidden_characters_are_found_after_normalizing() {
+        // fullwidth solidus becomes '/'
+        assert_err!(Username::parse("atheer\u{ff0f}2104".to_string()));
+    }
+
+    #[test]
+    fn a_username_mixing_scripts_is_rejected() {
+        // the first letter is a cyrillic "а"
+        assert_err!(Username::parse("\u{430}theer".to_string()));
+        assert_err!(Username::parse("paypal\u{3b1}".to_string()));
+    }
+
+    #[test]
+    fn a_username_in_a_single_script_is_valid() {
+        for username in ["атеер", "アテール2104", "東京たろう", "atheer_2104"] {
+            assert_ok!(Username::parse(username.to_string()));
+        }
+    }
+
     #[test]
     fn a_valid_username_is_parsed_successfully() {
         let username = "atheer2104".to_string();
@@ -0,0 +1,31 @@
+use super::{DisplayName, Email, Firstname, Lastname, RegisterDataError, StatusText};
+use crate::proto::auth::UpdateProfileRequest;
+
+impl TryFrom<UpdateProfileRequest> for ProfileUpdate {
+    type Error = RegisterDataError;
+
+    fn try_from(value: UpdateProfileRequest) -> Result<Self, Self::Error> {
+        let firstname = value.firstname.map(Firstname::parse).transpose()?;
+        let lastname = value.lastname.map(Lastname::parse).transpose()?;
+        let email = value.email.map(Email::parse).transpose()?;
+        let display_name = value.display_name.map(DisplayName::parse).transpose()?;
+        let status_text = value.status_text.map(StatusText::parse).transpose()?;
+
+        Ok(Self {
+            firstname,
+            lastname,
+            email,
+            display_name,
+            status_text,
+        })
+    }
+}
+
+// the fields that are None are left as they are
+pub struct ProfileUpdate {
+    pub firstname: Option<Firstname>,
+    pub lastname: Option<Lastname>,
+    pub email: Option<Email>,
+    pub display_name: Option<DisplayName>,
+    pub status_text: Option<StatusText>,
+}
@@ -0,0 +1,302 @@
+use auth::proto::auth::{
+    CreateApiKeyRequest, CreateServiceAccountRequest, ListApiKeysRequest, ListAuthEventsRequest,
+    RevokeApiKeyRequest, Token,
+};
+use auth::server::{hash_api_key, API_KEY_PREFIX, API_KEY_PREFIX_REDIS};
+use rand::{thread_rng, Rng};
+use redis::AsyncCommands;
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code};
+use crate::helpers::{authenticated_request, register_admin, register_user, App};
+
+async fn create_service_account(app: &App, admin_token: &Token, name: &str) {
+    app.create_service_account(authenticated_request(
+        CreateServiceAccountRequest { name: name.into() },
+        &admin_token.access_token,
+    ))
+    .await
+    .expect("failed to create service account");
+}
+
+async fn api_key_in_redis(app: &App, api_key: &str) -> Option<String> {
+    app.redis_con
+        .lock()
+        .await
+        .get(format!(
+            "{}:{}",
+            API_KEY_PREFIX_REDIS,
+            hash_api_key(api_key)
+        ))
+        .await
+        .expect("failed to get API key from redis")
+}
+
+#[tokio::test]
+async fn service_accounts_can_only_be_managed_by_admins() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let error = app
+        .create_service_account(authenticated_request(
+            CreateServiceAccountRequest {
+                name: "weather-bot".into(),
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect_err("users shouldn't be able to create service accounts");
+    assert_eq!(error.code(), Code::PermissionDenied);
+
+    let error = app
+        .create_api_key(authenticated_request(
+            CreateApiKeyRequest {
+                service_account: "weather-bot".into(),
+                scopes: vec!["chat:read".into()],
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect_err("users shouldn't be able to create API keys");
+    assert_eq!(error.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn service_account_names_are_validated_and_unique() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+
+    let error = app
+        .create_service_account(authenticated_request(
+            CreateServiceAccountRequest {
+                name: "Weather Bot".into(),
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect_err("service account names can't contain uppercase letters or spaces");
+    assert_eq!(error.code(), Code::InvalidArgument);
+    let violations = error.get_error_details().bad_request().unwrap().clone();
+    assert_eq!(violations.field_violations[0].field, "name");
+
+    let service_account = app
+        .create_service_account(authenticated_request(
+            CreateServiceAccountRequest {
+                name: "weather-bot".into(),
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to create service account")
+        .into_inner();
+    assert_eq!(service_account.name, "weather-bot");
+
+    let error = app
+        .create_service_account(authenticated_request(
+            CreateServiceAccountRequest {
+                name: "weather-bot".into(),
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect_err("service account names have to be unique");
+    assert_eq!(error.code(), Code::AlreadyExists);
+}
+
+#[tokio::test]
+async fn api_keys_are_only_stored_as_hash() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    create_service_account(&app, &admin_token, "weather-bot").await;
+
+    let created = app
+        .create_api_key(authenticated_request(
+            CreateApiKeyRequest {
+                service_account: "weather-bot".into(),
+                scopes: vec!["chat:post".into(), "chat:post".into()],
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to create API key")
+        .into_inner();
+
+    assert!(created.api_key.starts_with(API_KEY_PREFIX));
+    assert_eq!(created.scopes, vec!["chat:post"]);
+
+    let stored_hashes: Vec<String> = sqlx::query_scalar("SELECT key_hash FROM api_keys")
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("failed to get API keys");
+    assert_eq!(stored_hashes, vec![hash_api_key(&created.api_key)]);
+
+    // services look the API key up in redis
+    let in_redis = api_key_in_redis(&app, &created.api_key)
+        .await
+        .expect("API key isn't in redis");
+    assert!(in_redis.starts_with(&created.key_id));
+    assert!(in_redis.ends_with("weather-bot chat:post"));
+
+    let api_keys = app
+        .list_api_keys(authenticated_request(
+            ListApiKeysRequest {
+                service_account: "weather-bot".into(),
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to list API keys")
+        .into_inner()
+        .api_keys;
+    assert_eq!(api_keys.len(), 1);
+    assert_eq!(api_keys[0].key_id, created.key_id);
+    assert!(api_keys[0].revoked_at.is_none());
+}
+
+#[tokio::test]
+async fn api_keys_need_known_scopes_and_an_existing_service_account() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+
+    let error = app
+        .create_api_key(authenticated_request(
+            CreateApiKeyRequest {
+                service_account: "weather-bot".into(),
+                scopes: vec!["chat:read".into()],
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect_err("service account doesn't exist");
+    assert_eq!(error.code(), Code::NotFound);
+
+    create_service_account(&app, &admin_token, "weather-bot").await;
+
+    for scopes in [
+        vec![],
+        vec!["chat:read".to_string(), "chat:delete".to_string()],
+    ] {
+        let error = app
+            .create_api_key(authenticated_request(
+                CreateApiKeyRequest {
+                    service_account: "weather-bot".into(),
+                    scopes,
+                },
+                &admin_token.access_token,
+            ))
+            .await
+            .expect_err("API key needs known scopes");
+        assert_eq!(error.code(), Code::InvalidArgument);
+        let violations = error.get_error_details().bad_request().unwrap().clone();
+        assert_eq!(violations.field_violations[0].field, "scopes");
+    }
+}
+
+#[tokio::test]
+async fn revoked_api_keys_are_removed_from_redis() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    create_service_account(&app, &admin_token, "weather-bot").await;
+
+    let mut created = Vec::new();
+    for _ in 0..2 {
+        created.push(
+            app.create_api_key(authenticated_request(
+                CreateApiKeyRequest {
+                    service_account: "weather-bot".into(),
+                    scopes: vec!["chat:read".into()],
+                },
+                &admin_token.access_token,
+            ))
+            .await
+            .expect("failed to create API key")
+            .into_inner(),
+        );
+    }
+
+    app.revoke_api_key(authenticated_request(
+        RevokeApiKeyRequest {
+            key_id: created[0].key_id.clone(),
+        },
+        &admin_token.access_token,
+    ))
+    .await
+    .expect("failed to revoke API key");
+
+    // every API key is revoked on its own
+    assert!(api_key_in_redis(&app, &created[0].api_key).await.is_none());
+    assert!(api_key_in_redis(&app, &created[1].api_key).await.is_some());
+
+    // like when redis couldn't be updated after the revocation was committed, revoking it again takes it out
+    let _: () = app
+        .redis_con
+        .lock()
+        .await
+        .set(
+            format!(
+                "{}:{}",
+                API_KEY_PREFIX_REDIS,
+                hash_api_key(&created[0].api_key)
+            ),
+            "left behind",
+        )
+        .await
+        .expect("failed to store API key into redis");
+
+    app.revoke_api_key(authenticated_request(
+        RevokeApiKeyRequest {
+            key_id: created[0].key_id.clone(),
+        },
+        &admin_token.access_token,
+    ))
+    .await
+    .expect("failed to revoke API key again");
+    assert!(api_key_in_redis(&app, &created[0].api_key).await.is_none());
+
+    let api_keys = app
+        .list_api_keys(authenticated_request(
+            ListApiKeysRequest {
+                service_account: "weather-bot".into(),
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to list API keys")
+        .into_inner()
+        .api_keys;
+    let revoked: Vec<bool> = api_keys
+        .iter()
+        .map(|api_key| api_key.revoked_at.is_some())
+        .collect();
+    assert_eq!(revoked, vec![false, true]);
+
+    let events = app
+        .list_auth_events(authenticated_request(
+            ListAuthEventsRequest {
+                event_type: "api_key_revoked".into(),
+                ..Default::default()
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to list auth events")
+        .into_inner()
+        .events;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].username.as_deref(), Some("admin"));
+}
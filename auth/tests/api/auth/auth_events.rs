@@ -0,0 +1,254 @@
+use auth::proto::auth::{
+    AuthEvent, DeleteAccountRequest, ListAuthEventsRequest, LogoutRequest, RefreshRequest, Token,
+};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{
+    authenticated_request, login_user, register_admin, register_user, try_login_user, App,
+};
+
+async fn list_auth_events(
+    app: &App,
+    admin_token: &Token,
+    list_auth_events_request: ListAuthEventsRequest,
+) -> (Vec<AuthEvent>, String) {
+    let response = app
+        .list_auth_events(authenticated_request(
+            list_auth_events_request,
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to list auth events")
+        .into_inner();
+
+    (response.events, response.next_page_token)
+}
+
+#[tokio::test]
+async fn authentication_events_of_a_user_are_recorded() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    let token = register_user(&app).await;
+
+    try_login_user(&app, "atheer2104", "wrong password")
+        .await
+        .expect_err("login with a wrong password succeeded");
+    login_user(&app).await;
+
+    let token = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: token.refresh_token,
+        }))
+        .await
+        .expect("failed to refresh auth token")
+        .into_inner();
+
+    app.logout(authenticated_request(LogoutRequest {}, &token.access_token))
+        .await
+        .expect("failed to logout");
+
+    let (events, next_page_token) = list_auth_events(
+        &app,
+        &admin_token,
+        ListAuthEventsRequest {
+            username: "atheer2104".into(),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    let event_types: Vec<&str> = events
+        .iter()
+        .map(|event| event.event_type.as_str())
+        .collect();
+
+    // the newest events come first
+    assert_eq!(
+        event_types,
+        vec![
+            "session_revoked",
+            "token_refreshed",
+            "login_succeeded",
+            "login_failed",
+            "registered"
+        ]
+    );
+    assert!(next_page_token.is_empty());
+
+    let claims = app.auth_token_claims(&token.access_token);
+    for event in &events {
+        assert_eq!(event.user_id, Some(claims.user_id));
+        assert_eq!(event.username.as_deref(), Some("atheer2104"));
+        assert!(!event.peer_address.is_empty());
+        assert!(event.user_agent.contains("tonic"));
+        assert!(event.created_at.is_some());
+    }
+
+    assert_eq!(events[3].detail, "wrong password");
+}
+
+#[tokio::test]
+async fn failed_login_of_an_unknown_user_is_recorded_without_user() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+
+    try_login_user(&app, "nobody", "strong password")
+        .await
+        .expect_err("login of a user that doesn't exist succeeded");
+
+    let (events, _) = list_auth_events(
+        &app,
+        &admin_token,
+        ListAuthEventsRequest {
+            event_type: "login_failed".into(),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].user_id, None);
+    assert!(events[0].detail.contains("nobody"));
+
+    // the failed login above counts towards the login throttle of the address
+    try_login_user(&app, "admin", "strong password")
+        .await
+        .expect("failed to login admin");
+}
+
+#[tokio::test]
+async fn deleting_an_account_is_recorded_with_its_username() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    let token = register_user(&app).await;
+
+    app.delete_account(authenticated_request(
+        DeleteAccountRequest {
+            password: "strong password".into(),
+        },
+        &token.access_token,
+    ))
+    .await
+    .expect("failed to delete account");
+
+    let (events, _) = list_auth_events(
+        &app,
+        &admin_token,
+        ListAuthEventsRequest {
+            event_type: "account_deleted".into(),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    // the event outlives the account, its user is gone but the username is kept
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].user_id, None);
+    assert_eq!(events[0].detail, "username atheer2104");
+}
+
+#[tokio::test]
+async fn auth_events_are_listed_page_by_page() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    for _ in 0..4 {
+        try_login_user(&app, "admin", "strong password")
+            .await
+            .expect("failed to login admin");
+    }
+
+    let (all_events, _) =
+        list_auth_events(&app, &admin_token, ListAuthEventsRequest::default()).await;
+
+    let mut paged_events = Vec::new();
+    let mut page_token = String::new();
+    loop {
+        let (events, next_page_token) = list_auth_events(
+            &app,
+            &admin_token,
+            ListAuthEventsRequest {
+                page_size: 2,
+                page_token,
+                ..Default::default()
+            },
+        )
+        .await;
+
+        assert!(events.len() <= 2);
+        paged_events.extend(events);
+
+        if next_page_token.is_empty() {
+            break;
+        }
+        page_token = next_page_token;
+    }
+
+    assert_eq!(paged_events, all_events);
+    assert!(all_events.len() >= 6);
+}
+
+#[tokio::test]
+async fn listing_auth_events_requires_the_admin_role() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let error = app
+        .list_auth_events(authenticated_request(
+            ListAuthEventsRequest::default(),
+            &token.access_token,
+        ))
+        .await
+        .expect_err("user without the admin role listed auth events");
+
+    assert!(error.code() == Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn listing_auth_events_with_invalid_filters_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+
+    let test_cases = vec![
+        ListAuthEventsRequest {
+            event_type: "logged_in".into(),
+            ..Default::default()
+        },
+        ListAuthEventsRequest {
+            page_token: "not a page token".into(),
+            ..Default::default()
+        },
+    ];
+
+    for list_auth_events_request in test_cases {
+        let error = app
+            .list_auth_events(authenticated_request(
+                list_auth_events_request,
+                &admin_token.access_token,
+            ))
+            .await
+            .expect_err("invalid filter was accepted");
+
+        assert!(error.code() == Code::InvalidArgument);
+        assert!(error.get_error_details().has_bad_request_violations());
+    }
+}
@@ -0,0 +1,97 @@
+use auth::proto::auth::CheckAvailabilityRequest;
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{register_user, try_register_user};
+
+#[tokio::test]
+async fn taken_username_and_email_are_not_available() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+
+    let availability = app
+        .check_availability(Request::new(CheckAvailabilityRequest {
+            username: "atheer2104".into(),
+            email: "atheer@gmail.com".into(),
+        }))
+        .await
+        .expect("failed to check availability")
+        .into_inner();
+    assert_eq!(availability.username_available, Some(false));
+    assert_eq!(availability.email_available, Some(false));
+
+    let availability = app
+        .check_availability(Request::new(CheckAvailabilityRequest {
+            username: "someone_else".into(),
+            email: "someone@gmail.com".into(),
+        }))
+        .await
+        .expect("failed to check availability")
+        .into_inner();
+    assert_eq!(availability.username_available, Some(true));
+    assert_eq!(availability.email_available, Some(true));
+}
+
+#[tokio::test]
+async fn only_the_fields_that_are_sent_are_checked() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let availability = app
+        .check_availability(Request::new(CheckAvailabilityRequest {
+            username: "atheer2104".into(),
+            ..Default::default()
+        }))
+        .await
+        .expect("failed to check availability")
+        .into_inner();
+    assert_eq!(availability.username_available, Some(true));
+    assert_eq!(availability.email_available, None);
+}
+
+#[tokio::test]
+async fn invalid_fields_are_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let error = app
+        .check_availability(Request::new(CheckAvailabilityRequest {
+            username: "atheer2104".into(),
+            email: "not an email".into(),
+        }))
+        .await
+        .expect_err("email is not valid");
+    assert_eq!(error.code(), Code::InvalidArgument);
+
+    let violations = error.get_error_details().bad_request().unwrap().clone();
+    assert_eq!(violations.field_violations.len(), 1);
+    assert_eq!(violations.field_violations[0].field, "email");
+}
+
+#[tokio::test]
+async fn registering_a_taken_username_or_email_already_exists() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+
+    for (username, email, field) in [
+        ("atheer2104", "other@gmail.com", "username"),
+        ("someone_else", "atheer@gmail.com", "email"),
+    ] {
+        let error = try_register_user(&app, username, email)
+            .await
+            .expect_err("username or email is taken");
+        assert_eq!(error.code(), Code::AlreadyExists);
+
+        let violations = error.get_error_details().bad_request().unwrap().clone();
+        assert_eq!(violations.field_violations[0].field, field);
+    }
+}
@@ -0,0 +1,177 @@
+use auth::proto::auth::{ChangePasswordRequest, ListSessionsRequest, RefreshRequest};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{
+    authenticated_request, login_user, register_user, register_user_as,
+    spawn_app_with_configuration, try_login_user,
+};
+
+#[tokio::test]
+async fn change_password_without_auth_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let response = app
+        .change_password(Request::new(ChangePasswordRequest {
+            old_password: "strong password".into(),
+            new_password: "even stronger password".into(),
+        }))
+        .await;
+
+    let error = response.expect_err("The response was not a error");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn change_password_with_wrong_or_invalid_password_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let test_cases = [
+        ("wrong password", "even stronger password", "old_password"),
+        ("strong password", "short", "new_password"),
+        ("strong password", "         ", "new_password"),
+    ];
+
+    for (old_password, new_password, field) in test_cases {
+        let response = app
+            .change_password(authenticated_request(
+                ChangePasswordRequest {
+                    old_password: old_password.into(),
+                    new_password: new_password.into(),
+                },
+                &token.access_token,
+            ))
+            .await;
+
+        let error = response.expect_err("The response was not a error");
+        assert!(error.code() == Code::InvalidArgument);
+
+        let bad_request = error
+            .get_details_bad_request()
+            .expect("response doesn't contain bad request details");
+        assert_eq!(bad_request.field_violations[0].field, field);
+    }
+
+    // the password is left untouched
+    assert!(try_login_user(&app, "atheer2104", "strong password")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn change_password_signs_out_every_other_session() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let register_token = register_user(&app).await;
+    let login_token = login_user(&app).await;
+
+    let response = app
+        .change_password(authenticated_request(
+            ChangePasswordRequest {
+                old_password: "strong password".into(),
+                new_password: "even stronger password".into(),
+            },
+            &login_token.access_token,
+        ))
+        .await;
+
+    assert!(response.is_ok());
+
+    try_login_user(&app, "atheer2104", "strong password")
+        .await
+        .expect_err("old password was accepted");
+
+    assert!(try_login_user(&app, "atheer2104", "even stronger password")
+        .await
+        .is_ok());
+
+    // the other session can't be used anymore
+    let response = app
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &register_token.access_token,
+        ))
+        .await;
+
+    let error = response.expect_err("auth token of other session was accepted");
+    assert!(error.code() == Code::Unauthenticated);
+
+    let response = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: register_token.refresh_token,
+        }))
+        .await;
+
+    let error = response.expect_err("refresh token of other session was accepted");
+    assert!(error.code() == Code::Unauthenticated);
+
+    // while the session that changed the password is kept
+    let response = app
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &login_token.access_token,
+        ))
+        .await;
+
+    assert!(response.is_ok());
+}
+
+#[tokio::test]
+async fn wrong_current_passwords_count_towards_the_login_throttle() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration
+            .login_throttle
+            .max_failed_attempts_per_username = 3;
+        configuration.login_throttle.base_lockout_seconds = 60;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    // the failed logins are kept in redis between runs, so the username has to be new
+    let username = format!("atheer-{}", uuid::Uuid::new_v4());
+
+    let token = register_user_as(&app, &username, &format!("{}@gmail.com", username)).await;
+
+    for _ in 0..3 {
+        let error = app
+            .change_password(authenticated_request(
+                ChangePasswordRequest {
+                    old_password: "wrong password".into(),
+                    new_password: "even stronger password".into(),
+                },
+                &token.access_token,
+            ))
+            .await
+            .expect_err("changing password with wrong password succeeded");
+        assert!(error.code() == Code::InvalidArgument);
+    }
+
+    // the password can't be guessed any further, neither here nor by logging in
+    let error = app
+        .change_password(authenticated_request(
+            ChangePasswordRequest {
+                old_password: "strong password".into(),
+                new_password: "even stronger password".into(),
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect_err("changing password during lockout succeeded");
+    assert!(error.code() == Code::ResourceExhausted);
+
+    let error = try_login_user(&app, &username, "strong password")
+        .await
+        .expect_err("login during lockout succeeded");
+    assert!(error.code() == Code::ResourceExhausted);
+}
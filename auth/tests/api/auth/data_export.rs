@@ -0,0 +1,75 @@
+use auth::proto::auth::ExportMyDataRequest;
+use rand::{thread_rng, Rng};
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, login_user_on_device, register_user_on_device};
+
+#[tokio::test]
+async fn export_my_data_without_auth_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let error = app
+        .export_my_data(Request::new(ExportMyDataRequest {}))
+        .await
+        .expect_err("data was exported without an auth token");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn export_my_data_returns_the_stored_data_without_secrets() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user_on_device(&app, "laptop").await;
+    login_user_on_device(&app, "phone").await;
+
+    let document = app
+        .export_my_data(authenticated_request(
+            ExportMyDataRequest {},
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to export data")
+        .into_inner()
+        .document;
+
+    let export: serde_json::Value =
+        serde_json::from_str(&document).expect("export is not valid json");
+
+    assert_eq!(export["account"]["username"], "atheer2104");
+    assert_eq!(export["account"]["email"], "atheer@gmail.com");
+    assert_eq!(export["account"]["email_verified"], false);
+    assert_eq!(export["roles"], serde_json::json!(["user"]));
+    assert_eq!(export["two_factor"]["enabled"], false);
+
+    let mut devices: Vec<&str> = export["sessions"]
+        .as_array()
+        .expect("sessions is not a list")
+        .iter()
+        .map(|session| session["device"].as_str().expect("device is not a string"))
+        .collect();
+    devices.sort();
+
+    assert_eq!(devices, vec!["laptop", "phone"]);
+
+    let mut event_types: Vec<&str> = export["auth_events"]
+        .as_array()
+        .expect("auth_events is not a list")
+        .iter()
+        .map(|event| {
+            event["event_type"]
+                .as_str()
+                .expect("event_type is not a string")
+        })
+        .collect();
+    event_types.sort();
+
+    assert_eq!(event_types, vec!["login_succeeded", "registered"]);
+
+    assert!(!document.contains("password"));
+    assert!(!document.contains("$argon2"));
+}
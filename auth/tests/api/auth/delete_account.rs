@@ -0,0 +1,120 @@
+use auth::proto::auth::{DeleteAccountRequest, IntrospectRequest, RefreshRequest};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, login_user, register_user, try_login_user};
+
+#[tokio::test]
+async fn delete_account_without_auth_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let error = app
+        .delete_account(Request::new(DeleteAccountRequest {
+            password: "strong password".into(),
+        }))
+        .await
+        .expect_err("account was deleted without an auth token");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn delete_account_with_wrong_password_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    for password in ["", "wrong password"] {
+        let error = app
+            .delete_account(authenticated_request(
+                DeleteAccountRequest {
+                    password: password.into(),
+                },
+                &token.access_token,
+            ))
+            .await
+            .expect_err("account was deleted with a wrong password");
+
+        assert!(error.code() == Code::InvalidArgument);
+        assert!(error.get_error_details().has_bad_request_violations());
+    }
+
+    let user = sqlx::query!("SELECT user_id FROM account WHERE username = 'atheer2104'")
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("failed to query account");
+
+    assert!(user.is_some());
+}
+
+#[tokio::test]
+async fn deleted_account_can_no_longer_be_used() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    let claims = app.auth_token_claims(&token.access_token);
+
+    app.delete_account(authenticated_request(
+        DeleteAccountRequest {
+            password: "strong password".into(),
+        },
+        &token.access_token,
+    ))
+    .await
+    .expect("failed to delete account");
+
+    let user = sqlx::query!(
+        "SELECT user_id FROM account WHERE user_id = $1",
+        claims.user_id
+    )
+    .fetch_optional(&app.db_pool)
+    .await
+    .expect("failed to query account");
+
+    assert!(user.is_none());
+
+    let sessions = sqlx::query!(
+        "SELECT session_id FROM auth_tokens WHERE user_id = $1",
+        claims.user_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("failed to query sessions");
+
+    assert!(sessions.is_empty());
+
+    // the auth token that was handed out before the deletion is no longer active
+    let introspection = app
+        .introspect(Request::new(IntrospectRequest {
+            token: token.access_token.clone(),
+        }))
+        .await
+        .expect("failed to introspect auth token")
+        .into_inner();
+
+    assert!(!introspection.active);
+
+    let error = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: token.refresh_token,
+        }))
+        .await
+        .expect_err("refresh token of a deleted account was accepted");
+
+    assert!(error.code() == Code::Unauthenticated);
+
+    try_login_user(&app, "atheer2104", "strong password")
+        .await
+        .expect_err("deleted account could still login");
+
+    // the username and email are free to be used again, which also clears the failed login above
+    register_user(&app).await;
+    login_user(&app).await;
+}
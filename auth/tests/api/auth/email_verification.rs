@@ -0,0 +1,143 @@
+use auth::{
+    configuration::UnverifiedLoginPolicy,
+    proto::auth::{ResendVerificationEmailRequest, Token, VerifyEmailRequest},
+};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{
+    login_user, register_user, spawn_app_with_configuration, try_login_user, App,
+};
+
+fn verification_token_from_last_mail(app: &App) -> String {
+    let mails = app.sent_mails();
+    let mail = mails.last().expect("no mail was sent");
+
+    assert!(mail.contains("To: atheer@gmail.com"));
+
+    mail.lines()
+        .find_map(|line| line.strip_prefix("Your email verification code is: "))
+        .expect("mail doesn't contain a verification code")
+        .trim()
+        .to_string()
+}
+
+fn email_verified_claim(app: &App, token: &Token) -> bool {
+    app.auth_token_claims(&token.access_token).email_verified
+}
+
+#[tokio::test]
+async fn verifying_email_marks_auth_tokens_as_verified() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let register_token = register_user(&app).await;
+    assert!(!email_verified_claim(&app, &register_token));
+
+    let verification_token = verification_token_from_last_mail(&app);
+
+    let response = app
+        .verify_email(Request::new(VerifyEmailRequest {
+            token: verification_token.clone(),
+        }))
+        .await;
+
+    assert!(response.is_ok());
+
+    let login_token = login_user(&app).await;
+    assert!(email_verified_claim(&app, &login_token));
+
+    // the token can only be used once
+    let response = app
+        .verify_email(Request::new(VerifyEmailRequest {
+            token: verification_token,
+        }))
+        .await;
+
+    let error = response.expect_err("verification token was used twice");
+    assert!(error.code() == Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn verifying_email_with_invalid_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+
+    for token in ["", "ABCDEFGHJK"] {
+        let response = app
+            .verify_email(Request::new(VerifyEmailRequest {
+                token: token.into(),
+            }))
+            .await;
+
+        let error = response.expect_err("The response was not a error");
+        assert!(error.code() == Code::InvalidArgument);
+        assert!(error.get_error_details().has_bad_request_violations());
+    }
+}
+
+#[tokio::test]
+async fn unverified_login_is_rejected_when_configured() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration.email_verification.unverified_login = UnverifiedLoginPolicy::Reject;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+
+    let error = try_login_user(&app, "atheer2104", "strong password")
+        .await
+        .expect_err("unverified user was able to login");
+    assert!(error.code() == Code::FailedPrecondition);
+
+    // a lost verification mail can be sent again, which only the latest token works for
+    let first_verification_token = verification_token_from_last_mail(&app);
+
+    let response = app
+        .resend_verification_email(Request::new(ResendVerificationEmailRequest {
+            email: "atheer@gmail.com".into(),
+        }))
+        .await;
+
+    assert!(response.is_ok());
+
+    let second_verification_token = verification_token_from_last_mail(&app);
+    assert_ne!(first_verification_token, second_verification_token);
+
+    let response = app
+        .verify_email(Request::new(VerifyEmailRequest {
+            token: second_verification_token,
+        }))
+        .await;
+
+    assert!(response.is_ok());
+
+    let login_token = login_user(&app).await;
+    assert!(email_verified_claim(&app, &login_token));
+}
+
+#[tokio::test]
+async fn resending_verification_email_doesnt_reveal_emails() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+    let sent_mail_count = app.sent_mails().len();
+
+    let response = app
+        .resend_verification_email(Request::new(ResendVerificationEmailRequest {
+            email: "someone.else@gmail.com".into(),
+        }))
+        .await;
+
+    assert!(response.is_ok());
+    assert_eq!(app.sent_mails().len(), sent_mail_count);
+}
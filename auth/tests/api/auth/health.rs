@@ -0,0 +1,64 @@
+use rand::{thread_rng, Rng};
+use tonic_health::pb::health_check_response::ServingStatus;
+
+use super::{sleep, spawn_app, Code};
+use crate::helpers::spawn_app_with_configuration;
+
+#[tokio::test]
+async fn auth_service_is_serving_when_postgres_and_redis_are_reachable() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    for service in ["", "authentication.Auth"] {
+        let status = app
+            .health_status(service)
+            .await
+            .expect("failed to check health");
+        assert_eq!(
+            status,
+            ServingStatus::Serving,
+            "{:?} isn't serving",
+            service
+        );
+    }
+
+    let error = app
+        .health_status("authentication.Unknown")
+        .await
+        .expect_err("there is no such service");
+    assert_eq!(error.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn auth_service_is_not_serving_when_postgres_is_unreachable() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration.health_check.interval_seconds = 1;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    assert_eq!(
+        app.health_status("authentication.Auth").await.unwrap(),
+        ServingStatus::Serving
+    );
+
+    // the server shares the pool, so it can't reach postgres anymore either
+    app.db_pool.close().await;
+
+    let mut status = ServingStatus::Serving;
+    for _ in 0..30 {
+        sleep(100).await;
+
+        status = app.health_status("authentication.Auth").await.unwrap();
+        if status == ServingStatus::NotServing {
+            break;
+        }
+    }
+    assert_eq!(status, ServingStatus::NotServing);
+    assert_eq!(
+        app.health_status("").await.unwrap(),
+        ServingStatus::NotServing
+    );
+}
@@ -0,0 +1,92 @@
+use auth::proto::auth::{IntrospectRequest, IntrospectResponse, LogoutRequest};
+use rand::{thread_rng, Rng};
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, login_user, register_user, App};
+
+async fn introspect(app: &App, token: &str) -> IntrospectResponse {
+    app.introspect(Request::new(IntrospectRequest {
+        token: token.into(),
+    }))
+    .await
+    .expect("failed to introspect auth token")
+    .into_inner()
+}
+
+#[tokio::test]
+async fn introspecting_a_valid_auth_token_returns_its_session() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    let claims = app.auth_token_claims(&token.access_token);
+
+    let introspection = introspect(&app, &token.access_token).await;
+
+    assert!(introspection.active);
+    assert_eq!(introspection.user_id, claims.user_id);
+    assert_eq!(introspection.username, "atheer2104");
+    assert_eq!(introspection.session_id, claims.session_id.to_string());
+    assert_eq!(introspection.issued_at, claims.iat);
+    assert_eq!(introspection.expires_at, claims.exp);
+}
+
+#[tokio::test]
+async fn introspecting_an_invalid_auth_token_returns_inactive() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let introspection = introspect(&app, "not.a.token").await;
+
+    assert!(!introspection.active);
+    assert!(introspection.username.is_empty());
+    assert!(introspection.session_id.is_empty());
+}
+
+#[tokio::test]
+async fn introspecting_without_a_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let error = app
+        .introspect(Request::new(IntrospectRequest::default()))
+        .await
+        .expect_err("introspection without a token was accepted");
+
+    assert!(error.code() == Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn introspection_is_cached_but_revocations_are_not() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    assert!(introspect(&app, &token.access_token).await.active);
+
+    // the session is gone from postgres but the cached introspection still has it
+    sqlx::query!(r#"DELETE FROM auth_tokens"#)
+        .execute(&app.db_pool)
+        .await
+        .expect("failed to delete session");
+
+    assert!(introspect(&app, &token.access_token).await.active);
+
+    // a revocation takes effect right away
+    let other_token = login_user(&app).await;
+    assert!(introspect(&app, &other_token.access_token).await.active);
+
+    app.logout(authenticated_request(
+        LogoutRequest {},
+        &other_token.access_token,
+    ))
+    .await
+    .expect("failed to logout");
+
+    assert!(!introspect(&app, &other_token.access_token).await.active);
+}
@@ -1,8 +1,12 @@
-use auth::proto::auth::{LoginRequest, RegisterRequest};
+use argon2::{
+    password_hash::SaltString, Algorithm, Argon2, Params, PasswordHash, PasswordHasher, Version,
+};
+use auth::configuration::get_configuration;
 use rand::{thread_rng, Rng};
 use tonic_types::StatusExt;
 
-use super::{sleep, spawn_app, Code, Request};
+use super::{sleep, spawn_app, Code};
+use crate::helpers::{login_user, register_user, register_user_as, try_login_user};
 
 #[tokio::test]
 async fn missing_field_in_login_request() {
@@ -13,12 +17,7 @@ async fn missing_field_in_login_request() {
     let test_cases = vec![("", "password"), ("user", "")];
 
     for (username, password) in test_cases {
-        let response = app
-            .login(Request::new(LoginRequest {
-                username: username.into(),
-                password: password.into(),
-            }))
-            .await;
+        let response = try_login_user(&app, username, password).await;
 
         let error = response.expect_err("The response was not a error");
 
@@ -38,12 +37,7 @@ async fn login_as_non_existing_user() {
     let mut rng = thread_rng();
     sleep(rng.gen_range(100..200)).await;
 
-    let response = app
-        .login(Request::new(LoginRequest {
-            username: "ABCDEFG".into(),
-            password: "123456789".into(),
-        }))
-        .await;
+    let response = try_login_user(&app, "ABCDEFG", "123456789").await;
 
     let error = response.expect_err("The response was not a error");
 
@@ -53,31 +47,35 @@ async fn login_as_non_existing_user() {
 }
 
 #[tokio::test]
-async fn login_as_a_registered_user() {
+async fn login_with_wrong_password() {
     let app = spawn_app().await;
     let mut rng = thread_rng();
     sleep(rng.gen_range(100..200)).await;
 
-    let register_response = app
-        .register(Request::new(RegisterRequest {
-            firstname: "atheer".into(),
-            lastname: "ABC".into(),
-            username: "atheer2104".into(),
-            email: "atheer@gmail.com".into(),
-            password: "strong password".into(),
-        }))
-        .await;
+    register_user(&app).await;
 
-    println!("REGISTER RESPONSE: {:?}", register_response);
+    let error = try_login_user(&app, "atheer2104", "wrong password")
+        .await
+        .expect_err("login with wrong password succeeded");
 
-    assert!(register_response.is_ok());
+    // the same answer as for a user that doesn't exist, so it doesn't give away which accounts exist
+    let non_existing_user_error = try_login_user(&app, "ABCDEFG", "wrong password")
+        .await
+        .expect_err("login as non existing user succeeded");
 
-    let login_request = app
-        .login(Request::new(LoginRequest {
-            username: "atheer2104".into(),
-            password: "strong password".into(),
-        }))
-        .await;
+    assert!(error.code() == Code::Unauthenticated);
+    assert_eq!(error.message(), non_existing_user_error.message());
+}
+
+#[tokio::test]
+async fn login_as_a_registered_user() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+
+    let login_request = try_login_user(&app, "atheer2104", "strong password").await;
 
     println!("LOGIN REQUEST: {:?}", login_request);
 
@@ -85,37 +83,57 @@ async fn login_as_a_registered_user() {
 }
 
 #[tokio::test]
-async fn login_as_a_registered_user_check_token() {
+async fn login_with_email_as_a_registered_user() {
     let app = spawn_app().await;
     let mut rng = thread_rng();
     sleep(rng.gen_range(100..200)).await;
 
-    let username = String::from("atheer");
+    register_user(&app).await;
 
-    let register_response = app
-        .register(Request::new(RegisterRequest {
-            firstname: "atheer".into(),
-            lastname: "ABC".into(),
-            username: username.clone(),
-            email: "atheer@gmail.com".into(),
-            password: "strong password".into(),
-        }))
-        .await;
+    let error = try_login_user(&app, "atheer@gmail.com", "wrong password")
+        .await
+        .expect_err("login with wrong password succeeded");
 
-    println!("REGISTER RESPONSE: {:?}", register_response);
+    assert!(error.code() == Code::Unauthenticated);
 
-    assert!(register_response.is_ok());
+    // the successful login also resets the failed login from above
+    let token = try_login_user(&app, "atheer@gmail.com", "strong password")
+        .await
+        .expect("failed to login with email");
 
-    let login_request = app
-        .login(Request::new(LoginRequest {
-            username: username.clone(),
-            password: "strong password".into(),
-        }))
-        .await;
+    // the auth token carries the username even when logging in with the email
+    assert_eq!(
+        app.auth_token_claims(&token.access_token).username,
+        "atheer2104"
+    );
+}
 
-    let login_request_token = login_request
+#[tokio::test]
+async fn login_with_a_username_that_looks_like_an_email() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user_as(&app, "atheer@example.com", "atheer@gmail.com").await;
+
+    let login_response = try_login_user(&app, "atheer@example.com", "strong password").await;
+
+    assert!(login_response.is_ok());
+}
+
+#[tokio::test]
+async fn login_as_a_registered_user_check_token() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let username = String::from("atheer");
+
+    register_user_as(&app, &username, "atheer@gmail.com").await;
+
+    let login_request_token = try_login_user(&app, &username, "strong password")
+        .await
         .expect("there was en error in the login request")
-        .into_inner()
         .access_token;
 
     let user_id = sqlx::query!(
@@ -128,7 +146,7 @@ async fn login_as_a_registered_user_check_token() {
     .user_id;
 
     let token_from_db = sqlx::query!(
-        r#"SELECT auth_token FROM auth_tokens WHERE user_id = $1 "#,
+        r#"SELECT auth_token FROM auth_tokens WHERE user_id = $1 ORDER BY created_at DESC LIMIT 1"#,
         user_id
     )
     .fetch_one(&app.db_pool)
@@ -136,5 +154,67 @@ async fn login_as_a_registered_user_check_token() {
     .expect("failed to fetch from db")
     .auth_token;
 
-    assert_eq!(token_from_db, login_request_token)
+    assert_eq!(token_from_db, login_request_token);
+
+    // services take the identity of the user from the auth token
+    let claims = app.auth_token_claims(&login_request_token);
+    assert_eq!(claims.user_id, user_id);
+    assert_eq!(claims.username, username);
+}
+
+#[tokio::test]
+async fn login_rehashes_password_with_outdated_params() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+
+    // a hash like the ones made before the argon params were raised
+    let outdated_password_hash = Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(8192, 1, 1, None).expect("failed to create argon params"),
+    )
+    .hash_password(
+        "strong password".as_bytes(),
+        &SaltString::generate(&mut thread_rng()),
+    )
+    .expect("failed to hash password")
+    .to_string();
+
+    sqlx::query!(
+        r#"UPDATE account SET password_hash = $1 WHERE username = $2"#,
+        outdated_password_hash,
+        "atheer2104"
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("failed to update password hash");
+
+    login_user(&app).await;
+
+    let stored_password_hash = sqlx::query!(
+        r#"SELECT password_hash FROM account WHERE username = $1"#,
+        "atheer2104"
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("failed to fetch from db")
+    .password_hash;
+
+    let stored_password_hash =
+        PasswordHash::new(&stored_password_hash).expect("failed to parse password hash");
+    let stored_params = Params::try_from(&stored_password_hash).expect("failed to read params");
+
+    let argon_configuration = get_configuration()
+        .expect("Failed to read configuration.")
+        .argon;
+
+    assert_eq!(stored_params.m_cost(), argon_configuration.memory);
+    assert_eq!(stored_params.t_cost(), argon_configuration.iterations);
+    assert_eq!(stored_params.p_cost(), argon_configuration.parallelism);
+
+    // the new hash still belongs to the same password
+    login_user(&app).await;
 }
@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use rand::{thread_rng, Rng};
+use redis::AsyncCommands;
+use tonic_types::StatusExt;
+use uuid::Uuid;
+
+use super::{sleep, Code};
+use crate::helpers::{register_user_as, spawn_app_with_configuration, try_login_user, App};
+
+// usernames are unique per test run since the failed logins are kept in redis between runs
+async fn register_unique_user(app: &App) -> String {
+    let username = format!("atheer-{}", Uuid::new_v4());
+
+    register_user_as(app, &username, &format!("{}@gmail.com", username)).await;
+
+    username
+}
+
+// every test logs in from the same address and its failed logins aren't reset by logging in, so the tests that lock
+// out the address start and end without any
+async fn forget_failed_logins_of_address(app: &App) {
+    let _: () = app
+        .redis_con
+        .lock()
+        .await
+        .del(&["login_failures:ip:::1", "login_lockout:ip:::1"])
+        .await
+        .expect("failed to delete failed logins from redis");
+}
+
+#[tokio::test]
+async fn repeated_failed_logins_lock_out_the_username() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration
+            .login_throttle
+            .max_failed_attempts_per_username = 3;
+        configuration.login_throttle.base_lockout_seconds = 60;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let username = register_unique_user(&app).await;
+
+    for _ in 0..3 {
+        let error = try_login_user(&app, &username, "wrong password")
+            .await
+            .expect_err("login with wrong password succeeded");
+        assert!(error.code() != Code::ResourceExhausted);
+    }
+
+    // even the right password is refused during the lockout
+    let error = try_login_user(&app, &username, "strong password")
+        .await
+        .expect_err("login during lockout succeeded");
+    assert!(error.code() == Code::ResourceExhausted);
+
+    let retry_delay = error
+        .get_details_retry_info()
+        .expect("response doesn't contain retry info")
+        .retry_delay
+        .expect("retry info doesn't contain a delay");
+    assert!(retry_delay > Duration::ZERO);
+    assert!(retry_delay <= Duration::from_secs(60));
+
+    // other users aren't affected
+    let other_username = register_unique_user(&app).await;
+    assert!(try_login_user(&app, &other_username, "strong password")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn failed_logins_with_the_username_and_the_email_count_for_the_same_account() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration
+            .login_throttle
+            .max_failed_attempts_per_username = 3;
+        configuration.login_throttle.base_lockout_seconds = 60;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let username = register_unique_user(&app).await;
+    let email = format!("{}@gmail.com", username);
+
+    for identifier in [&username, &email, &username] {
+        try_login_user(&app, identifier, "wrong password")
+            .await
+            .expect_err("login with wrong password succeeded");
+    }
+
+    for identifier in [&username, &email] {
+        let error = try_login_user(&app, identifier, "strong password")
+            .await
+            .expect_err("login during lockout succeeded");
+        assert!(error.code() == Code::ResourceExhausted);
+    }
+}
+
+#[tokio::test]
+async fn successful_login_with_the_email_resets_failed_logins_of_the_username() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration
+            .login_throttle
+            .max_failed_attempts_per_username = 3;
+        configuration.login_throttle.base_lockout_seconds = 60;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let username = register_unique_user(&app).await;
+    let email = format!("{}@gmail.com", username);
+
+    for _ in 0..2 {
+        try_login_user(&app, &username, "wrong password")
+            .await
+            .expect_err("login with wrong password succeeded");
+    }
+
+    assert!(try_login_user(&app, &email, "strong password")
+        .await
+        .is_ok());
+
+    for _ in 0..2 {
+        try_login_user(&app, &username, "wrong password")
+            .await
+            .expect_err("login with wrong password succeeded");
+    }
+
+    assert!(try_login_user(&app, &username, "strong password")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn successful_login_resets_failed_logins() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration
+            .login_throttle
+            .max_failed_attempts_per_username = 3;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let username = register_unique_user(&app).await;
+
+    for _ in 0..2 {
+        try_login_user(&app, &username, "wrong password")
+            .await
+            .expect_err("login with wrong password succeeded");
+    }
+
+    assert!(try_login_user(&app, &username, "strong password")
+        .await
+        .is_ok());
+
+    for _ in 0..2 {
+        try_login_user(&app, &username, "wrong password")
+            .await
+            .expect_err("login with wrong password succeeded");
+    }
+
+    assert!(try_login_user(&app, &username, "strong password")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn failed_logins_from_one_address_lock_it_out() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration.login_throttle.max_failed_attempts_per_ip = 3;
+        configuration.login_throttle.base_lockout_seconds = 1;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    forget_failed_logins_of_address(&app).await;
+
+    // different usernames every time, so only the address of the client is locked out
+    for _ in 0..3 {
+        let error = try_login_user(&app, &Uuid::new_v4().to_string(), "some password")
+            .await
+            .expect_err("login as non existing user succeeded");
+        assert!(error.code() == Code::Unauthenticated);
+    }
+
+    let error = try_login_user(&app, &Uuid::new_v4().to_string(), "some password")
+        .await
+        .expect_err("login during lockout succeeded");
+    assert!(error.code() == Code::ResourceExhausted);
+
+    // the lockout is short but the failed logins of this address shouldn't be seen by other tests
+    forget_failed_logins_of_address(&app).await;
+}
+
+#[tokio::test]
+async fn logging_in_doesnt_reset_failed_logins_of_the_address() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration.login_throttle.max_failed_attempts_per_ip = 3;
+        configuration.login_throttle.base_lockout_seconds = 1;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    forget_failed_logins_of_address(&app).await;
+
+    let victim = register_unique_user(&app).await;
+    let own_username = register_unique_user(&app).await;
+
+    for _ in 0..2 {
+        try_login_user(&app, &victim, "wrong password")
+            .await
+            .expect_err("login with wrong password succeeded");
+    }
+
+    // logging into an account of your own doesn't wipe the guesses that were made from the same address
+    assert!(try_login_user(&app, &own_username, "strong password")
+        .await
+        .is_ok());
+
+    try_login_user(&app, &victim, "wrong password")
+        .await
+        .expect_err("login with wrong password succeeded");
+
+    let error = try_login_user(&app, &victim, "wrong password")
+        .await
+        .expect_err("login during lockout succeeded");
+    assert!(error.code() == Code::ResourceExhausted);
+
+    forget_failed_logins_of_address(&app).await;
+}
@@ -0,0 +1,75 @@
+use auth::proto::auth::{LogoutRequest, RefreshRequest};
+use rand::{thread_rng, Rng};
+use redis::AsyncCommands;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, register_user};
+
+#[tokio::test]
+async fn logout_without_auth_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let response = app.logout(Request::new(LogoutRequest {})).await;
+
+    let error = response.expect_err("The response was not a error");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn logout_revokes_the_auth_token() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let session_id = sqlx::query!(r#"SELECT session_id FROM auth_tokens"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("failed to fetch from db")
+        .session_id;
+
+    let response = app
+        .logout(authenticated_request(LogoutRequest {}, &token.access_token))
+        .await;
+
+    assert!(response.is_ok());
+
+    let stored_tokens = sqlx::query!(r#"SELECT auth_token FROM auth_tokens"#)
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("failed to fetch from db");
+
+    assert!(stored_tokens.is_empty());
+
+    let cached_token: Option<String> = app
+        .redis_con
+        .lock()
+        .await
+        .get(format!("auth_token:{}", session_id))
+        .await
+        .expect("failed to read from redis");
+
+    assert!(cached_token.is_none());
+
+    // the same auth token can't be used again
+    let response = app
+        .logout(authenticated_request(LogoutRequest {}, &token.access_token))
+        .await;
+
+    let error = response.expect_err("revoked auth token was accepted");
+    assert!(error.code() == Code::Unauthenticated);
+
+    // and neither can the refresh token
+    let response = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: token.refresh_token,
+        }))
+        .await;
+
+    let error = response.expect_err("refresh token was accepted after logout");
+    assert!(error.code() == Code::Unauthenticated);
+}
@@ -1,5 +1,26 @@
+mod api_keys;
+mod auth_events;
+mod availability;
+mod change_password;
+mod data_export;
+mod delete_account;
+mod email_verification;
+mod health;
+mod introspect;
 mod login;
+mod login_throttle;
+mod logout;
+mod normalization;
+mod password_policy;
+mod password_reset;
+mod profile;
+mod refresh;
 mod register;
+mod roles;
+mod sessions;
+mod shutdown;
+mod signing_keys;
+mod two_factor;
 
 use std::time::Duration;
 use tonic::{Code, Request};
@@ -0,0 +1,281 @@
+use auth::proto::auth::{CheckAvailabilityRequest, GetProfileRequest};
+use auth::server::{normalize_email, normalize_username};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{
+    authenticated_request, register_user, register_user_as, try_login_user, try_register_user,
+};
+
+#[tokio::test]
+async fn usernames_and_emails_are_stored_normalized() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    // the username is written with fullwidth letters
+    let token = try_register_user(&app, "ＡＴＨＥＥＲ2104", "Atheer.ABC@GMAIL.com")
+        .await
+        .expect("failed to register user");
+
+    let profile = app
+        .get_profile(authenticated_request(
+            GetProfileRequest {},
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to get profile")
+        .into_inner();
+    assert_eq!(profile.username, "atheer2104");
+    assert_eq!(profile.email, "Atheer.ABC@gmail.com");
+}
+
+#[tokio::test]
+async fn usernames_and_emails_that_only_differ_by_case_are_taken() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    try_register_user(&app, "atheer2104", "atheer@gmail.com")
+        .await
+        .expect("failed to register user");
+
+    let test_cases = [
+        ("Atheer2104", "someone@gmail.com", "username"),
+        ("someone", "atheer@GMAIL.COM", "email"),
+    ];
+
+    for (username, email, field) in test_cases {
+        let error = try_register_user(&app, username, email)
+            .await
+            .expect_err("the account already exists");
+        assert_eq!(error.code(), Code::AlreadyExists);
+
+        let bad_request = error
+            .get_details_bad_request()
+            .expect("response doesn't contain bad request details");
+        assert_eq!(bad_request.field_violations[0].field, field);
+    }
+
+    let availability = app
+        .check_availability(Request::new(CheckAvailabilityRequest {
+            username: "ATHEER2104".into(),
+            email: "atheer@Gmail.com".into(),
+        }))
+        .await
+        .expect("failed to check availability")
+        .into_inner();
+    assert_eq!(availability.username_available, Some(false));
+    assert_eq!(availability.email_available, Some(false));
+}
+
+#[tokio::test]
+async fn login_identifier_is_normalized() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+
+    for identifier in ["Atheer2104", "ａｔｈｅｅｒ２１０４", "atheer@GMAIL.com"] {
+        try_login_user(&app, identifier, "strong password")
+            .await
+            .unwrap_or_else(|e| panic!("failed to log in as {}: {:?}", identifier, e));
+    }
+}
+
+#[tokio::test]
+async fn register_with_mixed_script_username_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    // the "а" is cyrillic, the rest is latin
+    let error = try_register_user(&app, "\u{430}theer2104", "atheer@gmail.com")
+        .await
+        .expect_err("mixed script usernames shouldn't be accepted");
+    assert_eq!(error.code(), Code::InvalidArgument);
+
+    let bad_request = error
+        .get_details_bad_request()
+        .expect("response doesn't contain bad request details");
+    assert_eq!(bad_request.field_violations[0].field, "username");
+
+    assert!(try_register_user(&app, "атеер", "atheer@gmail.com")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn usernames_and_emails_are_normalized_the_same_way_by_the_database() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    // every character on its own, postgres can't store the nul character and the @ would split the email. the
+    // spaces keep combining marks apart, which postgres before 16 can order differently
+    let characters: String = (1..=char::MAX as u32)
+        .filter_map(char::from_u32)
+        .filter(|c| *c != '@')
+        .flat_map(|c| [c, ' '])
+        .collect();
+    let email = format!("{}@{}", characters, characters);
+
+    let normalized = sqlx::query!(
+        r#"SELECT normalize_username($1) AS "username!", normalize_email($2) AS "email!""#,
+        characters,
+        email
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("failed to normalize in the database");
+
+    assert!(normalized.username == normalize_username(&characters));
+    assert!(normalized.email == normalize_email(&email));
+}
+
+#[tokio::test]
+async fn accounts_that_only_differ_once_normalized_are_refused_by_the_database() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+
+    // like a registration that got around the parsers
+    for (username, email, index) in [
+        (
+            "Atheer2104",
+            "someone@gmail.com",
+            "account_username_normalized_key",
+        ),
+        (
+            "someone",
+            "atheer@GMAIL.com",
+            "account_email_normalized_key",
+        ),
+    ] {
+        let error = sqlx::query!(
+            r#"INSERT INTO account (firstname, lastname, username, email, password_hash)
+            VALUES ('atheer', 'ABC', $1, $2, 'password hash')"#,
+            username,
+            email
+        )
+        .execute(&app.db_pool)
+        .await
+        .expect_err("account that collides once normalized was stored");
+
+        let database_error = error.as_database_error().expect("not a database error");
+        assert!(database_error.is_unique_violation());
+        assert_eq!(database_error.constraint(), Some(index));
+    }
+}
+
+#[tokio::test]
+async fn accounts_made_before_normalization_can_log_in_once_they_are_normalized() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user_as(&app, "strasse", "atheer@gmail.com").await;
+    register_user_as(&app, "someone", "someone@gmail.com").await;
+
+    // stored the way they were written before usernames and emails were normalized. full case folding turns "ß"
+    // into "ss" and cherokee letters into their uppercase form, where lowercasing would keep "ß" and turn cherokee
+    // letters into their lowercase form
+    let legacy_accounts = [
+        ("strasse", "Straße", "atheer@GMAIL.com"),
+        ("someone", "\u{ab7a}\u{ab72}\u{ab85}", "someone@gmail.com"),
+    ];
+    for (username, legacy_username, legacy_email) in legacy_accounts {
+        sqlx::query!(
+            r#"UPDATE account SET username = $1, email = $2 WHERE username = $3"#,
+            legacy_username,
+            legacy_email,
+            username
+        )
+        .execute(&app.db_pool)
+        .await
+        .expect("failed to update account");
+    }
+
+    let login = |identifier| try_login_user(&app, identifier, "strong password");
+
+    assert!(login("Straße").await.is_err());
+
+    // what the migration does to the accounts that are already there
+    sqlx::query!(r#"CALL normalize_accounts()"#)
+        .execute(&app.db_pool)
+        .await
+        .expect("failed to normalize accounts");
+
+    let accounts: Vec<(String, String)> =
+        sqlx::query!(r#"SELECT username, email FROM account ORDER BY user_id"#)
+            .fetch_all(&app.db_pool)
+            .await
+            .expect("failed to fetch from db")
+            .into_iter()
+            .map(|row| (row.username, row.email))
+            .collect();
+    assert_eq!(
+        accounts,
+        [
+            ("strasse".to_string(), "atheer@gmail.com".to_string()),
+            (
+                "\u{13aa}\u{13a2}\u{13b5}".to_string(),
+                "someone@gmail.com".to_string()
+            ),
+        ]
+    );
+
+    for identifier in [
+        "Straße",
+        "STRASSE",
+        "atheer@gmail.com",
+        "\u{13aa}\u{13a2}\u{13b5}",
+        "\u{ab7a}\u{ab72}\u{ab85}",
+    ] {
+        login(identifier)
+            .await
+            .unwrap_or_else(|e| panic!("failed to log in as {}: {:?}", identifier, e));
+    }
+}
+
+#[tokio::test]
+async fn accounts_that_collide_once_normalized_are_listed_and_left_alone() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+    register_user_as(&app, "someone", "someone@gmail.com").await;
+
+    // a database from before the migration, where nothing kept these accounts apart
+    sqlx::query!(r#"DROP INDEX account_username_normalized_key, account_email_normalized_key"#)
+        .execute(&app.db_pool)
+        .await
+        .expect("failed to drop indexes");
+    sqlx::query!(r#"UPDATE account SET username = 'Atheer2104' WHERE username = 'someone'"#)
+        .execute(&app.db_pool)
+        .await
+        .expect("failed to update account");
+
+    let error = sqlx::query!(r#"CALL normalize_accounts()"#)
+        .execute(&app.db_pool)
+        .await
+        .expect_err("the accounts should collide");
+    let database_error = error.as_database_error().expect("not a database error");
+    assert!(database_error
+        .message()
+        .contains("username 'atheer2104' is used by the accounts with user_id 1, 2"));
+
+    let usernames: Vec<String> = sqlx::query!(r#"SELECT username FROM account ORDER BY user_id"#)
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("failed to fetch from db")
+        .into_iter()
+        .map(|row| row.username)
+        .collect();
+    assert_eq!(usernames, ["atheer2104", "Atheer2104"]);
+}
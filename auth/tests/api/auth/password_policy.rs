@@ -0,0 +1,117 @@
+use auth::proto::auth::{ChangePasswordRequest, LoginRequest, RegisterRequest, Token};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, App};
+
+async fn register_user(app: &App, password: &str) -> Result<Token, tonic::Status> {
+    app.register(Request::new(RegisterRequest {
+        firstname: "atheer".into(),
+        lastname: "ABC".into(),
+        username: "atheer2104".into(),
+        email: "atheer.abc@gmail.com".into(),
+        password: password.into(),
+        ..Default::default()
+    }))
+    .await
+    .map(|response| response.into_inner())
+}
+
+#[tokio::test]
+async fn register_with_breached_password_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    // the denylist ignores case, so changing it doesn't help
+    for password in ["password123", "Password123"] {
+        let error = register_user(&app, password)
+            .await
+            .expect_err("breached passwords shouldn't be accepted");
+        assert_eq!(error.code(), Code::InvalidArgument);
+
+        let bad_request = error
+            .get_details_bad_request()
+            .expect("response doesn't contain bad request details");
+        assert_eq!(bad_request.field_violations[0].field, "password");
+        assert!(bad_request.field_violations[0]
+            .description
+            .contains("data breach"));
+    }
+}
+
+#[tokio::test]
+async fn register_with_weak_password_is_rejected_with_score_and_hints() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let test_cases = [
+        ("aaaaaaaaaaaa", "repeated characters"),
+        ("qwertyuiop12", "keyboard patterns"),
+        ("atheer2104!!", "username or email"),
+        ("Atheer.ABC#7", "username or email"),
+    ];
+
+    for (password, hint) in test_cases {
+        let error = register_user(&app, password)
+            .await
+            .expect_err("weak passwords shouldn't be accepted");
+        assert_eq!(error.code(), Code::InvalidArgument);
+
+        let bad_request = error
+            .get_details_bad_request()
+            .expect("response doesn't contain bad request details");
+        let violation = &bad_request.field_violations[0];
+        assert_eq!(violation.field, "password");
+        assert!(
+            violation.description.contains("of 4") && violation.description.contains(hint),
+            "{} gave {}",
+            password,
+            violation.description
+        );
+    }
+
+    assert!(register_user(&app, "strong password").await.is_ok());
+}
+
+#[tokio::test]
+async fn change_password_to_weak_password_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app, "strong password")
+        .await
+        .expect("failed to register user");
+
+    for new_password in ["letmein123", "atheer2104abc"] {
+        let error = app
+            .change_password(authenticated_request(
+                ChangePasswordRequest {
+                    old_password: "strong password".into(),
+                    new_password: new_password.into(),
+                },
+                &token.access_token,
+            ))
+            .await
+            .expect_err("weak passwords shouldn't be accepted");
+        assert_eq!(error.code(), Code::InvalidArgument);
+
+        let bad_request = error
+            .get_details_bad_request()
+            .expect("response doesn't contain bad request details");
+        assert_eq!(bad_request.field_violations[0].field, "new_password");
+    }
+
+    // the password is left untouched
+    assert!(app
+        .login(Request::new(LoginRequest {
+            identifier: "atheer2104".into(),
+            password: "strong password".into(),
+            ..Default::default()
+        }))
+        .await
+        .is_ok());
+}
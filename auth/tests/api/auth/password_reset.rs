@@ -0,0 +1,149 @@
+use auth::proto::auth::{ListSessionsRequest, RequestPasswordResetRequest, ResetPasswordRequest};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, register_user, try_login_user, App};
+
+async fn request_reset_code(app: &App) -> String {
+    let sent_mail_count = app.sent_mails().len();
+
+    app.request_password_reset(Request::new(RequestPasswordResetRequest {
+        email: "atheer@gmail.com".into(),
+    }))
+    .await
+    .expect("failed to request password reset");
+
+    let mails = app.wait_for_sent_mails(sent_mail_count + 1).await;
+    let mail = mails.last().expect("no mail was sent");
+
+    assert!(mail.contains("To: atheer@gmail.com"));
+
+    mail.lines()
+        .find_map(|line| line.strip_prefix("Your password reset code is: "))
+        .expect("mail doesn't contain a reset code")
+        .trim()
+        .to_string()
+}
+
+#[tokio::test]
+async fn password_reset_for_unknown_email_looks_the_same() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+    let sent_mail_count = app.sent_mails().len();
+
+    let response = app
+        .request_password_reset(Request::new(RequestPasswordResetRequest {
+            email: "someone.else@gmail.com".into(),
+        }))
+        .await;
+
+    assert!(response.is_ok());
+    // a mail would only be sent in the background
+    sleep(500).await;
+    assert_eq!(app.sent_mails().len(), sent_mail_count);
+}
+
+#[tokio::test]
+async fn password_reset_with_invalid_email_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let response = app
+        .request_password_reset(Request::new(RequestPasswordResetRequest {
+            email: "not an email".into(),
+        }))
+        .await;
+
+    let error = response.expect_err("The response was not a error");
+    assert!(error.code() == Code::InvalidArgument);
+    assert!(error.get_error_details().has_bad_request_violations());
+}
+
+#[tokio::test]
+async fn reset_password_with_code_from_mail() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    let code = request_reset_code(&app).await;
+
+    let response = app
+        .reset_password(Request::new(ResetPasswordRequest {
+            code: code.to_lowercase(),
+            new_password: "even stronger password".into(),
+        }))
+        .await;
+
+    assert!(response.is_ok());
+
+    let response = try_login_user(&app, "atheer2104", "even stronger password").await;
+
+    assert!(response.is_ok());
+
+    // every session from before the reset is signed out
+    let response = app
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &token.access_token,
+        ))
+        .await;
+
+    let error = response.expect_err("auth token from before the reset was accepted");
+    assert!(error.code() == Code::Unauthenticated);
+
+    // the code can only be used once
+    let response = app
+        .reset_password(Request::new(ResetPasswordRequest {
+            code,
+            new_password: "another strong password".into(),
+        }))
+        .await;
+
+    let error = response.expect_err("reset code was used twice");
+    assert!(error.code() == Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn only_the_latest_reset_code_can_be_used() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user(&app).await;
+    let first_code = request_reset_code(&app).await;
+    let second_code = request_reset_code(&app).await;
+
+    let test_cases = [first_code.as_str(), "ABCDEFGHJK"];
+
+    for code in test_cases {
+        let response = app
+            .reset_password(Request::new(ResetPasswordRequest {
+                code: code.into(),
+                new_password: "even stronger password".into(),
+            }))
+            .await;
+
+        let error = response.expect_err("The response was not a error");
+        assert!(error.code() == Code::InvalidArgument);
+
+        let bad_request = error
+            .get_details_bad_request()
+            .expect("response doesn't contain bad request details");
+        assert_eq!(bad_request.field_violations[0].field, "code");
+    }
+
+    let response = app
+        .reset_password(Request::new(ResetPasswordRequest {
+            code: second_code,
+            new_password: "even stronger password".into(),
+        }))
+        .await;
+
+    assert!(response.is_ok());
+}
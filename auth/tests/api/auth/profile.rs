@@ -0,0 +1,254 @@
+use auth::proto::auth::{GetProfileRequest, UpdateProfileRequest, VerifyEmailRequest};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, register_user, register_user_as, App};
+
+fn verification_token_from_last_mail(app: &App, email: &str) -> String {
+    let mails = app.sent_mails();
+    let mail = mails
+        .iter()
+        .rev()
+        .find(|mail| mail.contains(&format!("To: {}", email)))
+        .expect("no mail was sent to the email");
+
+    mail.lines()
+        .find_map(|line| line.strip_prefix("Your email verification code is: "))
+        .expect("mail doesn't contain a verification code")
+        .trim()
+        .to_string()
+}
+
+#[tokio::test]
+async fn get_profile_returns_the_registered_details() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let profile = app
+        .get_profile(authenticated_request(
+            GetProfileRequest {},
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to get profile")
+        .into_inner();
+
+    assert_eq!(profile.username, "atheer2104");
+    assert_eq!(profile.firstname, "atheer");
+    assert_eq!(profile.lastname, "ABC");
+    assert_eq!(profile.email, "atheer@gmail.com");
+    assert!(!profile.email_verified);
+    assert!(profile.display_name.is_empty());
+    assert!(profile.status_text.is_empty());
+
+    let error = app
+        .get_profile(Request::new(GetProfileRequest {}))
+        .await
+        .expect_err("profile was returned without an auth token");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn update_profile_only_changes_the_given_fields() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let profile = app
+        .update_profile(authenticated_request(
+            UpdateProfileRequest {
+                lastname: Some("DEF".into()),
+                display_name: Some("  Atheer  ".into()),
+                status_text: Some("out for lunch".into()),
+                ..Default::default()
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to update profile")
+        .into_inner();
+
+    assert_eq!(profile.firstname, "atheer");
+    assert_eq!(profile.lastname, "DEF");
+    assert_eq!(profile.display_name, "Atheer");
+    assert_eq!(profile.status_text, "out for lunch");
+
+    // an empty display name removes it
+    let profile = app
+        .update_profile(authenticated_request(
+            UpdateProfileRequest {
+                display_name: Some("".into()),
+                ..Default::default()
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to update profile")
+        .into_inner();
+
+    assert!(profile.display_name.is_empty());
+    assert_eq!(profile.status_text, "out for lunch");
+}
+
+#[tokio::test]
+async fn update_profile_with_invalid_fields_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let test_cases = vec![
+        UpdateProfileRequest {
+            firstname: Some("".into()),
+            ..Default::default()
+        },
+        UpdateProfileRequest {
+            lastname: Some("ABC123".into()),
+            ..Default::default()
+        },
+        UpdateProfileRequest {
+            email: Some("not an email".into()),
+            current_password: "strong password".into(),
+            ..Default::default()
+        },
+        UpdateProfileRequest {
+            display_name: Some("a".repeat(65)),
+            ..Default::default()
+        },
+    ];
+
+    for update_profile_request in test_cases {
+        let error = app
+            .update_profile(authenticated_request(
+                update_profile_request,
+                &token.access_token,
+            ))
+            .await
+            .expect_err("invalid profile update was accepted");
+
+        assert!(error.code() == Code::InvalidArgument);
+        assert!(error.get_error_details().has_bad_request_violations());
+    }
+}
+
+#[tokio::test]
+async fn changing_email_requires_the_current_password() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    for current_password in ["", "wrong password"] {
+        let error = app
+            .update_profile(authenticated_request(
+                UpdateProfileRequest {
+                    email: Some("atheer@example.com".into()),
+                    current_password: current_password.into(),
+                    ..Default::default()
+                },
+                &token.access_token,
+            ))
+            .await
+            .expect_err("email was changed without the current password");
+
+        assert!(error.code() == Code::InvalidArgument);
+        assert!(error.get_error_details().has_bad_request_violations());
+    }
+
+    let profile = app
+        .get_profile(authenticated_request(
+            GetProfileRequest {},
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to get profile")
+        .into_inner();
+
+    assert_eq!(profile.email, "atheer@gmail.com");
+}
+
+#[tokio::test]
+async fn changed_email_has_to_be_verified_again() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    app.verify_email(Request::new(VerifyEmailRequest {
+        token: verification_token_from_last_mail(&app, "atheer@gmail.com"),
+    }))
+    .await
+    .expect("failed to verify email");
+
+    let profile = app
+        .update_profile(authenticated_request(
+            UpdateProfileRequest {
+                email: Some("atheer@example.com".into()),
+                current_password: "strong password".into(),
+                ..Default::default()
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to change email")
+        .into_inner();
+
+    assert_eq!(profile.email, "atheer@example.com");
+    assert!(!profile.email_verified);
+
+    // the previous email is told about the change
+    assert!(app.sent_mails().iter().any(|mail| {
+        mail.contains("To: atheer@gmail.com") && mail.contains("atheer@example.com")
+    }));
+
+    app.verify_email(Request::new(VerifyEmailRequest {
+        token: verification_token_from_last_mail(&app, "atheer@example.com"),
+    }))
+    .await
+    .expect("failed to verify new email");
+
+    let profile = app
+        .get_profile(authenticated_request(
+            GetProfileRequest {},
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to get profile")
+        .into_inner();
+
+    assert!(profile.email_verified);
+}
+
+#[tokio::test]
+async fn changing_email_to_one_in_use_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    register_user_as(&app, "someone", "someone@gmail.com").await;
+    let token = register_user(&app).await;
+
+    let error = app
+        .update_profile(authenticated_request(
+            UpdateProfileRequest {
+                email: Some("someone@gmail.com".into()),
+                current_password: "strong password".into(),
+                ..Default::default()
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect_err("email of another user was taken");
+
+    assert!(error.code() == Code::AlreadyExists);
+}
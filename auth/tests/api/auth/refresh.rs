@@ -0,0 +1,128 @@
+use auth::proto::auth::RefreshRequest;
+use auth::server::hash_refresh_token;
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::register_user;
+
+#[tokio::test]
+async fn missing_refresh_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let response = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: "".into(),
+        }))
+        .await;
+
+    let error = response.expect_err("The response was not a error");
+
+    assert!(error.code() == Code::InvalidArgument);
+    assert!(error.get_error_details().has_bad_request_violations());
+}
+
+#[tokio::test]
+async fn unknown_refresh_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let response = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: "not a refresh token".into(),
+        }))
+        .await;
+
+    let error = response.expect_err("The response was not a error");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn refresh_returns_a_new_token_pair() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let register_token = register_user(&app).await;
+
+    assert!(!register_token.refresh_token.is_empty());
+    assert!(register_token.expires_in > 0);
+
+    let refreshed_token = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: register_token.refresh_token.clone(),
+        }))
+        .await
+        .expect("failed to refresh token")
+        .into_inner();
+
+    // the refresh token is rotated on every use
+    assert_ne!(refreshed_token.refresh_token, register_token.refresh_token);
+
+    let token_from_db = sqlx::query!(r#"SELECT auth_token FROM auth_tokens"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("failed to fetch from db")
+        .auth_token;
+
+    assert_eq!(token_from_db, refreshed_token.access_token);
+    assert_eq!(
+        app.auth_token_claims(&refreshed_token.access_token)
+            .username,
+        "atheer2104"
+    );
+
+    // only the hash of the refresh token is stored
+    let stored_refresh_tokens = sqlx::query!(r#"SELECT refresh_token_hash FROM refresh_tokens"#)
+        .fetch_all(&app.db_pool)
+        .await
+        .expect("failed to fetch from db");
+
+    assert_eq!(stored_refresh_tokens.len(), 2);
+    assert!(stored_refresh_tokens
+        .iter()
+        .any(|row| row.refresh_token_hash == hash_refresh_token(&refreshed_token.refresh_token)));
+    assert!(stored_refresh_tokens
+        .iter()
+        .all(|row| row.refresh_token_hash != refreshed_token.refresh_token));
+}
+
+#[tokio::test]
+async fn reusing_a_refresh_token_revokes_the_whole_chain() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let register_token = register_user(&app).await;
+
+    let refreshed_token = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: register_token.refresh_token.clone(),
+        }))
+        .await
+        .expect("failed to refresh token")
+        .into_inner();
+
+    let reuse_response = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: register_token.refresh_token.clone(),
+        }))
+        .await;
+
+    let error = reuse_response.expect_err("reusing a refresh token was not an error");
+    assert!(error.code() == Code::Unauthenticated);
+
+    // the refresh token that was handed out after the reused one is revoked as well
+    let response = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: refreshed_token.refresh_token,
+        }))
+        .await;
+
+    let error = response.expect_err("revoked refresh token was accepted");
+    assert!(error.code() == Code::Unauthenticated);
+}
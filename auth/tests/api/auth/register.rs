@@ -1,14 +1,8 @@
-use std::collections::BTreeMap;
-
-use auth::proto::auth::RegisterRequest;
-use hmac::{digest::KeyInit, Hmac};
-use jwt::VerifyWithKey;
 use rand::{thread_rng, Rng};
 use redis::AsyncCommands;
-use secrecy::ExposeSecret;
-use sha2::Sha512;
 
-use super::{sleep, spawn_app, Request};
+use super::{sleep, spawn_app};
+use crate::helpers::{register_user, try_register_user};
 
 #[tokio::test]
 async fn register_user_dont_check_token() {
@@ -16,17 +10,7 @@ async fn register_user_dont_check_token() {
     let mut rng = thread_rng();
     sleep(rng.gen_range(100..200)).await;
 
-    let response = app
-        .register(Request::new(RegisterRequest {
-            firstname: "atheer".into(),
-            lastname: "ABC".into(),
-            username: "atheer2104".into(),
-            email: "atheer@gmail.com".into(),
-            password: "strong password".into(),
-        }))
-        .await;
-
-    assert!(response.is_ok());
+    register_user(&app).await;
 
     let saved = sqlx::query!(r#"SELECT * FROM account"#)
         .fetch_one(&app.db_pool)
@@ -47,53 +31,42 @@ async fn register_user_check_token() {
 
     let username = String::from("atheer21");
 
-    let response = app
-        .register(Request::new(RegisterRequest {
-            firstname: "atheer".into(),
-            lastname: "abc".into(),
-            username: username.clone(),
-            email: "atheer21@gmail.com".into(),
-            password: "secret secret".into(),
-        }))
-        .await;
-
-    let auth_token_response = response
-        .expect("no auth token found creating user")
-        .into_inner();
+    let auth_token_response = try_register_user(&app, &username, "atheer21@gmail.com")
+        .await
+        .expect("no auth token found creating user");
 
     println!("auth token: {:?}", auth_token_response);
 
     let saved = sqlx::query!(
-        r#"SELECT user_id FROM account WHERE username = $1"#,
+        r#"SELECT auth_tokens.session_id FROM auth_tokens
+        JOIN account ON account.user_id = auth_tokens.user_id
+        WHERE account.username = $1"#,
         username,
     )
     .fetch_one(&app.db_pool)
     .await
     .expect("failed to fetch from db");
 
-    println!("fetched user_id: {}", saved.user_id);
+    println!("fetched session_id: {}", saved.session_id);
 
     let mut redis_con = app.redis_con.lock().await;
 
-    let auth_token_redis: String = match redis_con.get(saved.user_id).await {
+    let auth_token_redis: String = match redis_con
+        .get(format!("auth_token:{}", saved.session_id))
+        .await
+    {
         Ok(token) => token,
-        Err(_) => panic!("couldn't get user_id from redis"),
+        Err(_) => panic!("couldn't get auth token of session from redis"),
     };
 
     println!("auth token redis: {}", auth_token_redis);
 
     assert_eq!(auth_token_redis, auth_token_response.access_token);
 
-    let key: Hmac<Sha512> =
-        Hmac::new_from_slice(app.dummy_secrets.jwt_secret.expose_secret().as_bytes())
-            .expect("failed to create hmac");
-
-    let claims: BTreeMap<String, String> = auth_token_response
-        .access_token
-        .verify_with_key(&key)
-        .expect("failed to verify auth token");
+    let claims = app.auth_token_claims(&auth_token_response.access_token);
 
     println!("claims: {:?}", claims);
 
-    assert_eq!(claims["iss"], "Chat-gRPC");
+    assert_eq!(claims.iss, "Chat-gRPC");
+    assert_eq!(claims.username, username);
 }
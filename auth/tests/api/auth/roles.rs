@@ -0,0 +1,175 @@
+use auth::proto::auth::{GrantRoleRequest, RefreshRequest, RevokeRoleRequest, Token};
+use auth::server::Role;
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, register_admin, register_user, App};
+
+async fn refresh(app: &App, token: Token) -> Token {
+    app.refresh(Request::new(RefreshRequest {
+        refresh_token: token.refresh_token,
+    }))
+    .await
+    .expect("failed to refresh auth token")
+    .into_inner()
+}
+
+#[tokio::test]
+async fn new_users_have_the_user_role() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    assert_eq!(
+        app.auth_token_claims(&token.access_token).roles,
+        vec!["user"]
+    );
+
+    let token = refresh(&app, token).await;
+    assert_eq!(
+        app.auth_token_claims(&token.access_token).roles,
+        vec!["user"]
+    );
+}
+
+#[tokio::test]
+async fn granting_roles_requires_the_admin_role() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let error = app
+        .grant_role(authenticated_request(
+            GrantRoleRequest {
+                username: "atheer2104".into(),
+                role: "admin".into(),
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect_err("user without the admin role granted a role");
+
+    assert!(error.code() == Code::PermissionDenied);
+
+    let error = app
+        .revoke_role(authenticated_request(
+            RevokeRoleRequest {
+                username: "atheer2104".into(),
+                role: "user".into(),
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect_err("user without the admin role revoked a role");
+
+    assert!(error.code() == Code::PermissionDenied);
+
+    let error = app
+        .grant_role(Request::new(GrantRoleRequest {
+            username: "atheer2104".into(),
+            role: "admin".into(),
+        }))
+        .await
+        .expect_err("role was granted without an auth token");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn granted_and_revoked_roles_end_up_in_the_refreshed_auth_token() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    let admin_claims = app.auth_token_claims(&admin_token.access_token);
+    assert!(admin_claims.has_role(Role::Admin));
+
+    let token = register_user(&app).await;
+
+    let response = app
+        .grant_role(authenticated_request(
+            GrantRoleRequest {
+                username: "atheer2104".into(),
+                role: "moderator".into(),
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to grant role")
+        .into_inner();
+
+    assert_eq!(response.roles, vec!["user", "moderator"]);
+
+    let token = refresh(&app, token).await;
+    let claims = app.auth_token_claims(&token.access_token);
+    assert!(claims.has_role(Role::Moderator));
+
+    let response = app
+        .revoke_role(authenticated_request(
+            RevokeRoleRequest {
+                username: "atheer2104".into(),
+                role: "moderator".into(),
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to revoke role")
+        .into_inner();
+
+    assert_eq!(response.roles, vec!["user"]);
+
+    let token = refresh(&app, token).await;
+    let claims = app.auth_token_claims(&token.access_token);
+    assert!(!claims.has_role(Role::Moderator));
+}
+
+#[tokio::test]
+async fn granting_an_unknown_role_or_to_an_unknown_user_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+
+    let test_cases = vec![
+        GrantRoleRequest {
+            username: "admin".into(),
+            role: "superuser".into(),
+        },
+        GrantRoleRequest {
+            username: "".into(),
+            role: "moderator".into(),
+        },
+    ];
+
+    for grant_role_request in test_cases {
+        let error = app
+            .grant_role(authenticated_request(
+                grant_role_request,
+                &admin_token.access_token,
+            ))
+            .await
+            .expect_err("invalid grant was accepted");
+
+        assert!(error.code() == Code::InvalidArgument);
+        assert!(error.get_error_details().has_bad_request_violations());
+    }
+
+    let error = app
+        .grant_role(authenticated_request(
+            GrantRoleRequest {
+                username: "nobody".into(),
+                role: "moderator".into(),
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect_err("role was granted to a user that doesn't exist");
+
+    assert!(error.code() == Code::NotFound);
+}
@@ -0,0 +1,169 @@
+use auth::proto::auth::{ListSessionsRequest, LogoutRequest, RefreshRequest, RevokeSessionRequest};
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{authenticated_request, login_user_on_device, register_user_on_device};
+
+#[tokio::test]
+async fn list_sessions_without_auth_token_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let response = app
+        .list_sessions(Request::new(ListSessionsRequest {}))
+        .await;
+
+    let error = response.expect_err("The response was not a error");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn every_login_creates_a_new_session() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let register_token = register_user_on_device(&app, "laptop").await;
+    let phone_token = login_user_on_device(&app, "phone").await;
+
+    let sessions = app
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &phone_token.access_token,
+        ))
+        .await
+        .expect("failed to list sessions")
+        .into_inner()
+        .sessions;
+
+    assert_eq!(sessions.len(), 2);
+
+    let mut devices: Vec<&str> = sessions
+        .iter()
+        .map(|session| session.device.as_str())
+        .collect();
+    devices.sort();
+    assert_eq!(devices, ["laptop", "phone"]);
+
+    let current_sessions: Vec<_> = sessions.iter().filter(|session| session.current).collect();
+    assert_eq!(current_sessions.len(), 1);
+    assert_eq!(current_sessions[0].device, "phone");
+    assert!(current_sessions[0].created_at.is_some());
+    assert!(current_sessions[0].last_used_at.is_some());
+
+    // logging in on another device doesn't affect the first session
+    let response = app
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &register_token.access_token,
+        ))
+        .await;
+
+    assert!(response.is_ok());
+}
+
+#[tokio::test]
+async fn revoking_a_session_only_affects_that_session() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let laptop_token = register_user_on_device(&app, "laptop").await;
+    let phone_token = login_user_on_device(&app, "phone").await;
+
+    let sessions = app
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &laptop_token.access_token,
+        ))
+        .await
+        .expect("failed to list sessions")
+        .into_inner()
+        .sessions;
+
+    let phone_session = sessions
+        .iter()
+        .find(|session| session.device == "phone")
+        .expect("phone session is missing");
+
+    let response = app
+        .revoke_session(authenticated_request(
+            RevokeSessionRequest {
+                session_id: phone_session.session_id.clone(),
+            },
+            &laptop_token.access_token,
+        ))
+        .await;
+
+    assert!(response.is_ok());
+
+    // neither the access token nor the refresh token of the revoked session can be used anymore
+    let response = app
+        .logout(authenticated_request(
+            LogoutRequest {},
+            &phone_token.access_token,
+        ))
+        .await;
+
+    let error = response.expect_err("access token of revoked session was accepted");
+    assert!(error.code() == Code::Unauthenticated);
+
+    let response = app
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: phone_token.refresh_token,
+        }))
+        .await;
+
+    let error = response.expect_err("refresh token of revoked session was accepted");
+    assert!(error.code() == Code::Unauthenticated);
+
+    let sessions = app
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &laptop_token.access_token,
+        ))
+        .await
+        .expect("failed to list sessions")
+        .into_inner()
+        .sessions;
+
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].device, "laptop");
+}
+
+#[tokio::test]
+async fn revoking_an_unknown_session_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user_on_device(&app, "laptop").await;
+
+    let response = app
+        .revoke_session(authenticated_request(
+            RevokeSessionRequest {
+                session_id: "not a session id".into(),
+            },
+            &token.access_token,
+        ))
+        .await;
+
+    let error = response.expect_err("The response was not a error");
+    assert!(error.code() == Code::InvalidArgument);
+    assert!(error.get_error_details().has_bad_request_violations());
+
+    let response = app
+        .revoke_session(authenticated_request(
+            RevokeSessionRequest {
+                session_id: uuid::Uuid::new_v4().to_string(),
+            },
+            &token.access_token,
+        ))
+        .await;
+
+    let error = response.expect_err("The response was not a error");
+    assert!(error.code() == Code::NotFound);
+}
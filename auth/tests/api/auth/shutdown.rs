@@ -0,0 +1,73 @@
+use std::time::Instant;
+
+use auth::proto::auth::{auth_client::AuthClient, RegisterRequest, Token};
+use rand::{thread_rng, Rng};
+use tokio::task::JoinHandle;
+
+use super::{sleep, spawn_app, Request};
+use crate::helpers::{spawn_app_with_configuration, App};
+
+// registering hashes the password, so the request is still being answered for a while after it has been sent
+fn register_in_background(app: &App) -> JoinHandle<Result<Token, tonic::Status>> {
+    let address = format!("http://{}", app.address);
+
+    tokio::spawn(async move {
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client
+            .register(Request::new(RegisterRequest {
+                firstname: "atheer".into(),
+                lastname: "ABC".into(),
+                username: "atheer2104".into(),
+                email: "atheer@gmail.com".into(),
+                password: "strong password".into(),
+                ..Default::default()
+            }))
+            .await
+            .map(|response| response.into_inner())
+    })
+}
+
+#[tokio::test]
+async fn requests_being_answered_finish_before_the_server_stops() {
+    let mut app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let register = register_in_background(&app);
+    sleep(50).await;
+
+    app.shutdown().await;
+
+    register
+        .await
+        .expect("register task panicked")
+        .expect("the request should have been answered");
+
+    // new connections aren't accepted anymore
+    assert!(AuthClient::connect(format!("http://{}", app.address))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn server_stops_waiting_for_requests_after_the_drain_deadline() {
+    let mut app = spawn_app_with_configuration(|configuration| {
+        configuration.shutdown.drain_deadline_seconds = 0;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let register = register_in_background(&app);
+    sleep(50).await;
+
+    let shutdown_started = Instant::now();
+    app.shutdown().await;
+    assert!(shutdown_started.elapsed().as_millis() < 500);
+
+    // the server stopped waiting while the request was still being answered
+    assert!(!register.is_finished());
+}
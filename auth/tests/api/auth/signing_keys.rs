@@ -0,0 +1,251 @@
+use auth::configuration::JwtAlgorithm;
+use auth::proto::auth::{GetSigningKeysRequest, ListSessionsRequest, LogoutRequest};
+use auth::secrets::{JwtKeySecret, JwtKeyringSecret};
+use auth::server::{generate_auth_token, Claims, JwtKeys, Role};
+use chrono::Duration;
+use data_encoding::BASE64URL_NOPAD;
+use jwt::{AlgorithmType, Header, Token as Jwt, Unverified};
+use rand::{thread_rng, Rng};
+use rsa::{BigUint, RsaPublicKey};
+use secrecy::{ExposeSecret, Secret};
+use token_claims::{TokenVerifier, VerifyingKey};
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{
+    authenticated_request, login_user, register_user, spawn_app_with_configuration, App,
+};
+
+// auth tokens are only signed with RSA when it's configured
+async fn spawn_rs256_app() -> App {
+    spawn_app_with_configuration(|configuration| {
+        configuration.auth_token.signing_algorithm = JwtAlgorithm::Rs256;
+    })
+    .await
+}
+
+// a keyring with the key of the dummy secrets and a newer one, like after they have been rotated
+fn rotated_keyring(app: &App, active_kid: &str, kids: &[&str]) -> JwtKeys {
+    let current_key = app.dummy_secrets.jwt_private_key.clone().unwrap();
+    let next_key = Secret::new(include_str!("../jwt_next_private_key.pem").to_string());
+
+    let keys = kids
+        .iter()
+        .map(|kid| JwtKeySecret {
+            kid: kid.to_string(),
+            key: if *kid == "current" {
+                current_key.clone()
+            } else {
+                next_key.clone()
+            },
+        })
+        .collect();
+
+    JwtKeys::from_keyring(
+        &JwtKeyringSecret {
+            active_kid: active_kid.to_string(),
+            keys,
+        },
+        JwtAlgorithm::Rs256,
+    )
+    .expect("failed to read keyring")
+}
+
+// the algorithm and kid of the auth token
+fn token_header(auth_token: &str) -> (AlgorithmType, Option<String>) {
+    let token: Jwt<Header, Claims, Unverified<'_>> =
+        Jwt::parse_unverified(auth_token).expect("failed to parse auth token");
+
+    (token.header().algorithm, token.header().key_id.clone())
+}
+
+#[tokio::test]
+async fn auth_tokens_can_be_verified_with_the_signing_keys() {
+    let app = spawn_rs256_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let keys = app
+        .get_signing_keys(Request::new(GetSigningKeysRequest {}))
+        .await
+        .expect("failed to get signing keys")
+        .into_inner()
+        .keys;
+
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].kty, "RSA");
+    assert_eq!(keys[0].alg, "RS256");
+
+    let (algorithm, kid) = token_header(&token.access_token);
+    assert_eq!(algorithm, AlgorithmType::Rs256);
+    assert_eq!(kid.as_ref(), Some(&keys[0].kid));
+
+    // only the public key is needed to verify the auth token
+    let public_key = RsaPublicKey::new(
+        BigUint::from_bytes_be(&BASE64URL_NOPAD.decode(keys[0].n.as_bytes()).unwrap()),
+        BigUint::from_bytes_be(&BASE64URL_NOPAD.decode(keys[0].e.as_bytes()).unwrap()),
+    )
+    .expect("signing key is not a valid RSA key");
+
+    let mut verifier = TokenVerifier::new();
+    verifier.add_key(keys[0].kid.clone(), VerifyingKey::rs256(public_key));
+
+    let claims = verifier
+        .verify(&token.access_token)
+        .expect("failed to verify auth token");
+
+    assert_eq!(claims.iss, "Chat-gRPC");
+}
+
+#[tokio::test]
+async fn auth_token_signed_with_hmac_is_rejected_when_using_rs256() {
+    let app = spawn_rs256_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    let claims = app.auth_token_claims(&token.access_token);
+
+    // a token with the right claims that has been signed with the HMAC secret instead of the private key
+    let hmac_keys = JwtKeys::hmac(app.dummy_secrets.jwt_secret.as_ref().unwrap()).unwrap();
+    let hmac_token = generate_auth_token(
+        &hmac_keys,
+        claims.user_id,
+        claims.username,
+        vec![Role::User],
+        claims.session_id,
+        true,
+        Duration::minutes(15),
+    )
+    .expect("failed to generate auth token");
+
+    let error = app
+        .list_sessions(authenticated_request(ListSessionsRequest {}, &hmac_token))
+        .await
+        .expect_err("auth token signed with hmac was accepted");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn auth_tokens_are_signed_with_hmac_by_default() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let (algorithm, kid) = token_header(&token.access_token);
+    assert_eq!(algorithm, AlgorithmType::Hs512);
+
+    let mut verifier = TokenVerifier::new();
+    verifier.add_key(
+        kid.expect("auth token has no kid"),
+        VerifyingKey::hmac(
+            app.dummy_secrets
+                .jwt_secret
+                .as_ref()
+                .unwrap()
+                .expose_secret()
+                .as_bytes(),
+        ),
+    );
+
+    let claims = verifier
+        .verify(&token.access_token)
+        .expect("failed to verify auth token");
+    assert_eq!(claims.iss, "Chat-gRPC");
+
+    // the secret is never handed out
+    let error = app
+        .get_signing_keys(Request::new(GetSigningKeysRequest {}))
+        .await
+        .expect_err("signing keys of HMAC were handed out");
+    assert_eq!(error.code(), Code::FailedPrecondition);
+
+    assert!(app
+        .logout(authenticated_request(LogoutRequest {}, &token.access_token))
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn auth_tokens_signed_with_a_retired_key_are_still_accepted() {
+    let app = spawn_rs256_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    app.jwt_keyring
+        .replace(rotated_keyring(&app, "current", &["current", "next"]));
+    let old_token = register_user(&app).await;
+    assert_eq!(
+        token_header(&old_token.access_token).1.as_deref(),
+        Some("current")
+    );
+
+    // both keys are handed out before the next one signs anything, so other services know it in time
+    let keys = app
+        .get_signing_keys(Request::new(GetSigningKeysRequest {}))
+        .await
+        .expect("failed to get signing keys")
+        .into_inner()
+        .keys;
+    let kids: Vec<&str> = keys.iter().map(|key| key.kid.as_str()).collect();
+    assert_eq!(kids, vec!["current", "next"]);
+
+    app.jwt_keyring
+        .replace(rotated_keyring(&app, "next", &["current", "next"]));
+    let new_token = login_user(&app).await;
+    assert_eq!(
+        token_header(&new_token.access_token).1.as_deref(),
+        Some("next")
+    );
+
+    for token in [&old_token, &new_token] {
+        assert!(app
+            .list_sessions(authenticated_request(
+                ListSessionsRequest {},
+                &token.access_token
+            ))
+            .await
+            .is_ok());
+    }
+}
+
+#[tokio::test]
+async fn auth_tokens_signed_with_a_removed_key_are_rejected() {
+    let app = spawn_rs256_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    app.jwt_keyring
+        .replace(rotated_keyring(&app, "current", &["current", "next"]));
+    let token = register_user(&app).await;
+
+    app.jwt_keyring
+        .replace(rotated_keyring(&app, "next", &["next"]));
+
+    let error = app
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &token.access_token,
+        ))
+        .await
+        .expect_err("auth token signed with a removed key was accepted");
+
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[test]
+fn keyring_has_to_contain_the_active_key() {
+    let keyring = JwtKeyringSecret {
+        active_kid: "missing".into(),
+        keys: vec![JwtKeySecret {
+            kid: "current".into(),
+            key: Secret::new(include_str!("../jwt_private_key.pem").to_string()),
+        }],
+    };
+
+    assert!(JwtKeys::from_keyring(&keyring, JwtAlgorithm::Rs256).is_err());
+}
@@ -0,0 +1,236 @@
+use auth::proto::auth::{CompleteLoginRequest, ConfirmTotpRequest, EnrollTotpRequest, Token};
+use auth::server::{
+    totp_code, totp_time_step, LOGIN_CHALLENGE_METADATA_KEY, SECOND_FACTOR_REQUIRED,
+};
+use chrono::Utc;
+use data_encoding::BASE32_NOPAD;
+use rand::{thread_rng, Rng};
+use tonic_types::StatusExt;
+
+use super::{sleep, spawn_app, Code, Request};
+use crate::helpers::{
+    authenticated_request, register_user, spawn_app_with_configuration, try_login_user, App,
+};
+
+// logs in a user with two-factor authentication and returns the challenge that login responds with
+async fn login_challenge(app: &App) -> String {
+    let error = try_login_user(app, "atheer2104", "strong password")
+        .await
+        .expect_err("login didn't require a second factor");
+
+    assert!(error.code() == Code::Unauthenticated);
+
+    let error_info = error
+        .get_details_error_info()
+        .expect("response doesn't contain error info");
+    assert_eq!(error_info.reason, SECOND_FACTOR_REQUIRED);
+
+    error_info.metadata[LOGIN_CHALLENGE_METADATA_KEY].clone()
+}
+
+async fn complete_login(app: &App, challenge: &str, code: &str) -> Result<Token, tonic::Status> {
+    app.complete_login(Request::new(CompleteLoginRequest {
+        challenge: challenge.into(),
+        code: code.into(),
+    }))
+    .await
+    .map(|response| response.into_inner())
+}
+
+// enables two-factor authentication for the user and returns the secret of the authenticator app, the time step
+// whose code confirmed it and the backup codes
+async fn enable_totp(app: &App, access_token: &str) -> (Vec<u8>, i64, Vec<String>) {
+    let enrollment = app
+        .enroll_totp(authenticated_request(EnrollTotpRequest {}, access_token))
+        .await
+        .expect("failed to enroll totp")
+        .into_inner();
+
+    let secret = BASE32_NOPAD
+        .decode(enrollment.secret.as_bytes())
+        .expect("secret is not base32");
+    let time_step = totp_time_step(Utc::now().timestamp());
+
+    let backup_codes = app
+        .confirm_totp(authenticated_request(
+            ConfirmTotpRequest {
+                code: totp_code(&secret, time_step),
+            },
+            access_token,
+        ))
+        .await
+        .expect("failed to confirm totp")
+        .into_inner()
+        .backup_codes;
+
+    (secret, time_step, backup_codes)
+}
+
+#[tokio::test]
+async fn login_requires_second_factor_once_totp_is_confirmed() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let enrollment = app
+        .enroll_totp(authenticated_request(
+            EnrollTotpRequest {},
+            &token.access_token,
+        ))
+        .await
+        .expect("failed to enroll totp")
+        .into_inner();
+
+    assert!(enrollment
+        .otpauth_uri
+        .starts_with("otpauth://totp/Chat-gRPC:atheer2104?"));
+    assert!(enrollment
+        .otpauth_uri
+        .contains(&format!("secret={}", enrollment.secret)));
+
+    // the secret isn't stored as it is
+    let stored_secret = sqlx::query!("SELECT encrypted_secret FROM totp_secrets")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch totp secret")
+        .encrypted_secret;
+    let secret = BASE32_NOPAD
+        .decode(enrollment.secret.as_bytes())
+        .expect("secret is not base32");
+    assert!(stored_secret != secret);
+
+    // an enrollment that hasn't been confirmed doesn't change the login
+    assert!(try_login_user(&app, "atheer2104", "strong password")
+        .await
+        .is_ok());
+
+    let (secret, confirmed_time_step, backup_codes) = enable_totp(&app, &token.access_token).await;
+    assert_eq!(backup_codes.len(), 10);
+
+    let challenge = login_challenge(&app).await;
+
+    // the code that confirmed the secret has been used already
+    let error = complete_login(&app, &challenge, &totp_code(&secret, confirmed_time_step))
+        .await
+        .expect_err("code was used twice");
+    assert!(error.code() == Code::InvalidArgument);
+
+    let token = complete_login(
+        &app,
+        &challenge,
+        &totp_code(&secret, confirmed_time_step + 1),
+    )
+    .await
+    .expect("failed to complete login");
+    assert!(!token.access_token.is_empty());
+
+    // the challenge can only be exchanged once
+    let error = complete_login(&app, &challenge, &backup_codes[0])
+        .await
+        .expect_err("challenge was used twice");
+    assert!(error.code() == Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn confirm_totp_with_wrong_code_is_rejected() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    app.enroll_totp(authenticated_request(
+        EnrollTotpRequest {},
+        &token.access_token,
+    ))
+    .await
+    .expect("failed to enroll totp");
+
+    let error = app
+        .confirm_totp(authenticated_request(
+            ConfirmTotpRequest {
+                code: "not a code".into(),
+            },
+            &token.access_token,
+        ))
+        .await
+        .expect_err("confirming with a wrong code succeeded");
+
+    assert!(error.code() == Code::InvalidArgument);
+    assert_eq!(
+        error
+            .get_details_bad_request()
+            .expect("response doesn't contain bad request details")
+            .field_violations[0]
+            .field,
+        "code"
+    );
+
+    // two-factor authentication stays disabled
+    assert!(try_login_user(&app, "atheer2104", "strong password")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn backup_code_can_only_be_used_once() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    let (_, _, backup_codes) = enable_totp(&app, &token.access_token).await;
+
+    // backup codes are typed by hand so the case doesn't matter
+    let challenge = login_challenge(&app).await;
+    assert!(
+        complete_login(&app, &challenge, &backup_codes[0].to_lowercase())
+            .await
+            .is_ok()
+    );
+
+    let challenge = login_challenge(&app).await;
+    let error = complete_login(&app, &challenge, &backup_codes[0])
+        .await
+        .expect_err("backup code was used twice");
+    assert!(error.code() == Code::InvalidArgument);
+
+    assert!(complete_login(&app, &challenge, &backup_codes[1])
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn challenge_is_dropped_after_too_many_wrong_codes() {
+    let app = spawn_app_with_configuration(|configuration| {
+        configuration.totp.max_code_attempts = 3;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    let (_, _, backup_codes) = enable_totp(&app, &token.access_token).await;
+
+    let challenge = login_challenge(&app).await;
+
+    for _ in 0..3 {
+        let error = complete_login(&app, &challenge, "000000")
+            .await
+            .expect_err("wrong code was accepted");
+        assert!(error.code() == Code::InvalidArgument);
+    }
+
+    // even the right code is refused now
+    let error = complete_login(&app, &challenge, &backup_codes[0])
+        .await
+        .expect_err("challenge was still usable");
+    assert!(error.code() == Code::Unauthenticated);
+
+    let challenge = login_challenge(&app).await;
+    assert!(complete_login(&app, &challenge, &backup_codes[0])
+        .await
+        .is_ok());
+}
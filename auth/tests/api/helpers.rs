@@ -1,17 +1,39 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
 use auth::{
-    configuration::{get_configuration, DatabaseSettings},
+    configuration::{get_configuration, DatabaseSettings, Settings},
     logging::{get_subscriber, init_subscriber},
-    proto::auth::{auth_client::AuthClient, LoginRequest, RegisterRequest, Token},
+    proto::auth::{
+        auth_client::AuthClient, ChangePasswordRequest, ChangePasswordResponse,
+        CheckAvailabilityRequest, CheckAvailabilityResponse, CompleteLoginRequest,
+        ConfirmTotpRequest, ConfirmTotpResponse, CreateApiKeyRequest, CreateApiKeyResponse,
+        CreateServiceAccountRequest, DeleteAccountRequest, DeleteAccountResponse,
+        EnrollTotpRequest, EnrollTotpResponse, ExportMyDataRequest, ExportMyDataResponse,
+        GetProfileRequest, GetSigningKeysRequest, GetSigningKeysResponse, GrantRoleRequest,
+        GrantRoleResponse, IntrospectRequest, IntrospectResponse, ListApiKeysRequest,
+        ListApiKeysResponse, ListAuthEventsRequest, ListAuthEventsResponse, ListSessionsRequest,
+        ListSessionsResponse, LoginRequest, LogoutRequest, LogoutResponse, Profile, RefreshRequest,
+        RegisterRequest, RequestPasswordResetRequest, RequestPasswordResetResponse,
+        ResendVerificationEmailRequest, ResendVerificationEmailResponse, ResetPasswordRequest,
+        ResetPasswordResponse, RevokeApiKeyRequest, RevokeApiKeyResponse, RevokeRoleRequest,
+        RevokeRoleResponse, RevokeSessionRequest, RevokeSessionResponse, ServiceAccount, Token,
+        UpdateProfileRequest, VerifyEmailRequest, VerifyEmailResponse,
+    },
     secrets::Secrets,
-    server::{build_server, RedisCon},
+    server::{
+        build_server, get_user_id_by_username_db, grant_role_db, serve_until_shutdown,
+        verify_auth_token, Claims, JwtKeyring, JwtKeys, RedisCon, Role,
+    },
 };
 use once_cell::sync::Lazy;
 use secrecy::{ExposeSecret, Secret};
 use sqlx::{Connection, Executor, PgConnection, PgPool};
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+};
 
-use tokio::sync::Mutex;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
 use tonic::{Request, Response, Status};
 
 use uuid::Uuid;
@@ -57,9 +79,47 @@ pub struct App {
     pub db_pool: PgPool,
     pub redis_con: RedisCon,
     pub dummy_secrets: Secrets,
+    pub jwt_keyring: JwtKeyring,
+    pub outbox_directory: PathBuf,
+    shutdown_sender: Option<oneshot::Sender<()>>,
+    server_handle: Option<JoinHandle<Result<(), tonic::transport::Error>>>,
 }
 
 impl App {
+    // shuts the server down like a shutdown signal would, resolves once it has stopped serving
+    pub async fn shutdown(&mut self) {
+        if let Some(shutdown_sender) = self.shutdown_sender.take() {
+            let _ = shutdown_sender.send(());
+        }
+
+        if let Some(server_handle) = self.server_handle.take() {
+            server_handle
+                .await
+                .expect("server panicked")
+                .expect("server failed");
+        }
+    }
+
+    // the status that the health service reports for a service, the empty name is the server as a whole
+    pub async fn health_status(&self, service: &str) -> Result<ServingStatus, Status> {
+        let address = format!("http://{}", self.address);
+        // the health client is generated without a connect function
+        let channel = tonic::transport::Endpoint::from_shared(address)
+            .expect("Failed to create endpoint")
+            .connect()
+            .await
+            .expect("Failed to create client");
+        let mut client = HealthClient::new(channel);
+
+        let response = client
+            .check(HealthCheckRequest {
+                service: service.into(),
+            })
+            .await?;
+
+        Ok(response.into_inner().status())
+    }
+
     pub async fn login(
         &self,
         request: tonic::Request<LoginRequest>,
@@ -83,24 +143,501 @@ impl App {
 
         client.register(request).await
     }
+
+    pub async fn check_availability(
+        &self,
+        request: Request<CheckAvailabilityRequest>,
+    ) -> Result<Response<CheckAvailabilityResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.check_availability(request).await
+    }
+
+    pub async fn refresh(
+        &self,
+        request: Request<RefreshRequest>,
+    ) -> Result<Response<Token>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.refresh(request).await
+    }
+
+    pub async fn logout(
+        &self,
+        request: Request<LogoutRequest>,
+    ) -> Result<Response<LogoutResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.logout(request).await
+    }
+
+    pub async fn list_sessions(
+        &self,
+        request: Request<ListSessionsRequest>,
+    ) -> Result<Response<ListSessionsResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.list_sessions(request).await
+    }
+
+    pub async fn revoke_session(
+        &self,
+        request: Request<RevokeSessionRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.revoke_session(request).await
+    }
+
+    pub async fn change_password(
+        &self,
+        request: Request<ChangePasswordRequest>,
+    ) -> Result<Response<ChangePasswordResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.change_password(request).await
+    }
+
+    pub async fn request_password_reset(
+        &self,
+        request: Request<RequestPasswordResetRequest>,
+    ) -> Result<Response<RequestPasswordResetResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.request_password_reset(request).await
+    }
+
+    pub async fn reset_password(
+        &self,
+        request: Request<ResetPasswordRequest>,
+    ) -> Result<Response<ResetPasswordResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.reset_password(request).await
+    }
+
+    pub async fn verify_email(
+        &self,
+        request: Request<VerifyEmailRequest>,
+    ) -> Result<Response<VerifyEmailResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.verify_email(request).await
+    }
+
+    pub async fn resend_verification_email(
+        &self,
+        request: Request<ResendVerificationEmailRequest>,
+    ) -> Result<Response<ResendVerificationEmailResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.resend_verification_email(request).await
+    }
+
+    pub async fn complete_login(
+        &self,
+        request: Request<CompleteLoginRequest>,
+    ) -> Result<Response<Token>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.complete_login(request).await
+    }
+
+    pub async fn enroll_totp(
+        &self,
+        request: Request<EnrollTotpRequest>,
+    ) -> Result<Response<EnrollTotpResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.enroll_totp(request).await
+    }
+
+    pub async fn confirm_totp(
+        &self,
+        request: Request<ConfirmTotpRequest>,
+    ) -> Result<Response<ConfirmTotpResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.confirm_totp(request).await
+    }
+
+    pub async fn get_signing_keys(
+        &self,
+        request: Request<GetSigningKeysRequest>,
+    ) -> Result<Response<GetSigningKeysResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.get_signing_keys(request).await
+    }
+
+    pub async fn introspect(
+        &self,
+        request: Request<IntrospectRequest>,
+    ) -> Result<Response<IntrospectResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.introspect(request).await
+    }
+
+    pub async fn get_profile(
+        &self,
+        request: Request<GetProfileRequest>,
+    ) -> Result<Response<Profile>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.get_profile(request).await
+    }
+
+    pub async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<Profile>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.update_profile(request).await
+    }
+
+    pub async fn delete_account(
+        &self,
+        request: Request<DeleteAccountRequest>,
+    ) -> Result<Response<DeleteAccountResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.delete_account(request).await
+    }
+
+    pub async fn export_my_data(
+        &self,
+        request: Request<ExportMyDataRequest>,
+    ) -> Result<Response<ExportMyDataResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.export_my_data(request).await
+    }
+
+    pub async fn grant_role(
+        &self,
+        request: Request<GrantRoleRequest>,
+    ) -> Result<Response<GrantRoleResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.grant_role(request).await
+    }
+
+    pub async fn revoke_role(
+        &self,
+        request: Request<RevokeRoleRequest>,
+    ) -> Result<Response<RevokeRoleResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.revoke_role(request).await
+    }
+
+    pub async fn list_auth_events(
+        &self,
+        request: Request<ListAuthEventsRequest>,
+    ) -> Result<Response<ListAuthEventsResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.list_auth_events(request).await
+    }
+
+    pub async fn create_service_account(
+        &self,
+        request: Request<CreateServiceAccountRequest>,
+    ) -> Result<Response<ServiceAccount>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.create_service_account(request).await
+    }
+
+    pub async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.create_api_key(request).await
+    }
+
+    pub async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ListApiKeysResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.list_api_keys(request).await
+    }
+
+    pub async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<RevokeApiKeyResponse>, Status> {
+        let address = format!("http://{}", self.address);
+        let mut client = AuthClient::connect(address)
+            .await
+            .expect("Failed to create client");
+
+        client.revoke_api_key(request).await
+    }
+
+    // the claims of an auth token that the server has signed
+    pub fn auth_token_claims(&self, auth_token: &str) -> Claims {
+        verify_auth_token(&self.jwt_keyring.current(), auth_token)
+            .expect("failed to verify auth token")
+    }
+
+    // the mails that the server has sent, oldest first
+    pub fn sent_mails(&self) -> Vec<String> {
+        let mut mail_paths: Vec<PathBuf> = match std::fs::read_dir(&self.outbox_directory) {
+            Ok(entries) => entries
+                .map(|entry| entry.expect("failed to read outbox entry").path())
+                .collect(),
+            // nothing has been sent yet
+            Err(_) => return Vec::new(),
+        };
+        mail_paths.sort();
+
+        mail_paths
+            .iter()
+            .map(|path| std::fs::read_to_string(path).expect("failed to read mail"))
+            .collect()
+    }
+
+    // password reset codes are mailed in the background, so the mail can arrive after the response
+    pub async fn wait_for_sent_mails(&self, count: usize) -> Vec<String> {
+        for _ in 0..100 {
+            let mails = self.sent_mails();
+            if mails.len() >= count {
+                return mails;
+            }
+
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        panic!("the server didn't send {} mails", count);
+    }
+}
+
+// creates a request that carries the access token the same way the client does
+pub fn authenticated_request<T>(message: T, access_token: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    request.metadata_mut().insert(
+        "authorization",
+        format!("Bearer {}", access_token)
+            .parse()
+            .expect("failed to create authorization metadata"),
+    );
+
+    request
+}
+
+// registers a user with the password "strong password", which every user of the tests has
+pub async fn try_register_user(app: &App, username: &str, email: &str) -> Result<Token, Status> {
+    app.register(Request::new(RegisterRequest {
+        firstname: "atheer".into(),
+        lastname: "ABC".into(),
+        username: username.into(),
+        email: email.into(),
+        password: "strong password".into(),
+        ..Default::default()
+    }))
+    .await
+    .map(|response| response.into_inner())
+}
+
+pub async fn register_user_as(app: &App, username: &str, email: &str) -> Token {
+    try_register_user(app, username, email)
+        .await
+        .expect("failed to register user")
+}
+
+// the user that tests which only need one user register
+pub async fn register_user(app: &App) -> Token {
+    register_user_as(app, "atheer2104", "atheer@gmail.com").await
+}
+
+// like register_user, with the device that the session is labelled with
+pub async fn register_user_on_device(app: &App, device: &str) -> Token {
+    app.register(Request::new(RegisterRequest {
+        firstname: "atheer".into(),
+        lastname: "ABC".into(),
+        username: "atheer2104".into(),
+        email: "atheer@gmail.com".into(),
+        password: "strong password".into(),
+        device: device.into(),
+    }))
+    .await
+    .expect("failed to register user")
+    .into_inner()
+}
+
+pub async fn try_login_user(app: &App, identifier: &str, password: &str) -> Result<Token, Status> {
+    app.login(Request::new(LoginRequest {
+        identifier: identifier.into(),
+        password: password.into(),
+        ..Default::default()
+    }))
+    .await
+    .map(|response| response.into_inner())
+}
+
+// logs in as the user that register_user registers
+pub async fn login_user(app: &App) -> Token {
+    try_login_user(app, "atheer2104", "strong password")
+        .await
+        .expect("failed to login user")
+}
+
+pub async fn login_user_on_device(app: &App, device: &str) -> Token {
+    app.login(Request::new(LoginRequest {
+        identifier: "atheer2104".into(),
+        password: "strong password".into(),
+        device: device.into(),
+    }))
+    .await
+    .expect("failed to login user")
+    .into_inner()
+}
+
+// registers a user named admin and grants them the admin role outside of the RPCs, like auth-roles does. the returned
+// auth token already carries the role
+pub async fn register_admin(app: &App) -> Token {
+    let token = register_user_as(app, "admin", "admin@gmail.com").await;
+
+    let user_id = get_user_id_by_username_db(&app.db_pool, "admin")
+        .await
+        .expect("failed to get user_id")
+        .expect("admin doesn't exist");
+    grant_role_db(&app.db_pool, &user_id, Role::Admin)
+        .await
+        .expect("failed to grant admin role");
+
+    app.refresh(Request::new(RefreshRequest {
+        refresh_token: token.refresh_token,
+    }))
+    .await
+    .expect("failed to refresh auth token")
+    .into_inner()
 }
 
 pub async fn spawn_app() -> App {
+    spawn_app_with_configuration(|_| {}).await
+}
+
+// spawns the app with a configuration that has been changed by the test
+pub async fn spawn_app_with_configuration(modify_configuration: impl FnOnce(&mut Settings)) -> App {
     // we force evaluate TRACING
     Lazy::force(&TRACING);
 
     let address: SocketAddr = "[::1]:10000".parse().unwrap();
 
     let mut configuration = get_configuration().expect("Failed to read configuration.");
+    // every test talks to the same redis, so failed logins in one test could otherwise lock out the ones after it
+    configuration
+        .login_throttle
+        .max_failed_attempts_per_username = u32::MAX;
+    configuration.login_throttle.max_failed_attempts_per_ip = u32::MAX;
+    modify_configuration(&mut configuration);
     configuration.database.database_name = Uuid::new_v4().to_string();
+    // every test gets its own outbox so the mails of different tests don't mix
+    let outbox_directory =
+        std::env::temp_dir().join(format!("chat-grpc-outbox-{}", Uuid::new_v4()));
+    configuration.mailer.outbox_directory = outbox_directory.to_string_lossy().into_owned();
 
     let dummy_jwt_secret =
         String::from("04c1582b55ba64e0cd085d6edc23ab65578470ef03a8afb19897be536927f670");
 
     let dummy_secrets = Secrets {
-        jwt_secret: Secret::new(dummy_jwt_secret),
+        jwt_secret: Some(Secret::new(dummy_jwt_secret)),
+        jwt_private_key: Some(Secret::new(include_str!("jwt_private_key.pem").to_string())),
+        jwt_keyring: None,
+        totp_encryption_key: Secret::new(String::from(
+            "4b8c5e0a3f1d27a9e6b04c1d58f2a7e39c0d16b4a8e5f27c3d9b0a61e4f8c25d",
+        )),
     };
 
+    // the keyring is shared with the server so tests can swap the keys like a reload of the secrets file would
+    let jwt_keyring = JwtKeyring::new(
+        JwtKeys::from_secrets(&dummy_secrets, configuration.auth_token.signing_algorithm)
+            .expect("Failed to read jwt keys"),
+    );
+
     let connection_pool = configure_database(&configuration.database).await;
 
     let redis_client = redis::Client::open(configuration.redis_uri.expose_secret().to_owned())
@@ -115,13 +652,27 @@ pub async fn spawn_app() -> App {
         connection_pool.clone(),
         redis_con.clone(),
         dummy_secrets.clone(),
+        jwt_keyring.clone(),
+        &configuration,
     );
-    tokio::spawn(server.serve(address));
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+    let server_handle = tokio::spawn(serve_until_shutdown(
+        server,
+        address,
+        async {
+            let _ = shutdown_receiver.await;
+        },
+        configuration.shutdown.drain_deadline(),
+    ));
 
     App {
         address: address.to_string(),
         db_pool: connection_pool,
         redis_con: Arc::new(Mutex::new(redis_con)),
         dummy_secrets,
+        jwt_keyring,
+        outbox_directory,
+        shutdown_sender: Some(shutdown_sender),
+        server_handle: Some(server_handle),
     }
 }
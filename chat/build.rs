@@ -16,5 +16,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // first argumetn is for the files we want to compile, second argumeent is the root location for these files
         .compile(&[proto_file], &["proto"])?;
 
+    // only the client of the auth service is needed, it's used to get the keys that auth tokens are verified with
+    tonic_build::configure()
+        .build_server(false)
+        .out_dir("src")
+        .compile(&["../auth/proto/auth.proto"], &["../auth/proto"])?;
+
     Ok(())
 }
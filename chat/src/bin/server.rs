@@ -1,10 +1,15 @@
 use std::net::SocketAddr;
 
 use chat::{
-    configuration::get_configuration,
+    configuration::{get_configuration, JwtAlgorithm},
     logging::{get_subscriber, init_subscriber},
-    server::build_server,
+    server::{
+        build_server, serve_until_shutdown, shutdown_signal, sync_api_keys, sync_revocation_list,
+        sync_signing_keys, ApiKeyList, RevocationList, SigningKeys,
+    },
 };
+use secrecy::ExposeSecret;
+use tokio::sync::watch;
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
@@ -19,15 +24,63 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let configuration = get_configuration().expect("Failed to read config file");
 
+    tracing::info!("Creating redis client");
+
+    let redis_client = redis::Client::open(configuration.redis_uri.expose_secret().to_owned())?;
+
+    tracing::info!("Successfully created redis client");
+
+    let revocation_list = RevocationList::new();
+    tokio::spawn(sync_revocation_list(
+        redis_client.clone(),
+        revocation_list.clone(),
+    ));
+
+    let api_keys = ApiKeyList::new();
+    tokio::spawn(sync_api_keys(redis_client.clone(), api_keys.clone()));
+
+    // auth tokens signed with HMAC are verified with the jwt_secret instead
+    let signing_keys = SigningKeys::new();
+    if configuration.signing_algorithm == JwtAlgorithm::Rs256 {
+        tokio::spawn(sync_signing_keys(
+            configuration.auth_service_uri.clone(),
+            signing_keys.clone(),
+            configuration.signing_keys_refresh_interval(),
+        ));
+    }
+
+    // chat streams are closed with a "going away" status once the server has been asked to stop
+    let (going_away_sender, going_away) = watch::channel(false);
+
     tracing::info!("Building gRPC Server");
 
-    let server = build_server();
+    let server = build_server(
+        revocation_list,
+        signing_keys,
+        api_keys,
+        redis_client,
+        going_away,
+        &configuration,
+    );
 
     tracing::info!("Succesfully built gRPC Server");
 
     let address: SocketAddr = format!("[::1]:{}", configuration.application_port).parse()?;
 
-    server.serve(address).await?;
+    let shutdown = async move {
+        shutdown_signal().await;
+
+        tracing::info!("Telling chat clients that the server is going away");
+        let _ = going_away_sender.send(true);
+    };
+
+    serve_until_shutdown(
+        server,
+        address,
+        shutdown,
+        configuration.shutdown_drain_deadline(),
+    )
+    .await?;
 
     tracing::info!("Successfully served Server");
 
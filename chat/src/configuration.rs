@@ -1,6 +1,45 @@
+use std::time::Duration;
+
+use secrecy::Secret;
+
 #[derive(serde::Deserialize)]
 pub struct Settings {
     pub application_port: u16,
+    pub redis_uri: Secret<String>,
+    pub require_verified_email: bool,
+    #[serde(default)]
+    pub signing_algorithm: JwtAlgorithm,
+    pub auth_service_uri: String,
+    pub signing_keys_refresh_seconds: u64,
+    pub health_check_seconds: u64,
+    pub health_check_timeout_seconds: u64,
+    pub shutdown_drain_deadline_seconds: u64,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum JwtAlgorithm {
+    #[default]
+    Hs512,
+    Rs256,
+}
+
+impl Settings {
+    pub fn signing_keys_refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.signing_keys_refresh_seconds)
+    }
+
+    pub fn health_check_interval(&self) -> Duration {
+        Duration::from_secs(self.health_check_seconds)
+    }
+
+    pub fn health_check_timeout(&self) -> Duration {
+        Duration::from_secs(self.health_check_timeout_seconds)
+    }
+
+    pub fn shutdown_drain_deadline(&self) -> Duration {
+        Duration::from_secs(self.shutdown_drain_deadline_seconds)
+    }
 }
 
 pub fn get_configuration() -> Result<Settings, config::ConfigError> {
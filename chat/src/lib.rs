@@ -1,3 +1,5 @@
+// the generated code names the stream type after the lowercase `chat` rpc
+#[allow(non_camel_case_types)]
 pub mod chat;
 pub mod configuration;
 pub mod logging;
@@ -0,0 +1 @@
+include!("../authentication.rs");
@@ -1,8 +1,54 @@
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
+use token_claims::{TokenVerifier, VerifyingKey};
+
+// the kid of auth tokens that are signed with the jwt_secret, when the secrets file doesn't have a keyring
+const JWT_SECRET_KID: &str = "jwt_secret";
 
 #[derive(serde::Deserialize, Clone)]
 pub struct Secrets {
-    pub jwt_secret: Secret<String>,
+    pub jwt_secret: Option<Secret<String>>,
+    // the HMAC secrets that auth tokens are signed with, takes the place of the jwt_secret
+    pub jwt_keyring: Option<JwtKeyringSecret>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct JwtKeyringSecret {
+    pub active_kid: String,
+    pub keys: Vec<JwtKeySecret>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct JwtKeySecret {
+    pub kid: String,
+    pub key: Secret<String>,
+}
+
+impl Secrets {
+    // every key of the keyring is accepted, not only the active one, so auth tokens stay valid after a rotation
+    pub fn token_verifier(&self) -> TokenVerifier {
+        let mut verifier = TokenVerifier::new();
+
+        match &self.jwt_keyring {
+            Some(keyring) => {
+                for key in &keyring.keys {
+                    verifier.add_key(
+                        key.kid.clone(),
+                        VerifyingKey::hmac(key.key.expose_secret().as_bytes()),
+                    );
+                }
+            }
+            None => {
+                if let Some(jwt_secret) = &self.jwt_secret {
+                    verifier.add_key(
+                        JWT_SECRET_KID,
+                        VerifyingKey::hmac(jwt_secret.expose_secret().as_bytes()),
+                    );
+                }
+            }
+        }
+
+        verifier
+    }
 }
 
 pub fn get_secrets() -> Result<Secrets, config::ConfigError> {
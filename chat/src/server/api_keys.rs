@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use redis::{AsyncCommands, Client};
+use token_claims::Scope;
+use tokio_stream::StreamExt;
+
+// these have to match what the auth service writes into redis when it creates or revokes an API key
+const API_KEY_PREFIX_REDIS: &str = "api_key";
+const API_KEYS_CHANNEL: &str = "api_keys";
+
+// how long to wait before reconnecting to redis when the subscription is lost
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// the service account that an API key belongs to and what the API key is allowed to do
+#[derive(Clone, Debug)]
+pub struct ApiKeyIdentity {
+    pub key_id: String,
+    pub service_account_id: i32,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+// in-memory copy of the API keys in redis, keyed by the hash of the API key so the interceptor can look them up
+// without having to await anything
+#[derive(Clone, Debug, Default)]
+pub struct ApiKeyList {
+    api_keys: Arc<RwLock<HashMap<String, ApiKeyIdentity>>>,
+}
+
+impl ApiKeyList {
+    pub fn new() -> ApiKeyList {
+        Self::default()
+    }
+
+    pub fn get(&self, key_hash: &str) -> Option<ApiKeyIdentity> {
+        self.api_keys
+            .read()
+            .expect("API key list lock is poisoned")
+            .get(key_hash)
+            .cloned()
+    }
+
+    pub fn insert(&self, key_hash: String, identity: ApiKeyIdentity) {
+        self.api_keys
+            .write()
+            .expect("API key list lock is poisoned")
+            .insert(key_hash, identity);
+    }
+
+    pub fn remove(&self, key_hash: &str) {
+        self.api_keys
+            .write()
+            .expect("API key list lock is poisoned")
+            .remove(key_hash);
+    }
+
+    // API keys that were revoked while the subscription was lost have to go as well, so everything is replaced
+    fn replace(&self, api_keys: HashMap<String, ApiKeyIdentity>) {
+        *self
+            .api_keys
+            .write()
+            .expect("API key list lock is poisoned") = api_keys;
+    }
+}
+
+// keeps the API key list in sync with redis for as long as the server is running
+pub async fn sync_api_keys(redis_client: Client, api_key_list: ApiKeyList) {
+    loop {
+        if let Err(e) = subscribe_to_api_keys(&redis_client, &api_key_list).await {
+            tracing::error!("Lost subscription to API keys: {:?}", e);
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn subscribe_to_api_keys(
+    redis_client: &Client,
+    api_key_list: &ApiKeyList,
+) -> Result<(), anyhow::Error> {
+    let mut pubsub = redis_client.get_async_pubsub().await?;
+    // we subscribe before loading the existing API keys so that nothing falls between the two
+    pubsub.subscribe(API_KEYS_CHANNEL).await?;
+
+    let mut redis_con = redis_client.get_multiplexed_async_connection().await?;
+
+    load_api_keys(&mut redis_con, api_key_list).await?;
+
+    tracing::info!("Subscribed to API keys");
+
+    // only the hash is published, whether the API key has been added or removed is seen in redis itself
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let key_hash: String = message.get_payload()?;
+
+        let value: Option<String> = redis_con
+            .get(format!("{}:{}", API_KEY_PREFIX_REDIS, key_hash))
+            .await?;
+
+        match value.map(|value| parse_api_key(&value)) {
+            Some(Some(identity)) => {
+                tracing::info!("API key has been added");
+                api_key_list.insert(key_hash, identity);
+            }
+            Some(None) => tracing::error!("Received malformed API key"),
+            None => {
+                tracing::info!("API key has been revoked");
+                api_key_list.remove(&key_hash);
+            }
+        }
+    }
+
+    Err(anyhow!("redis closed the subscription"))
+}
+
+async fn load_api_keys(
+    redis_con: &mut redis::aio::MultiplexedConnection,
+    api_key_list: &ApiKeyList,
+) -> Result<(), anyhow::Error> {
+    let keys: Vec<String> = {
+        let mut iter = redis_con
+            .scan_match::<_, String>(format!("{}:*", API_KEY_PREFIX_REDIS))
+            .await?;
+
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        keys
+    };
+
+    let mut api_keys = HashMap::new();
+
+    for key in keys {
+        // the API key might have been revoked in between
+        let value: Option<String> = redis_con.get(&key).await?;
+
+        if let (Some(key_hash), Some(identity)) = (
+            key.strip_prefix(&format!("{}:", API_KEY_PREFIX_REDIS)),
+            value.as_deref().and_then(parse_api_key),
+        ) {
+            api_keys.insert(key_hash.to_string(), identity);
+        }
+    }
+
+    api_key_list.replace(api_keys);
+
+    Ok(())
+}
+
+// API keys are stored as "<key id> <service account id> <service account name> <comma separated scopes>", scopes
+// that aren't known are left out
+fn parse_api_key(value: &str) -> Option<ApiKeyIdentity> {
+    let mut parts = value.splitn(4, ' ');
+
+    let key_id = parts.next()?.to_string();
+    let service_account_id = parts.next()?.parse().ok()?;
+    let name = parts.next()?.to_string();
+    let scopes = parts
+        .next()?
+        .split(',')
+        .filter_map(|scope| scope.parse().ok())
+        .collect();
+
+    Some(ApiKeyIdentity {
+        key_id,
+        service_account_id,
+        name,
+        scopes,
+    })
+}
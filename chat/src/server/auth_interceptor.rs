@@ -1,76 +1,215 @@
-use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
 
+use crate::configuration::JwtAlgorithm;
 use crate::secret::get_secrets;
-use chrono::Local;
-use hmac::{Hmac, Mac};
-use jwt::VerifyWithKey;
-use secrecy::ExposeSecret;
-use sha2::Sha512;
-use tonic::{Request, Status};
-
-macro_rules! check_claim_key {
-    ($claims:expr, $claim_key:expr, $true_claim:expr, $error_msg:expr) => {
-        if !$claims.contains_key($claim_key) {
-            return Err(Status::invalid_argument(format!(
-                "JWT auth token does not contain {} key",
-                $claim_key
-            )));
-        }
+use token_claims::{get_api_key, get_bearer_token, hash_api_key, Claims, Role, Scope, TokenError};
+use tonic::{service::Interceptor, Request, Status};
+
+use super::{ApiKeyList, RevocationList, RpcPath, SigningKeys};
+
+// who a request has been made by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Caller {
+    // a user that logged in through the auth service
+    User(i32),
+    // a service account using one of its API keys
+    ServiceAccount(i32),
+}
 
-        if $claims[$claim_key] != $true_claim {
-            return Err(Status::unauthenticated($error_msg));
+impl fmt::Display for Caller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Caller::User(user_id) => write!(f, "user {}", user_id),
+            Caller::ServiceAccount(service_account_id) => {
+                write!(f, "service account {}", service_account_id)
+            }
         }
-    };
+    }
+}
+
+// the auth token or API key that was used for the request, it's put into the request extensions by the interceptor
+#[derive(Clone, Debug)]
+pub struct VerifiedToken {
+    // both are the key id for API keys
+    pub jti: String,
+    pub session_id: String,
+    pub caller: Caller,
+    pub username: String,
+    pub roles: Vec<String>,
+    // users can do everything, API keys only what they have been created for
+    pub scopes: Vec<Scope>,
+}
+
+impl VerifiedToken {
+    // a token is revoked either on its own or together with the rest of its session
+    pub fn is_revoked_by(&self, revoked_id: &str) -> bool {
+        self.jti == revoked_id || self.session_id == revoked_id
+    }
+
+    pub fn is_revoked(&self, revocation_list: &RevocationList) -> bool {
+        revocation_list.is_revoked(&self.jti) || revocation_list.is_revoked(&self.session_id)
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
 }
 
-macro_rules! check_claim_expired {
-    ($claim:expr, $error_msg:expr) => {
-        let now_timestamp = Local::now().timestamp().to_string();
-        if now_timestamp > $claim {
-            return Err(Status::unauthenticated($error_msg));
+// what is needed to call the RPCs of a service, keyed by the path of the RPC. auth tokens need one of the roles of
+// an RPC, an RPC that isn't listed only needs a valid auth token. API keys need one of the scopes of an RPC and
+// can't call RPCs that aren't listed
+#[derive(Clone, Debug, Default)]
+pub struct RpcPermissions {
+    roles: HashMap<String, Vec<Role>>,
+    scopes: HashMap<String, Vec<Scope>>,
+}
+
+impl RpcPermissions {
+    pub fn new() -> RpcPermissions {
+        Self::default()
+    }
+
+    // the auth token has to carry at least one of the roles to call the RPC
+    pub fn require_roles(mut self, rpc_path: impl Into<String>, roles: &[Role]) -> RpcPermissions {
+        self.roles.insert(rpc_path.into(), roles.to_vec());
+        self
+    }
+
+    // API keys with at least one of the scopes can call the RPC
+    pub fn allow_scopes(mut self, rpc_path: impl Into<String>, scopes: &[Scope]) -> RpcPermissions {
+        self.scopes.insert(rpc_path.into(), scopes.to_vec());
+        self
+    }
+
+    // an auth token is allowed to call the RPC when it carries at least one of the roles that the RPC requires
+    pub fn allows_roles(&self, rpc_path: Option<&RpcPath>, claims: &Claims) -> bool {
+        if self.roles.is_empty() {
+            return true;
         }
-    };
+
+        // without the path there's no telling which RPC is called, so it's refused rather than let through
+        let rpc_path = match rpc_path {
+            Some(rpc_path) => rpc_path,
+            None => return false,
+        };
+
+        match self.roles.get(&rpc_path.0) {
+            Some(roles) => roles.iter().any(|role| claims.has_role(*role)),
+            None => true,
+        }
+    }
+
+    pub fn allows_scopes(&self, rpc_path: Option<&RpcPath>, scopes: &[Scope]) -> bool {
+        rpc_path
+            .and_then(|rpc_path| self.scopes.get(&rpc_path.0))
+            .is_some_and(|allowed| allowed.iter().any(|scope| scopes.contains(scope)))
+    }
 }
 
-pub fn auth_interceptor(req: Request<()>) -> Result<Request<()>, Status> {
-    match req.metadata().get("authorization") {
-        Some(t) => {
-            tracing::info!("Reading secrets");
-
-            let secrets = match get_secrets() {
-                Ok(s) => s,
-                Err(_) => return Err(Status::internal("couldn't read auth secret key")),
-            };
-
-            let key: Hmac<Sha512> =
-                Hmac::new_from_slice(secrets.jwt_secret.expose_secret().as_bytes()).unwrap();
-
-            let bearer_token = match t.to_str() {
-                Ok(t) => t,
-                Err(_) => {
-                    return Err(Status::invalid_argument(
-                        "the provided jwt token does not contain valid ASCII chars",
-                    ))
+#[derive(Clone)]
+pub struct AuthInterceptor {
+    pub revocation_list: RevocationList,
+    pub require_verified_email: bool,
+    pub signing_algorithm: JwtAlgorithm,
+    pub signing_keys: SigningKeys,
+    pub api_keys: ApiKeyList,
+    // the service has to be wrapped in WithRpcPath for these to be checked
+    pub rpc_permissions: RpcPermissions,
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        // the auth token is used when a request carries both
+        let verified_token = match get_api_key(req.metadata()) {
+            Some(api_key) if !req.metadata().contains_key("authorization") => {
+                let identity = match self.api_keys.get(&hash_api_key(api_key?)) {
+                    Some(identity) => identity,
+                    None => return Err(Status::unauthenticated("API key is not valid")),
+                };
+
+                if !self
+                    .rpc_permissions
+                    .allows_scopes(req.extensions().get::<RpcPath>(), &identity.scopes)
+                {
+                    return Err(Status::permission_denied(
+                        "API key doesn't have the scope that is required",
+                    ));
                 }
-            };
 
-            let mut token_split_stream = bearer_token.split_whitespace();
-            token_split_stream.next();
-            let token_str = match token_split_stream.next() {
-                Some(t) => t,
-                None => return Err(Status::internal("Couldn't destructure the auth token")),
-            };
+                VerifiedToken {
+                    jti: identity.key_id.clone(),
+                    session_id: identity.key_id,
+                    caller: Caller::ServiceAccount(identity.service_account_id),
+                    // parentheses aren't allowed in usernames, so service accounts can't be mistaken for users
+                    username: format!("{} (bot)", identity.name),
+                    roles: Vec::new(),
+                    scopes: identity.scopes,
+                }
+            }
+            _ => {
+                let token_str = get_bearer_token(req.metadata())?;
 
-            let claims: BTreeMap<String, String> = token_str.verify_with_key(&key).unwrap();
+                // the auth token has to be signed with the algorithm that has been configured, so it can't pick a
+                // weaker one
+                let claims = match self.signing_algorithm {
+                    JwtAlgorithm::Hs512 => {
+                        tracing::info!("Reading secrets");
 
-            check_claim_key!(&claims, "iss", "Chat-gRPC", "JWT iss does not match");
-            check_claim_key!(&claims, "sub", "auth token", "JWT sub doese not match");
-            check_claim_expired!(claims["exp"], "Auth token has expired");
+                        // the secrets are read for every request, so keys that have been added to the keyring are
+                        // picked up right away
+                        let secrets = match get_secrets() {
+                            Ok(s) => s,
+                            Err(_) => {
+                                return Err(Status::internal("couldn't read auth secret key"))
+                            }
+                        };
 
-            tracing::info!("access token was valid");
+                        secrets.token_verifier().verify(token_str)?
+                    }
+                    JwtAlgorithm::Rs256 => self.signing_keys.verifier().verify(token_str)?,
+                };
 
-            Ok(req)
+                // messages are sent in the name of the username, so auth tokens from before the claim existed can't
+                // be used
+                if claims.username.is_empty() {
+                    return Err(TokenError::WrongClaim("username").into());
+                }
+
+                if self.require_verified_email && !claims.email_verified {
+                    return Err(Status::permission_denied(
+                        "Email address has not been verified",
+                    ));
+                }
+
+                if !self
+                    .rpc_permissions
+                    .allows_roles(req.extensions().get::<RpcPath>(), &claims)
+                {
+                    return Err(Status::permission_denied(
+                        "Auth token doesn't have the role that is required",
+                    ));
+                }
+
+                VerifiedToken {
+                    jti: claims.jti.to_string(),
+                    session_id: claims.session_id.to_string(),
+                    caller: Caller::User(claims.user_id),
+                    username: claims.username,
+                    roles: claims.roles,
+                    scopes: Scope::ALL.to_vec(),
+                }
+            }
+        };
+
+        if verified_token.is_revoked(&self.revocation_list) {
+            return Err(TokenError::Revoked.into());
         }
-        None => Err(Status::unauthenticated("no valid auth token")),
+
+        tracing::info!("access token was valid");
+
+        req.extensions_mut().insert(verified_token);
+
+        Ok(req)
     }
 }
@@ -1,15 +1,59 @@
 use std::{pin::Pin, sync::Arc};
 
-use tokio::sync::{broadcast, Mutex};
+use tokio::sync::{broadcast, oneshot, watch, Mutex};
 
+use token_claims::Scope;
 use tonic::{Request, Response, Status};
 
 use crate::proto::chat::{chatting_server::Chatting, ChatMessage};
 
 use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+use super::{RevocationList, VerifiedToken};
+
 #[derive(Debug)]
 pub struct ChatService {
     pub sender: Arc<Mutex<broadcast::Sender<ChatMessage>>>,
+    pub revocation_list: RevocationList,
+    // becomes true once the server has been asked to stop
+    pub going_away: watch::Receiver<bool>,
+}
+
+// resolves once the auth token or its session has been revoked
+async fn wait_for_revocation(
+    revocation_list: &RevocationList,
+    revocations: &mut broadcast::Receiver<String>,
+    verified_token: &VerifiedToken,
+) {
+    loop {
+        match revocations.recv().await {
+            Ok(revoked_id) if verified_token.is_revoked_by(&revoked_id) => return,
+            Ok(_) => {}
+            // we might have missed our own revocation so the revocation list is checked directly
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                if verified_token.is_revoked(revocation_list) {
+                    return;
+                }
+            }
+            // the revocation list lives as long as the server so this shouldn't happen, just keep the stream open
+            Err(broadcast::error::RecvError::Closed) => std::future::pending::<()>().await,
+        }
+    }
+}
+
+// resolves once the server is going away
+async fn wait_for_going_away(going_away: &mut watch::Receiver<bool>) {
+    // the sender lives as long as the server so an error means it's stopping anyway
+    let _ = going_away.wait_for(|going_away| *going_away).await;
+}
+
+// resolves with the status that the chat stream has to be closed with, it never resolves when the incoming messages
+// just end
+async fn wait_for_denial(receiver: oneshot::Receiver<Status>) -> Status {
+    match receiver.await {
+        Ok(status) => status,
+        Err(_) => std::future::pending().await,
+    }
 }
 
 #[tonic::async_trait]
@@ -24,6 +68,11 @@ impl Chatting for ChatService {
         &self,
         request: Request<tonic::Streaming<ChatMessage>>,
     ) -> Result<Response<Self::chatStream>, Status> {
+        let verified_token = match request.extensions().get::<VerifiedToken>() {
+            Some(verified_token) => verified_token.clone(),
+            None => return Err(Status::unauthenticated("no valid auth token")),
+        };
+
         let mut incoming_data = request.into_inner();
 
         let receiver = self.sender.lock().await.subscribe();
@@ -31,29 +80,95 @@ impl Chatting for ChatService {
 
         let sender = self.sender.clone();
 
+        let mut incoming_revocations = self.revocation_list.subscribe();
+        let mut outbound_revocations = self.revocation_list.subscribe();
+        let incoming_revocation_list = self.revocation_list.clone();
+        let outbound_revocation_list = self.revocation_list.clone();
+        let incoming_verified_token = verified_token.clone();
+        let (denial_sender, denial_receiver) = oneshot::channel();
+        let mut incoming_going_away = self.going_away.clone();
+        let mut outbound_going_away = self.going_away.clone();
+
         tokio::spawn(async move {
-            while let Some(message) = incoming_data.next().await {
-                let message = match message {
-                    Ok(message) => message,
-                    Err(e) => {
+            loop {
+                let message = tokio::select! {
+                    message = incoming_data.next() => message,
+                    _ = wait_for_revocation(&incoming_revocation_list, &mut incoming_revocations, &incoming_verified_token) => break,
+                    _ = wait_for_going_away(&mut incoming_going_away) => break,
+                };
+
+                let mut message = match message {
+                    Some(Ok(message)) => message,
+                    Some(Err(e)) => {
                         tracing::error!("Error receiving message: {:?}", e);
                         break;
                     }
+                    None => break,
                 };
 
+                // API keys that can only read close the stream when they try to send anything
+                if !incoming_verified_token.has_scope(Scope::ChatPost) {
+                    tracing::warn!(
+                        "{} sent a message without being allowed to",
+                        incoming_verified_token.caller
+                    );
+                    let _ = denial_sender.send(Status::permission_denied(
+                        "API key doesn't have the chat:post scope",
+                    ));
+                    break;
+                }
+
+                // the author of a message is whoever the auth token belongs to, no matter what the client says
+                if !message.username.is_empty()
+                    && message.username != incoming_verified_token.username
+                {
+                    tracing::warn!(
+                        "{} sent a message as {}, sending it as {} instead",
+                        incoming_verified_token.caller,
+                        message.username,
+                        incoming_verified_token.username
+                    );
+                }
+                message.username = incoming_verified_token.username.clone();
+
                 let _ = sender.lock().await.send(message);
             }
         });
 
         // Map broadcast stream to tonic stream
         let output = async_stream::stream! {
-            while let Some(result) = outbound_messages.next().await {
+            let denial = wait_for_denial(denial_receiver);
+            tokio::pin!(denial);
+
+            loop {
+                let result = tokio::select! {
+                    result = outbound_messages.next() => result,
+                    _ = wait_for_revocation(&outbound_revocation_list, &mut outbound_revocations, &verified_token) => {
+                        tracing::info!("closing chat stream, auth token has been revoked");
+                        yield Err(Status::unauthenticated("Auth token has been revoked"));
+                        break;
+                    }
+                    status = &mut denial => {
+                        yield Err(status);
+                        break;
+                    }
+                    // the client can connect again once the server is back, or to another one
+                    _ = wait_for_going_away(&mut outbound_going_away) => {
+                        tracing::info!("closing chat stream, server is going away");
+                        yield Err(Status::unavailable("Chat server is going away, reconnect to keep chatting"));
+                        break;
+                    }
+                };
+
                 match result {
-                    Ok(message) => yield Ok(message),
-                    Err(e) => {
+                    // API keys that can only post don't get the messages of the chat
+                    Some(Ok(message)) if verified_token.has_scope(Scope::ChatRead) => yield Ok(message),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
                         eprintln!("Error in outbound stream: {:?}", e);
                         yield Err(Status::internal("Error in outbound stream"));
                     }
+                    None => break,
                 }
             }
         };
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use redis::Client;
+use tokio::sync::watch;
+use tonic_health::{server::HealthReporter, ServingStatus};
+
+use crate::proto::chat::chatting_server::ChattingServer;
+
+use super::{fetch_signing_keys, ChatService};
+
+// the dependencies that auth tokens and API keys are verified with are reported under this name, next to the
+// Chatting service itself
+pub const TOKEN_VERIFICATION_HEALTH_SERVICE: &str = "token-verification";
+
+// keeps the health of the chat service up to date for as long as the server is running. the Chatting service keeps
+// serving when its dependencies can't be reached, since auth tokens are still verified with the signing keys and
+// revocations that it already knows about, so whether they can be reached is reported on its own. once the server is
+// going away everything is reported as not serving, so no new chat clients are sent here while the open streams close
+pub async fn report_health(
+    mut health_reporter: HealthReporter,
+    redis_client: Client,
+    // only set when the signing keys are fetched from the auth service
+    auth_service_uri: Option<String>,
+    interval: Duration,
+    timeout: Duration,
+    mut going_away: watch::Receiver<bool>,
+) {
+    health_reporter
+        .set_serving::<ChattingServer<ChatService>>()
+        .await;
+
+    let mut reported_status = None;
+
+    loop {
+        let redis_reachable = ping_redis(&redis_client, timeout).await;
+        let auth_service_reachable = match &auth_service_uri {
+            Some(auth_service_uri) => ping_auth_service(auth_service_uri, timeout).await,
+            None => true,
+        };
+
+        let status = if redis_reachable && auth_service_reachable {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+
+        // only changes are reported, so the log isn't flooded while a dependency is down
+        if reported_status != Some(status) {
+            match status {
+                ServingStatus::Serving => tracing::info!("Token verification is serving"),
+                _ => tracing::error!(
+                    "Token verification is not serving, redis reachable: {}, auth service reachable: {}",
+                    redis_reachable,
+                    auth_service_reachable
+                ),
+            }
+
+            health_reporter
+                .set_service_status(TOKEN_VERIFICATION_HEALTH_SERVICE, status)
+                .await;
+
+            reported_status = Some(status);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            // the sender lives as long as the server so an error means it's stopping anyway
+            _ = going_away.wait_for(|going_away| *going_away) => break,
+        }
+    }
+
+    tracing::info!("Chat service is not serving, the server is going away");
+
+    health_reporter
+        .set_not_serving::<ChattingServer<ChatService>>()
+        .await;
+    // the empty name is the server as a whole
+    health_reporter
+        .set_service_status("", ServingStatus::NotServing)
+        .await;
+}
+
+async fn ping_redis(redis_client: &Client, timeout: Duration) -> bool {
+    let ping = async {
+        let mut redis_con = redis_client.get_multiplexed_async_connection().await?;
+        redis::cmd("PING")
+            .query_async::<_, ()>(&mut redis_con)
+            .await
+    };
+
+    match tokio::time::timeout(timeout, ping).await {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to ping redis: {:?}", e);
+            false
+        }
+        Err(_) => {
+            tracing::error!("Pinging redis timed out");
+            false
+        }
+    }
+}
+
+// the auth service is reachable when it hands out its signing keys, which is all that it's needed for
+async fn ping_auth_service(auth_service_uri: &str, timeout: Duration) -> bool {
+    match tokio::time::timeout(timeout, fetch_signing_keys(auth_service_uri)).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to reach the auth service: {:?}", e);
+            false
+        }
+        Err(_) => {
+            tracing::error!("Reaching the auth service timed out");
+            false
+        }
+    }
+}
@@ -1,7 +1,19 @@
+mod api_keys;
 mod auth_interceptor;
 mod chat_service;
+mod health;
+mod revocation;
+mod rpc_path;
+mod shutdown;
+mod signing_keys;
 mod startup;
 
+pub use api_keys::*;
 pub use auth_interceptor::*;
 pub use chat_service::*;
+pub use health::*;
+pub use revocation::*;
+pub use rpc_path::*;
+pub use shutdown::*;
+pub use signing_keys::*;
 pub use startup::*;
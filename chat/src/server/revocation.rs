@@ -0,0 +1,149 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use chrono::Local;
+use redis::{AsyncCommands, Client};
+use tokio::sync::broadcast;
+use tokio_stream::StreamExt;
+
+// these have to match what the auth service writes into redis when it revokes a token
+const REVOKED_TOKEN_PREFIX: &str = "revoked_token";
+const REVOKED_TOKENS_CHANNEL: &str = "revoked_tokens";
+
+// how long to wait before reconnecting to redis when the subscription is lost
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// in-memory copy of the revoked auth tokens in redis, this is what makes it possible for the interceptor to check
+// revocations without having to await anything
+#[derive(Clone, Debug)]
+pub struct RevocationList {
+    // jti of the revoked token or id of the revoked session -> the time its tokens expire anyway
+    revoked: Arc<RwLock<HashMap<String, i64>>>,
+    // used to notify open chat streams about a token that was just revoked
+    sender: broadcast::Sender<String>,
+}
+
+impl Default for RevocationList {
+    fn default() -> RevocationList {
+        let (sender, _) = broadcast::channel(100);
+        Self {
+            revoked: Arc::new(RwLock::new(HashMap::new())),
+            sender,
+        }
+    }
+}
+
+impl RevocationList {
+    pub fn new() -> RevocationList {
+        Self::default()
+    }
+
+    pub fn is_revoked(&self, revoked_id: &str) -> bool {
+        self.revoked
+            .read()
+            .expect("revocation list lock is poisoned")
+            .contains_key(revoked_id)
+    }
+
+    pub fn revoke(&self, revoked_id: String, expiration_timestamp: i64) {
+        let mut revoked = self
+            .revoked
+            .write()
+            .expect("revocation list lock is poisoned");
+
+        // expired tokens are rejected by the interceptor anyway so there is no need to remember them
+        let now_timestamp = Local::now().timestamp();
+        revoked.retain(|_, exp| *exp > now_timestamp);
+
+        revoked.insert(revoked_id.clone(), expiration_timestamp);
+
+        // there might not be any open streams which is fine
+        let _ = self.sender.send(revoked_id);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+}
+
+// keeps the revocation list in sync with redis for as long as the server is running
+pub async fn sync_revocation_list(redis_client: Client, revocation_list: RevocationList) {
+    loop {
+        if let Err(e) = subscribe_to_revocations(&redis_client, &revocation_list).await {
+            tracing::error!("Lost subscription to revoked auth tokens: {:?}", e);
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn subscribe_to_revocations(
+    redis_client: &Client,
+    revocation_list: &RevocationList,
+) -> Result<(), anyhow::Error> {
+    let mut pubsub = redis_client.get_async_pubsub().await?;
+    // we subscribe before loading the existing revocations so that nothing falls between the two
+    pubsub.subscribe(REVOKED_TOKENS_CHANNEL).await?;
+
+    load_revoked_tokens(redis_client, revocation_list).await?;
+
+    tracing::info!("Subscribed to revoked auth tokens");
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let payload: String = message.get_payload()?;
+
+        match parse_revocation(&payload) {
+            Some((revoked_id, expiration_timestamp)) => {
+                tracing::info!("auth token has been revoked");
+                revocation_list.revoke(revoked_id, expiration_timestamp)
+            }
+            None => tracing::error!("Received malformed revocation: {}", payload),
+        }
+    }
+
+    Err(anyhow!("redis closed the subscription"))
+}
+
+async fn load_revoked_tokens(
+    redis_client: &Client,
+    revocation_list: &RevocationList,
+) -> Result<(), anyhow::Error> {
+    let mut redis_con = redis_client.get_multiplexed_async_connection().await?;
+
+    let keys: Vec<String> = {
+        let mut iter = redis_con
+            .scan_match::<_, String>(format!("{}:*", REVOKED_TOKEN_PREFIX))
+            .await?;
+
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        keys
+    };
+
+    for key in keys {
+        // the key might have expired in between
+        let expiration_timestamp: Option<i64> = redis_con.get(&key).await?;
+
+        if let (Some(revoked_id), Some(expiration_timestamp)) = (
+            key.strip_prefix(&format!("{}:", REVOKED_TOKEN_PREFIX)),
+            expiration_timestamp,
+        ) {
+            revocation_list.revoke(revoked_id.to_string(), expiration_timestamp);
+        }
+    }
+
+    Ok(())
+}
+
+// revocations are published as "<jti or session id> <expiration timestamp>"
+fn parse_revocation(payload: &str) -> Option<(String, i64)> {
+    let (revoked_id, expiration_timestamp) = payload.split_once(' ')?;
+    Some((revoked_id.to_string(), expiration_timestamp.parse().ok()?))
+}
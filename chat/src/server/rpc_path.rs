@@ -0,0 +1,43 @@
+use tonic::codegen::{http, Context, Poll, Service};
+use tonic::server::NamedService;
+
+// the path of the RPC that a request is for, like "/chat.Chatting/chat"
+#[derive(Clone, Debug)]
+pub struct RpcPath(pub String);
+
+// an interceptor only gets to see the metadata and extensions of a request, so this puts the path of the RPC into the
+// extensions before the request reaches the interceptor
+#[derive(Clone, Debug)]
+pub struct WithRpcPath<S> {
+    inner: S,
+}
+
+impl<S> WithRpcPath<S> {
+    pub fn new(inner: S) -> WithRpcPath<S> {
+        WithRpcPath { inner }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for WithRpcPath<S>
+where
+    S: Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let rpc_path = RpcPath(req.uri().path().to_string());
+        req.extensions_mut().insert(rpc_path);
+
+        self.inner.call(req)
+    }
+}
+
+impl<S: NamedService> NamedService for WithRpcPath<S> {
+    const NAME: &'static str = S::NAME;
+}
@@ -0,0 +1,63 @@
+use std::{future::Future, net::SocketAddr, time::Duration};
+
+use tokio::sync::oneshot;
+use tonic::transport::server::Router;
+
+// resolves once the process has been asked to stop, either with ctrl-c or with SIGTERM like docker sends
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for ctrl-c");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received ctrl-c"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+// serves until shutdown resolves, then stops accepting connections and gives the requests that are being answered
+// until the drain deadline to finish. the server stops waiting for the requests that are still left after that, they
+// end with the process
+pub async fn serve_until_shutdown(
+    server: Router,
+    address: SocketAddr,
+    shutdown: impl Future<Output = ()>,
+    drain_deadline: Duration,
+) -> Result<(), tonic::transport::Error> {
+    let (draining_sender, draining_receiver) = oneshot::channel();
+
+    let serve = server.serve_with_shutdown(address, async move {
+        shutdown.await;
+        let _ = draining_sender.send(());
+    });
+    tokio::pin!(serve);
+
+    tokio::select! {
+        result = &mut serve => return result,
+        _ = draining_receiver => tracing::info!("Draining connections"),
+    }
+
+    match tokio::time::timeout(drain_deadline, serve).await {
+        Ok(result) => {
+            tracing::info!("Every connection has been drained");
+            result
+        }
+        Err(_) => {
+            tracing::warn!("Drain deadline has passed, not waiting for the requests that are left");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use data_encoding::BASE64URL_NOPAD;
+use rsa::{BigUint, RsaPublicKey};
+use token_claims::{TokenVerifier, VerifyingKey};
+
+use crate::proto::auth::{auth_client::AuthClient, GetSigningKeysRequest, SigningKey};
+
+// how long to wait before asking the auth service again when fetching the keys failed
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+// in-memory copy of the public keys of the auth service, like the revocation list this lets the interceptor verify
+// auth tokens without having to await anything
+#[derive(Clone, Default)]
+pub struct SigningKeys {
+    verifier: Arc<RwLock<Arc<TokenVerifier>>>,
+}
+
+impl SigningKeys {
+    pub fn new() -> SigningKeys {
+        Self::default()
+    }
+
+    pub fn verifier(&self) -> Arc<TokenVerifier> {
+        self.verifier
+            .read()
+            .expect("signing keys lock is poisoned")
+            .clone()
+    }
+
+    fn replace(&self, verifier: TokenVerifier) {
+        *self
+            .verifier
+            .write()
+            .expect("signing keys lock is poisoned") = Arc::new(verifier);
+    }
+}
+
+// keeps the signing keys in sync with the auth service for as long as the server is running
+pub async fn sync_signing_keys(
+    auth_service_uri: String,
+    signing_keys: SigningKeys,
+    refresh_interval: Duration,
+) {
+    loop {
+        match fetch_signing_keys(&auth_service_uri).await {
+            Ok(verifier) => {
+                tracing::info!(
+                    "Fetched {} signing keys from the auth service",
+                    verifier.len()
+                );
+                signing_keys.replace(verifier);
+
+                tokio::time::sleep(refresh_interval).await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to fetch signing keys: {:?}", e);
+
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+pub async fn fetch_signing_keys(auth_service_uri: &str) -> Result<TokenVerifier, anyhow::Error> {
+    let mut client = AuthClient::connect(auth_service_uri.to_string()).await?;
+
+    let response = client
+        .get_signing_keys(GetSigningKeysRequest {})
+        .await
+        .map_err(|e| anyhow!("auth service responded with {}", e.message()))?;
+
+    let mut verifier = TokenVerifier::new();
+    for signing_key in response.into_inner().keys {
+        // keys of other algorithms can't be used
+        if signing_key.kty != "RSA" || signing_key.alg != "RS256" {
+            continue;
+        }
+
+        let kid = signing_key.kid.clone();
+        verifier.add_key(kid, parse_signing_key(signing_key)?);
+    }
+
+    Ok(verifier)
+}
+
+fn parse_signing_key(signing_key: SigningKey) -> Result<VerifyingKey, anyhow::Error> {
+    let n = BASE64URL_NOPAD.decode(signing_key.n.as_bytes())?;
+    let e = BASE64URL_NOPAD.decode(signing_key.e.as_bytes())?;
+
+    let public_key = RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e))?;
+
+    Ok(VerifyingKey::rs256(public_key))
+}
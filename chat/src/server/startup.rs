@@ -1,30 +1,80 @@
 use std::sync::Arc;
 
-use tokio::sync::{broadcast, Mutex};
+use redis::Client;
+use tokio::sync::{broadcast, watch, Mutex};
 
+use token_claims::{Role, Scope};
 use tonic::transport::{server::Router, Server};
 
+use crate::configuration::{JwtAlgorithm, Settings};
 use crate::proto::chat::{chatting_server::ChattingServer, FILE_DESCRIPTOR_SET};
 
-use super::{auth_interceptor, ChatService};
+use super::{
+    report_health, ApiKeyList, AuthInterceptor, ChatService, RevocationList, RpcPermissions,
+    SigningKeys, WithRpcPath,
+};
+
+pub fn build_server(
+    revocation_list: RevocationList,
+    signing_keys: SigningKeys,
+    api_keys: ApiKeyList,
+    redis_client: Client,
+    going_away: watch::Receiver<bool>,
+    configuration: &Settings,
+) -> Router {
+    // auth tokens signed with HMAC don't need the auth service to be verified
+    let auth_service_uri = match configuration.signing_algorithm {
+        JwtAlgorithm::Rs256 => Some(configuration.auth_service_uri.clone()),
+        JwtAlgorithm::Hs512 => None,
+    };
+
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    tokio::spawn(report_health(
+        health_reporter,
+        redis_client,
+        auth_service_uri,
+        configuration.health_check_interval(),
+        configuration.health_check_timeout(),
+        going_away.clone(),
+    ));
 
-pub fn build_server() -> Router {
     // how many values that the broadcast channel can keep
     let (sender, _) = broadcast::channel(100);
     let chat_service = ChatService {
         sender: Arc::new(Mutex::new(sender)),
+        revocation_list: revocation_list.clone(),
+        going_away,
     };
 
     let reflection_service = tonic_reflection::server::Builder::configure()
         .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
         .build()
         .unwrap();
 
+    // chatting is open to every user, so a user whose user role has been revoked can no longer chat. API keys can
+    // join with either scope, the chat service checks which of the two they have
+    let rpc_permissions = RpcPermissions::new()
+        .require_roles(
+            "/chat.Chatting/chat",
+            &[Role::User, Role::Moderator, Role::Admin],
+        )
+        .allow_scopes("/chat.Chatting/chat", &[Scope::ChatRead, Scope::ChatPost]);
+
+    let chat_server = ChattingServer::with_interceptor(
+        chat_service,
+        AuthInterceptor {
+            revocation_list,
+            require_verified_email: configuration.require_verified_email,
+            signing_algorithm: configuration.signing_algorithm,
+            signing_keys,
+            api_keys,
+            rpc_permissions,
+        },
+    );
+
     Server::builder()
-        // .add_service(health_service)
-        .add_service(ChattingServer::with_interceptor(
-            chat_service,
-            auth_interceptor,
-        ))
+        .add_service(health_service)
+        .add_service(WithRpcPath::new(chat_server))
         .add_service(reflection_service)
 }
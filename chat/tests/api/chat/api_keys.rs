@@ -0,0 +1,215 @@
+use auth::proto::auth::{
+    CreateApiKeyRequest, CreateApiKeyResponse, CreateServiceAccountRequest, RevokeApiKeyRequest,
+};
+use chat::chat::ChatMessage;
+use rand::{thread_rng, Rng};
+
+use super::{sleep, spawn_app, Code};
+use crate::helpers::{authenticated_request, register_admin, register_user, App};
+
+async fn create_service_account(app: &App, admin_access_token: &str) {
+    app.auth_client()
+        .await
+        .create_service_account(authenticated_request(
+            CreateServiceAccountRequest {
+                name: "weather-bot".into(),
+            },
+            admin_access_token,
+        ))
+        .await
+        .expect("failed to create service account");
+}
+
+async fn create_api_key(
+    app: &App,
+    admin_access_token: &str,
+    scopes: &[&str],
+) -> CreateApiKeyResponse {
+    let created = app
+        .auth_client()
+        .await
+        .create_api_key(authenticated_request(
+            CreateApiKeyRequest {
+                service_account: "weather-bot".into(),
+                scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            },
+            admin_access_token,
+        ))
+        .await
+        .expect("failed to create API key")
+        .into_inner();
+
+    // the chat service learns about the API key through redis
+    sleep(100).await;
+
+    created
+}
+
+#[tokio::test]
+async fn api_key_chats_as_its_service_account() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    create_service_account(&app, &admin_token.access_token).await;
+    let created =
+        create_api_key(&app, &admin_token.access_token, &["chat:read", "chat:post"]).await;
+
+    let mut chat = app
+        .open_chat_with_api_key(&created.api_key)
+        .await
+        .expect("failed to open chat stream");
+
+    chat.send(ChatMessage {
+        username: "atheer2104".into(),
+        message: "it's sunny".into(),
+        ..Default::default()
+    });
+
+    let message = chat
+        .next()
+        .await
+        .expect("failed to chat")
+        .expect("the chat stream has ended");
+    assert_eq!(message.username, "weather-bot (bot)");
+    assert_eq!(message.message, "it's sunny");
+}
+
+#[tokio::test]
+async fn api_key_that_can_only_read_has_its_chat_stream_closed_when_it_posts() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    create_service_account(&app, &admin_token.access_token).await;
+    let created = create_api_key(&app, &admin_token.access_token, &["chat:read"]).await;
+    let token = register_user(&app).await;
+
+    let mut bot_chat = app
+        .open_chat_with_api_key(&created.api_key)
+        .await
+        .expect("API keys that can read should be able to chat");
+    let chat = app
+        .open_chat(&token.access_token)
+        .await
+        .expect("failed to open chat stream");
+
+    chat.send(ChatMessage {
+        message: "hello".into(),
+        ..Default::default()
+    });
+
+    let message = bot_chat
+        .next()
+        .await
+        .expect("failed to chat")
+        .expect("the chat stream has ended");
+    assert_eq!(message.username, "atheer2104");
+
+    bot_chat.send(ChatMessage {
+        message: "hello".into(),
+        ..Default::default()
+    });
+
+    let error = bot_chat
+        .next()
+        .await
+        .expect_err("the chat stream should have been closed");
+    assert_eq!(error.code(), Code::PermissionDenied);
+    assert_eq!(error.message(), "API key doesn't have the chat:post scope");
+}
+
+#[tokio::test]
+async fn api_key_that_can_only_post_sends_its_messages_into_the_chat() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    create_service_account(&app, &admin_token.access_token).await;
+    let created = create_api_key(&app, &admin_token.access_token, &["chat:post"]).await;
+    let token = register_user(&app).await;
+
+    let mut chat = app
+        .open_chat(&token.access_token)
+        .await
+        .expect("failed to open chat stream");
+    let bot_chat = app
+        .open_chat_with_api_key(&created.api_key)
+        .await
+        .expect("API keys that can post should be able to chat");
+
+    bot_chat.send(ChatMessage {
+        message: "it's sunny".into(),
+        ..Default::default()
+    });
+
+    let message = chat
+        .next()
+        .await
+        .expect("failed to chat")
+        .expect("the chat stream has ended");
+    assert_eq!(message.username, "weather-bot (bot)");
+    assert_eq!(message.message, "it's sunny");
+}
+
+#[tokio::test]
+async fn unknown_api_key_is_refused() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let error = app
+        .open_chat_with_api_key("chatgrpc_unknown")
+        .await
+        .err()
+        .expect("unknown API keys shouldn't be able to chat");
+    assert_eq!(error.code(), Code::Unauthenticated);
+    assert_eq!(error.message(), "API key is not valid");
+}
+
+#[tokio::test]
+async fn revoked_api_key_has_its_chat_stream_closed_and_is_refused() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    create_service_account(&app, &admin_token.access_token).await;
+    let created =
+        create_api_key(&app, &admin_token.access_token, &["chat:read", "chat:post"]).await;
+
+    let mut chat = app
+        .open_chat_with_api_key(&created.api_key)
+        .await
+        .expect("failed to open chat stream");
+
+    app.auth_client()
+        .await
+        .revoke_api_key(authenticated_request(
+            RevokeApiKeyRequest {
+                key_id: created.key_id,
+            },
+            &admin_token.access_token,
+        ))
+        .await
+        .expect("failed to revoke API key");
+
+    let error = chat
+        .next()
+        .await
+        .expect_err("the chat stream should have been closed");
+    assert_eq!(error.code(), Code::Unauthenticated);
+
+    // the chat service takes the API key out of its list through redis as well
+    sleep(100).await;
+
+    let error = app
+        .open_chat_with_api_key(&created.api_key)
+        .await
+        .err()
+        .expect("revoked API keys shouldn't be able to chat");
+    assert_eq!(error.code(), Code::Unauthenticated);
+}
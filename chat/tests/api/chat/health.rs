@@ -0,0 +1,84 @@
+use rand::{thread_rng, Rng};
+use tonic::Streaming;
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+    HealthCheckResponse,
+};
+
+use super::{sleep, spawn_app};
+use crate::helpers::{spawn_app_with_configuration, App};
+
+// streams every change of the status that the health service reports for a service, the empty name is the server as
+// a whole
+async fn watch_health(app: &App, service: &str) -> Streaming<HealthCheckResponse> {
+    // the health client is generated without a connect function
+    let channel = tonic::transport::Endpoint::from_shared(format!("http://{}", app.address))
+        .expect("Failed to create endpoint")
+        .connect()
+        .await
+        .expect("Failed to create client");
+
+    HealthClient::new(channel)
+        .watch(HealthCheckRequest {
+            service: service.into(),
+        })
+        .await
+        .expect("failed to watch health")
+        .into_inner()
+}
+
+async fn next_status(statuses: &mut Streaming<HealthCheckResponse>) -> ServingStatus {
+    statuses
+        .message()
+        .await
+        .expect("failed to watch health")
+        .expect("the health stream has ended")
+        .status()
+}
+
+#[tokio::test]
+async fn chat_service_is_serving_when_its_dependencies_are_reachable() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    for service in ["", "chat.Chatting", "token-verification"] {
+        let mut statuses = watch_health(&app, service).await;
+        assert_eq!(
+            next_status(&mut statuses).await,
+            ServingStatus::Serving,
+            "{:?} isn't serving",
+            service
+        );
+    }
+}
+
+#[tokio::test]
+async fn chat_service_is_not_serving_once_the_server_is_going_away() {
+    // the health streams are open as well, so the server waits for them until the drain deadline
+    let mut app = spawn_app_with_configuration(|configuration| {
+        configuration.shutdown_drain_deadline_seconds = 1;
+    })
+    .await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let mut server_statuses = watch_health(&app, "").await;
+    let mut chatting_statuses = watch_health(&app, "chat.Chatting").await;
+    assert_eq!(
+        next_status(&mut server_statuses).await,
+        ServingStatus::Serving
+    );
+    assert_eq!(
+        next_status(&mut chatting_statuses).await,
+        ServingStatus::Serving
+    );
+
+    let (_, server_status, chatting_status) = tokio::join!(
+        app.shutdown(),
+        next_status(&mut server_statuses),
+        next_status(&mut chatting_statuses)
+    );
+    assert_eq!(server_status, ServingStatus::NotServing);
+    assert_eq!(chatting_status, ServingStatus::NotServing);
+}
@@ -0,0 +1,60 @@
+use chat::chat::ChatMessage;
+use rand::{thread_rng, Rng};
+
+use super::{sleep, spawn_app};
+use crate::helpers::{register_user, register_user_as};
+
+#[tokio::test]
+async fn messages_are_sent_as_the_user_of_the_auth_token() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    let other_token = register_user_as(&app, "mallory", "mallory@gmail.com").await;
+
+    let mut chat = app
+        .open_chat(&token.access_token)
+        .await
+        .expect("failed to open chat stream");
+    let mut other_chat = app
+        .open_chat(&other_token.access_token)
+        .await
+        .expect("failed to open chat stream");
+
+    // mallory claims to be the other user
+    other_chat.send(ChatMessage {
+        username: "atheer2104".into(),
+        message: "hello".into(),
+        ..Default::default()
+    });
+
+    let message = chat
+        .next()
+        .await
+        .expect("failed to chat")
+        .expect("the chat stream has ended");
+    assert_eq!(message.username, "mallory");
+    assert_eq!(message.message, "hello");
+
+    // messages without a username are sent as the user as well
+    chat.send(ChatMessage {
+        message: "hi".into(),
+        ..Default::default()
+    });
+
+    let message = other_chat
+        .next()
+        .await
+        .expect("failed to chat")
+        .expect("the chat stream has ended");
+    // mallory gets their own message first
+    assert_eq!(message.username, "mallory");
+    let message = other_chat
+        .next()
+        .await
+        .expect("failed to chat")
+        .expect("the chat stream has ended");
+    assert_eq!(message.username, "atheer2104");
+    assert_eq!(message.message, "hi");
+}
@@ -0,0 +1,16 @@
+mod api_keys;
+mod health;
+mod messages;
+mod revocation;
+mod roles;
+mod shutdown;
+
+use std::time::Duration;
+use tonic::Code;
+
+use crate::helpers::spawn_app;
+
+pub async fn sleep(duration: u64) {
+    // we sleep to make sure the server is up and running and has subscribed to the revocations and API keys in redis
+    tokio::time::sleep(Duration::from_millis(duration)).await;
+}
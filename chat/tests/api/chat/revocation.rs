@@ -0,0 +1,96 @@
+use auth::proto::auth::{ListSessionsRequest, LogoutRequest, RevokeSessionRequest};
+use rand::{thread_rng, Rng};
+
+use super::{sleep, spawn_app, Code};
+use crate::helpers::{authenticated_request, login_user, register_user};
+
+#[tokio::test]
+async fn open_chat_stream_is_closed_when_its_session_is_logged_out() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let mut chat = app
+        .open_chat(&token.access_token)
+        .await
+        .expect("failed to open chat stream");
+
+    app.auth_client()
+        .await
+        .logout(authenticated_request(LogoutRequest {}, &token.access_token))
+        .await
+        .expect("failed to logout");
+
+    let error = chat
+        .next()
+        .await
+        .expect_err("the chat stream should have been closed");
+    assert_eq!(error.code(), Code::Unauthenticated);
+    assert_eq!(error.message(), "Auth token has been revoked");
+
+    // the auth token can't be used to open another one either
+    let error = app
+        .open_chat(&token.access_token)
+        .await
+        .err()
+        .expect("revoked auth tokens shouldn't be able to chat");
+    assert_eq!(error.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn open_chat_stream_is_closed_when_its_session_is_revoked_from_another_one() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+    let other_token = login_user(&app).await;
+
+    let mut chat = app
+        .open_chat(&token.access_token)
+        .await
+        .expect("failed to open chat stream");
+    let mut other_chat = app
+        .open_chat(&other_token.access_token)
+        .await
+        .expect("failed to open chat stream");
+
+    let sessions = app
+        .auth_client()
+        .await
+        .list_sessions(authenticated_request(
+            ListSessionsRequest {},
+            &other_token.access_token,
+        ))
+        .await
+        .expect("failed to list sessions")
+        .into_inner()
+        .sessions;
+    let session = sessions
+        .into_iter()
+        .find(|session| !session.current)
+        .expect("the session of the first login is missing");
+
+    app.auth_client()
+        .await
+        .revoke_session(authenticated_request(
+            RevokeSessionRequest {
+                session_id: session.session_id,
+            },
+            &other_token.access_token,
+        ))
+        .await
+        .expect("failed to revoke session");
+
+    let error = chat
+        .next()
+        .await
+        .expect_err("the chat stream should have been closed");
+    assert_eq!(error.code(), Code::Unauthenticated);
+
+    // the stream of the session that is left stays open, so it still gets the messages of the chat
+    other_chat.send(Default::default());
+    assert!(other_chat.next().await.expect("failed to chat").is_some());
+}
@@ -0,0 +1,87 @@
+use auth::proto::auth::{GrantRoleRequest, RevokeRoleRequest};
+use rand::{thread_rng, Rng};
+
+use super::{sleep, spawn_app, Code};
+use crate::helpers::{authenticated_request, login_user, register_admin, register_user, App};
+
+async fn grant_role(app: &App, admin_access_token: &str, role: &str) {
+    app.auth_client()
+        .await
+        .grant_role(authenticated_request(
+            GrantRoleRequest {
+                username: "atheer2104".into(),
+                role: role.into(),
+            },
+            admin_access_token,
+        ))
+        .await
+        .expect("failed to grant role");
+}
+
+async fn revoke_role(app: &App, admin_access_token: &str, role: &str) {
+    app.auth_client()
+        .await
+        .revoke_role(authenticated_request(
+            RevokeRoleRequest {
+                username: "atheer2104".into(),
+                role: role.into(),
+            },
+            admin_access_token,
+        ))
+        .await
+        .expect("failed to revoke role");
+}
+
+#[tokio::test]
+async fn auth_token_without_a_role_that_can_chat_is_refused() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    register_user(&app).await;
+
+    revoke_role(&app, &admin_token.access_token, "user").await;
+
+    // the roles are read when the auth token is issued
+    let token = login_user(&app).await;
+
+    let error = app
+        .open_chat(&token.access_token)
+        .await
+        .err()
+        .expect("users without a role shouldn't be able to chat");
+    assert_eq!(error.code(), Code::PermissionDenied);
+    assert_eq!(
+        error.message(),
+        "Auth token doesn't have the role that is required"
+    );
+}
+
+#[tokio::test]
+async fn auth_token_with_any_role_that_can_chat_is_allowed() {
+    let app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let admin_token = register_admin(&app).await;
+    register_user(&app).await;
+
+    grant_role(&app, &admin_token.access_token, "moderator").await;
+    revoke_role(&app, &admin_token.access_token, "user").await;
+
+    let token = login_user(&app).await;
+
+    let mut chat = app
+        .open_chat(&token.access_token)
+        .await
+        .expect("moderators should be able to chat");
+
+    chat.send(Default::default());
+    let message = chat
+        .next()
+        .await
+        .expect("failed to chat")
+        .expect("the chat stream has ended");
+    assert_eq!(message.username, "atheer2104");
+}
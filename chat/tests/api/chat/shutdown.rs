@@ -0,0 +1,41 @@
+use std::time::Instant;
+
+use chat::chat::chatting_client::ChattingClient;
+use rand::{thread_rng, Rng};
+
+use super::{sleep, spawn_app, Code};
+use crate::helpers::register_user;
+
+#[tokio::test]
+async fn open_chat_streams_are_told_that_the_server_is_going_away() {
+    let mut app = spawn_app().await;
+    let mut rng = thread_rng();
+    sleep(rng.gen_range(100..200)).await;
+
+    let token = register_user(&app).await;
+
+    let mut chat = app
+        .open_chat(&token.access_token)
+        .await
+        .expect("failed to open chat stream");
+
+    // the server closes the chat streams itself, so it doesn't have to wait for the drain deadline
+    let shutdown_started = Instant::now();
+    app.shutdown().await;
+    assert!(shutdown_started.elapsed().as_secs() < 5);
+
+    let error = chat
+        .next()
+        .await
+        .expect_err("the chat stream should have been closed");
+    assert_eq!(error.code(), Code::Unavailable);
+    assert_eq!(
+        error.message(),
+        "Chat server is going away, reconnect to keep chatting"
+    );
+
+    // new connections aren't accepted anymore
+    assert!(ChattingClient::connect(format!("http://{}", app.address))
+        .await
+        .is_err());
+}
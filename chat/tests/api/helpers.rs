@@ -0,0 +1,352 @@
+use std::{net::SocketAddr, path::Path, time::Duration};
+
+use auth::{
+    configuration::{
+        get_configuration as get_auth_configuration, DatabaseSettings,
+        JwtAlgorithm as AuthJwtAlgorithm,
+    },
+    proto::auth::{auth_client::AuthClient, LoginRequest, RefreshRequest, RegisterRequest, Token},
+    secrets::Secrets,
+    server::{
+        build_server as build_auth_server, get_user_id_by_username_db, grant_role_db, JwtKeyring,
+        JwtKeys, Role,
+    },
+};
+use chat::{
+    chat::{chatting_client::ChattingClient, ChatMessage},
+    configuration::{get_configuration, JwtAlgorithm, Settings},
+    logging::{get_subscriber, init_subscriber},
+    server::{
+        build_server, serve_until_shutdown, sync_api_keys, sync_revocation_list, sync_signing_keys,
+        ApiKeyList, RevocationList, SigningKeys,
+    },
+};
+use once_cell::sync::Lazy;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::{Connection, Executor, PgConnection, PgPool};
+use token_claims::API_KEY_METADATA;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tonic::{transport::Channel, Request, Status, Streaming};
+
+use uuid::Uuid;
+
+// this makes sure that we only initialize tracing only once, the tests run in parallel which would otherwise initialize
+// it for each test
+static TRACING: Lazy<()> = Lazy::new(|| {
+    let default_filter_level = "info".to_string();
+    let subscriber_name = "chat-grpc-chat-TEST".to_string();
+    // checking if we want to output logs we do to stdout othwerise we output to sink which will not ouput anything at all
+    if std::env::var("TEST_LOG").is_ok() {
+        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
+        init_subscriber(subscriber);
+    } else {
+        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
+        init_subscriber(subscriber);
+    }
+});
+
+// how long a chat stream gets to yield something before the test fails
+const CHAT_STREAM_TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn configure_database(config: &DatabaseSettings) -> PgPool {
+    let mut connection = PgConnection::connect(config.connection_string_no_db().expose_secret())
+        .await
+        .expect("Failed to connect to postgresql");
+    connection
+        .execute(&*format!(r#"CREATE DATABASE "{}";"#, config.database_name))
+        .await
+        .expect("Failed to create database");
+
+    // running the migrations of the auth service
+    let connection_pool = PgPool::connect(config.connection_string().expose_secret())
+        .await
+        .expect("Failed to connect to postgresql");
+    sqlx::migrate!("../auth/migrations")
+        .run(&connection_pool)
+        .await
+        .expect("Failed to migrate the DB");
+
+    connection_pool
+}
+
+pub struct App {
+    pub address: String,
+    pub auth_address: String,
+    pub auth_db_pool: PgPool,
+    shutdown_sender: Option<oneshot::Sender<()>>,
+    server_handle: Option<JoinHandle<Result<(), tonic::transport::Error>>>,
+}
+
+impl App {
+    // shuts the server down like a shutdown signal would, resolves once it has stopped serving
+    pub async fn shutdown(&mut self) {
+        if let Some(shutdown_sender) = self.shutdown_sender.take() {
+            let _ = shutdown_sender.send(());
+        }
+
+        if let Some(server_handle) = self.server_handle.take() {
+            server_handle
+                .await
+                .expect("server panicked")
+                .expect("server failed");
+        }
+    }
+
+    pub async fn auth_client(&self) -> AuthClient<Channel> {
+        AuthClient::connect(format!("http://{}", self.auth_address))
+            .await
+            .expect("Failed to create auth client")
+    }
+
+    // opens a chat stream with the auth token, like the client does
+    pub async fn open_chat(&self, access_token: &str) -> Result<ChatStream, Status> {
+        self.open_chat_with_metadata("authorization", format!("Bearer {}", access_token))
+            .await
+    }
+
+    // opens a chat stream with the API key of a service account, like bots do
+    pub async fn open_chat_with_api_key(&self, api_key: &str) -> Result<ChatStream, Status> {
+        self.open_chat_with_metadata(API_KEY_METADATA, api_key.to_string())
+            .await
+    }
+
+    async fn open_chat_with_metadata(
+        &self,
+        key: &'static str,
+        value: String,
+    ) -> Result<ChatStream, Status> {
+        let mut client = ChattingClient::connect(format!("http://{}", self.address))
+            .await
+            .expect("Failed to create chat client");
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let mut request = Request::new(UnboundedReceiverStream::new(receiver));
+        request.metadata_mut().insert(
+            key,
+            value.parse().expect("failed to create request metadata"),
+        );
+
+        let messages = client.chat(request).await?.into_inner();
+
+        Ok(ChatStream { sender, messages })
+    }
+}
+
+// a chat stream that is open with the chat service, messages are sent with the sender and the ones of the chat come
+// out of messages
+pub struct ChatStream {
+    sender: mpsc::UnboundedSender<ChatMessage>,
+    messages: Streaming<ChatMessage>,
+}
+
+impl ChatStream {
+    pub fn send(&self, message: ChatMessage) {
+        self.sender
+            .send(message)
+            .expect("the chat stream has been closed");
+    }
+
+    // the next message of the chat, or the status that the stream has been closed with
+    pub async fn next(&mut self) -> Result<Option<ChatMessage>, Status> {
+        tokio::time::timeout(CHAT_STREAM_TIMEOUT, self.messages.message())
+            .await
+            .expect("the chat stream didn't yield anything in time")
+    }
+}
+
+// creates a request that carries the access token the same way the client does
+pub fn authenticated_request<T>(message: T, access_token: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    request.metadata_mut().insert(
+        "authorization",
+        format!("Bearer {}", access_token)
+            .parse()
+            .expect("failed to create authorization metadata"),
+    );
+
+    request
+}
+
+pub async fn register_user_as(app: &App, username: &str, email: &str) -> Token {
+    app.auth_client()
+        .await
+        .register(Request::new(RegisterRequest {
+            firstname: "atheer".into(),
+            lastname: "ABC".into(),
+            username: username.into(),
+            email: email.into(),
+            password: "strong password".into(),
+            ..Default::default()
+        }))
+        .await
+        .expect("failed to register user")
+        .into_inner()
+}
+
+// the user that tests which only need one user register
+pub async fn register_user(app: &App) -> Token {
+    register_user_as(app, "atheer2104", "atheer@gmail.com").await
+}
+
+// logs in as the user that register_user registers, which starts another session
+pub async fn login_user(app: &App) -> Token {
+    app.auth_client()
+        .await
+        .login(Request::new(LoginRequest {
+            identifier: "atheer2104".into(),
+            password: "strong password".into(),
+            ..Default::default()
+        }))
+        .await
+        .expect("failed to login user")
+        .into_inner()
+}
+
+// registers a user named admin and grants them the admin role outside of the RPCs, like auth-roles does. the returned
+// auth token already carries the role
+pub async fn register_admin(app: &App) -> Token {
+    let token = register_user_as(app, "admin", "admin@gmail.com").await;
+
+    let user_id = get_user_id_by_username_db(&app.auth_db_pool, "admin")
+        .await
+        .expect("failed to get user_id")
+        .expect("admin doesn't exist");
+    grant_role_db(&app.auth_db_pool, &user_id, Role::Admin)
+        .await
+        .expect("failed to grant admin role");
+
+    app.auth_client()
+        .await
+        .refresh(Request::new(RefreshRequest {
+            refresh_token: token.refresh_token,
+        }))
+        .await
+        .expect("failed to refresh auth token")
+        .into_inner()
+}
+
+// the auth service that the users of the tests register with. it signs auth tokens with RS256, so the chat service
+// gets the keys that it verifies them with from the auth service like it does when it's deployed and doesn't need
+// the secrets file
+async fn spawn_auth_service(outbox_directory: &Path) -> (SocketAddr, PgPool) {
+    let address: SocketAddr = "[::1]:10000".parse().unwrap();
+
+    let mut configuration = get_auth_configuration().expect("Failed to read auth configuration.");
+    configuration.auth_token.signing_algorithm = AuthJwtAlgorithm::Rs256;
+    configuration.database.database_name = Uuid::new_v4().to_string();
+    configuration.mailer.outbox_directory = outbox_directory.to_string_lossy().into_owned();
+
+    let dummy_secrets = Secrets {
+        jwt_secret: None,
+        jwt_private_key: Some(Secret::new(include_str!("jwt_private_key.pem").to_string())),
+        jwt_keyring: None,
+        totp_encryption_key: Secret::new(String::from(
+            "4b8c5e0a3f1d27a9e6b04c1d58f2a7e39c0d16b4a8e5f27c3d9b0a61e4f8c25d",
+        )),
+    };
+
+    let jwt_keyring = JwtKeyring::new(
+        JwtKeys::from_secrets(&dummy_secrets, AuthJwtAlgorithm::Rs256)
+            .expect("Failed to read jwt keys"),
+    );
+
+    let connection_pool = configure_database(&configuration.database).await;
+
+    let redis_client = redis::Client::open(configuration.redis_uri.expose_secret().to_owned())
+        .expect("failed to create redis client");
+    let redis_con = redis_client
+        .get_multiplexed_async_connection()
+        .await
+        .expect("failed to create redis connection");
+
+    let server = build_auth_server(
+        connection_pool.clone(),
+        redis_con,
+        dummy_secrets,
+        jwt_keyring,
+        &configuration,
+    );
+    tokio::spawn(server.serve(address));
+
+    (address, connection_pool)
+}
+
+pub async fn spawn_app() -> App {
+    spawn_app_with_configuration(|_| {}).await
+}
+
+// spawns the app with a configuration that has been changed by the test
+pub async fn spawn_app_with_configuration(modify_configuration: impl FnOnce(&mut Settings)) -> App {
+    // we force evaluate TRACING
+    Lazy::force(&TRACING);
+
+    // every test gets its own outbox so the mails of different tests don't mix
+    let outbox_directory =
+        std::env::temp_dir().join(format!("chat-grpc-outbox-{}", Uuid::new_v4()));
+    let (auth_address, auth_db_pool) = spawn_auth_service(&outbox_directory).await;
+
+    let address: SocketAddr = "[::1]:10001".parse().unwrap();
+
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    configuration.signing_algorithm = JwtAlgorithm::Rs256;
+    configuration.auth_service_uri = format!("http://{}", auth_address);
+    modify_configuration(&mut configuration);
+
+    let redis_client = redis::Client::open(configuration.redis_uri.expose_secret().to_owned())
+        .expect("failed to create redis client");
+
+    let revocation_list = RevocationList::new();
+    tokio::spawn(sync_revocation_list(
+        redis_client.clone(),
+        revocation_list.clone(),
+    ));
+
+    let api_keys = ApiKeyList::new();
+    tokio::spawn(sync_api_keys(redis_client.clone(), api_keys.clone()));
+
+    // the auth tokens of the first requests can only be verified once the keys have been fetched
+    let signing_keys = SigningKeys::new();
+    tokio::spawn(sync_signing_keys(
+        configuration.auth_service_uri.clone(),
+        signing_keys.clone(),
+        configuration.signing_keys_refresh_interval(),
+    ));
+    for _ in 0..300 {
+        if !signing_keys.verifier().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let (going_away_sender, going_away) = watch::channel(false);
+
+    let server = build_server(
+        revocation_list,
+        signing_keys,
+        api_keys,
+        redis_client,
+        going_away,
+        &configuration,
+    );
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+    let server_handle = tokio::spawn(serve_until_shutdown(
+        server,
+        address,
+        async move {
+            let _ = shutdown_receiver.await;
+            let _ = going_away_sender.send(true);
+        },
+        configuration.shutdown_drain_deadline(),
+    ));
+
+    App {
+        address: address.to_string(),
+        auth_address: auth_address.to_string(),
+        auth_db_pool,
+        shutdown_sender: Some(shutdown_sender),
+        server_handle: Some(server_handle),
+    }
+}
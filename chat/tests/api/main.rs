@@ -0,0 +1,2 @@
+mod chat;
+mod helpers;
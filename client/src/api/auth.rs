@@ -1,10 +1,59 @@
-use auth::authentication::{auth_client::AuthClient, LoginRequest, RegisterRequest, Token};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use auth::authentication::{
+    auth_client::AuthClient, CheckAvailabilityRequest, CheckAvailabilityResponse,
+    CompleteLoginRequest, GetProfileRequest, LoginRequest, Profile, RefreshRequest,
+    RegisterRequest, Token, UpdateProfileRequest,
+};
+use auth::server::{LOGIN_CHALLENGE_METADATA_KEY, SECOND_FACTOR_REQUIRED};
+use token_claims::Claims;
+use tokio::task::JoinHandle;
 use tonic::{transport::Channel, Request};
+use tonic_types::StatusExt;
 
 const ADDRESS: &str = "http://[::1]:8000";
+// how many seconds before the access token expires that we ask for a new one
+const REFRESH_MARGIN: i64 = 30;
+
+// the current access token, it's shared between the chat api which sends it and the task that refreshes it
+pub type AccessToken = Arc<RwLock<String>>;
+
+// the label the auth server shows for the session that is created when logging in from this client
+pub fn session_device() -> String {
+    format!("Chat-gRPC TUI on {}", std::env::consts::OS)
+}
+
+// the exp claim says when the access token expires, expires_in is only used when the claims can't be read
+fn seconds_until_expiry(token: &Token) -> i64 {
+    match Claims::decode_unverified(&token.access_token) {
+        Ok(claims) => claims.exp - chrono::Local::now().timestamp(),
+        Err(_) => token.expires_in,
+    }
+}
+
+// the username that the auth service put into the access token, users can log in with their email so this isn't
+// necessarily what they typed
+pub fn token_username(token: &Token) -> Option<String> {
+    Claims::decode_unverified(&token.access_token)
+        .ok()
+        .map(|claims| claims.username)
+}
+
+// users with two-factor authentication get a challenge instead of a token, which is exchanged for the token together
+// with a code
+pub enum LoginOutcome {
+    LoggedIn(Token),
+    SecondFactorRequired(String),
+}
 
 pub struct AuthApi {
     client: AuthClient<Channel>,
+    refresh_task: Option<JoinHandle<()>>,
+    // the access token of the user that is logged in, it's sent with the requests that need it
+    access_token: Option<AccessToken>,
 }
 
 impl AuthApi {
@@ -13,10 +62,14 @@ impl AuthApi {
             .await
             .expect("failed to create client");
 
-        Self { client }
+        Self {
+            client,
+            refresh_task: None,
+            access_token: None,
+        }
     }
 
-    pub async fn login(&mut self, login_request: LoginRequest) -> Result<Token, String> {
+    pub async fn login(&mut self, login_request: LoginRequest) -> Result<LoginOutcome, String> {
         let request = Request::new(login_request);
 
         let login_result = self.client.login(request).await;
@@ -24,8 +77,32 @@ impl AuthApi {
         match login_result {
             Ok(res) => {
                 // println!("token: {}", res.into_inner().access_token);
-                Ok(res.into_inner())
+                Ok(LoginOutcome::LoggedIn(res.into_inner()))
             }
+            Err(e) => {
+                let challenge = e
+                    .get_details_error_info()
+                    .filter(|error_info| error_info.reason == SECOND_FACTOR_REQUIRED)
+                    .and_then(|mut error_info| {
+                        error_info.metadata.remove(LOGIN_CHALLENGE_METADATA_KEY)
+                    });
+
+                match challenge {
+                    Some(challenge) => Ok(LoginOutcome::SecondFactorRequired(challenge)),
+                    None => Err(e.message().into()),
+                }
+            }
+        }
+    }
+
+    pub async fn complete_login(
+        &mut self,
+        complete_login_request: CompleteLoginRequest,
+    ) -> Result<Token, String> {
+        let request = Request::new(complete_login_request);
+
+        match self.client.complete_login(request).await {
+            Ok(res) => Ok(res.into_inner()),
             Err(e) => Err(e.message().into()),
         }
     }
@@ -43,4 +120,96 @@ impl AuthApi {
             Err(e) => Err(e.message().into()),
         }
     }
+
+    pub async fn check_availability(
+        &mut self,
+        check_availability_request: CheckAvailabilityRequest,
+    ) -> Result<CheckAvailabilityResponse, String> {
+        let request = Request::new(check_availability_request);
+
+        match self.client.check_availability(request).await {
+            Ok(res) => Ok(res.into_inner()),
+            Err(e) => Err(e.message().into()),
+        }
+    }
+
+    // spawns a task that silently exchanges the refresh token for a new access token shortly before the current one
+    // expires, the returned access token is always the latest one
+    pub fn keep_token_fresh(&mut self, token: Token) -> AccessToken {
+        let access_token = Arc::new(RwLock::new(token.access_token.clone()));
+
+        // a new login replaces the previous tokens
+        if let Some(refresh_task) = self.refresh_task.take() {
+            refresh_task.abort();
+        }
+
+        let mut client = self.client.clone();
+        let shared_access_token = access_token.clone();
+
+        self.refresh_task = Some(tokio::spawn(async move {
+            let mut token = token;
+
+            loop {
+                let wait = (seconds_until_expiry(&token) - REFRESH_MARGIN).max(1) as u64;
+                tokio::time::sleep(Duration::from_secs(wait)).await;
+
+                let request = Request::new(RefreshRequest {
+                    refresh_token: token.refresh_token.clone(),
+                });
+
+                token = match client.refresh(request).await {
+                    Ok(res) => res.into_inner(),
+                    // the refresh token is no longer valid so the user has to login again
+                    Err(_) => break,
+                };
+
+                *shared_access_token
+                    .write()
+                    .expect("access token lock is poisoned") = token.access_token.clone();
+            }
+        }));
+
+        self.access_token = Some(access_token.clone());
+
+        access_token
+    }
+
+    pub async fn get_profile(&mut self) -> Result<Profile, String> {
+        let request = self.authenticated_request(GetProfileRequest {})?;
+
+        match self.client.get_profile(request).await {
+            Ok(res) => Ok(res.into_inner()),
+            Err(e) => Err(e.message().into()),
+        }
+    }
+
+    pub async fn update_profile(
+        &mut self,
+        update_profile_request: UpdateProfileRequest,
+    ) -> Result<Profile, String> {
+        let request = self.authenticated_request(update_profile_request)?;
+
+        match self.client.update_profile(request).await {
+            Ok(res) => Ok(res.into_inner()),
+            Err(e) => Err(e.message().into()),
+        }
+    }
+
+    fn authenticated_request<T>(&self, message: T) -> Result<Request<T>, String> {
+        let access_token = match &self.access_token {
+            Some(access_token) => access_token
+                .read()
+                .expect("access token lock is poisoned")
+                .clone(),
+            None => return Err("Not logged in".into()),
+        };
+
+        let mut request = Request::new(message);
+        let token = format!("Bearer {}", access_token)
+            .parse()
+            .map_err(|_| "Failed to create access token".to_string())?;
+        request.metadata_mut().insert("authorization", token);
+
+        Ok(request)
+    }
 }
@@ -10,10 +10,12 @@ use tonic::{
 
 use crate::events::{Event, Sender};
 
+use super::AccessToken;
+
 const ADDRESS: &str = "http://[::1]:8001";
 
 struct MyInterceptor {
-    access_token: String,
+    access_token: AccessToken,
 }
 
 impl Interceptor for MyInterceptor {
@@ -21,7 +23,12 @@ impl Interceptor for MyInterceptor {
         &mut self,
         mut request: tonic::Request<()>,
     ) -> std::result::Result<tonic::Request<()>, Status> {
-        let token: MetadataValue<_> = format!("Bearer {}", self.access_token)
+        let access_token = self
+            .access_token
+            .read()
+            .expect("access token lock is poisoned");
+
+        let token: MetadataValue<_> = format!("Bearer {}", access_token)
             .parse()
             .expect("Failed to create access token");
         request.metadata_mut().insert("authorization", token);
@@ -34,7 +41,7 @@ pub struct ChatApi {
 }
 
 impl ChatApi {
-    pub async fn new(access_token: String, event_sender: Sender) -> Self {
+    pub async fn new(access_token: AccessToken, event_sender: Sender) -> Self {
         let channel = Channel::from_static(ADDRESS)
             .connect()
             .await
@@ -60,8 +67,18 @@ impl ChatApi {
 
             let mut inbound = response.into_inner();
 
-            while let Some(message) = inbound.message().await.expect("Failed to read") {
-                let _ = event_sender.send(Event::Message(message));
+            loop {
+                match inbound.message().await {
+                    Ok(Some(message)) => {
+                        let _ = event_sender.send(Event::Message(message));
+                    }
+                    Ok(None) => break,
+                    // e.g. the token was revoked or the server is going away
+                    Err(status) => {
+                        let _ = event_sender.send(Event::ChatClosed(status.message().to_string()));
+                        break;
+                    }
+                }
             }
         });
 
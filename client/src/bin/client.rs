@@ -1,7 +1,7 @@
 use anyhow::Result;
 use chat::chat::ChatMessage;
 use client::{
-    api::{AuthApi, ChatApi},
+    api::{token_username, AuthApi, ChatApi, LoginOutcome},
     app::App,
     events::*,
     tui::Tui,
@@ -35,13 +35,16 @@ async fn main() -> Result<()> {
             Event::Login => {
                 let login_request = app.home.login.get_login_request();
                 match authapi.login(login_request.clone()).await {
-                    Ok(token) => {
+                    Ok(LoginOutcome::LoggedIn(token)) => {
                         // println!("access token: {}", token.access_token)
-                        chatapi =
-                            Some(ChatApi::new(token.access_token, events.sender.clone()).await);
-                        app.username = login_request.username;
+                        app.username = token_username(&token).unwrap_or(login_request.identifier);
+                        let access_token = authapi.keep_token_fresh(token);
+                        chatapi = Some(ChatApi::new(access_token, events.sender.clone()).await);
                         app.home.set_action_to_chat();
                     }
+                    Ok(LoginOutcome::SecondFactorRequired(challenge)) => {
+                        app.home.login.require_second_factor(challenge);
+                    }
                     Err(error_msg) => {
                         app.home.login.show_error_popup = true;
                         app.home.login.error_description = error_msg;
@@ -49,13 +52,31 @@ async fn main() -> Result<()> {
                     }
                 };
             }
+            Event::CompleteLogin => {
+                let complete_login_request = app.home.login.get_complete_login_request();
+                match authapi.complete_login(complete_login_request).await {
+                    Ok(token) => {
+                        app.username = token_username(&token)
+                            .unwrap_or(app.home.login.get_login_request().identifier);
+                        let access_token = authapi.keep_token_fresh(token);
+                        chatapi = Some(ChatApi::new(access_token, events.sender.clone()).await);
+                        app.home.set_action_to_chat();
+                    }
+                    Err(error_msg) => {
+                        app.home.login.reset_code_state();
+                        app.home.login.show_error_popup = true;
+                        app.home.login.error_description = error_msg;
+                        app.set_error_mode();
+                    }
+                }
+            }
             Event::Register => {
                 let register_request = app.home.register.get_register_request();
                 match authapi.register(register_request.clone()).await {
                     Ok(token) => {
                         // println!("access token: {}", token.access_token)
-                        chatapi =
-                            Some(ChatApi::new(token.access_token, events.sender.clone()).await);
+                        let access_token = authapi.keep_token_fresh(token);
+                        chatapi = Some(ChatApi::new(access_token, events.sender.clone()).await);
                         app.username = register_request.username;
                         app.home.set_action_to_chat();
                     }
@@ -66,6 +87,15 @@ async fn main() -> Result<()> {
                     }
                 }
             }
+            // the availability is only a hint, registering tells for sure
+            Event::CheckAvailability => {
+                let check_availability_request = app.home.register.get_availability_request();
+                if let Ok(availability) =
+                    authapi.check_availability(check_availability_request).await
+                {
+                    app.home.register.set_availability(availability);
+                }
+            }
             Event::Chat => {
                 let message = app.home.chat.get_message();
                 let chat_message = ChatMessage {
@@ -76,6 +106,31 @@ async fn main() -> Result<()> {
 
                 chatapi.as_mut().unwrap().chat(chat_message).await;
             }
+            Event::OpenProfile => match authapi.get_profile().await {
+                Ok(details) => {
+                    app.home.profile.set_details(details);
+                    app.home.set_action_to_profile();
+                }
+                Err(error_msg) => {
+                    app.home.chat.show_error_popup = true;
+                    app.home.chat.error_description = error_msg;
+                    app.set_error_mode();
+                }
+            },
+            Event::UpdateProfile => {
+                let update_profile_request = app.home.profile.get_update_profile_request();
+                match authapi.update_profile(update_profile_request).await {
+                    Ok(details) => {
+                        app.home.profile.set_details(details);
+                        app.home.set_action_to_chat();
+                    }
+                    Err(error_msg) => {
+                        app.home.profile.show_error_popup = true;
+                        app.home.profile.error_description = error_msg;
+                        app.set_error_mode();
+                    }
+                }
+            }
             Event::Message(message) => {
                 if !app
                     .home
@@ -94,6 +149,11 @@ async fn main() -> Result<()> {
                 app.home.chat.chat_messages.push(message);
                 app.home.chat.reset_message_prompt_state();
             }
+            Event::ChatClosed(reason) => {
+                app.home.chat.show_error_popup = true;
+                app.home.chat.error_description = reason;
+                app.set_error_mode();
+            }
         }
     }
 
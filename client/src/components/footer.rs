@@ -8,13 +8,10 @@ use ratatui::{
 
 use crate::app::{App, AppMode};
 
-pub struct Footer {}
+use super::Action;
 
-impl Default for Footer {
-    fn default() -> Footer {
-        Self {}
-    }
-}
+#[derive(Default)]
+pub struct Footer {}
 
 impl Footer {
     pub fn new() -> Footer {
@@ -44,6 +41,10 @@ impl Footer {
                     text.push(Span::styled(" Use ↓↑ to Scroll. ", Style::default()))
                 }
 
+                if matches!(app.home.selected_action(), Some(Action::Chat)) {
+                    text.push(Span::styled(" Ctrl + p : Profile.", Style::default()))
+                }
+
                 text
             }
             AppMode::Error => {
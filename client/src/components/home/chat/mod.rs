@@ -110,10 +110,7 @@ impl<'a> Chat<'a> {
                         Style::default().fg(*color),
                     ),
                     Span::styled(" - ", Style::default().fg(*color)),
-                    Span::styled(
-                        format!("{}", chat_message.message),
-                        Style::default().fg(*color),
-                    ),
+                    Span::styled(chat_message.message.clone(), Style::default().fg(*color)),
                 ])
             })
             .collect();
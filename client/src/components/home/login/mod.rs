@@ -1,4 +1,5 @@
-use auth::authentication::LoginRequest;
+use crate::api::session_device;
+use auth::authentication::{CompleteLoginRequest, LoginRequest};
 use tui_popup::Popup;
 
 use crossterm::event::KeyEvent;
@@ -16,37 +17,30 @@ use crate::{
     ui::centered_rect,
 };
 
-use super::{validate_password, validate_username};
+use super::{validate_code, validate_identifier, validate_password};
 
 #[derive(Default)]
 enum Field {
     #[default]
-    Username,
+    Identifier,
     Password,
+    // only asked for when the user has two-factor authentication
+    Code,
 }
 
+#[derive(Default)]
 pub struct Login<'a> {
     show_login: bool,
     current_field: Field,
-    username_state: TextState<'a>,
+    identifier_state: TextState<'a>,
     password_state: TextState<'a>,
+    code_state: TextState<'a>,
+    // the challenge that login responded with, which means the code step is shown
+    challenge: Option<String>,
     pub show_error_popup: bool,
     pub error_description: String,
 }
 
-impl<'a> Default for Login<'a> {
-    fn default() -> Login<'a> {
-        Self {
-            show_login: false,
-            current_field: Field::default(),
-            username_state: TextState::default(),
-            password_state: TextState::default(),
-            show_error_popup: false,
-            error_description: String::from(""),
-        }
-    }
-}
-
 impl<'a> Login<'a> {
     pub fn new() -> Login<'a> {
         Self::default()
@@ -61,21 +55,54 @@ impl<'a> Login<'a> {
     }
 
     pub fn is_finished(&self) -> bool {
-        self.username_state.is_finished() && self.password_state.is_finished()
+        if self.awaiting_second_factor() {
+            return self.code_state.is_finished();
+        }
+
+        self.identifier_state.is_finished() && self.password_state.is_finished()
+    }
+
+    pub fn awaiting_second_factor(&self) -> bool {
+        self.challenge.is_some()
+    }
+
+    // moves on to the code step, the username or email and the password have been accepted already
+    pub fn require_second_factor(&mut self, challenge: String) {
+        self.current_state().blur();
+        self.challenge = Some(challenge);
+        self.code_state = TextState::default();
+        self.current_field = Field::Code;
+        self.focus_current_field();
+    }
+
+    // a wrong code can be retyped as long as the challenge is valid
+    pub fn reset_code_state(&mut self) {
+        self.code_state = TextState::default();
+    }
+
+    pub fn get_complete_login_request(&self) -> CompleteLoginRequest {
+        CompleteLoginRequest {
+            challenge: self.challenge.clone().unwrap_or_default(),
+            code: self.code_state.value().into(),
+        }
     }
 
     pub fn get_login_request(&self) -> LoginRequest {
-        let username = self.username_state.value();
+        let identifier = self.identifier_state.value();
         let password = self.password_state.value();
         LoginRequest {
-            username: username.into(),
+            identifier: identifier.into(),
             password: password.into(),
+            device: session_device(),
         }
     }
 
     pub fn reset_textfields_state(&mut self) {
-        self.username_state = TextState::default();
+        self.identifier_state = TextState::default();
         self.password_state = TextState::default();
+        self.code_state = TextState::default();
+        self.challenge = None;
+        self.current_field = Field::Identifier;
     }
 
     pub fn focus_next(&mut self) {
@@ -102,8 +129,9 @@ impl<'a> Login<'a> {
         // have to validate the value here then mark it as complete
 
         let validation_result = match self.current_field {
-            Field::Username => validate_username(self.current_state().value()),
+            Field::Identifier => validate_identifier(self.current_state().value()),
             Field::Password => validate_password(self.current_state().value()),
+            Field::Code => validate_code(self.current_state().value()),
         };
 
         match validation_result {
@@ -118,7 +146,7 @@ impl<'a> Login<'a> {
                     // println!("all done");
                     // println!(
                     //     "username: {}, password: {}",
-                    //     self.username_state.value(),
+                    //     self.identifier_state.value(),
                     //     self.password_state.value()
                     // )
                 }
@@ -141,8 +169,9 @@ impl<'a> Login<'a> {
     fn next_field(&mut self) -> Option<Field> {
         if !self.current_state().status().is_aborted() {
             return match self.current_field {
-                Field::Username => Some(Field::Password),
-                Field::Password => Some(Field::Username),
+                Field::Identifier => Some(Field::Password),
+                Field::Password => Some(Field::Identifier),
+                Field::Code => Some(Field::Code),
             };
         }
 
@@ -152,8 +181,9 @@ impl<'a> Login<'a> {
     fn prev_field(&mut self) -> Option<Field> {
         if !self.current_state().status().is_aborted() {
             return match self.current_field {
-                Field::Username => Some(Field::Password),
-                Field::Password => Some(Field::Username),
+                Field::Identifier => Some(Field::Password),
+                Field::Password => Some(Field::Identifier),
+                Field::Code => Some(Field::Code),
             };
         }
 
@@ -162,8 +192,9 @@ impl<'a> Login<'a> {
 
     fn current_state(&mut self) -> &mut TextState<'a> {
         match self.current_field {
-            Field::Username => &mut self.username_state,
+            Field::Identifier => &mut self.identifier_state,
             Field::Password => &mut self.password_state,
+            Field::Code => &mut self.code_state,
         }
     }
 
@@ -190,9 +221,46 @@ impl<'a> Login<'a> {
         frame.render_widget(Clear, block_area);
         frame.render_widget(login_block, block_area);
 
-        TextPrompt::from("Username").draw(frame, layout[1], &mut self.username_state);
+        if self.awaiting_second_factor() {
+            TextPrompt::from("Code").draw(frame, layout[1], &mut self.code_state);
+
+            let code_helper_text = vec![
+                Line::from(Span::styled(
+                    "Enter the code shown by your authenticator app",
+                    Style::default(),
+                )),
+                Line::from(Span::styled(
+                    "or one of your backup codes",
+                    Style::default(),
+                )),
+            ];
+
+            let code_helper_paragraph = Paragraph::new(code_helper_text);
+            frame.render_widget(code_helper_paragraph, layout[2]);
+        } else {
+            self.render_credentials(frame, &layout);
+        }
+
+        if self.show_error_popup {
+            let error_popup = Popup::new(self.error_description.as_str())
+                .title("Login Error")
+                .style(Style::default().on_red());
+
+            frame.render_widget(&error_popup, area)
+        } else {
+            // when we have an error we enter error mode which will unfoucus the current field thus we have to focus back the current field
+            self.focus_current_field()
+        }
+    }
+
+    fn render_credentials(&mut self, frame: &mut Frame, layout: &[Rect]) {
+        TextPrompt::from("Username or email").draw(frame, layout[1], &mut self.identifier_state);
 
-        let username_helper_text = vec![
+        let identifier_helper_text = vec![
+            Line::from(Span::styled(
+                "Log in with either your username or your email",
+                Style::default(),
+            )),
             Line::from(Span::styled("Maximum of 255 character", Style::default())),
             Line::from(Span::styled(
                 "Following charcters are forbidden",
@@ -207,8 +275,8 @@ impl<'a> Login<'a> {
             )),
         ];
 
-        let username_helper_paragraph = Paragraph::new(username_helper_text);
-        frame.render_widget(username_helper_paragraph, layout[2]);
+        let identifier_helper_paragraph = Paragraph::new(identifier_helper_text);
+        frame.render_widget(identifier_helper_paragraph, layout[2]);
 
         TextPrompt::from("Password")
             .with_render_style(TextRenderStyle::Password)
@@ -220,16 +288,5 @@ impl<'a> Login<'a> {
         ];
         let password_helper_paragraph = Paragraph::new(password_helper_text);
         frame.render_widget(password_helper_paragraph, layout[4]);
-
-        if self.show_error_popup {
-            let error_popup = Popup::new(self.error_description.as_str())
-                .title("Login Error")
-                .style(Style::default().on_red());
-
-            frame.render_widget(&error_popup, area)
-        } else {
-            // when we have an error we enter error mode which will unfoucus the current field thus we have to focus back the current field
-            self.focus_current_field()
-        }
     }
 }
@@ -1,10 +1,12 @@
 mod chat;
 mod login;
+mod profile;
 mod register;
 mod validation;
 
 pub use chat::*;
 pub use login::*;
+pub use profile::*;
 pub use register::*;
 pub use validation::*;
 
@@ -25,6 +27,7 @@ pub enum Action {
     Login,
     Register,
     Chat,
+    Profile,
 }
 
 pub struct Home<'a> {
@@ -34,6 +37,7 @@ pub struct Home<'a> {
     pub login: Login<'a>,
     pub register: Register<'a>,
     pub chat: Chat<'a>,
+    pub profile: Profile<'a>,
 }
 
 impl<'a> Default for Home<'a> {
@@ -45,6 +49,7 @@ impl<'a> Default for Home<'a> {
             login: Login::new(),
             register: Register::new(),
             chat: Chat::new(),
+            profile: Profile::new(),
         }
     }
 }
@@ -76,6 +81,10 @@ impl<'a> Home<'a> {
         self.selected_action = Some(Action::Chat)
     }
 
+    pub fn set_action_to_profile(&mut self) {
+        self.selected_action = Some(Action::Profile)
+    }
+
     pub fn select(&mut self) {
         if let Some(i) = self.list_state.selected() {
             // println!("choose : {}", self.list_items[i]);
@@ -104,7 +113,14 @@ impl<'a> Home<'a> {
         frame.render_widget(title_paragraph, layout[0]);
 
         // we haven't selected an action so the default home page options is shown
-        if self.selected_action.is_none() {
+        if let Some(action) = self.selected_action.as_ref() {
+            match action {
+                Action::Login => self.login.render(frame, area),
+                Action::Register => self.register.render(frame, area),
+                Action::Chat => self.chat.render(frame, area),
+                Action::Profile => self.profile.render(frame, area),
+            }
+        } else {
             let items: Vec<Text> = self
                 .list_items
                 .iter()
@@ -122,12 +138,6 @@ impl<'a> Home<'a> {
                 .direction(ListDirection::TopToBottom);
 
             frame.render_stateful_widget(list, layout[1], &mut self.list_state);
-        } else {
-            match self.selected_action.as_ref().unwrap() {
-                Action::Login => self.login.render(frame, area),
-                Action::Register => self.register.render(frame, area),
-                Action::Chat => self.chat.render(frame, area),
-            }
         }
     }
 }
@@ -0,0 +1,307 @@
+use auth::authentication::{Profile as ProfileDetails, UpdateProfileRequest};
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Padding, Paragraph},
+    Frame,
+};
+use tui_popup::Popup;
+use tui_prompts::{Prompt, State, TextPrompt, TextRenderStyle, TextState};
+
+use crate::{
+    events::{Event, Sender},
+    ui::centered_rect,
+};
+
+use super::{parse_email, validate_name, validate_profile_text};
+
+const MAX_DISPLAY_NAME_LENGTH: u8 = 64;
+const MAX_STATUS_TEXT_LENGTH: u8 = 255;
+
+enum Field {
+    Firstname,
+    Lastname,
+    DisplayName,
+    StatusText,
+    Email,
+    CurrentPassword,
+}
+
+// shows the profile of the user, the fields start out with the current values and every field has to be submitted
+// before the profile is updated
+pub struct Profile<'a> {
+    details: ProfileDetails,
+    current_field: Field,
+    firstname_state: TextState<'a>,
+    lastname_state: TextState<'a>,
+    display_name_state: TextState<'a>,
+    status_text_state: TextState<'a>,
+    email_state: TextState<'a>,
+    current_password_state: TextState<'a>,
+    pub show_error_popup: bool,
+    pub error_description: String,
+}
+
+impl<'a> Default for Profile<'a> {
+    fn default() -> Profile<'a> {
+        Self {
+            details: ProfileDetails::default(),
+            current_field: Field::Firstname,
+            firstname_state: TextState::default(),
+            lastname_state: TextState::default(),
+            display_name_state: TextState::default(),
+            status_text_state: TextState::default(),
+            email_state: TextState::default(),
+            current_password_state: TextState::default(),
+            show_error_popup: false,
+            error_description: "".into(),
+        }
+    }
+}
+
+impl<'a> Profile<'a> {
+    pub fn new() -> Profile<'a> {
+        Self::default()
+    }
+
+    // fills the fields with the profile that the auth service returned
+    pub fn set_details(&mut self, details: ProfileDetails) {
+        self.firstname_state = TextState::new().with_value(details.firstname.clone());
+        self.lastname_state = TextState::new().with_value(details.lastname.clone());
+        self.display_name_state = TextState::new().with_value(details.display_name.clone());
+        self.status_text_state = TextState::new().with_value(details.status_text.clone());
+        self.email_state = TextState::new().with_value(details.email.clone());
+        self.current_password_state = TextState::default();
+        self.current_field = Field::Firstname;
+        self.details = details;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.firstname_state.is_finished()
+            && self.lastname_state.is_finished()
+            && self.display_name_state.is_finished()
+            && self.status_text_state.is_finished()
+            && self.email_state.is_finished()
+            && self.current_password_state.is_finished()
+    }
+
+    pub fn get_update_profile_request(&self) -> UpdateProfileRequest {
+        UpdateProfileRequest {
+            firstname: Some(self.firstname_state.value().into()),
+            lastname: Some(self.lastname_state.value().into()),
+            email: Some(self.email_state.value().into()),
+            current_password: self.current_password_state.value().into(),
+            display_name: Some(self.display_name_state.value().into()),
+            status_text: Some(self.status_text_state.value().into()),
+        }
+    }
+
+    fn email_changed(&self) -> bool {
+        self.email_state.value() != self.details.email
+    }
+
+    fn focus_current_field(&mut self) {
+        self.current_state().focus();
+    }
+
+    pub fn focus_next(&mut self) {
+        self.current_state().blur();
+        if let Some(field) = self.next_field() {
+            self.current_field = field;
+        }
+        self.current_state().focus();
+    }
+
+    pub fn focus_prev(&mut self) {
+        self.current_state().blur();
+        if let Some(field) = self.prev_field() {
+            self.current_field = field;
+        }
+        self.current_state().focus();
+    }
+
+    pub fn submit(&mut self, sender: Sender) {
+        let validation_result = match self.current_field {
+            Field::Firstname => validate_name(self.current_state().value(), "Firstname"),
+            Field::Lastname => validate_name(self.current_state().value(), "Lastname"),
+            Field::DisplayName => validate_profile_text(
+                self.current_state().value(),
+                MAX_DISPLAY_NAME_LENGTH,
+                "Display name",
+            ),
+            Field::StatusText => validate_profile_text(
+                self.current_state().value(),
+                MAX_STATUS_TEXT_LENGTH,
+                "Status text",
+            ),
+            Field::Email => parse_email(self.current_state().value()),
+            Field::CurrentPassword => {
+                if self.email_changed() && self.current_state().value().is_empty() {
+                    Err("Current password is needed to change the email".into())
+                } else {
+                    Ok(())
+                }
+            }
+        };
+
+        match validation_result {
+            Ok(_) => {
+                self.show_error_popup = false;
+                self.current_state().complete();
+
+                if self.current_state().is_finished() && !self.is_finished() {
+                    self.focus_next();
+                }
+            }
+            Err(e) => {
+                self.show_error_popup = true;
+                self.error_description = e;
+                self.current_state().abort();
+                self.current_state().blur();
+                let _ = sender.send(Event::Error);
+            }
+        }
+    }
+
+    pub fn handle_event_current_field(&mut self, key_event: KeyEvent) {
+        let state = self.current_state();
+        state.handle_key_event(key_event);
+    }
+
+    fn next_field(&mut self) -> Option<Field> {
+        if !self.current_state().status().is_aborted() {
+            return match self.current_field {
+                Field::Firstname => Some(Field::Lastname),
+                Field::Lastname => Some(Field::DisplayName),
+                Field::DisplayName => Some(Field::StatusText),
+                Field::StatusText => Some(Field::Email),
+                Field::Email => Some(Field::CurrentPassword),
+                Field::CurrentPassword => Some(Field::Firstname),
+            };
+        }
+
+        None
+    }
+
+    fn prev_field(&mut self) -> Option<Field> {
+        if !self.current_state().status().is_aborted() {
+            return match self.current_field {
+                Field::Firstname => Some(Field::CurrentPassword),
+                Field::Lastname => Some(Field::Firstname),
+                Field::DisplayName => Some(Field::Lastname),
+                Field::StatusText => Some(Field::DisplayName),
+                Field::Email => Some(Field::StatusText),
+                Field::CurrentPassword => Some(Field::Email),
+            };
+        }
+
+        None
+    }
+
+    fn current_state(&mut self) -> &mut TextState<'a> {
+        match self.current_field {
+            Field::Firstname => &mut self.firstname_state,
+            Field::Lastname => &mut self.lastname_state,
+            Field::DisplayName => &mut self.display_name_state,
+            Field::StatusText => &mut self.status_text_state,
+            Field::Email => &mut self.email_state,
+            Field::CurrentPassword => &mut self.current_password_state,
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let profile_block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(2))
+            .title("Profile".bold().into_centered_line());
+
+        let block_area = centered_rect(45, 45, area);
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                // username and email verification
+                Constraint::Length(3),
+                // firstname
+                Constraint::Length(2),
+                // lastname
+                Constraint::Length(2),
+                // display name
+                Constraint::Length(1),
+                Constraint::Length(2),
+                // status text
+                Constraint::Length(2),
+                // email
+                Constraint::Length(1),
+                Constraint::Length(2),
+                // current password
+                Constraint::Length(1),
+                Constraint::Length(2),
+            ])
+            .split(profile_block.inner(block_area));
+
+        frame.render_widget(Clear, block_area);
+        frame.render_widget(profile_block, block_area);
+
+        let email_verification = if self.details.email_verified {
+            Span::styled("Email is verified", Style::default().green())
+        } else {
+            Span::styled(
+                "Email is not verified, check your mail for the code",
+                Style::default().red(),
+            )
+        };
+
+        let details_text = vec![
+            Line::from(Span::styled(
+                format!("Username: {}", self.details.username),
+                Style::default().bold(),
+            )),
+            Line::from(email_verification),
+        ];
+        frame.render_widget(Paragraph::new(details_text), layout[0]);
+
+        TextPrompt::from("Firstname").draw(frame, layout[1], &mut self.firstname_state);
+        TextPrompt::from("Lastname").draw(frame, layout[2], &mut self.lastname_state);
+
+        TextPrompt::from("Display name").draw(frame, layout[3], &mut self.display_name_state);
+
+        let display_name_helper_text = vec![Line::from(Span::styled(
+            "Leave empty to remove it",
+            Style::default(),
+        ))];
+        frame.render_widget(Paragraph::new(display_name_helper_text), layout[4]);
+
+        TextPrompt::from("Status").draw(frame, layout[5], &mut self.status_text_state);
+
+        TextPrompt::from("Email").draw(frame, layout[6], &mut self.email_state);
+
+        let email_helper_text = vec![Line::from(Span::styled(
+            "A new email has to be verified again",
+            Style::default(),
+        ))];
+        frame.render_widget(Paragraph::new(email_helper_text), layout[7]);
+
+        TextPrompt::from("Current password")
+            .with_render_style(TextRenderStyle::Password)
+            .draw(frame, layout[8], &mut self.current_password_state);
+
+        let current_password_helper_text = vec![Line::from(Span::styled(
+            "Only needed when changing the email",
+            Style::default(),
+        ))];
+        frame.render_widget(Paragraph::new(current_password_helper_text), layout[9]);
+
+        if self.show_error_popup {
+            let error_popup = Popup::new(self.error_description.as_str())
+                .title("Profile Error")
+                .style(Style::default().on_red());
+
+            frame.render_widget(&error_popup, area)
+        } else {
+            self.focus_current_field()
+        }
+    }
+}
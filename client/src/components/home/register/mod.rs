@@ -1,4 +1,6 @@
-use auth::authentication::RegisterRequest;
+use crate::api::session_device;
+use auth::authentication::{CheckAvailabilityRequest, CheckAvailabilityResponse, RegisterRequest};
+use auth::server::{estimate_password_strength, PasswordStrength, MAX_PASSWORD_SCORE};
 use crossterm::event::KeyEvent;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -33,6 +35,9 @@ pub struct Register<'a> {
     username_state: TextState<'a>,
     email_state: TextState<'a>,
     password_state: TextState<'a>,
+    // whether the username and email that have been entered can still be registered, none until it's known
+    username_available: Option<bool>,
+    email_available: Option<bool>,
     pub show_error_popup: bool,
     pub error_description: String,
 }
@@ -47,6 +52,8 @@ impl<'a> Default for Register<'a> {
             username_state: TextState::default(),
             email_state: TextState::default(),
             password_state: TextState::default(),
+            username_available: None,
+            email_available: None,
             show_error_popup: false,
             error_description: "".into(),
         }
@@ -77,6 +84,30 @@ impl<'a> Register<'a> {
             username: self.username_state.value().into(),
             email: self.email_state.value().into(),
             password: self.password_state.value().into(),
+            device: session_device(),
+        }
+    }
+
+    // only the fields that have been completed are checked
+    pub fn get_availability_request(&self) -> CheckAvailabilityRequest {
+        let value_if_finished = |state: &TextState| match state.is_finished() {
+            true => state.value().to_string(),
+            false => String::new(),
+        };
+
+        CheckAvailabilityRequest {
+            username: value_if_finished(&self.username_state),
+            email: value_if_finished(&self.email_state),
+        }
+    }
+
+    pub fn set_availability(&mut self, availability: CheckAvailabilityResponse) {
+        if availability.username_available.is_some() {
+            self.username_available = availability.username_available;
+        }
+
+        if availability.email_available.is_some() {
+            self.email_available = availability.email_available;
         }
     }
 
@@ -90,6 +121,8 @@ impl<'a> Register<'a> {
         self.username_state = TextState::default();
         self.email_state = TextState::default();
         self.password_state = TextState::default();
+        self.username_available = None;
+        self.email_available = None;
     }
 
     pub fn focus_next(&mut self) {
@@ -122,6 +155,10 @@ impl<'a> Register<'a> {
                 self.show_error_popup = false;
                 self.current_state().complete();
 
+                if matches!(self.current_field, Field::Username | Field::Email) {
+                    let _ = sender.send(Event::CheckAvailability);
+                }
+
                 if self.current_state().is_finished() && !self.is_finished() {
                     self.focus_next();
                 } else {
@@ -140,6 +177,13 @@ impl<'a> Register<'a> {
     }
 
     pub fn handle_event_current_field(&mut self, key_event: KeyEvent) {
+        // what was checked no longer matches once the field is edited
+        match self.current_field {
+            Field::Username => self.username_available = None,
+            Field::Email => self.email_available = None,
+            _ => {}
+        }
+
         let state = self.current_state();
         state.handle_key_event(key_event);
     }
@@ -204,10 +248,11 @@ impl<'a> Register<'a> {
                 Constraint::Length(1),
                 Constraint::Length(4),
                 //email
-                Constraint::Length(2),
+                Constraint::Length(1),
+                Constraint::Length(1),
                 // password
                 Constraint::Length(1),
-                Constraint::Length(2),
+                Constraint::Length(4),
             ])
             .split(register_block.inner(block_area));
 
@@ -233,7 +278,7 @@ impl<'a> Register<'a> {
 
         TextPrompt::from("Username").draw(frame, layout[5], &mut self.username_state);
 
-        let username_helper_text = vec![
+        let mut username_helper_text = vec![
             Line::from(Span::styled("Maximum of 255 character", Style::default())),
             Line::from(Span::styled(
                 "Following charcters are forbidden",
@@ -248,21 +293,46 @@ impl<'a> Register<'a> {
             )),
         ];
 
+        if let Some(line) = availability_line("Username", self.username_available) {
+            username_helper_text.push(line);
+        }
+
         let username_helper_paragraph = Paragraph::new(username_helper_text);
         frame.render_widget(username_helper_paragraph, layout[6]);
 
         TextPrompt::from("Email").draw(frame, layout[7], &mut self.email_state);
 
+        if let Some(line) = availability_line("Email", self.email_available) {
+            frame.render_widget(Paragraph::new(line), layout[8]);
+        }
+
         TextPrompt::from("Password")
             .with_render_style(TextRenderStyle::Password)
-            .draw(frame, layout[8], &mut self.password_state);
+            .draw(frame, layout[9], &mut self.password_state);
 
-        let password_helper_text = vec![
+        let mut password_helper_text = vec![
             Line::from(Span::styled("Minimum of 8 character", Style::default())),
             Line::from(Span::styled("Maximum of 255 character", Style::default())),
         ];
+
+        let password = self.password_state.value();
+        if !password.is_empty() {
+            // the auth service refuses passwords that are too weak, so the meter uses the same estimate
+            let strength = estimate_password_strength(
+                password,
+                &[self.username_state.value(), self.email_state.value()],
+            );
+
+            password_helper_text.push(strength_meter_line(&strength));
+            if let Some(hint) = strength.hints.first() {
+                password_helper_text.push(Line::from(Span::styled(
+                    hint.clone(),
+                    Style::default().italic(),
+                )));
+            }
+        }
         let password_helper_paragraph = Paragraph::new(password_helper_text);
-        frame.render_widget(password_helper_paragraph, layout[9]);
+        frame.render_widget(password_helper_paragraph, layout[10]);
 
         if self.show_error_popup {
             // popup error goes here
@@ -276,3 +346,34 @@ impl<'a> Register<'a> {
         }
     }
 }
+
+fn availability_line(quantity: &str, available: Option<bool>) -> Option<Line<'static>> {
+    match available? {
+        true => Some(Line::from(Span::styled(
+            format!("{} is available", quantity),
+            Style::default().green(),
+        ))),
+        false => Some(Line::from(Span::styled(
+            format!("{} is already taken", quantity),
+            Style::default().red(),
+        ))),
+    }
+}
+
+fn strength_meter_line(strength: &PasswordStrength) -> Line<'static> {
+    let style = match strength.score {
+        0 | 1 => Style::default().red(),
+        2 => Style::default().yellow(),
+        _ => Style::default().green(),
+    };
+
+    let filled = strength.score as usize + 1;
+    let empty = MAX_PASSWORD_SCORE as usize + 1 - filled;
+
+    Line::from(vec![
+        Span::styled("Strength ", Style::default()),
+        Span::styled("■".repeat(filled), style),
+        Span::styled("□".repeat(empty), Style::default()),
+        Span::styled(format!(" {}", strength.label()), style),
+    ])
+}
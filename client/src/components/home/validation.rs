@@ -39,6 +39,15 @@ pub fn validate_username(username: &str) -> Result<(), String> {
     Ok(())
 }
 
+// logging in works with either the username or the email
+pub fn validate_identifier(identifier: &str) -> Result<(), String> {
+    if parse_email(identifier).is_ok() {
+        return Ok(());
+    }
+
+    validate_username(identifier)
+}
+
 pub fn validate_password(password: &str) -> Result<(), String> {
     // is_empty_or_whitespace
     if password.trim().is_empty() {
@@ -64,6 +73,29 @@ pub fn validate_password(password: &str) -> Result<(), String> {
     Ok(())
 }
 
+pub fn validate_code(code: &str) -> Result<(), String> {
+    if code.trim().is_empty() {
+        return Err("Code is empty".into());
+    }
+
+    Ok(())
+}
+
+// the display name and status text can be empty, which removes them
+pub fn validate_profile_text(s: &str, max_length: u8, quantity: &str) -> Result<(), String> {
+    // is_too_long
+    if s.trim().graphemes(true).count() > max_length.into() {
+        return Err(format!("{} is longer than {} chars", quantity, max_length));
+    }
+
+    // is_contain_control_characters
+    if s.chars().any(|c| c.is_control()) {
+        return Err(format!("{} contains control characters", quantity));
+    }
+
+    Ok(())
+}
+
 pub fn parse_email(s: &str) -> Result<(), String> {
     if validate_email(s) {
         Ok(())
@@ -13,10 +13,10 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
             KeyCode::Char('q') => {
                 app.exit();
             }
-            KeyCode::Char('c') | KeyCode::Char('C') => {
-                if key_event.modifiers == KeyModifiers::CONTROL {
-                    app.exit()
-                }
+            KeyCode::Char('c') | KeyCode::Char('C')
+                if key_event.modifiers == KeyModifiers::CONTROL =>
+            {
+                app.exit()
             }
             // KeyCode::Char('w') => app.toggle_mode(),
             KeyCode::Char('j') | KeyCode::Down => app.home.select_next(),
@@ -35,20 +35,18 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
                     match action {
                         Action::Login => {
                             app.home.login.submit(sender.clone());
-                            match app.home.login.is_finished() {
-                                true => {
+                            if app.home.login.is_finished() {
+                                if app.home.login.awaiting_second_factor() {
+                                    let _ = sender.send(Event::CompleteLogin);
+                                } else {
                                     let _ = sender.send(Event::Login);
                                 }
-                                false => {}
                             }
                         }
                         Action::Register => {
                             app.home.register.submit(sender.clone());
-                            match app.home.register.is_finished() {
-                                true => {
-                                    let _ = sender.send(Event::Register);
-                                }
-                                false => {}
+                            if app.home.register.is_finished() {
+                                let _ = sender.send(Event::Register);
                             }
                         }
                         Action::Chat => {
@@ -56,6 +54,12 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
                             app.home.chat.handle_submit(sender.clone());
                             // let _ = sender.send(Event::Chat);
                         }
+                        Action::Profile => {
+                            app.home.profile.submit(sender.clone());
+                            if app.home.profile.is_finished() {
+                                let _ = sender.send(Event::UpdateProfile);
+                            }
+                        }
                     }
                 }
             }
@@ -66,6 +70,7 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
                         Action::Login => app.home.login.focus_next(),
                         Action::Register => app.home.register.focus_next(),
                         Action::Chat => todo!(),
+                        Action::Profile => app.home.profile.focus_next(),
                     }
                 }
             }
@@ -75,19 +80,31 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
                         Action::Login => app.home.login.focus_prev(),
                         Action::Register => app.home.register.focus_prev(),
                         Action::Chat => todo!(),
+                        Action::Profile => app.home.profile.focus_prev(),
                     }
                 }
             }
 
+            // the profile is opened from the chat, so that's where going back from it leads
+            KeyCode::Esc if matches!(app.home.selected_action(), Some(Action::Profile)) => {
+                app.home.set_action_to_chat();
+            }
             KeyCode::Esc => {
                 app.home.reset_action();
                 app.toggle_mode();
             }
+            KeyCode::Char('p') | KeyCode::Char('P')
+                if key_event.modifiers == KeyModifiers::CONTROL
+                    && matches!(app.home.selected_action(), Some(Action::Chat)) =>
+            {
+                let _ = sender.send(Event::OpenProfile);
+            }
             KeyCode::Up => {
                 if let Some(action) = app.home.selected_action() {
                     match action {
                         Action::Login => {}
                         Action::Register => {}
+                        Action::Profile => {}
                         Action::Chat => {
                             // println!("scrolling up");
                             app.home.chat.vertical_scroll =
@@ -101,6 +118,7 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
                     match action {
                         Action::Login => {}
                         Action::Register => {}
+                        Action::Profile => {}
                         Action::Chat => {
                             // println!("scrolling down");
                             app.home.chat.vertical_scroll =
@@ -116,6 +134,7 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
                         Action::Login => app.home.login.handle_event_current_field(key_event),
                         Action::Register => app.home.register.handle_event_current_field(key_event),
                         Action::Chat => app.home.chat.handle_event(key_event),
+                        Action::Profile => app.home.profile.handle_event_current_field(key_event),
                     }
                 }
             }
@@ -127,6 +146,7 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
                         Action::Login => app.home.login.show_error_popup = false,
                         Action::Register => app.home.register.show_error_popup = false,
                         Action::Chat => app.home.chat.show_error_popup = false,
+                        Action::Profile => app.home.profile.show_error_popup = false,
                     }
                 }
                 app.mode = AppMode::Write;
@@ -134,10 +154,10 @@ pub async fn action<'a>(app: &mut App<'a>, key_event: KeyEvent, sender: Sender)
             KeyCode::Char('q') => {
                 app.exit();
             }
-            KeyCode::Char('c') | KeyCode::Char('C') => {
-                if key_event.modifiers == KeyModifiers::CONTROL {
-                    app.exit()
-                }
+            KeyCode::Char('c') | KeyCode::Char('C')
+                if key_event.modifiers == KeyModifiers::CONTROL =>
+            {
+                app.exit()
             }
             _ => {}
         },
@@ -12,9 +12,15 @@ pub enum Event {
     Mouse(MouseEvent),
     Error,
     Login,
+    CompleteLogin,
     Register,
+    CheckAvailability,
     Chat,
     Message(ChatMessage),
+    // the chat stream was closed by the server, with the reason it gave
+    ChatClosed(String),
+    OpenProfile,
+    UpdateProfile,
 }
 
 pub type Sender = UnboundedSender<Event>;
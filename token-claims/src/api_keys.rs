@@ -0,0 +1,92 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+use tonic::metadata::MetadataMap;
+
+use crate::TokenError;
+
+// service accounts send their API key in this metadata instead of an auth token
+pub const API_KEY_METADATA: &str = "x-api-key";
+
+// every API key starts with this, so a leaked key is easy to recognize
+pub const API_KEY_PREFIX: &str = "chatgrpc_";
+
+// what an API key is allowed to do, auth tokens of users are allowed to do everything
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scope {
+    // receive the messages that are sent in the chat
+    ChatRead,
+    // send messages into the chat
+    ChatPost,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 2] = [Scope::ChatRead, Scope::ChatPost];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ChatRead => "chat:read",
+            Scope::ChatPost => "chat:post",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Scope, String> {
+        match s {
+            "chat:read" => Ok(Scope::ChatRead),
+            "chat:post" => Ok(Scope::ChatPost),
+            _ => Err(format!("{} is not a scope", s)),
+        }
+    }
+}
+
+// only the hash of an API key is stored, API keys are long random strings so a fast hash is enough
+pub fn hash_api_key(api_key: &str) -> String {
+    Sha256::digest(api_key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// none when the request doesn't carry an API key
+pub fn get_api_key(metadata: &MetadataMap) -> Option<Result<&str, TokenError>> {
+    metadata.get(API_KEY_METADATA).map(|api_key| {
+        api_key
+            .to_str()
+            .map(str::trim)
+            .map_err(|_| TokenError::MalformedToken)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_are_parsed_from_their_name() {
+        for scope in Scope::ALL {
+            assert_eq!(scope.as_str().parse::<Scope>(), Ok(scope));
+        }
+
+        assert!("chat".parse::<Scope>().is_err());
+    }
+
+    #[test]
+    fn api_key_is_read_from_the_metadata() {
+        let mut metadata = MetadataMap::new();
+        assert!(get_api_key(&metadata).is_none());
+
+        metadata.insert(API_KEY_METADATA, "chatgrpc_abc".parse().unwrap());
+        assert_eq!(get_api_key(&metadata), Some(Ok("chatgrpc_abc")));
+    }
+}
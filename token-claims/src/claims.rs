@@ -0,0 +1,77 @@
+use chrono::{Duration, Local};
+use jwt::{Header, Token, Unverified};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{Role, TokenError};
+
+pub const ISSUER: &str = "Chat-gRPC";
+pub const SUBJECT: &str = "auth token";
+
+// the claims of the auth tokens that the auth service hands out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Claims {
+    pub iss: String,
+    pub sub: String,
+    // issued at and expiration time, as unix timestamps in seconds
+    pub iat: i64,
+    pub exp: i64,
+    // JWT ID, used to revoke this specific token
+    pub jti: Uuid,
+    pub user_id: i32,
+    // auth tokens handed out before the claim was added don't have it
+    #[serde(default)]
+    pub username: String,
+    pub session_id: Uuid,
+    // the names of the roles the user had when the auth token was handed out, names that a service doesn't know
+    // are ignored
+    #[serde(default)]
+    pub roles: Vec<String>,
+    // services decide themselves whether they accept users that haven't verified their email, auth tokens without
+    // the claim are treated as unverified
+    #[serde(default)]
+    pub email_verified: bool,
+}
+
+impl Claims {
+    pub fn new(
+        user_id: i32,
+        username: String,
+        roles: &[Role],
+        session_id: Uuid,
+        email_verified: bool,
+        lifetime: Duration,
+    ) -> Claims {
+        let now = Local::now();
+
+        Claims {
+            iss: ISSUER.to_string(),
+            sub: SUBJECT.to_string(),
+            iat: now.timestamp(),
+            exp: (now + lifetime).timestamp(),
+            jti: Uuid::new_v4(),
+            user_id,
+            username,
+            session_id,
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            email_verified,
+        }
+    }
+
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.iter().any(|name| name == role.as_str())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Local::now().timestamp() > self.exp
+    }
+
+    // reads the claims without checking the signature, this is only meant for clients that want to know what their
+    // own auth token says and never to decide whether an auth token is valid
+    pub fn decode_unverified(auth_token: &str) -> Result<Claims, TokenError> {
+        let token: Token<Header, Claims, Unverified<'_>> =
+            Token::parse_unverified(auth_token).map_err(|_| TokenError::MalformedToken)?;
+
+        Ok(token.claims().clone())
+    }
+}
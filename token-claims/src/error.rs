@@ -0,0 +1,36 @@
+use thiserror::Error;
+use tonic::{Code, Status};
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    #[error("no valid auth token")]
+    MissingToken,
+    #[error("the provided auth token is malformed")]
+    MalformedToken,
+    #[error("auth token was signed with an unknown key")]
+    UnknownKey,
+    #[error("auth token signature could not be verified")]
+    InvalidSignature,
+    #[error("JWT {0} does not match")]
+    WrongClaim(&'static str),
+    #[error("Auth token has expired")]
+    Expired,
+    #[error("Auth token has been revoked")]
+    Revoked,
+}
+
+impl TokenError {
+    // a token that can't even be read is a bad request, every other problem means the caller isn't authenticated
+    pub fn code(&self) -> Code {
+        match self {
+            TokenError::MalformedToken => Code::InvalidArgument,
+            _ => Code::Unauthenticated,
+        }
+    }
+}
+
+impl From<TokenError> for Status {
+    fn from(error: TokenError) -> Status {
+        Status::new(error.code(), error.to_string())
+    }
+}
@@ -0,0 +1,11 @@
+mod api_keys;
+mod claims;
+mod error;
+mod roles;
+mod verifier;
+
+pub use api_keys::*;
+pub use claims::*;
+pub use error::*;
+pub use roles::*;
+pub use verifier::*;
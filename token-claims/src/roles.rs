@@ -0,0 +1,74 @@
+use std::fmt;
+use std::str::FromStr;
+
+// the roles that can be granted to a user, they are carried in the auth tokens by their name
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Role, String> {
+        match s {
+            "user" => Ok(Role::User),
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            _ => Err(format!("{} is not a role", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::Role;
+    use crate::Claims;
+
+    #[test]
+    fn roles_are_parsed_from_their_name() {
+        for role in [Role::User, Role::Moderator, Role::Admin] {
+            assert_eq!(role.as_str().parse::<Role>(), Ok(role));
+        }
+
+        assert!("Admin".parse::<Role>().is_err());
+        assert!("".parse::<Role>().is_err());
+    }
+
+    #[test]
+    fn claims_only_have_the_roles_they_were_created_with() {
+        let claims = Claims::new(
+            1,
+            "alice".to_string(),
+            &[Role::User, Role::Moderator],
+            Uuid::new_v4(),
+            true,
+            Duration::minutes(5),
+        );
+
+        assert!(claims.has_role(Role::User));
+        assert!(claims.has_role(Role::Moderator));
+        assert!(!claims.has_role(Role::Admin));
+    }
+}
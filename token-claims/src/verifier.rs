@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use hmac::{Hmac, Mac};
+use jwt::{AlgorithmType, Header, Token, Unverified, VerifyWithKey, VerifyingAlgorithm};
+use rsa::pkcs1v15::{Signature, VerifyingKey as RsaVerifyingKey};
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::{Sha256, Sha512};
+use tonic::metadata::MetadataMap;
+
+use crate::{Claims, TokenError, ISSUER, SUBJECT};
+
+// RS256 of RFC 7518, which is RSASSA-PKCS1-v1_5 with SHA-256
+#[derive(Clone)]
+pub struct Rs256VerifyingKey(RsaVerifyingKey<Sha256>);
+
+impl Rs256VerifyingKey {
+    pub fn new(public_key: RsaPublicKey) -> Rs256VerifyingKey {
+        Rs256VerifyingKey(RsaVerifyingKey::new(public_key))
+    }
+
+    pub fn public_key(&self) -> &RsaPublicKey {
+        self.0.as_ref()
+    }
+}
+
+impl VerifyingAlgorithm for Rs256VerifyingKey {
+    fn algorithm_type(&self) -> AlgorithmType {
+        AlgorithmType::Rs256
+    }
+
+    fn verify_bytes(
+        &self,
+        header: &str,
+        claims: &str,
+        signature: &[u8],
+    ) -> Result<bool, jwt::Error> {
+        let signature = match Signature::try_from(signature) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(false),
+        };
+
+        Ok(self
+            .0
+            .verify(format!("{}.{}", header, claims).as_bytes(), &signature)
+            .is_ok())
+    }
+}
+
+// a key that auth tokens can be verified with
+#[derive(Clone)]
+pub enum VerifyingKey {
+    // boxed because the HMAC state is a lot bigger than the RSA key
+    Hmac(Box<Hmac<Sha512>>),
+    Rs256(Rs256VerifyingKey),
+}
+
+impl VerifyingKey {
+    pub fn hmac(secret: &[u8]) -> VerifyingKey {
+        VerifyingKey::Hmac(Box::new(
+            Hmac::new_from_slice(secret).expect("HMAC accepts keys of any length"),
+        ))
+    }
+
+    pub fn rs256(public_key: RsaPublicKey) -> VerifyingKey {
+        VerifyingKey::Rs256(Rs256VerifyingKey::new(public_key))
+    }
+}
+
+// verifies auth tokens with the key that their kid header names, the algorithm of the token has to match the key so a
+// token can't pick a weaker algorithm than the one the key is meant for
+#[derive(Clone, Default)]
+pub struct TokenVerifier {
+    // kid -> key
+    keys: HashMap<String, VerifyingKey>,
+}
+
+impl TokenVerifier {
+    pub fn new() -> TokenVerifier {
+        Self::default()
+    }
+
+    pub fn add_key(&mut self, kid: impl Into<String>, key: VerifyingKey) {
+        self.keys.insert(kid.into(), key);
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // checks the signature, the generic claims and the expiration time of the auth token and returns its claims
+    pub fn verify(&self, auth_token: &str) -> Result<Claims, TokenError> {
+        let token: Token<Header, Claims, Unverified<'_>> =
+            Token::parse_unverified(auth_token).map_err(|_| TokenError::MalformedToken)?;
+
+        let key = token
+            .header()
+            .key_id
+            .as_ref()
+            .and_then(|kid| self.keys.get(kid))
+            .ok_or(TokenError::UnknownKey)?;
+
+        let token = match key {
+            VerifyingKey::Hmac(key) => token.verify_with_key(key.as_ref()),
+            VerifyingKey::Rs256(key) => token.verify_with_key(key),
+        }
+        .map_err(|_| TokenError::InvalidSignature)?;
+
+        let claims = token.claims();
+
+        if claims.iss != ISSUER {
+            return Err(TokenError::WrongClaim("iss"));
+        }
+
+        if claims.sub != SUBJECT {
+            return Err(TokenError::WrongClaim("sub"));
+        }
+
+        if claims.is_expired() {
+            return Err(TokenError::Expired);
+        }
+
+        Ok(claims.clone())
+    }
+}
+
+// reads the token out of the "authorization: Bearer <token>" metadata
+pub fn get_bearer_token(metadata: &MetadataMap) -> Result<&str, TokenError> {
+    let bearer_token = match metadata.get("authorization") {
+        Some(t) => t.to_str().map_err(|_| TokenError::MalformedToken)?,
+        None => return Err(TokenError::MissingToken),
+    };
+
+    let mut token_split_stream = bearer_token.split_whitespace();
+    token_split_stream.next();
+
+    token_split_stream.next().ok_or(TokenError::MalformedToken)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use jwt::SignWithKey;
+    use tonic::{Code, Status};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::Role;
+
+    const SECRET: &[u8] = b"04c1582b55ba64e0cd085d6edc23ab65578470ef03a8afb19897be536927f670";
+
+    fn sign(kid: &str, claims: Claims) -> String {
+        let key: Hmac<Sha512> = Hmac::new_from_slice(SECRET).unwrap();
+        let header = Header {
+            algorithm: AlgorithmType::Hs512,
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        };
+
+        Token::new(header, claims)
+            .sign_with_key(&key)
+            .unwrap()
+            .as_str()
+            .to_string()
+    }
+
+    fn verifier() -> TokenVerifier {
+        let mut verifier = TokenVerifier::new();
+        verifier.add_key("current", VerifyingKey::hmac(SECRET));
+
+        verifier
+    }
+
+    fn claims(lifetime: Duration) -> Claims {
+        Claims::new(
+            1,
+            "alice".to_string(),
+            &[Role::User],
+            Uuid::new_v4(),
+            true,
+            lifetime,
+        )
+    }
+
+    #[test]
+    fn valid_auth_token_returns_its_claims() {
+        let claims = claims(Duration::minutes(15));
+
+        assert_eq!(
+            verifier().verify(&sign("current", claims.clone())),
+            Ok(claims)
+        );
+    }
+
+    #[test]
+    fn expired_auth_token_is_rejected() {
+        let auth_token = sign("current", claims(Duration::minutes(-1)));
+
+        assert_eq!(verifier().verify(&auth_token), Err(TokenError::Expired));
+    }
+
+    #[test]
+    fn auth_token_of_another_issuer_is_rejected() {
+        let auth_token = sign(
+            "current",
+            Claims {
+                iss: "someone else".into(),
+                ..claims(Duration::minutes(15))
+            },
+        );
+
+        assert_eq!(
+            verifier().verify(&auth_token),
+            Err(TokenError::WrongClaim("iss"))
+        );
+    }
+
+    #[test]
+    fn auth_token_with_unknown_kid_is_rejected() {
+        let auth_token = sign("retired", claims(Duration::minutes(15)));
+
+        assert_eq!(verifier().verify(&auth_token), Err(TokenError::UnknownKey));
+    }
+
+    #[test]
+    fn errors_map_to_status_codes() {
+        let malformed = verifier().verify("not.a.token").unwrap_err();
+
+        assert_eq!(Status::from(malformed).code(), Code::InvalidArgument);
+        assert_eq!(
+            Status::from(TokenError::Expired).code(),
+            Code::Unauthenticated
+        );
+    }
+}